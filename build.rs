@@ -0,0 +1,97 @@
+//! Embeds the commit and date of the build for `gwt version`.
+//!
+//! Both fall back to `unknown`, e.g. when building from a crates.io download
+//! without git, so a missing value never fails the build.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Only ask git in a checkout of gwt itself, not in a repository that
+    // happens to contain the sources
+    let checkout = Path::new(".git").exists();
+    let commit = if checkout {
+        git(&["rev-parse", "--short=12", "HEAD"])
+    } else {
+        packaged_commit()
+    };
+    println!(
+        "cargo:rustc-env=GWT_BUILD_COMMIT={}",
+        commit.as_deref().unwrap_or("unknown")
+    );
+    println!("cargo:rustc-env=GWT_BUILD_DATE={}", build_date());
+
+    // Rebuild when HEAD moves, but only watch files that exist: cargo reruns
+    // the script on every build for a missing one
+    if !checkout {
+        return;
+    }
+    if let (Some(git_dir), Some(common_dir)) = (
+        git(&["rev-parse", "--git-dir"]),
+        git(&["rev-parse", "--git-common-dir"]),
+    ) {
+        let common_dir = Path::new(&common_dir);
+        let mut watched = vec![
+            Path::new(&git_dir).join("HEAD"),
+            common_dir.join("packed-refs"),
+        ];
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            watched.push(common_dir.join(head_ref));
+        }
+        for path in watched.iter().filter(|p| p.exists()) {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+/// Trimmed stdout of a successful git command in the crate directory
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Commit `cargo package` recorded in `.cargo_vcs_info.json`, as in a
+/// crates.io download
+fn packaged_commit() -> Option<String> {
+    let info = std::fs::read_to_string(".cargo_vcs_info.json").ok()?;
+    let (_, rest) = info.split_once("\"sha1\"")?;
+    let sha1 = rest.split('"').nth(1)?;
+    sha1.get(..12).map(str::to_string)
+}
+
+/// `YYYY-MM-DD` in UTC, from `SOURCE_DATE_EPOCH` for reproducible builds
+fn build_date() -> String {
+    let secs = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => value.trim().parse().ok(),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs()),
+    };
+    let Some(secs) = secs else {
+        return "unknown".to_string();
+    };
+
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,134 @@
+use crate::utility::{Worktree, Worktrees, unix_now, write_atomic};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Worktree list of one repository as last seen by `gwt ls`
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    /// Seconds since the Unix epoch
+    timestamp: u64,
+    worktrees: Vec<Worktree>,
+}
+
+/// What `load` found in a cache file
+pub enum Lookup {
+    /// Written within the TTL
+    Fresh(Worktrees),
+    /// Older than the TTL, but still better than nothing while it's refreshed
+    Stale(Worktrees),
+    /// No cache, or one that can't be read
+    Missing,
+}
+
+/// Cache file of the repository with the common dir `common_dir`, under `dir`
+pub fn file_for(dir: &Path, common_dir: &Path) -> PathBuf {
+    let mut hasher = Sha1::new();
+    hasher.update(common_dir.to_string_lossy().as_bytes());
+    let digest = hasher.finalize();
+    dir.join(format!("{}.json", &format!("{digest:x}")[0..16]))
+}
+
+/// Replace the cached worktree list in `file`
+pub fn store(file: &Path, worktrees: &Worktrees) -> Result<()> {
+    let content = serde_json::to_string(&CacheFile {
+        timestamp: unix_now(),
+        worktrees: worktrees.to_vec(),
+    })?;
+    write_atomic(file, content)
+        .with_context(|| format!("Failed to write cache file '{}'", file.display()))
+}
+
+/// The cached worktree list in `file`, and whether it's younger than `ttl`
+pub fn load(file: &Path, ttl: Duration) -> Lookup {
+    load_at(file, ttl, unix_now())
+}
+
+fn load_at(file: &Path, ttl: Duration, now: u64) -> Lookup {
+    let Some(cache) = fs::read_to_string(file)
+        .ok()
+        .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+    else {
+        return Lookup::Missing;
+    };
+    let worktrees = Worktrees::new(cache.worktrees);
+    if now.saturating_sub(cache.timestamp) < ttl.as_secs() {
+        Lookup::Fresh(worktrees)
+    } else {
+        Lookup::Stale(worktrees)
+    }
+}
+
+/// Drop the cache in `file` after the worktrees changed
+pub fn invalidate(file: &Path) {
+    let _ = fs::remove_file(file);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worktrees() -> Worktrees {
+        Worktrees::new(vec![
+            Worktree::new(
+                PathBuf::from("/code/repo"),
+                "0".repeat(40),
+                Some("main".to_string()),
+            )
+            .into_main(),
+            Worktree::new(PathBuf::from("/store/69fa950d"), "1".repeat(40), None),
+        ])
+    }
+
+    #[test]
+    fn test_file_for_is_per_repository() {
+        let dir = Path::new("/home/me/.gwt/cache");
+        let a = file_for(dir, Path::new("/code/a/.git"));
+        assert_eq!(a, file_for(dir, Path::new("/code/a/.git")));
+        assert_ne!(a, file_for(dir, Path::new("/code/b/.git")));
+        assert_eq!(a.parent(), Some(dir));
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("cache/repo.json");
+        assert!(matches!(
+            load(&file, Duration::from_secs(5)),
+            Lookup::Missing
+        ));
+
+        store(&file, &worktrees()).unwrap();
+        let Lookup::Fresh(cached) = load(&file, Duration::from_secs(5)) else {
+            panic!("expected a fresh cache");
+        };
+        assert_eq!(cached.to_vec(), worktrees().to_vec());
+        assert!(cached[0].is_main());
+
+        let later = unix_now() + 5;
+        assert!(matches!(
+            load_at(&file, Duration::from_secs(5), later),
+            Lookup::Stale(_)
+        ));
+
+        invalidate(&file);
+        assert!(matches!(
+            load(&file, Duration::from_secs(5)),
+            Lookup::Missing
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("repo.json");
+        fs::write(&file, "{not json").unwrap();
+        assert!(matches!(
+            load(&file, Duration::from_secs(5)),
+            Lookup::Missing
+        ));
+    }
+}
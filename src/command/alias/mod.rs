@@ -0,0 +1,302 @@
+use crate::command::Cli;
+use crate::config;
+use crate::ui::{self, EvalShell, shell_quote};
+use anyhow::Result;
+use clap::{CommandFactory, Subcommand, ValueEnum};
+use console::style;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+/// Alias names mapped to the arguments they expand to
+pub type Aliases = BTreeMap<String, Vec<String>>;
+
+/// How `gwt alias expand` prints the words of an expansion
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Words {
+    /// Quoted for `eval` in bash, zsh and other POSIX shells
+    Sh,
+    /// Quoted for `eval` in fish
+    Fish,
+    /// One word per line, unquoted, for shells that read them into a list
+    /// (PowerShell, nushell)
+    Lines,
+}
+
+impl Words {
+    fn format(self, words: &[OsString]) -> String {
+        let words = words.iter().map(|w| w.to_string_lossy());
+        let (words, separator): (Vec<String>, _) = match self {
+            Self::Sh => (words.map(|w| shell_quote(EvalShell::Sh, &w)).collect(), " "),
+            Self::Fish => (
+                words.map(|w| shell_quote(EvalShell::Fish, &w)).collect(),
+                " ",
+            ),
+            Self::Lines => (words.map(String::from).collect(), "\n"),
+        };
+        words.join(separator)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum AliasCommands {
+    /// List the aliases of the config with what they expand to
+    ///
+    /// Aliases are set in the `[aliases]` table of the global config, `~/.gwt/config.toml` by
+    /// default. Aliases that can't be used, such as one named like a built-in command, are
+    /// listed with the reason.
+    List {
+        /// Output only the names of usable aliases, one per line (for shell completion)
+        #[arg(long = "raw", hide = true)]
+        raw: bool,
+    },
+    /// Print the expansion of an alias as words for the shell wrapper
+    ///
+    /// Prints nothing when the command isn't an alias.
+    #[command(hide = true)]
+    Expand {
+        /// Quoting of the printed words
+        #[arg(value_enum)]
+        words: Words,
+
+        /// The command line after `gwt`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+pub fn handle(cmd: &AliasCommands) -> Result<()> {
+    let aliases = configured();
+    match cmd {
+        AliasCommands::List { raw: true } => {
+            for name in aliases.keys().filter(|n| problem(n, &aliases).is_none()) {
+                println!("{}", name);
+            }
+        }
+        AliasCommands::List { raw: false } => {
+            if aliases.is_empty() {
+                ui::info!(
+                    "No aliases. Add them to the [aliases] table of '{}'.",
+                    config::file_path()?.display()
+                );
+            }
+            for line in list(&aliases) {
+                println!("{}", line);
+            }
+        }
+        AliasCommands::Expand { words, args } => {
+            let mut argv = vec![OsString::from("gwt")];
+            argv.extend(args.iter().map(OsString::from));
+            // An unusable alias is left to gwtree to report
+            if let Ok(expanded) = expand(&argv, &aliases)
+                && expanded != argv
+            {
+                println!("{}", words.format(&expanded[1..]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The aliases of the config file. A missing or broken config has none; the
+/// command run reports the broken one.
+pub fn configured() -> Aliases {
+    config::file_path()
+        .and_then(|path| config::read(&path))
+        .map(|data| data.aliases)
+        .unwrap_or_default()
+}
+
+/// `args` (the program name first) with an alias in the command position
+/// replaced by its expansion. Anything after the alias is kept, so clap
+/// reports mistakes in it against the expanded command. Built-in commands
+/// are never expanded.
+pub fn expand(args: &[OsString], aliases: &Aliases) -> Result<Vec<OsString>, String> {
+    let alias = command_position(args).and_then(|i| Some((i, args[i].to_str()?)));
+    let Some((index, name)) = alias.filter(|(_, name)| aliases.contains_key(*name)) else {
+        return Ok(args.to_vec());
+    };
+    if is_builtin(name) {
+        return Ok(args.to_vec());
+    }
+    if let Some(problem) = problem(name, aliases) {
+        return Err(format!("alias '{}' {}", name, problem));
+    }
+
+    let mut expanded = args[..index].to_vec();
+    expanded.extend(aliases[name].iter().map(OsString::from));
+    expanded.extend_from_slice(&args[index + 1..]);
+    Ok(expanded)
+}
+
+/// Index of the first argument that isn't a global option
+fn command_position<S: AsRef<std::ffi::OsStr>>(args: &[S]) -> Option<usize> {
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        match arg.as_ref().to_str() {
+            Some("--") => return None,
+            // The only global option taking a separate value
+            Some("--color") => i += 2,
+            Some(a) if a.starts_with('-') => i += 1,
+            _ => return Some(i),
+        }
+    }
+    None
+}
+
+fn is_builtin(name: &str) -> bool {
+    name == "help"
+        || Cli::command()
+            .get_subcommands()
+            .any(|c| c.get_name() == name || c.get_all_aliases().any(|a| a == name))
+}
+
+/// Why the alias `name` can't be used, if it can't
+fn problem(name: &str, aliases: &Aliases) -> Option<String> {
+    if is_builtin(name) {
+        return Some("is ignored: it's the name of a built-in command".to_string());
+    }
+    let words = aliases.get(name)?;
+    // Prepend a program name so the command is found like on the command line
+    let argv: Vec<&str> = std::iter::once("gwt")
+        .chain(words.iter().map(String::as_str))
+        .collect();
+    let Some(command) = command_position(&argv).map(|i| argv[i]) else {
+        return Some("names no command".to_string());
+    };
+    (aliases.contains_key(command) && !is_builtin(command)).then(|| {
+        format!(
+            "expands to the alias '{}', but aliases can't use other aliases",
+            command
+        )
+    })
+}
+
+/// One line per alias: name and expansion, or why it's ignored
+fn list(aliases: &Aliases) -> Vec<String> {
+    let width = aliases.keys().map(|n| n.len()).max().unwrap_or(0);
+    aliases
+        .iter()
+        .map(|(name, words)| {
+            let words: Vec<String> = words
+                .iter()
+                .map(|w| {
+                    if w.is_empty() || w.contains(char::is_whitespace) {
+                        shell_quote(EvalShell::Sh, w)
+                    } else {
+                        w.clone()
+                    }
+                })
+                .collect();
+            let line = format!("{:width$}  {}", name, words.join(" "));
+            match problem(name, aliases) {
+                Some(problem) => format!("{}  {}", line, style(format!("({problem})")).dim()),
+                None => line,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(entries: &[(&str, &[&str])]) -> Aliases {
+        entries
+            .iter()
+            .map(|(name, words)| {
+                (
+                    name.to_string(),
+                    words.iter().map(|w| w.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn argv(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_words_format() {
+        let words = argv(&["sw", "-b", "it's here"]);
+        assert_eq!(Words::Sh.format(&words), r"'sw' '-b' 'it'\''s here'");
+        assert_eq!(Words::Fish.format(&words), r"'sw' '-b' 'it\'s here'");
+        assert_eq!(Words::Lines.format(&words), "sw\n-b\nit's here");
+    }
+
+    #[test]
+    fn test_expand() {
+        let aliases = aliases(&[("co", &["sw", "-b"]), ("nuke", &["rm", "-B", "-b"])]);
+
+        assert_eq!(
+            expand(&argv(&["gwt", "co", "feature"]), &aliases).unwrap(),
+            argv(&["gwt", "sw", "-b", "feature"])
+        );
+        // Global options may come first
+        assert_eq!(
+            expand(
+                &argv(&["gwt", "-q", "--color", "never", "nuke", "x"]),
+                &aliases
+            )
+            .unwrap(),
+            argv(&["gwt", "-q", "--color", "never", "rm", "-B", "-b", "x"])
+        );
+        // Only the command position is expanded
+        for args in [&["gwt", "sw", "co"][..], &["gwt"], &["gwt", "--", "co"]] {
+            assert_eq!(expand(&argv(args), &aliases).unwrap(), argv(args));
+        }
+    }
+
+    #[test]
+    fn test_builtin_commands_win() {
+        let aliases = aliases(&[("sw", &["ls"]), ("main", &["ls"])]);
+        for args in [&["gwt", "sw", "x"][..], &["gwt", "main"]] {
+            assert_eq!(expand(&argv(args), &aliases).unwrap(), argv(args));
+        }
+        assert!(problem("sw", &aliases).unwrap().contains("built-in"));
+        assert!(problem("main", &aliases).unwrap().contains("built-in"));
+    }
+
+    #[test]
+    fn test_aliases_cannot_use_aliases() {
+        let aliases = aliases(&[
+            ("a", &["b"]),
+            ("b", &["-q", "a"]),
+            ("up", &["ls"]),
+            ("empty", &[]),
+            ("shadow", &["sw"]),
+            ("sw", &["ls"]),
+        ]);
+        let err = expand(&argv(&["gwt", "a"]), &aliases).unwrap_err();
+        assert_eq!(
+            err,
+            "alias 'a' expands to the alias 'b', but aliases can't use other aliases"
+        );
+        assert!(expand(&argv(&["gwt", "b"]), &aliases).is_err());
+        assert!(expand(&argv(&["gwt", "empty"]), &aliases).is_err());
+        // A built-in command named like an alias is still the command
+        assert_eq!(
+            expand(&argv(&["gwt", "shadow"]), &aliases).unwrap(),
+            argv(&["gwt", "sw"])
+        );
+        assert_eq!(problem("up", &aliases), None);
+    }
+
+    #[test]
+    fn test_list() {
+        console::set_colors_enabled(false);
+        let aliases = aliases(&[
+            ("co", &["sw", "-b"]),
+            ("ls", &["ls", "--full"]),
+            ("x", &["exec", "-a", "--", "git status"]),
+        ]);
+        assert_eq!(
+            list(&aliases),
+            [
+                "co  sw -b",
+                "ls  ls --full  (is ignored: it's the name of a built-in command)",
+                "x   exec -a -- 'git status'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use crate::config::Config;
+use crate::ui;
+use crate::utility::Git;
+use anyhow::{Context, Result, anyhow, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under `worktree_root/<name>` holding the clone itself
+pub(crate) const REPO_DIR_NAME: &str = ".repo";
+
+/// Clone `url` to `worktree_root/<name>/.repo` and print the path of the
+/// default branch's worktree for the shell wrapper to cd into
+pub fn handle(git: &Git, config: &Config, url: &str, name: Option<&str>) -> Result<()> {
+    config.ensure_worktree_root()?;
+
+    let data = config.data().ok_or_else(|| anyhow!("Config not loaded"))?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => repo_name_from_url(url)
+            .ok_or_else(|| anyhow!("Can't derive a name from '{}'; pass one explicitly", url))?,
+    };
+
+    let worktree = clone_into(git, &data.worktree_root, url, &name, data.clone_bare)?;
+
+    println!("{}", worktree.display());
+    Ok(())
+}
+
+/// Clone and return the path of the default branch's worktree.
+///
+/// A regular clone is itself the default branch's worktree. A bare clone gets
+/// a worktree for the default branch next to it, at `<name>/<branch>`.
+fn clone_into(git: &Git, root: &Path, url: &str, name: &str, bare: bool) -> Result<PathBuf> {
+    let dest = root.join(name);
+    if dest.exists() {
+        bail!(
+            "Cannot clone: '{}' already exists. Pass a different name: gwt clone {} <name>",
+            dest.display(),
+            url
+        );
+    }
+
+    let repo = dest.join(REPO_DIR_NAME);
+    let repo_str = repo
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid target path"))?;
+
+    let mut args = vec!["clone", "--progress"];
+    if bare {
+        args.push("--bare");
+    }
+    args.extend([url, repo_str]);
+    if let Err(e) = git.run_streaming(&args) {
+        // Don't leave a half-written clone that blocks the next attempt
+        let _ = fs::remove_dir_all(&dest);
+        return Err(e).context(format!("Failed to clone '{}'", url));
+    }
+
+    if !bare {
+        ui::info!("Cloned '{}' to '{}'", url, repo.display());
+        return Ok(repo);
+    }
+
+    let git = git.clone().with_cwd(&repo);
+
+    // Bare clones map remote branches onto local ones and have no remote-tracking
+    // refs; restore the usual layout so `gwt sw` can find remote branches
+    git.run(&[
+        "config",
+        "remote.origin.fetch",
+        "+refs/heads/*:refs/remotes/origin/*",
+    ])?;
+    git.run_streaming(&["fetch", "--progress", "origin"])
+        .context("Failed to fetch remote branches")?;
+
+    let branch = default_branch(&git)?;
+    let worktree = dest.join(&branch);
+    let worktree_str = worktree
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid target path"))?;
+    git.run(&["worktree", "add", worktree_str, &branch])
+        .context("Failed to add worktree")?;
+    // Best effort: the default branch may not exist on `origin` under that name
+    let _ = git.run(&[
+        "branch",
+        "--set-upstream-to",
+        &format!("origin/{branch}"),
+        &branch,
+    ]);
+
+    ui::info!(
+        "Cloned '{}' to '{}' with a worktree for '{}' at '{}'",
+        url,
+        repo.display(),
+        branch,
+        worktree.display()
+    );
+    Ok(worktree)
+}
+
+/// The branch `HEAD` of the fresh clone points to, which is the remote's default branch
+fn default_branch(git: &Git) -> Result<String> {
+    let output = git
+        .run(&["symbolic-ref", "--short", "HEAD"])
+        .context("Failed to detect the default branch")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `https://github.com/troydai/gwt.git`, `git@github.com:troydai/gwt` and
+/// `/path/to/gwt/` all become `gwt`
+fn repo_name_from_url(url: &str) -> Option<String> {
+    let last = url.trim_end_matches('/').rsplit(['/', ':', '\\']).next()?;
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_repo_name_from_url() {
+        for (url, name) in [
+            ("https://github.com/troydai/gwt.git", Some("gwt")),
+            ("https://github.com/troydai/gwt", Some("gwt")),
+            ("git@github.com:troydai/gwt.git", Some("gwt")),
+            ("git@example.com:gwt", Some("gwt")),
+            ("/path/to/gwt/", Some("gwt")),
+            ("C:\\src\\gwt.git", Some("gwt")),
+            ("https://example.com/", Some("example.com")),
+            ("..", None),
+        ] {
+            assert_eq!(repo_name_from_url(url).as_deref(), name, "{url}");
+        }
+    }
+
+    /// An "upstream" repository whose default branch is `trunk`
+    fn setup_origin() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "git init -q -b trunk origin && cd origin && echo hi > README && git add . && \
+                 git -c user.name=t -c user.email=t@e commit -q -m init && git branch feature",
+            )
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        fs::create_dir_all(dir.path().join("store")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_clone_regular() {
+        let dir = setup_origin();
+        let url = dir.path().join("origin");
+
+        let git = Git::new();
+        let worktree = clone_into(
+            &git,
+            &dir.path().join("store"),
+            url.to_str().unwrap(),
+            "proj",
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(worktree, dir.path().join("store/proj/.repo"));
+        assert!(worktree.join("README").exists());
+    }
+
+    #[test]
+    fn test_clone_bare_adds_default_branch_worktree() {
+        let dir = setup_origin();
+        let url = dir.path().join("origin");
+        let root = dir.path().join("store");
+
+        let git = Git::new();
+        let worktree = clone_into(&git, &root, url.to_str().unwrap(), "proj", true).unwrap();
+
+        assert_eq!(worktree, root.join("proj/trunk"));
+        assert!(worktree.join("README").exists());
+
+        // Remote branches are available for `gwt sw` and the branch tracks origin
+        let repo = root.join("proj/.repo");
+        let refs = git
+            .run_in(&repo, &["for-each-ref", "--format=%(refname) %(upstream)"])
+            .unwrap();
+        let refs = String::from_utf8_lossy(&refs.stdout);
+        assert!(refs.contains("refs/remotes/origin/feature"), "{refs}");
+        assert!(
+            refs.contains("refs/heads/trunk refs/remotes/origin/trunk"),
+            "{refs}"
+        );
+    }
+
+    #[test]
+    fn test_clone_refuses_existing_destination() {
+        let dir = setup_origin();
+        let root = dir.path().join("store");
+        fs::create_dir_all(root.join("proj")).unwrap();
+
+        let err = clone_into(&Git::new(), &root, "/nowhere", "proj", false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("already exists"), "{err}");
+        assert!(root.join("proj").exists());
+    }
+
+    #[test]
+    fn test_failed_clone_cleans_up() {
+        let dir = setup_origin();
+        let root = dir.path().join("store");
+
+        let result = clone_into(
+            &Git::new(),
+            &root,
+            dir.path().join("missing").to_str().unwrap(),
+            "proj",
+            false,
+        );
+        assert!(result.is_err());
+        assert!(!root.join("proj").exists());
+    }
+}
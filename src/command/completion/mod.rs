@@ -1,75 +1,251 @@
+use super::shell::resolve_binary_name;
 use super::{Cli, ShellType};
-use anyhow::Result;
+use crate::ui;
+use anyhow::{Context, Result, anyhow, bail};
 use clap::CommandFactory;
 use clap_complete::{Generator, Shell, generate};
-use std::io;
+use clap_complete_nushell::Nushell;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn handle(shell: ShellType) -> Result<()> {
-    let clap_shell = match shell {
-        ShellType::Bash => Shell::Bash,
-        ShellType::Zsh => Shell::Zsh,
-        ShellType::Fish => Shell::Fish,
-    };
+// How the completion scripts query branch names and aliases; the binary name
+// is substituted
+const BRANCH_QUERY: &str = "gwtree ls";
+const ALIAS_QUERY: &str = "gwtree alias";
+
+// PowerShell keeps a single native completer per command, so the static
+// completer is stored in a variable and invoked by the dynamic one.
+const POWERSHELL_STATIC_REGISTRATION: &str =
+    "Register-ArgumentCompleter -Native -CommandName 'gwt' -ScriptBlock {";
+const POWERSHELL_STATIC_COMPLETER: &str = "$global:__gwtStaticCompleter = {";
+
+// The clap-generated bash and zsh completers are renamed to a private
+// function that the dynamic ones fall back to, so the script works without
+// any other completion installed. The dynamic completer takes over the
+// registration.
+const STATIC_FUNCTION: &str = "_gwt() {";
+const PRIVATE_STATIC_FUNCTION: &str = "__gwt_static() {";
+const BASH_STATIC_REGISTRATION: &str = "complete -F _gwt ";
+const BASH_DYNAMIC_REGISTRATION: &str = "complete -F _gwt_custom ";
+// Also run when the file is autoloaded from `fpath` as `_gwt`
+const ZSH_REGISTRATION: &str = r#"if [ "$funcstack[1]" = "_gwt" ]; then
+    _gwt "$@"
+else
+    compdef _gwt gwt
+fi
+"#;
+
+// Nushell completers are attached to extern parameters, and modules cannot see
+// commands defined outside of them, so the branch completer is injected into
+// the generated module and referenced from the `sw` and `run` signatures.
+const NUSHELL_MODULE_START: &str = "module completions {\n";
+const NUSHELL_BRANCH_COMPLETER: &str = r#"
+  # Dynamic completion for gwt sw command (branch names)
+  def "nu-complete gwt branches" [] {
+    ^gwtree ls --raw --cached | lines
+  }
+"#;
+const NUSHELL_BRANCH_PARAMS: &[(&str, &str)] = &[
+    ("export extern \"gwt sw\" [", "branch?: string"),
+    ("export extern \"gwt run\" [", "branch: string"),
+];
 
-    print_completions(clap_shell, &mut Cli::command());
+pub fn handle(
+    shell: ShellType,
+    binary_name: Option<&str>,
+    install: bool,
+    force: bool,
+) -> Result<()> {
+    let binary = resolve_binary_name(binary_name)?;
+    let script = render(shell, &binary)?;
 
-    // Print additional dynamic completion functions for branch suggestions
-    print_dynamic_completions(shell);
+    if !install {
+        print!("{script}");
+        return Ok(());
+    }
 
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Unable to find home directory"))?;
+    let path = install_script(shell, &script, &home, force)?;
+    ui::info!("Completion script written to {}", path.display());
+    if let Some(hint) = install_hint(shell) {
+        ui::info!("{hint}");
+    }
     Ok(())
 }
 
-fn print_completions<G: Generator>(generator: G, cmd: &mut clap::Command) {
-    generate(
-        generator,
-        cmd,
-        cmd.get_name().to_string(),
-        &mut io::stdout(),
+/// Conventional per-user completion file for `shell`, relative to `home`
+fn install_path(shell: ShellType, home: &Path) -> Result<PathBuf> {
+    match shell {
+        ShellType::Bash => Ok(home.join(".local/share/bash-completion/completions/gwt")),
+        ShellType::Zsh => Ok(home.join(".zfunc/_gwt")),
+        ShellType::Fish => Ok(home.join(".config/fish/completions/gwt.fish")),
+        ShellType::PowerShell | ShellType::Nushell => bail!(
+            "There is no standard completion directory for this shell. \
+            Use `gwtree init` in your profile or redirect `gwtree completion` to a file you source."
+        ),
+    }
+}
+
+/// Follow-up step needed before the shell picks up the installed file
+fn install_hint(shell: ShellType) -> Option<&'static str> {
+    match shell {
+        ShellType::Zsh => Some(
+            "Make sure ~/.zfunc is on your fpath, e.g. add `fpath=(~/.zfunc $fpath)` \
+            before `compinit` in ~/.zshrc.",
+        ),
+        ShellType::Bash => {
+            Some("Requires the bash-completion package. Open a new shell to load it.")
+        }
+        _ => None,
+    }
+}
+
+/// Write `script` to the standard location under `home`, creating directories
+/// as needed. An existing file is only replaced with `force`.
+fn install_script(shell: ShellType, script: &str, home: &Path, force: bool) -> Result<PathBuf> {
+    let path = install_path(shell, home)?;
+    if path.exists() && !force {
+        bail!(
+            "'{}' already exists. Use --force to overwrite it.",
+            path.display()
+        );
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+    fs::write(&path, script).with_context(|| format!("Failed to write '{}'", path.display()))?;
+
+    Ok(path)
+}
+
+/// Render the full completion script. Completions are registered for the `gwt`
+/// wrapper function; `binary` is the gwtree executable queried for branch names.
+fn render(shell: ShellType, binary: &str) -> Result<String> {
+    let cmd = &mut Cli::command();
+    let script = match shell {
+        ShellType::Bash => render_completions(Shell::Bash, cmd)?
+            .replacen(STATIC_FUNCTION, PRIVATE_STATIC_FUNCTION, 1)
+            .replace(BASH_STATIC_REGISTRATION, BASH_DYNAMIC_REGISTRATION),
+        ShellType::Zsh => render_completions(Shell::Zsh, cmd)?
+            .replacen(STATIC_FUNCTION, PRIVATE_STATIC_FUNCTION, 1)
+            .replace(ZSH_REGISTRATION, ""),
+        ShellType::Fish => render_completions(Shell::Fish, cmd)?,
+        ShellType::PowerShell => render_completions(Shell::PowerShell, cmd)?
+            .replace(POWERSHELL_STATIC_REGISTRATION, POWERSHELL_STATIC_COMPLETER),
+        ShellType::Nushell => attach_nushell_branch_completer(&render_completions(Nushell, cmd)?),
+    };
+
+    // Append the dynamic completion functions for branch suggestions
+    Ok(format!("{script}{}", dynamic_completions(shell))
+        .replace(BRANCH_QUERY, &format!("{binary} ls"))
+        .replace(ALIAS_QUERY, &format!("{binary} alias")))
+}
+
+fn render_completions<G: Generator>(generator: G, cmd: &mut clap::Command) -> Result<String> {
+    let mut buf = Vec::new();
+    generate(generator, cmd, cmd.get_name().to_string(), &mut buf);
+    Ok(String::from_utf8(buf)?)
+}
+
+fn attach_nushell_branch_completer(script: &str) -> String {
+    let mut script = script.replacen(
+        NUSHELL_MODULE_START,
+        &format!("{NUSHELL_MODULE_START}{NUSHELL_BRANCH_COMPLETER}"),
+        1,
     );
+
+    for (signature, param) in NUSHELL_BRANCH_PARAMS {
+        if let Some(end) = script.find(signature).and_then(|start| {
+            script[start..]
+                .find(param)
+                .map(|offset| start + offset + param.len())
+        }) {
+            script.insert_str(end, "@\"nu-complete gwt branches\"");
+        }
+    }
+
+    script
 }
 
-fn print_dynamic_completions(shell: ShellType) {
+fn dynamic_completions(shell: ShellType) -> &'static str {
     match shell {
-        ShellType::Bash => print!(
+        ShellType::Bash => {
             r#"
 # Dynamic completion for gwt sw command (branch names)
-_gwt_sw_completions() {{
+_gwt_sw_completions() {
     local branches
-    branches=$(gwtree ls --raw 2>/dev/null)
-    COMPREPLY=($(compgen -W "$branches" -- "${{COMP_WORDS[COMP_CWORD]}}"))
-}}
+    branches=$(gwtree ls --raw --cached 2>/dev/null)
+    COMPREPLY=($(compgen -W "$branches" -- "${COMP_WORDS[COMP_CWORD]}"))
+}
+
+# Dynamic completion for gwt rm command (branch names, main worktree excluded)
+_gwt_rm_completions() {
+    local branches
+    branches=$(gwtree ls --raw --cached --no-main 2>/dev/null)
+    COMPREPLY=($(compgen -W "$branches" -- "${COMP_WORDS[COMP_CWORD]}"))
+}
 
-# Override the default completion for 'sw' subcommand
-_gwt_custom() {{
+# Override the default completion for the 'sw' and 'run' subcommands
+_gwt_custom() {
     local cur prev words cword
     _init_completion || return
 
-    if [[ ${{cword}} -ge 2 && "${{words[1]}}" == "sw" ]]; then
+    if [[ ${cword} -ge 2 && "${words[1]}" == "sw" ]]; then
         # Complete branch names for 'gwt sw <branch>'
         _gwt_sw_completions
         return
     fi
 
-    # Fall back to default gwtree completions
-    _gwtree "$@"
-}}
+    if [[ ${cword} -eq 2 && "${words[1]}" == "run" ]]; then
+        # Complete the branch of 'gwt run <branch> -- <cmd>'
+        _gwt_sw_completions
+        return
+    fi
 
-complete -F _gwt_custom gwt
+    if [[ ${cword} -ge 2 && ( "${words[1]}" == "rm" || "${words[1]}" == "remove" ) ]]; then
+        # Complete branch names for 'gwt rm <branch>'
+        _gwt_rm_completions
+        return
+    fi
+
+    # Fall back to the static completions
+    __gwt_static "$@"
+
+    if [[ ${cword} -eq 1 ]]; then
+        # Aliases of the config
+        COMPREPLY+=($(compgen -W "$(gwtree alias list --raw 2>/dev/null)" -- "${COMP_WORDS[COMP_CWORD]}"))
+    fi
+}
 "#
-        ),
-        ShellType::Zsh => print!(
+        }
+        ShellType::Zsh => {
             r#"
-# Dynamic completion for gwt sw command (branch names)
-_gwt_branches() {{
-    local branches
-    branches=(${{(f)"$(gwtree ls --raw 2>/dev/null)"}})
+# Complete branch names, described by their worktree path.
+# Extra arguments are passed to `ls --raw --cached`.
+_gwt_describe_branches() {
+    local -a branches
+    local branch wt_path
+    while IFS=$'\t' read -r branch wt_path; do
+        # git forbids ':' in branch names, so it can't clash with the separator
+        branches+=("${branch}:${wt_path}")
+    done < <(gwtree ls --raw --cached --with-paths "$@" 2>/dev/null)
     _describe 'branch' branches
-}}
+}
+
+# Dynamic completion for gwt sw command (branch names)
+_gwt_branches() {
+    _gwt_describe_branches
+}
 
-# Custom completion for gwt wrapper function
-compdef _gwt_wrapper gwt
+# Dynamic completion for gwt rm command (branch names, main worktree excluded)
+_gwt_removable_branches() {
+    _gwt_describe_branches --no-main
+}
 
-_gwt_wrapper() {{
+# Completion for the gwt wrapper function
+_gwt() {
     local line state
 
     _arguments -C \
@@ -78,33 +254,79 @@ _gwt_wrapper() {{
 
     case $state in
         command)
-            _gwtree
+            __gwt_static
+            local -a aliases
+            aliases=(${(f)"$(gwtree alias list --raw 2>/dev/null)"})
+            _describe 'alias' aliases
             ;;
         args)
             case $line[1] in
-                sw|switch)
+                sw|switch|run)
                     _gwt_branches
                     ;;
+                rm|remove)
+                    _gwt_removable_branches
+                    ;;
                 *)
-                    _gwtree
+                    __gwt_static
                     ;;
             esac
             ;;
     esac
-}}
+}
+
+if [ "$funcstack[1]" = "_gwt" ]; then
+    _gwt "$@"
+else
+    compdef _gwt gwt
+fi
 "#
-        ),
-        ShellType::Fish => print!(
+        }
+        ShellType::Fish => {
             r#"
 # Dynamic completion for gwt sw command (branch names)
 function __gwt_branches
-    gwtree ls --raw 2>/dev/null
+    gwtree ls --raw --cached 2>/dev/null
+end
+
+function __gwt_removable_branches
+    gwtree ls --raw --cached --no-main 2>/dev/null
 end
 
-# Complete branch names after 'gwt sw'
-complete -c gwt -n '__fish_seen_subcommand_from sw switch' -a '(__gwt_branches)' -d 'branch'
+# Complete branch names after 'gwt sw', 'gwt run' and 'gwt rm' (never the main worktree's branch for rm)
+complete -c gwt -n '__fish_seen_subcommand_from sw switch run' -a '(__gwt_branches)' -d 'branch'
+complete -c gwt -n '__fish_seen_subcommand_from rm remove' -a '(__gwt_removable_branches)' -d 'branch'
+
+# Complete the aliases of the config as commands
+complete -c gwt -n '__fish_use_subcommand' -a '(gwtree alias list --raw 2>/dev/null)' -d 'alias'
 "#
-        ),
+        }
+        ShellType::PowerShell => {
+            r#"
+# Dynamic completion for gwt sw command (branch names)
+Register-ArgumentCompleter -Native -CommandName 'gwt' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $elements = $commandAst.CommandElements
+    if ($elements.Count -ge 2 -and
+        @('sw', 'switch', 'run') -contains $elements[1].ToString() -and
+        $elements[1].Extent.EndOffset -lt $cursorPosition -and
+        -not $wordToComplete.StartsWith('-')) {
+        gwtree ls --raw --cached 2>$null |
+            Where-Object { $_ -like "$wordToComplete*" } |
+            ForEach-Object {
+                [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+            }
+        return
+    }
+
+    # Fall back to the static gwt completions
+    & $global:__gwtStaticCompleter $wordToComplete $commandAst $cursorPosition
+}
+"#
+        }
+        // The branch completer is injected into the generated module instead
+        ShellType::Nushell => "",
     }
 }
 
@@ -115,16 +337,222 @@ mod tests {
     #[test]
     fn test_handle_bash() {
         // Just ensure it doesn't panic
-        assert!(handle(ShellType::Bash).is_ok());
+        assert!(handle(ShellType::Bash, Some("gwtree"), false, false).is_ok());
     }
 
     #[test]
     fn test_handle_zsh() {
-        assert!(handle(ShellType::Zsh).is_ok());
+        assert!(handle(ShellType::Zsh, Some("gwtree"), false, false).is_ok());
     }
 
     #[test]
     fn test_handle_fish() {
-        assert!(handle(ShellType::Fish).is_ok());
+        assert!(handle(ShellType::Fish, Some("gwtree"), false, false).is_ok());
+    }
+
+    #[test]
+    fn test_handle_powershell() {
+        assert!(handle(ShellType::PowerShell, Some("gwtree"), false, false).is_ok());
+    }
+
+    #[test]
+    fn test_handle_nushell() {
+        assert!(handle(ShellType::Nushell, Some("gwtree"), false, false).is_ok());
+    }
+
+    #[test]
+    fn test_nushell_branch_completer_is_attached() {
+        let script = render_completions(Nushell, &mut Cli::command()).unwrap();
+        let script = attach_nushell_branch_completer(&script);
+        assert!(script.contains("def \"nu-complete gwt branches\" []"));
+        assert!(script.contains("^gwtree ls --raw --cached | lines"));
+        assert!(script.contains("branch?: string@\"nu-complete gwt branches\""));
+        assert!(script.contains("branch: string@\"nu-complete gwt branches\""));
+
+        // The completer must be defined inside the module that references it
+        let module = script.find(NUSHELL_MODULE_START).unwrap();
+        let completer = script.find("def \"nu-complete gwt branches\"").unwrap();
+        assert!(module < completer);
+    }
+
+    #[test]
+    fn test_powershell_static_completer_is_renamed() {
+        let script = render_completions(Shell::PowerShell, &mut Cli::command()).unwrap();
+        assert!(script.contains(POWERSHELL_STATIC_REGISTRATION));
+    }
+
+    #[test]
+    fn test_powershell_dynamic_completions() {
+        let s = dynamic_completions(ShellType::PowerShell);
+        assert!(s.contains("Register-ArgumentCompleter -Native -CommandName 'gwt'"));
+        assert!(s.contains("gwtree ls --raw --cached"));
+        assert!(s.contains("@('sw', 'switch', 'run')"));
+        assert!(s.contains("& $global:__gwtStaticCompleter"));
+    }
+
+    #[test]
+    fn test_render_substitutes_binary_name() {
+        for shell in [
+            ShellType::Bash,
+            ShellType::Zsh,
+            ShellType::Fish,
+            ShellType::PowerShell,
+            ShellType::Nushell,
+        ] {
+            let script = render(shell, "gwt-bin").unwrap();
+            assert!(script.contains("gwt-bin ls --raw --cached"));
+            assert!(!script.contains("gwtree ls"));
+            assert!(!script.contains("gwtree alias"));
+        }
+    }
+
+    #[test]
+    fn test_dynamic_completions_cover_rm() {
+        let bash = dynamic_completions(ShellType::Bash);
+        assert!(bash.contains("_gwt_sw_completions"));
+        assert!(bash.contains(r#""${words[1]}" == "rm" || "${words[1]}" == "remove""#));
+        assert!(bash.contains("branches=$(gwtree ls --raw --cached --no-main 2>/dev/null)"));
+
+        let zsh = dynamic_completions(ShellType::Zsh);
+        assert!(zsh.contains("sw|switch|run)\n                    _gwt_branches"));
+        assert!(zsh.contains("rm|remove)\n                    _gwt_removable_branches"));
+        assert!(zsh.contains("_gwt_describe_branches --no-main"));
+
+        let fish = dynamic_completions(ShellType::Fish);
+        assert!(fish.contains("'__fish_seen_subcommand_from sw switch run' -a '(__gwt_branches)'"));
+        assert!(
+            fish.contains(
+                "'__fish_seen_subcommand_from rm remove' -a '(__gwt_removable_branches)'"
+            )
+        );
+        assert!(fish.contains("gwtree ls --raw --cached --no-main"));
+    }
+
+    #[test]
+    fn test_bash_and_zsh_scripts_are_self_contained() {
+        for shell in [ShellType::Bash, ShellType::Zsh] {
+            let script = render(shell, "gwtree").unwrap();
+            assert!(!script.contains("_gwtree"));
+            assert_eq!(script.matches(PRIVATE_STATIC_FUNCTION).count(), 1);
+        }
+
+        let bash = render(ShellType::Bash, "gwtree").unwrap();
+        assert!(!bash.contains(STATIC_FUNCTION));
+        assert!(!bash.contains(BASH_STATIC_REGISTRATION));
+        assert!(bash.contains("    __gwt_static \"$@\"\n"));
+        // The dynamic completer replaces the static one as the only `_gwt`
+        let zsh = render(ShellType::Zsh, "gwtree").unwrap();
+        assert_eq!(zsh.matches(STATIC_FUNCTION).count(), 1);
+        assert!(zsh.ends_with(ZSH_REGISTRATION));
+        assert!(zsh.contains("            __gwt_static\n"));
+    }
+
+    /// Whether `shell` can be run here; tests needing it are skipped otherwise
+    fn shell_available(shell: &str) -> bool {
+        std::process::Command::new(shell)
+            .arg("--version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    #[test]
+    fn test_bash_and_zsh_scripts_parse() {
+        for (shell, name) in [(ShellType::Bash, "bash"), (ShellType::Zsh, "zsh")] {
+            if !shell_available(name) {
+                eprintln!("{name} not installed, skipping");
+                continue;
+            }
+            let dir = tempfile::tempdir().unwrap();
+            let file = dir.path().join("gwt");
+            fs::write(&file, render(shell, "gwtree").unwrap()).unwrap();
+            let output = std::process::Command::new(name)
+                .arg("-n")
+                .arg(&file)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{name}: {output:?}");
+        }
+    }
+
+    #[test]
+    fn test_bash_script_sources_on_its_own() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("gwt");
+        fs::write(&file, render(ShellType::Bash, "gwtree").unwrap()).unwrap();
+        let output = std::process::Command::new("bash")
+            .arg("--norc")
+            .arg("-c")
+            .arg(format!(
+                "source '{}' && complete -p gwt && declare -F __gwt_static _gwt_custom",
+                file.display()
+            ))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("-F _gwt_custom gwt"), "{stdout}");
+        assert!(stdout.contains("__gwt_static\n_gwt_custom"), "{stdout}");
+    }
+
+    #[test]
+    fn test_dynamic_completions_offer_aliases() {
+        for shell in [ShellType::Bash, ShellType::Zsh, ShellType::Fish] {
+            let script = render(shell, "gwtree").unwrap();
+            assert!(script.contains("gwtree alias list --raw 2>/dev/null"));
+        }
+    }
+
+    #[test]
+    fn test_zsh_completions_describe_branches_with_paths() {
+        let zsh = dynamic_completions(ShellType::Zsh);
+        assert!(zsh.contains("< <(gwtree ls --raw --cached --with-paths \"$@\" 2>/dev/null)"));
+        assert!(zsh.contains(r#"branches+=("${branch}:${wt_path}")"#));
+        assert!(zsh.contains("_describe 'branch' branches"));
+
+        // Bash and fish keep using the plain format
+        assert!(!dynamic_completions(ShellType::Bash).contains("--with-paths"));
+        assert!(!dynamic_completions(ShellType::Fish).contains("--with-paths"));
+    }
+
+    #[test]
+    fn test_install_script_writes_standard_locations() {
+        let home = tempfile::tempdir().unwrap();
+        for (shell, expected) in [
+            (
+                ShellType::Bash,
+                ".local/share/bash-completion/completions/gwt",
+            ),
+            (ShellType::Zsh, ".zfunc/_gwt"),
+            (ShellType::Fish, ".config/fish/completions/gwt.fish"),
+        ] {
+            let path = install_script(shell, "script", home.path(), false).unwrap();
+            assert_eq!(path, home.path().join(expected));
+            assert_eq!(fs::read_to_string(&path).unwrap(), "script");
+        }
+    }
+
+    #[test]
+    fn test_install_script_refuses_to_overwrite_without_force() {
+        let home = tempfile::tempdir().unwrap();
+        install_script(ShellType::Zsh, "old", home.path(), false).unwrap();
+
+        let err = install_script(ShellType::Zsh, "new", home.path(), false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        let path = home.path().join(".zfunc/_gwt");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+
+        install_script(ShellType::Zsh, "new", home.path(), true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_install_script_unsupported_shell() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(install_script(ShellType::PowerShell, "script", home.path(), false).is_err());
+        assert!(install_script(ShellType::Nushell, "script", home.path(), false).is_err());
     }
 }
@@ -1,21 +1,56 @@
 use anyhow::{Result, anyhow};
 use clap::Subcommand;
-use config::Config;
+use config::{Config, ConfigData};
 use console::Style;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
 
 use crate::config;
 
 #[derive(Subcommand)]
 pub enum ConfigCommands {
     /// View the current configuration file path and contents
-    View,
+    ///
+    /// Prints where the config file is, `~/.gwt/config.toml` by default, followed by its
+    /// contents as written. `--effective` prints the configuration gwt actually uses instead,
+    /// parsed and normalized, and `--json` prints it for scripts with the source of every value.
+    View {
+        /// Print the configuration gwt uses rather than the file as written
+        #[arg(long, conflicts_with = "file")]
+        effective: bool,
+
+        /// Print the file as written (the default, except with --json)
+        #[arg(long)]
+        file: bool,
+
+        /// Print JSON without styling: the effective values with their sources, or with
+        /// --file the raw contents
+        #[arg(long)]
+        json: bool,
+    },
     /// Reset the configuration
+    ///
+    /// Asks for the worktree root and writes a new config file. When the root changes and the
+    /// old one still holds worktrees, it suggests `gwt migrate` to move them.
     Setup,
 }
 
 pub fn handle(config: &Config, cmd: &ConfigCommands) -> Result<()> {
     match cmd {
-        ConfigCommands::View => view_config(config),
+        ConfigCommands::View {
+            effective,
+            file,
+            json,
+        } => {
+            let effective = *effective || (*json && !*file);
+            match (effective, *json) {
+                (false, false) => view_config(config),
+                (false, true) => view_file_json(config),
+                (true, false) => view_effective(config),
+                (true, true) => view_effective_json(config),
+            }
+        }
         ConfigCommands::Setup => config::setup(),
     }
 }
@@ -43,6 +78,88 @@ fn view_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// A configured value and where it came from. Only the config file sets
+/// values so far; it is named by path so other sources can be told apart.
+#[derive(Debug, PartialEq, Serialize)]
+struct Sourced {
+    value: serde_json::Value,
+    source: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct EffectiveView {
+    config_path: String,
+    /// Options left out have their default values
+    values: BTreeMap<String, Sourced>,
+}
+
+fn loaded(config: &Config) -> Result<(&ConfigData, &str)> {
+    let data = config
+        .data()
+        .ok_or_else(|| anyhow!("unexpected error: invalid config"))?;
+    let path = config
+        .config_path()
+        .ok_or_else(|| anyhow!("unexpected error: invalid config"))?;
+    Ok((data, path))
+}
+
+fn effective_view(data: &ConfigData, path: &str) -> Result<EffectiveView> {
+    let serde_json::Value::Object(values) = serde_json::to_value(data)? else {
+        return Err(anyhow!("unexpected error: config is not a table"));
+    };
+    let values = values
+        .into_iter()
+        .map(|(key, value)| {
+            let source = path.to_string();
+            (key, Sourced { value, source })
+        })
+        .collect();
+    Ok(EffectiveView {
+        config_path: path.to_string(),
+        values,
+    })
+}
+
+fn view_effective(config: &Config) -> Result<()> {
+    let (data, path) = loaded(config)?;
+    let label_style = Style::new().cyan().bright();
+
+    println!(
+        "{} {}",
+        label_style.apply_to("Config file path:"),
+        Style::new().yellow().apply_to(path)
+    );
+    println!("\n{}", label_style.apply_to("Effective configuration:"));
+    let contents = toml::to_string_pretty(data)
+        .map_err(|e| anyhow!("failed marshalling config data in toml {e}"))?;
+    println!(
+        "{}",
+        Style::new().white().bright().apply_to(contents.trim_end())
+    );
+    println!(
+        "\n{}",
+        Style::new()
+            .dim()
+            .apply_to("Options not listed have their default values.")
+    );
+    Ok(())
+}
+
+fn view_effective_json(config: &Config) -> Result<()> {
+    let (data, path) = loaded(config)?;
+    let view = effective_view(data, path)?;
+    println!("{}", serde_json::to_string_pretty(&view)?);
+    Ok(())
+}
+
+fn view_file_json(config: &Config) -> Result<()> {
+    let (_, path) = loaded(config)?;
+    let contents = std::fs::read_to_string(Path::new(path))?;
+    let view = serde_json::json!({ "config_path": path, "contents": contents });
+    println!("{}", serde_json::to_string_pretty(&view)?);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,12 +204,38 @@ mod tests {
     #[test]
     fn test_config_commands_view_variant() {
         // Test that ConfigCommands::View can be created and matched
-        let cmd = ConfigCommands::View;
-        if let ConfigCommands::View = cmd {
+        let cmd = ConfigCommands::View {
+            effective: false,
+            file: false,
+            json: false,
+        };
+        if let ConfigCommands::View { .. } = cmd {
             // This ensures the variant exists and can be matched
         }
     }
 
+    #[test]
+    fn test_effective_view() {
+        let data: ConfigData = toml::from_str(
+            "worktree_root = '/store'\nfetch_on_create = true\n\n[list]\nmax_branch_width = 0\n",
+        )
+        .unwrap();
+        let view = effective_view(&data, "/home/me/.gwt/config.toml").unwrap();
+
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["config_path"], "/home/me/.gwt/config.toml");
+        assert_eq!(json["values"]["worktree_root"]["value"], "/store");
+        assert_eq!(
+            json["values"]["worktree_root"]["source"],
+            "/home/me/.gwt/config.toml"
+        );
+        assert_eq!(json["values"]["fetch_on_create"]["value"], true);
+        assert_eq!(json["values"]["list"]["value"]["max_branch_width"], 0);
+        // Defaults aren't listed
+        let keys: Vec<&String> = view.values.keys().collect();
+        assert_eq!(keys, ["fetch_on_create", "list", "worktree_root"]);
+    }
+
     #[test]
     fn test_execute_config_view_with_invalid_toml() {
         // Test that we can read a file even if it has invalid TOML
@@ -0,0 +1,266 @@
+use super::Cli;
+use super::version::BUILD;
+use crate::ui;
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command, CommandFactory, ValueEnum};
+use clap_mangen::Man;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DocFormat {
+    /// `gwt.1` and a `gwt-<command>.1` page per subcommand
+    Man,
+    /// `gwt.md` and a `gwt-<command>.md` page per subcommand
+    Markdown,
+}
+
+/// Write the reference pages of every visible command to `out`, generated
+/// from the clap definitions so they can't drift from `--help`
+pub fn handle(format: DocFormat, out: &Path) -> Result<()> {
+    fs::create_dir_all(out).with_context(|| format!("Failed to create '{}'", out.display()))?;
+
+    let mut cli = Cli::command().disable_help_subcommand(true);
+    cli.build();
+    let count = match format {
+        DocFormat::Man => {
+            let pages = pages(&cli);
+            for (_, cmd) in &pages {
+                // Subcommands have no version of their own for the page footer
+                let mut man = Man::new((*cmd).clone().version(BUILD.version));
+                if BUILD.build_date != "unknown" {
+                    man = man.date(BUILD.build_date);
+                }
+                man.generate_to(out)
+                    .with_context(|| format!("Failed to write man pages to '{}'", out.display()))?;
+            }
+            pages.len()
+        }
+        DocFormat::Markdown => {
+            let pages = pages(&cli);
+            for (name, cmd) in &pages {
+                let path = out.join(format!("{}.md", name));
+                fs::write(&path, markdown(name, cmd))
+                    .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            }
+            pages.len()
+        }
+    };
+    ui::info!("Wrote {} pages to '{}'", count, out.display());
+    Ok(())
+}
+
+/// `cmd` and its visible subcommands, parents first, each with its page name,
+/// e.g. `gwt-config-view`
+fn pages(cmd: &Command) -> Vec<(String, &Command)> {
+    fn walk<'a>(name: String, cmd: &'a Command, pages: &mut Vec<(String, &'a Command)>) {
+        for sub in visible_subcommands(cmd) {
+            pages.push((format!("{}-{}", name, sub.get_name()), sub));
+            walk(format!("{}-{}", name, sub.get_name()), sub, pages);
+        }
+    }
+
+    let mut pages = vec![(cmd.get_name().to_string(), cmd)];
+    walk(cmd.get_name().to_string(), cmd, &mut pages);
+    pages
+}
+
+fn visible_subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands().filter(|sub| !sub.is_hide_set())
+}
+
+/// Markdown reference page of `cmd`: description, usage, arguments, options
+/// and subcommands, plus any text clap shows after the help
+fn markdown(name: &str, cmd: &Command) -> String {
+    let title = name.replace('-', " ");
+    let mut page = format!("# {}\n\n", title);
+
+    if let Some(about) = cmd.get_long_about().or(cmd.get_about()) {
+        writeln!(page, "{}\n", about).unwrap();
+    }
+
+    let usage = cmd.clone().render_usage().to_string();
+    let usage = usage.strip_prefix("Usage: ").unwrap_or(&usage);
+    writeln!(page, "## Usage\n\n```\n{}\n```\n", usage).unwrap();
+
+    let args: Vec<&Arg> = cmd.get_arguments().filter(|a| !a.is_hide_set()).collect();
+    let (positionals, options): (Vec<&Arg>, Vec<&Arg>) =
+        args.into_iter().partition(|a| a.is_positional());
+    for (heading, args) in [("Arguments", positionals), ("Options", options)] {
+        if args.is_empty() {
+            continue;
+        }
+        writeln!(page, "## {}\n", heading).unwrap();
+        for arg in args {
+            writeln!(page, "{}", arg_item(arg)).unwrap();
+        }
+        page.push('\n');
+    }
+
+    let subcommands: Vec<&Command> = visible_subcommands(cmd).collect();
+    if !subcommands.is_empty() {
+        page.push_str("## Commands\n\n| Command | Description |\n|---------|-------------|\n");
+        for sub in subcommands {
+            writeln!(
+                page,
+                "| [`{} {}`]({}-{}.md) | {} |",
+                title,
+                sub.get_name(),
+                name,
+                sub.get_name(),
+                sub.get_about().map(|a| a.to_string()).unwrap_or_default()
+            )
+            .unwrap();
+        }
+        page.push('\n');
+    }
+
+    if let Some(after) = cmd.get_after_long_help().or(cmd.get_after_help()) {
+        writeln!(page, "{}\n", after).unwrap();
+    }
+
+    format!("{}\n", page.trim_end())
+}
+
+/// List item for one argument, e.g. ``- `-r`, `--remote <REMOTE>`: ...``
+fn arg_item(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .map(|names| {
+            names
+                .iter()
+                .map(|n| format!("<{}>", n))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_else(|| format!("<{}>", arg.get_id().as_str().to_uppercase()));
+    let takes_value = !matches!(
+        arg.get_action(),
+        ArgAction::SetTrue
+            | ArgAction::SetFalse
+            | ArgAction::Count
+            | ArgAction::Help
+            | ArgAction::HelpShort
+            | ArgAction::HelpLong
+            | ArgAction::Version
+    );
+
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("`-{}`", short));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(if takes_value {
+            format!("`--{} {}`", long, value)
+        } else {
+            format!("`--{}`", long)
+        });
+    }
+    if names.is_empty() {
+        names.push(format!("`{}`", value));
+    }
+
+    let mut item = format!("- {}", names.join(", "));
+    if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+        write!(item, ": {}", help).unwrap();
+    }
+    let defaults: Vec<_> = arg
+        .get_default_values()
+        .iter()
+        .map(|v| v.to_string_lossy())
+        .collect();
+    if !defaults.is_empty() && takes_value {
+        write!(item, " (default: `{}`)", defaults.join(", ")).unwrap();
+    }
+    let possible: Vec<_> = arg
+        .get_possible_values()
+        .into_iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| format!("`{}`", v.get_name()))
+        .collect();
+    if !possible.is_empty() && takes_value {
+        write!(item, " (possible values: {})", possible.join(", ")).unwrap();
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli() -> Command {
+        let mut cli = Cli::command().disable_help_subcommand(true);
+        cli.build();
+        cli
+    }
+
+    #[test]
+    fn test_pages_skip_hidden_commands() {
+        let cli = cli();
+        let names: Vec<_> = pages(&cli).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names[0], "gwt");
+        assert!(names.contains(&"gwt-sw".to_string()));
+        assert!(names.contains(&"gwt-config-view".to_string()));
+        assert!(!names.iter().any(|n| n.contains("gen-docs")));
+    }
+
+    #[test]
+    fn test_every_command_has_a_long_description() {
+        let cli = cli();
+        for (name, cmd) in pages(&cli) {
+            let about = cmd.get_about().map(|a| a.to_string()).unwrap_or_default();
+            let long_about = cmd.get_long_about().map(|a| a.to_string());
+            assert!(
+                long_about.is_some_and(|long| long.len() > about.len()),
+                "{name} has no long description"
+            );
+        }
+    }
+
+    #[test]
+    fn test_markdown_page() {
+        let cli = cli();
+        let sw = cli.find_subcommand("sw").unwrap();
+        let page = markdown("gwt-sw", sw);
+
+        assert!(page.starts_with("# gwt sw\n\nSwitch to an existing worktree"));
+        assert!(page.contains("## Usage\n\n```\ngwt sw "));
+        assert!(page.contains("## Arguments\n\n- `<BRANCH>`: Branch name to switch to\n"));
+        assert!(page.contains("- `-b`, `--create-branch`: Create a new branch\n"));
+        assert!(page.contains("- `--track <REMOTE_BRANCH>`: "));
+        // Hidden flags stay out of the docs
+        assert!(!markdown("gwt-ls", cli.find_subcommand("ls").unwrap()).contains("--raw"));
+    }
+
+    #[test]
+    fn test_markdown_defaults_and_values() {
+        let cli = cli();
+        let page = markdown("gwt-sync", cli.find_subcommand("sync").unwrap());
+        assert!(page.contains(
+            "`-r`, `--remote <REMOTE>`: Remote to fetch and read branches from (default: `origin`)"
+        ));
+
+        let page = markdown("gwt-completion", cli.find_subcommand("completion").unwrap());
+        assert!(page.contains("(possible values: `bash`, `zsh`"), "{page}");
+    }
+
+    #[test]
+    fn test_root_page_links_subcommands() {
+        let cli = cli();
+        let page = markdown("gwt", &cli);
+        assert!(page.contains("| [`gwt sw`](gwt-sw.md) | Switch to an existing worktree"));
+        assert!(!page.contains("gen-docs"));
+    }
+
+    #[test]
+    fn test_man_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        handle(DocFormat::Man, dir.path()).unwrap();
+        let root = fs::read_to_string(dir.path().join("gwt.1")).unwrap();
+        assert!(root.contains("gwt keeps one worktree per branch"));
+        let sw = fs::read_to_string(dir.path().join("gwt-sw.1")).unwrap();
+        assert!(sw.contains("create\\-branch"), "{sw}");
+        assert!(!dir.path().join("gwt-gen-docs.1").exists());
+    }
+}
@@ -0,0 +1,409 @@
+use super::version::BUILD;
+use crate::config::{self, ConfigData};
+use crate::utility::{Git, GitVersion, StoreOwner, Worktree, scan_store};
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Oldest git release providing everything gwt relies on (`git worktree move`)
+const MIN_GIT_VERSION: (u32, u32) = (2, 17);
+
+/// First git release whose `worktree list --porcelain` reports lock reasons
+const PORCELAIN_GIT_VERSION: (u32, u32) = (2, 31);
+
+/// Set by the code `gwtree init` generates, so its presence means the wrapper is loaded
+const WRAPPER_ENV_VAR: &str = "GWT_SHELL_WRAPPER";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of a single check
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    message: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+pub fn handle(git: &Git, json: bool) -> Result<()> {
+    let (config_check, data) = check_config_file(&config::file_path()?);
+    // Not a check as such, but bug reports with `gwt doctor` output should say
+    // which gwt they're about
+    let mut checks = vec![Check::new("gwt", Status::Pass, BUILD.summary())];
+    checks.extend([config_check, check_worktree_root(data.as_ref())]);
+    checks.push(check_git_version(git));
+
+    if let Some(root) = data
+        .as_ref()
+        .map(|d| &d.worktree_root)
+        .filter(|r| r.is_dir())
+    {
+        // Outside a repository every worktree is someone else's, which is fine
+        // for finding orphans
+        let common_dir = git.git_common_dir().unwrap_or_default();
+        checks.push(check_store_orphans(root, &common_dir));
+    }
+    if let Ok(worktrees) = git.list_worktrees() {
+        checks.push(check_missing_worktrees(&worktrees));
+    }
+    checks.push(check_shell_wrapper(|name| std::env::var(name).ok()));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        for check in &checks {
+            let label = match check.status {
+                Status::Pass => style("pass").green(),
+                Status::Warn => style("warn").yellow(),
+                Status::Fail => style("fail").red(),
+            };
+            println!("[{}] {}: {}", label, check.name, check.message);
+        }
+    }
+
+    if checks.iter().any(|c| c.status == Status::Fail) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The config file exists and parses
+fn check_config_file(path: &Path) -> (Check, Option<ConfigData>) {
+    const NAME: &str = "config file";
+
+    if !path.exists() {
+        let check = Check::new(
+            NAME,
+            Status::Fail,
+            format!("{} not found; run `gwtree config setup`", path.display()),
+        );
+        return (check, None);
+    }
+
+    match config::read(path) {
+        Ok(data) => (
+            Check::new(NAME, Status::Pass, path.display().to_string()),
+            Some(data),
+        ),
+        Err(e) => (
+            Check::new(
+                NAME,
+                Status::Fail,
+                format!("{} is invalid: {}", path.display(), e),
+            ),
+            None,
+        ),
+    }
+}
+
+/// `worktree_root` exists, is a directory and is writable
+fn check_worktree_root(data: Option<&ConfigData>) -> Check {
+    const NAME: &str = "worktree root";
+
+    let Some(root) = data.map(|d| &d.worktree_root) else {
+        return Check::new(
+            NAME,
+            Status::Fail,
+            "unknown, the config file couldn't be read",
+        );
+    };
+
+    if !root.exists() {
+        return Check::new(
+            NAME,
+            Status::Fail,
+            format!("{} does not exist", root.display()),
+        );
+    }
+    if !root.is_dir() {
+        return Check::new(
+            NAME,
+            Status::Fail,
+            format!("{} is not a directory", root.display()),
+        );
+    }
+
+    // Permission bits don't tell the whole story (ACLs, read-only mounts)
+    let probe = root.join(format!(".gwt-doctor-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Check::new(NAME, Status::Pass, root.display().to_string())
+        }
+        Err(e) => Check::new(
+            NAME,
+            Status::Fail,
+            format!("{} is not writable: {}", root.display(), e),
+        ),
+    }
+}
+
+/// The git executable runs and is recent enough
+fn check_git_version(git: &Git) -> Check {
+    match git.version() {
+        Ok(version) => check_version(&version),
+        Err(e) => Check::new("git", Status::Fail, format!("can't run git: {}", e)),
+    }
+}
+
+fn check_version(version: &GitVersion) -> Check {
+    const NAME: &str = "git";
+
+    match version.number {
+        Some(number) if number < MIN_GIT_VERSION => Check::new(
+            NAME,
+            Status::Fail,
+            format!(
+                "{} is too old, gwt needs {}.{} or newer",
+                version.raw, MIN_GIT_VERSION.0, MIN_GIT_VERSION.1
+            ),
+        ),
+        Some(number) if number < PORCELAIN_GIT_VERSION => Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "{} works, but lock reasons need {}.{} or newer",
+                version.raw, PORCELAIN_GIT_VERSION.0, PORCELAIN_GIT_VERSION.1
+            ),
+        ),
+        Some(_) => Check::new(NAME, Status::Pass, version.raw.as_str()),
+        None => Check::new(
+            NAME,
+            Status::Warn,
+            format!("can't tell the version from '{}'", version.raw),
+        ),
+    }
+}
+
+/// Directories under the worktree root that belong to no repository
+fn check_store_orphans(root: &Path, current_common_dir: &Path) -> Check {
+    const NAME: &str = "worktree store";
+
+    let entries = match scan_store(root, current_common_dir) {
+        Ok(entries) => entries,
+        Err(e) => return Check::new(NAME, Status::Fail, e.to_string()),
+    };
+
+    let orphans: Vec<String> = entries
+        .iter()
+        .filter(|e| e.owner == StoreOwner::Orphaned)
+        .map(|e| e.path.display().to_string())
+        .collect();
+
+    if orphans.is_empty() {
+        Check::new(
+            NAME,
+            Status::Pass,
+            format!("{} worktree directories, none orphaned", entries.len()),
+        )
+    } else {
+        Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "{} orphaned directories not used by any repository: {}",
+                orphans.len(),
+                orphans.join(", ")
+            ),
+        )
+    }
+}
+
+/// Registered worktrees whose directory is gone
+fn check_missing_worktrees<'a>(worktrees: impl IntoIterator<Item = &'a Worktree>) -> Check {
+    const NAME: &str = "worktrees";
+
+    let mut count = 0;
+    let missing: Vec<String> = worktrees
+        .into_iter()
+        .inspect(|_| count += 1)
+        .filter(|wt| !wt.path().exists())
+        .map(|wt| wt.path().display().to_string())
+        .collect();
+
+    if missing.is_empty() {
+        Check::new(
+            NAME,
+            Status::Pass,
+            format!("{} registered, all present", count),
+        )
+    } else {
+        Check::new(
+            NAME,
+            Status::Warn,
+            format!(
+                "{} directories missing, run `git worktree prune` to clean up: {}",
+                missing.len(),
+                missing.join(", ")
+            ),
+        )
+    }
+}
+
+/// Heuristic: the code generated by `gwtree init` exports `GWT_SHELL_WRAPPER`
+fn check_shell_wrapper(lookup: impl Fn(&str) -> Option<String>) -> Check {
+    const NAME: &str = "shell wrapper";
+
+    if lookup(WRAPPER_ENV_VAR).is_some_and(|v| !v.is_empty()) {
+        Check::new(NAME, Status::Pass, "installed")
+    } else {
+        Check::new(
+            NAME,
+            Status::Warn,
+            "not detected; without it `gwt sw` can't change directory. See `gwtree init`.",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    #[test]
+    fn test_check_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let (check, data) = check_config_file(&path);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.message.contains("not found"));
+        assert!(data.is_none());
+
+        fs::write(&path, "worktree_root = 42\n").unwrap();
+        let (check, data) = check_config_file(&path);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.message.contains("invalid"));
+        assert!(data.is_none());
+
+        fs::write(&path, "worktree_root = \"/tmp/store\"\n").unwrap();
+        let (check, data) = check_config_file(&path);
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(data.unwrap().worktree_root, PathBuf::from("/tmp/store"));
+    }
+
+    #[test]
+    fn test_check_worktree_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = |root: PathBuf| ConfigData {
+            worktree_root: root,
+            ..Default::default()
+        };
+
+        let check = check_worktree_root(Some(&data(dir.path().to_path_buf())));
+        assert_eq!(check.status, Status::Pass);
+        // The write probe is cleaned up
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let check = check_worktree_root(Some(&data(dir.path().join("missing"))));
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.message.contains("does not exist"));
+
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let check = check_worktree_root(Some(&data(file)));
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.message.contains("not a directory"));
+
+        assert_eq!(check_worktree_root(None).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_check_version() {
+        let version = |raw: &str, number| GitVersion {
+            raw: raw.to_string(),
+            number,
+        };
+
+        let check = check_version(&version("git version 2.39.5", Some((2, 39))));
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(check.message, "git version 2.39.5");
+
+        let check = check_version(&version("git version 2.25.1", Some((2, 25))));
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.message.contains("lock reasons"), "{}", check.message);
+
+        let check = check_version(&version("git version 2.16.0", Some((2, 16))));
+        assert_eq!(check.status, Status::Fail);
+
+        let check = check_version(&version("hub version 2.14.2", None));
+        assert_eq!(check.status, Status::Warn);
+    }
+
+    #[test]
+    fn test_check_store_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "git init -q -b main repo && \
+                 git -C repo -c user.name=t -c user.email=t@e commit -q --allow-empty -m init && \
+                 mkdir -p store/leftover && \
+                 git -C repo worktree add -q ../store/1111 -b feature",
+            )
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let store = dir.path().join("store");
+        let check = check_store_orphans(&store, &dir.path().join("repo/.git"));
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.message.contains("leftover"), "{}", check.message);
+        assert!(!check.message.contains("1111"), "{}", check.message);
+
+        fs::remove_dir(store.join("leftover")).unwrap();
+        let check = check_store_orphans(&store, Path::new(""));
+        assert_eq!(check.status, Status::Pass, "{}", check.message);
+    }
+
+    #[test]
+    fn test_check_missing_worktrees() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = Worktree::new(dir.path().to_path_buf(), "abc".into(), None);
+        let missing = Worktree::new(dir.path().join("gone"), "def".into(), None);
+
+        let check = check_missing_worktrees([&present]);
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(check.message, "1 registered, all present");
+
+        let check = check_missing_worktrees([&present, &missing]);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.message.contains("gone"));
+    }
+
+    #[test]
+    fn test_check_shell_wrapper() {
+        let check = check_shell_wrapper(|_| Some("1".to_string()));
+        assert_eq!(check.status, Status::Pass);
+        let check = check_shell_wrapper(|_| None);
+        assert_eq!(check.status, Status::Warn);
+    }
+
+    #[test]
+    fn test_checks_serialize_for_ci() {
+        let check = Check::new("git", Status::Fail, "too old");
+        assert_eq!(
+            serde_json::to_string(&check).unwrap(),
+            r#"{"name":"git","status":"fail","message":"too old"}"#
+        );
+    }
+}
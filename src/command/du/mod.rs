@@ -0,0 +1,237 @@
+use crate::utility::{GitBackend, Worktree};
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Disk usage of one worktree, as printed by `gwt du`
+#[derive(Debug, Serialize)]
+struct Usage {
+    branch: Option<String>,
+    path: PathBuf,
+    bytes: u64,
+    /// The directory no longer exists; counted as 0 bytes
+    missing: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    worktrees: Vec<Usage>,
+    total_bytes: u64,
+}
+
+pub fn handle(git: &dyn GitBackend, json: bool) -> Result<()> {
+    let worktrees: Vec<Worktree> = git.list_worktrees()?.iter().cloned().collect();
+
+    let mut usages = measure(&worktrees);
+    usages.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    let report = Report {
+        total_bytes: usages.iter().map(|u| u.bytes).sum(),
+        worktrees: usages,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for line in render(&report) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Walk every worktree on its own thread, since this is all disk IO; results
+/// are returned in `worktrees` order
+fn measure(worktrees: &[Worktree]) -> Vec<Usage> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = worktrees
+            .iter()
+            .map(|wt| (wt, scope.spawn(move || dir_size(wt.path()))))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|(wt, handle)| {
+                let size = handle.join().ok().flatten();
+                Usage {
+                    branch: wt.branch().map(String::from),
+                    path: wt.path().clone(),
+                    bytes: size.unwrap_or(0),
+                    missing: size.is_none(),
+                }
+            })
+            .collect()
+    })
+}
+
+/// Total size of the files under the worktree at `root`, or `None` when it
+/// doesn't exist. The `.git` link to the repository isn't counted, and other
+/// filesystems mounted inside aren't entered. Unreadable entries are skipped.
+pub(crate) fn dir_size(root: &Path) -> Option<u64> {
+    let metadata = fs::symlink_metadata(root).ok().filter(|m| m.is_dir())?;
+    let root_device = device(&metadata);
+
+    let mut total = 0;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if dir == root && entry.file_name() == ".git" {
+                continue;
+            }
+            // Symlinks are counted as themselves, never followed
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if device(&metadata) == root_device {
+                    pending.push(entry.path());
+                }
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    Some(total)
+}
+
+#[cfg(unix)]
+fn device(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+fn render(report: &Report) -> Vec<String> {
+    let branches: Vec<&str> = report
+        .worktrees
+        .iter()
+        .map(|u| u.branch.as_deref().unwrap_or("(detached)"))
+        .collect();
+    let sizes: Vec<String> = report
+        .worktrees
+        .iter()
+        .map(|u| human_size(u.bytes))
+        .collect();
+    let branch_width = branches.iter().map(|b| b.len()).max().unwrap_or(0);
+    let size_width = sizes
+        .iter()
+        .map(String::len)
+        .chain([human_size(report.total_bytes).len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut lines: Vec<String> = report
+        .worktrees
+        .iter()
+        .zip(branches.iter().zip(&sizes))
+        .map(|(usage, (branch, size))| {
+            let missing = if usage.missing {
+                format!(" {}", style("[missing]").red())
+            } else {
+                String::new()
+            };
+            format!(
+                "{:>size_width$}  {:<branch_width$}  {}{}",
+                size,
+                branch,
+                style(usage.path.display()).cyan(),
+                missing
+            )
+        })
+        .collect();
+    lines.push(format!(
+        "{:>size_width$}  {}",
+        style(human_size(report.total_bytes)).bold(),
+        style("total").bold()
+    ));
+    lines
+}
+
+/// Size in binary units with one decimal, e.g. "512 B" or "1.5 GiB"
+pub(crate) fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_size_skips_git_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let wt = dir.path();
+        fs::write(wt.join(".git"), "gitdir: /somewhere/else").unwrap();
+        fs::write(wt.join("a.txt"), vec![0u8; 100]).unwrap();
+        fs::create_dir_all(wt.join("target/debug")).unwrap();
+        fs::write(wt.join("target/debug/bin"), vec![0u8; 1000]).unwrap();
+        // Only a top-level .git is the link; anything deeper is content
+        fs::create_dir_all(wt.join("vendor/.git")).unwrap();
+        fs::write(wt.join("vendor/.git/HEAD"), vec![0u8; 10]).unwrap();
+
+        assert_eq!(dir_size(wt), Some(1110));
+        assert_eq!(dir_size(&wt.join("missing")), None);
+    }
+
+    #[test]
+    fn test_measure_reports_missing_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = dir.path().join("present");
+        fs::create_dir_all(&present).unwrap();
+        fs::write(present.join("f"), vec![0u8; 42]).unwrap();
+        let worktrees = vec![
+            Worktree::new(present, "a".repeat(40), Some("main".into())),
+            Worktree::new(dir.path().join("gone"), "b".repeat(40), None),
+        ];
+
+        let usages = measure(&worktrees);
+
+        assert_eq!(usages[0].bytes, 42);
+        assert!(!usages[0].missing);
+        assert_eq!(usages[1].bytes, 0);
+        assert!(usages[1].missing);
+
+        let report = Report {
+            total_bytes: 42,
+            worktrees: usages,
+        };
+        let lines: Vec<String> = render(&report)
+            .iter()
+            .map(|l| console::strip_ansi_codes(l).into_owned())
+            .collect();
+        assert!(lines[0].starts_with("42 B  main "), "{lines:?}");
+        assert!(lines[1].contains("(detached)") && lines[1].ends_with("[missing]"));
+        assert_eq!(lines[2], "42 B  total");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["total_bytes"], 42);
+        assert_eq!(json["worktrees"][1]["missing"], true);
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
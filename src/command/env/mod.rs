@@ -0,0 +1,129 @@
+use super::worktree::repo_store;
+use crate::config::Config;
+use crate::error::GwtError;
+use crate::utility::GitBackend;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What `gwt env` prints. The repository's values are `None` outside a
+/// repository.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Env {
+    config_path: Option<PathBuf>,
+    worktree_root: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo_store: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    main_worktree: Option<PathBuf>,
+}
+
+/// Print the paths gwt uses as `NAME=value` lines for `eval`, with `export`
+/// in front when asked to, or as JSON
+pub fn handle(git: &dyn GitBackend, config: &Config, json: bool, export: bool) -> Result<()> {
+    let env = collect(git, config)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&env)?);
+    } else {
+        for line in render(&env, export) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn collect(git: &dyn GitBackend, config: &Config) -> Result<Env> {
+    let data = config.data().ok_or_else(|| anyhow!("Config not loaded"))?;
+    let main_worktree = match git.get_main_worktree() {
+        Ok(main) => Some(main.path().clone()),
+        Err(e) if matches!(GwtError::find(&e), Some(GwtError::NotARepository { .. })) => None,
+        Err(e) => return Err(e),
+    };
+    let repo_store = match main_worktree {
+        Some(_) => Some(repo_store(git, config)?),
+        None => None,
+    };
+    Ok(Env {
+        config_path: config.config_path().map(PathBuf::from),
+        worktree_root: data.worktree_root.clone(),
+        repo_store,
+        main_worktree,
+    })
+}
+
+fn render(env: &Env, export: bool) -> Vec<String> {
+    let prefix = if export { "export " } else { "" };
+    [
+        ("GWT_CONFIG_PATH", env.config_path.as_ref()),
+        ("GWT_WORKTREE_ROOT", Some(&env.worktree_root)),
+        ("GWT_REPO_STORE", env.repo_store.as_ref()),
+        ("GWT_MAIN_WORKTREE", env.main_worktree.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(name, path)| {
+        let path = path?.to_string_lossy();
+        Some(format!("{}{}={}", prefix, name, shell_words::quote(&path)))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::config::ConfigData;
+    use crate::utility::FakeGit;
+
+    #[test]
+    fn test_collect_in_a_repository() {
+        let (_dir, config) = temp_config();
+        let root = config.data().unwrap().worktree_root.clone();
+        let git = FakeGit::new("/src/api", "main");
+
+        let env = collect(&git, &config).unwrap();
+        assert_eq!(env.main_worktree, Some(PathBuf::from("/src/api")));
+        assert_eq!(env.repo_store, Some(root.clone()));
+        assert_eq!(env.worktree_root, root);
+        assert!(env.config_path.unwrap().ends_with("config.toml"));
+
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: root.clone(),
+                path_template: Some("{repo}/{branch}".to_string()),
+                ..Default::default()
+            },
+            PathBuf::from("/home/me/.gwt/config.toml"),
+        );
+        let env = collect(&git, &config).unwrap();
+        assert_eq!(env.repo_store, Some(root.join("api")));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut env = Env {
+            config_path: Some(PathBuf::from("/home/me/.gwt/config.toml")),
+            worktree_root: PathBuf::from("/home/me/my store"),
+            repo_store: Some(PathBuf::from("/home/me/my store/api")),
+            main_worktree: Some(PathBuf::from("/src/api")),
+        };
+        assert_eq!(
+            render(&env, false),
+            [
+                "GWT_CONFIG_PATH=/home/me/.gwt/config.toml",
+                "GWT_WORKTREE_ROOT='/home/me/my store'",
+                "GWT_REPO_STORE='/home/me/my store/api'",
+                "GWT_MAIN_WORKTREE=/src/api",
+            ]
+        );
+
+        env.repo_store = None;
+        env.main_worktree = None;
+        assert_eq!(
+            render(&env, true),
+            [
+                "export GWT_CONFIG_PATH=/home/me/.gwt/config.toml",
+                "export GWT_WORKTREE_ROOT='/home/me/my store'",
+            ]
+        );
+    }
+}
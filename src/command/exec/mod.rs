@@ -0,0 +1,390 @@
+use super::worktree::{SwitchOptions, cache_file, resolve_worktree, switch_worktree};
+use crate::cache;
+use crate::config::Config;
+use crate::error::GwtError;
+use crate::ui;
+use crate::utility::{ExternalOptions, GitBackend, OutputMode, Worktree, run_external};
+use anyhow::{Result, anyhow, bail};
+use console::style;
+use std::process::Command;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+/// Outcome of running the command in one worktree
+#[derive(Debug)]
+enum Outcome {
+    Success,
+    /// Non-zero exit status (or killed by a signal)
+    Failed(i32),
+    /// The command couldn't be started at all
+    Error(String),
+    /// Not run because an earlier worktree failed
+    Skipped,
+}
+
+pub fn handle(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: Option<&str>,
+    all: bool,
+    keep_going: bool,
+    jobs: usize,
+    command: &[String],
+) -> Result<()> {
+    let targets = if all {
+        git.list_worktrees()?
+            .into_iter()
+            .filter(|wt| {
+                let exists = wt.path().is_dir();
+                if !exists {
+                    ui::warn!("Skipping '{}': directory is missing.", wt.path().display());
+                }
+                exists
+            })
+            .collect()
+    } else {
+        let branch = branch.ok_or_else(|| anyhow!("Either --branch or --all is required"))?;
+        vec![resolve_worktree(git, config, branch)?]
+    };
+
+    let outcomes = run_in_worktrees(&targets, command, keep_going, jobs);
+
+    let failures: Vec<_> = targets
+        .iter()
+        .zip(&outcomes)
+        .filter_map(|(wt, outcome)| match outcome {
+            Outcome::Failed(code) => Some((label(wt), format!("exit code {code}"), *code)),
+            Outcome::Error(e) => Some((label(wt), e.clone(), 127)),
+            Outcome::Success | Outcome::Skipped => None,
+        })
+        .collect();
+
+    let Some((_, _, first_code)) = failures.first() else {
+        return Ok(());
+    };
+
+    if keep_going {
+        eprintln!(
+            "{}",
+            style(format!(
+                "Failed in {} of {} worktrees:",
+                failures.len(),
+                targets.len()
+            ))
+            .red()
+        );
+        for (label, reason, _) in &failures {
+            eprintln!("  {}: {}", label, reason);
+        }
+    } else {
+        let (label, reason, _) = &failures[0];
+        eprintln!(
+            "{}",
+            style(format!("Failed in '{}': {}", label, reason)).red()
+        );
+    }
+
+    std::process::exit(*first_code);
+}
+
+/// `gwt run`: run `command` in the worktree of `branch` with the terminal
+/// attached, and exit with its status. With `create`, a missing worktree is
+/// created first, the way `gwt sw` would.
+pub fn run(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: &str,
+    create: bool,
+    command: &[String],
+) -> Result<()> {
+    let (branch, path) = match resolve_worktree(git, config, branch) {
+        Ok(worktree) => (
+            worktree.branch().unwrap_or(branch).to_string(),
+            worktree.path().clone(),
+        ),
+        Err(e) if create && matches!(GwtError::find(&e), Some(GwtError::WorktreeNotFound(_))) => {
+            let switched = switch_worktree(git, config, Some(branch), &SwitchOptions::default())?;
+            // Exiting with the command's status skips the invalidation in main
+            if let Some(file) = cache_file(git, config) {
+                cache::invalidate(&file);
+            }
+            (switched.branch, switched.path)
+        }
+        Err(e) => return Err(e),
+    };
+    if !path.is_dir() {
+        bail!(
+            "The worktree for branch '{}' is registered at '{}', which doesn't exist. Run `gwt repair` if the worktree store moved.",
+            branch,
+            path.display()
+        );
+    }
+
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("No command given"))?;
+    let mut cmd = Command::new(program);
+    cmd.args(args).current_dir(&path);
+    let opts = ExternalOptions::new()
+        .output(OutputMode::Inherit)
+        .env("GWT_BRANCH", branch)
+        .env("GWT_PATH", &path);
+
+    let output = run_external(cmd, &opts)?;
+    if !output.status.success() {
+        std::process::exit(output.status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Run `command` in each target worktree, at most `jobs` at a time.
+///
+/// Without `keep_going`, no new worktree is started after the first failure;
+/// commands already running are allowed to finish. Outcomes are returned in
+/// target order.
+fn run_in_worktrees(
+    targets: &[Worktree],
+    command: &[String],
+    keep_going: bool,
+    jobs: usize,
+) -> Vec<Outcome> {
+    // The prefix only helps when output of several worktrees is mixed
+    let prefixed = targets.len() > 1;
+    let width = targets.iter().map(|wt| label(wt).len()).max().unwrap_or(0);
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let outcomes = Mutex::new(
+        std::iter::repeat_with(|| Outcome::Skipped)
+            .take(targets.len())
+            .collect::<Vec<_>>(),
+    );
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, targets.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    if !keep_going && failed.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(worktree) = targets.get(index) else {
+                        break;
+                    };
+
+                    let prefix = if prefixed {
+                        format!("{} ", style(format!("[{:width$}]", label(worktree))).cyan())
+                    } else {
+                        String::new()
+                    };
+                    let outcome = run_in_worktree(worktree, command, prefix);
+                    if !matches!(outcome, Outcome::Success) {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    outcomes.lock().unwrap()[index] = outcome;
+                }
+            });
+        }
+    });
+
+    outcomes.into_inner().unwrap()
+}
+
+fn run_in_worktree(worktree: &Worktree, command: &[String], prefix: String) -> Outcome {
+    let Some((program, args)) = command.split_first() else {
+        return Outcome::Error("no command given".to_string());
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).current_dir(worktree.path());
+
+    let opts = ExternalOptions::new()
+        .output(OutputMode::Prefixed)
+        .prefix(prefix)
+        .env("GWT_BRANCH", worktree.branch().unwrap_or_default())
+        .env("GWT_PATH", worktree.path());
+
+    match run_external(cmd, &opts) {
+        Ok(output) if output.status.success() => Outcome::Success,
+        Ok(output) => Outcome::Failed(output.status.code().unwrap_or(1)),
+        Err(e) => Outcome::Error(e.to_string()),
+    }
+}
+
+/// How a worktree is referred to in prefixes and summaries
+fn label(worktree: &Worktree) -> String {
+    match worktree.branch() {
+        Some(branch) => branch.to_string(),
+        None => format!("(detached {})", short_head(worktree.head())),
+    }
+}
+
+fn short_head(head: &str) -> &str {
+    &head[..head.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn worktree(path: &Path, branch: &str) -> Worktree {
+        Worktree::new(
+            path.to_path_buf(),
+            "abc1234def".to_string(),
+            Some(branch.to_string()),
+        )
+    }
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    fn setup(count: usize) -> (tempfile::TempDir, Vec<Worktree>) {
+        let dir = tempfile::tempdir().unwrap();
+        let targets = (0..count)
+            .map(|i| {
+                let path = dir.path().join(format!("wt{i}"));
+                std::fs::create_dir_all(&path).unwrap();
+                worktree(&path, &format!("branch-{i}"))
+            })
+            .collect();
+        (dir, targets)
+    }
+
+    #[test]
+    fn test_runs_in_worktree_with_env() {
+        let (_dir, targets) = setup(2);
+        let outcomes = run_in_worktrees(
+            &targets,
+            &sh(r#"printf '%s|%s|%s' "$PWD" "$GWT_BRANCH" "$GWT_PATH" > out"#),
+            false,
+            1,
+        );
+
+        assert!(outcomes.iter().all(|o| matches!(o, Outcome::Success)));
+        for wt in &targets {
+            let out = std::fs::read_to_string(wt.path().join("out")).unwrap();
+            let path = wt.path().display();
+            assert_eq!(
+                out,
+                format!("{}|{}|{}", path, wt.branch().unwrap(), path),
+                "{out}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stops_after_first_failure() {
+        let (_dir, targets) = setup(3);
+        let outcomes = run_in_worktrees(
+            &targets,
+            &sh(r#"touch ran; [ "$GWT_BRANCH" != "branch-1" ] || exit 3"#),
+            false,
+            1,
+        );
+
+        assert!(matches!(outcomes[0], Outcome::Success));
+        assert!(matches!(outcomes[1], Outcome::Failed(3)));
+        assert!(matches!(outcomes[2], Outcome::Skipped));
+        assert!(!targets[2].path().join("ran").exists());
+    }
+
+    #[test]
+    fn test_keep_going_runs_everywhere() {
+        let (_dir, targets) = setup(3);
+        let outcomes = run_in_worktrees(
+            &targets,
+            &sh(r#"touch ran; [ "$GWT_BRANCH" != "branch-0" ] || exit 2"#),
+            true,
+            1,
+        );
+
+        assert!(matches!(outcomes[0], Outcome::Failed(2)));
+        assert!(matches!(outcomes[1], Outcome::Success));
+        assert!(matches!(outcomes[2], Outcome::Success));
+        assert!(targets.iter().all(|wt| wt.path().join("ran").exists()));
+    }
+
+    #[test]
+    fn test_parallel_jobs_run_concurrently() {
+        let (dir, targets) = setup(4);
+        let marker = dir.path().join("started");
+        std::fs::create_dir_all(&marker).unwrap();
+
+        // Each job waits until all four have started, which only finishes
+        // when they run at the same time
+        let script = format!(
+            r#"touch '{m}'/"$GWT_BRANCH"; i=0; while [ "$(ls '{m}' | wc -l)" -lt 4 ]; do i=$((i+1)); [ $i -gt 500 ] && exit 9; sleep 0.01; done"#,
+            m = marker.display()
+        );
+        let outcomes = run_in_worktrees(&targets, &sh(&script), false, 4);
+        assert!(
+            outcomes.iter().all(|o| matches!(o, Outcome::Success)),
+            "{outcomes:?}"
+        );
+    }
+
+    #[test]
+    fn test_missing_program_is_an_error() {
+        let (_dir, targets) = setup(1);
+        let outcomes = run_in_worktrees(&targets, &["gwt-no-such-command".to_string()], false, 1);
+        assert!(matches!(&outcomes[0], Outcome::Error(e) if e.contains("not found")));
+    }
+
+    #[test]
+    fn test_run_in_a_branchs_worktree() {
+        let (dir, config) = crate::command::worktree::test_utils::temp_config();
+        let path = dir.path().join("feature");
+        std::fs::create_dir_all(&path).unwrap();
+        let git = crate::utility::FakeGit::new("/src/api", "main").with_worktree(&path, "feature");
+
+        run(
+            &git,
+            &config,
+            "feature",
+            false,
+            &sh(r#"printf '%s|%s' "$GWT_BRANCH" "$GWT_PATH" > out"#),
+        )
+        .unwrap();
+        let out = std::fs::read_to_string(path.join("out")).unwrap();
+        assert_eq!(out, format!("feature|{}", path.display()));
+
+        let err = run(&git, &config, "nope", false, &sh("true")).unwrap_err();
+        assert!(matches!(
+            GwtError::find(&err),
+            Some(GwtError::WorktreeNotFound(_))
+        ));
+
+        // A path inside the worktree works in place of the branch, for both
+        std::fs::create_dir_all(path.join("src")).unwrap();
+        run(
+            &git,
+            &config,
+            path.join("src").to_str().unwrap(),
+            false,
+            &sh(r#"printf '%s' "$GWT_BRANCH" > out"#),
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path.join("out")).unwrap(),
+            "feature"
+        );
+        let err = handle(&git, &config, Some("nope"), false, false, 1, &sh("true")).unwrap_err();
+        assert!(matches!(
+            GwtError::find(&err),
+            Some(GwtError::WorktreeNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&path).unwrap();
+        let err = run(&git, &config, "feature", false, &sh("true")).unwrap_err();
+        assert!(err.to_string().contains("gwt repair"), "{err}");
+    }
+
+    #[test]
+    fn test_label_for_detached_worktree() {
+        let wt = Worktree::new(PathBuf::from("/tmp/x"), "abc1234def".to_string(), None);
+        assert_eq!(label(&wt), "(detached abc1234)");
+    }
+}
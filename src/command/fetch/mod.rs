@@ -0,0 +1,304 @@
+use crate::config::Config;
+use crate::utility::{Git, StoreOwner, canonical, repository_path, scan_store};
+use anyhow::{Result, anyhow, bail};
+use console::style;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// What a fetch did to the upstream of one worktree's branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    NoUpstream,
+    UpToDate,
+    /// The upstream moved from the first commit to the second
+    Updated(String, String),
+    /// The upstream is configured but was pruned (or never existed)
+    Gone,
+}
+
+/// One worktree's line in the report
+#[derive(Debug)]
+struct WorktreeUpdate {
+    branch: String,
+    upstream: Option<String>,
+    change: Change,
+}
+
+/// Fetch the current repository, or with `all` every repository that has
+/// worktrees in the store, and report whose upstream moved.
+///
+/// Worktrees of one repository share its object storage, so each repository is
+/// fetched once no matter how many worktrees it has. Repositories are fetched
+/// in parallel, and one failing doesn't stop the others.
+pub fn handle(git: &Git, config: &Config, all: bool) -> Result<()> {
+    let current = git.git_common_dir().ok().map(|d| canonical(&d));
+
+    let repos = if all {
+        let root = config
+            .data()
+            .map(|d| &d.worktree_root)
+            .ok_or_else(|| anyhow!("Config not loaded"))?;
+        store_repositories(root, current.as_deref())?
+    } else {
+        vec![current.ok_or_else(|| {
+            anyhow!(
+                "Not in a git repository. Use `gwt fetch --all` to fetch every repository with worktrees in the store."
+            )
+        })?]
+    };
+    if repos.is_empty() {
+        bail!("No repositories found in the worktree store.");
+    }
+
+    let results = fetch_all(git, &repos);
+
+    let mut failures = Vec::new();
+    for (repo, result) in repos.iter().zip(results) {
+        if all {
+            println!("{}", style(repository_path(repo).display()).bold());
+        }
+        match result {
+            Ok(updates) => render(&updates)
+                .iter()
+                .for_each(|line| println!("{}", line)),
+            Err(e) => {
+                eprintln!("{}", style(format!("  fetch failed: {}", e)).red());
+                failures.push(repo);
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "Failed to fetch {} of {} repositories: {}",
+            failures.len(),
+            repos.len(),
+            failures
+                .iter()
+                .map(|r| repository_path(r).display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Common dirs of the repositories owning worktrees in the store, the current
+/// repository first
+fn store_repositories(root: &Path, current: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let mut repos: Vec<PathBuf> = current.into_iter().map(Path::to_path_buf).collect();
+    for entry in scan_store(root, current.unwrap_or(Path::new("")))? {
+        if let StoreOwner::OtherClone(common_dir) = entry.owner
+            && !repos.contains(&common_dir)
+        {
+            repos.push(common_dir);
+        }
+    }
+    Ok(repos)
+}
+
+/// Fetch every repository concurrently; results are returned in `repos` order
+fn fetch_all(git: &Git, repos: &[PathBuf]) -> Vec<Result<Vec<WorktreeUpdate>>> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = repos
+            .iter()
+            .map(|repo| scope.spawn(move || fetch_repo(git, repo)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .unwrap_or_else(|_| Err(anyhow!("fetch thread panicked")))
+            })
+            .collect()
+    })
+}
+
+fn fetch_repo(git: &Git, repo: &Path) -> Result<Vec<WorktreeUpdate>> {
+    let git = git.clone().with_cwd(repo);
+    let before = remote_refs(&git)?;
+    // Output is captured rather than streamed: with several repositories in
+    // flight, progress lines would interleave
+    git.run(&["fetch", "--all", "--prune", "--quiet"])?;
+    let after = remote_refs(&git)?;
+    let upstreams = upstreams(&git)?;
+
+    Ok(git
+        .list_worktrees()?
+        .iter()
+        .filter_map(|wt| wt.branch())
+        .map(|branch| {
+            let upstream = upstreams.get(branch).cloned();
+            let change = match &upstream {
+                None => Change::NoUpstream,
+                Some(upstream) => classify(before.get(upstream), after.get(upstream)),
+            };
+            WorktreeUpdate {
+                branch: branch.to_string(),
+                upstream: upstream.map(|u| short_ref(&u).to_string()),
+                change,
+            }
+        })
+        .collect())
+}
+
+/// Compare an upstream ref's commit before and after the fetch
+fn classify(before: Option<&String>, after: Option<&String>) -> Change {
+    match (before, after) {
+        (_, None) => Change::Gone,
+        (Some(before), Some(after)) if before == after => Change::UpToDate,
+        (before, Some(after)) => Change::Updated(
+            before.map(|b| abbrev(b)).unwrap_or("(new)").to_string(),
+            abbrev(after).to_string(),
+        ),
+    }
+}
+
+/// Commit of every remote-tracking ref, keyed by full ref name
+fn remote_refs(git: &Git) -> Result<HashMap<String, String>> {
+    let output = git.run(&[
+        "for-each-ref",
+        "--format=%(refname)%09%(objectname)",
+        "refs/remotes",
+    ])?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, sha)| (name.to_string(), sha.to_string()))
+        .collect())
+}
+
+/// Full upstream ref name of every local branch that has one
+fn upstreams(git: &Git) -> Result<HashMap<String, String>> {
+    let output = git.run(&[
+        "for-each-ref",
+        "--format=%(refname:short)%09%(upstream)",
+        "refs/heads",
+    ])?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(_, upstream)| !upstream.is_empty())
+        .map(|(branch, upstream)| (branch.to_string(), upstream.to_string()))
+        .collect())
+}
+
+fn render(updates: &[WorktreeUpdate]) -> Vec<String> {
+    let branch_width = updates.iter().map(|u| u.branch.len()).max().unwrap_or(0);
+    let upstream_width = updates
+        .iter()
+        .map(|u| u.upstream.as_deref().unwrap_or("-").len())
+        .max()
+        .unwrap_or(0);
+
+    updates
+        .iter()
+        .map(|update| {
+            let change = match &update.change {
+                Change::NoUpstream => style("no upstream".to_string()).dim(),
+                Change::UpToDate => style("up to date".to_string()).green(),
+                Change::Updated(from, to) => style(format!("updated {from}..{to}")).yellow(),
+                Change::Gone => style("gone".to_string()).red(),
+            };
+            format!(
+                "  {:<branch_width$}  {:<upstream_width$}  {}",
+                update.branch,
+                update.upstream.as_deref().unwrap_or("-"),
+                change
+            )
+        })
+        .collect()
+}
+
+fn short_ref(name: &str) -> &str {
+    name.strip_prefix("refs/remotes/").unwrap_or(name)
+}
+
+fn abbrev(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+
+    /// `origin` with `main`, `feature` and `old`, and a clone with a worktree
+    /// for each of them plus one for a branch without upstream
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        sh(
+            dir.path(),
+            "git init -q -b main origin && git -C origin commit -q --allow-empty -m init && \
+             git -C origin branch feature && git -C origin branch old && \
+             git clone -q origin repo && cd repo && \
+             git worktree add -q ../feature feature && git worktree add -q ../old old && \
+             git worktree add -q -b local ../local",
+        );
+        dir
+    }
+
+    #[test]
+    fn test_fetch_reports_upstream_changes() {
+        let dir = setup();
+        sh(
+            &dir.path().join("origin"),
+            "git commit -q --allow-empty -m more && git branch -D -q old",
+        );
+
+        let repo = dir.path().join("repo/.git");
+        let updates = fetch_repo(&Git::new(), &repo).unwrap();
+        let changes: Vec<_> = updates
+            .iter()
+            .map(|u| (u.branch.as_str(), u.upstream.as_deref(), &u.change))
+            .collect();
+
+        assert_eq!(changes.len(), 4);
+        assert!(matches!(
+            changes[0],
+            ("main", Some("origin/main"), Change::Updated(_, _))
+        ));
+        assert_eq!(
+            changes[1..],
+            [
+                ("feature", Some("origin/feature"), &Change::UpToDate),
+                ("local", None, &Change::NoUpstream),
+                ("old", Some("origin/old"), &Change::Gone),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fetch_all_keeps_going_after_a_failure() {
+        let dir = setup();
+        let broken = dir.path().join("broken");
+        sh(
+            dir.path(),
+            "git init -q broken && git -C broken remote add origin ../nowhere",
+        );
+
+        let results = fetch_all(
+            &Git::new(),
+            &[broken.join(".git"), dir.path().join("repo/.git")],
+        );
+
+        assert!(results[0].is_err());
+        assert_eq!(results[1].as_ref().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_classify() {
+        let (a, b) = ("a".repeat(40), "b".repeat(40));
+        assert_eq!(classify(Some(&a), Some(&a)), Change::UpToDate);
+        assert_eq!(
+            classify(Some(&a), Some(&b)),
+            Change::Updated("aaaaaaa".into(), "bbbbbbb".into())
+        );
+        assert_eq!(
+            classify(None, Some(&b)),
+            Change::Updated("(new)".into(), "bbbbbbb".into())
+        );
+        assert_eq!(classify(Some(&a), None), Change::Gone);
+    }
+}
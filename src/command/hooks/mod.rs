@@ -0,0 +1,154 @@
+use crate::config::Config;
+use crate::error::GwtError;
+use crate::hooks::{Hook, Invocation};
+use crate::utility::GitBackend;
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use console::style;
+
+#[derive(Subcommand)]
+pub enum HooksCommands {
+    /// List the hooks with their configured commands
+    ///
+    /// Prints each hook gwt knows, where its command comes from and the command itself. Hooks
+    /// are set in the global config, `~/.gwt/config.toml` by default.
+    List,
+    /// Run a hook against an existing worktree, without the real operation
+    ///
+    /// Runs the hook's command with the same working directory and environment variables a real
+    /// trigger would give it, and streams its output. `post_remove` is run as if the worktree of
+    /// `--branch` (or the current worktree) had just been removed; nothing is removed.
+    Run {
+        /// Hook to run, e.g. `post_remove`
+        name: String,
+
+        /// Branch whose worktree the hook runs against (defaults to the current worktree)
+        #[arg(short = 'b', long = "branch")]
+        branch: Option<String>,
+    },
+}
+
+pub fn handle(git: &dyn GitBackend, config: &Config, cmd: &HooksCommands) -> Result<()> {
+    match cmd {
+        HooksCommands::List => {
+            for line in list(config) {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+        HooksCommands::Run { name, branch } => run(git, config, name, branch.as_deref()),
+    }
+}
+
+/// One line per hook: name, source and command
+fn list(config: &Config) -> Vec<String> {
+    let width = Hook::ALL.iter().map(|h| h.name().len()).max().unwrap_or(0);
+    Hook::ALL
+        .iter()
+        .map(|hook| match hook.command(config) {
+            Some(command) => format!("{:width$}  global  {}", hook.name(), command),
+            None => format!("{:width$}  {}", hook.name(), style("(not set)").dim()),
+        })
+        .collect()
+}
+
+fn run(git: &dyn GitBackend, config: &Config, name: &str, branch: Option<&str>) -> Result<()> {
+    let hook = Hook::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Hook::ALL.iter().map(|h| h.name()).collect();
+        GwtError::Usage(format!(
+            "Unknown hook '{}'. Valid hooks: {}",
+            name,
+            names.join(", ")
+        ))
+    })?;
+    let command = hook.command(config).ok_or_else(|| {
+        anyhow!(
+            "`{}` isn't set in '{}'",
+            hook.config_key(),
+            config.config_path().unwrap_or_default()
+        )
+    })?;
+
+    let (branch, path) = match branch {
+        Some(branch) => {
+            let worktree = git
+                .find_worktree_by_branch(branch)?
+                .ok_or_else(|| GwtError::WorktreeNotFound(branch.to_string()))?;
+            (branch.to_string(), worktree.path().clone())
+        }
+        None => (git.get_current_branch()?, git.git_toplevel()?),
+    };
+    let main_worktree = git.get_main_worktree()?.path().clone();
+    let invocation = match hook {
+        Hook::PostRemove => Invocation::post_remove(config, &main_worktree, &branch, &path),
+    };
+    crate::hooks::run(hook, command, &invocation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigData;
+    use crate::utility::FakeGit;
+    use std::path::PathBuf;
+
+    fn config_with_hook(root: PathBuf, hook: Option<&str>) -> Config {
+        Config::Loaded(
+            ConfigData {
+                worktree_root: root,
+                post_remove_hook: hook.map(String::from),
+                ..Default::default()
+            },
+            PathBuf::from("/home/me/.gwt/config.toml"),
+        )
+    }
+
+    #[test]
+    fn test_list() {
+        console::set_colors_enabled(false);
+        let config = config_with_hook(PathBuf::from("/store"), Some("make clean"));
+        assert_eq!(list(&config), ["post_remove  global  make clean"]);
+
+        let config = config_with_hook(PathBuf::from("/store"), None);
+        assert_eq!(list(&config), ["post_remove  (not set)"]);
+    }
+
+    #[test]
+    fn test_run_against_a_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("api");
+        std::fs::create_dir_all(&main).unwrap();
+        let git = FakeGit::new(&main, "main").with_worktree("/store/feature", "feature");
+        let config = config_with_hook(
+            dir.path().to_path_buf(),
+            Some(r#"printf '%s|%s' "$GWT_BRANCH" "$GWT_REMOVED_PATH" > out"#),
+        );
+
+        run(&git, &config, "post_remove", Some("feature")).unwrap();
+        let out = std::fs::read_to_string(main.join("out")).unwrap();
+        assert_eq!(out, "feature|/store/feature");
+        assert!(git.calls().is_empty());
+
+        let err = run(&git, &config, "post_remove", Some("nope")).unwrap_err();
+        assert!(matches!(
+            GwtError::find(&err),
+            Some(GwtError::WorktreeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_run_unknown_or_unset_hook() {
+        let git = FakeGit::new("/src/api", "main");
+        let config = config_with_hook(PathBuf::from("/store"), None);
+
+        let err = run(&git, &config, "pre_switch", None).unwrap_err();
+        assert!(matches!(GwtError::find(&err), Some(GwtError::Usage(_))));
+        assert!(
+            err.to_string().contains("Valid hooks: post_remove"),
+            "{err}"
+        );
+
+        let err = run(&git, &config, "post_remove", None).unwrap_err();
+        assert!(err.to_string().contains("post_remove_hook"), "{err}");
+    }
+}
@@ -1,16 +1,51 @@
+pub mod alias;
+pub mod clone;
 pub mod completion;
 pub mod config;
 pub mod current;
+pub mod docs;
+pub mod doctor;
+pub mod du;
+pub mod env;
+pub mod exec;
+pub mod fetch;
 pub mod home;
+pub mod hooks;
+pub mod prompt;
+pub mod recent;
+pub mod repos;
 pub mod shell;
+pub mod status;
+pub mod version;
+pub mod which;
 pub mod worktree;
 
 use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
-#[command(name = "gwt")]
-#[command(about = "A git worktree manager", long_about = None)]
+#[command(name = "gwt", version)]
+#[command(
+    about = "A git worktree manager",
+    long_about = "A git worktree manager. gwt keeps one worktree per branch under a single \
+                  worktree root and moves your shell between them: run `gwtree init` once to \
+                  set up the `gwt` shell function, then `gwt sw <branch>` switches to the \
+                  branch's worktree, creating it when needed."
+)]
 pub struct Cli {
+    /// Log every git command gwt runs to stderr (same as GWT_DEBUG=1)
+    #[arg(short = 'v', long = "verbose", global = true)]
+    pub verbose: bool,
+
+    /// Only print results and errors, no status messages (same as GWT_QUIET=1)
+    #[arg(short = 'q', long = "quiet", global = true)]
+    pub quiet: bool,
+
+    /// When to use colors; `auto` also honors NO_COLOR and `theme = "plain"`
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t, global = true)]
+    pub color: crate::ui::ColorChoice,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -18,10 +53,22 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Configure gwt
+    ///
+    /// Use `gwt config view` to see where the config file is and what it holds, and
+    /// `gwt config setup` to create or reset it.
     #[command(subcommand)]
     Config(config::ConfigCommands),
 
     /// List all worktrees
+    ///
+    /// Shows every worktree of the current repository with its commit, branch and path. `*` marks
+    /// the worktree you're in and `M` the main worktree. The layout fits the terminal: long branch
+    /// names are truncated and long paths shortened in the middle, unless the output is piped or
+    /// `--full` is given. Worktrees whose directory is missing are tagged `[missing]`.
+    ///
+    /// `--all` lists every repository with worktrees in the worktree store, and `--group-by repo`
+    /// groups them under a header per repository, with repositories that left only orphaned
+    /// directories marked `(orphaned)`.
     Ls {
         /// Show full branch names without truncation
         #[arg(long = "full")]
@@ -30,31 +77,165 @@ pub enum Commands {
         /// Output only branch names, one per line (for shell completion)
         #[arg(long = "raw", hide = true)]
         raw: bool,
+
+        /// Serve --raw output from the worktree list cache (for shell completion)
+        #[arg(long = "cached", hide = true, requires = "raw")]
+        cached: bool,
+
+        /// Leave out the main worktree's branch from --raw output (for `gwt rm` completion)
+        #[arg(long = "no-main", hide = true, requires = "raw")]
+        no_main: bool,
+
+        /// Print `branch<TAB>path` pairs in --raw output (for completion descriptions)
+        #[arg(
+            long = "with-paths",
+            hide = true,
+            requires = "raw",
+            conflicts_with = "ids"
+        )]
+        with_paths: bool,
+
+        /// Show the directory id of each managed worktree (accepted by `gwt rm`)
+        #[arg(long = "ids")]
+        ids: bool,
+
+        /// Tag worktrees whose upstream branch was deleted with [gone]
+        #[arg(long = "status")]
+        status: bool,
+
+        /// Show how long ago each worktree was last switched to
+        #[arg(long = "age")]
+        age: bool,
+
+        /// List directories in the worktree store whose repository is gone or no longer
+        /// knows them, with the branch each was last used for
+        #[arg(long = "orphans", conflicts_with_all = ["raw", "full", "ids", "status", "age"])]
+        orphans: bool,
+
+        /// Print machine-readable JSON
+        #[arg(long = "json", conflicts_with_all = ["raw", "full", "ids", "status", "age"])]
+        json: bool,
+
+        /// List the worktrees of every repository with worktrees in the worktree store
+        #[arg(long = "all", conflicts_with_all = ["raw", "orphans"])]
+        all: bool,
+
+        /// With --all, list each repository's worktrees under a header of its own
+        #[arg(long = "group-by", value_enum, requires = "all")]
+        group_by: Option<worktree::GroupBy>,
     },
 
     /// Switch to an existing worktree for a branch (prints path on success)
+    ///
+    /// Changes the shell into the worktree of a branch, through the shell wrapper from
+    /// `gwtree init`. When the branch has no worktree yet, one is created under `worktree_root`; a
+    /// branch that only exists on a remote gets a local tracking branch first, and `-b` creates a
+    /// new branch. The worktree's path is printed on stdout, and all other messages go to stderr.
     Sw {
         /// Branch name to switch to
-        #[arg(required_unless_present = "main", conflicts_with = "main")]
+        #[arg(
+            required_unless_present_any = ["main", "pick"],
+            conflicts_with_all = ["main", "pick"],
+            value_parser = non_blank
+        )]
         branch: Option<String>,
 
         /// Create a new branch
         #[arg(short = 'b', long = "create-branch")]
         create: bool,
 
-        /// Switch to the main branch (main or master)
+        /// Switch to the default branch (see `default_base_branch`)
         #[arg(short = 'm', long = "main")]
         main: bool,
 
         /// Specify the remote to resolve ambiguity when multiple remotes have the same branch name
         #[arg(long = "remote")]
         remote: Option<String>,
+
+        /// Add a worktree even if the branch is already checked out in another one, and leave a
+        /// dirty worktree despite `pre_switch_check = "block_dirty"`
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+
+        /// Pick the worktree with fzf (or $GWT_PICKER)
+        #[arg(long = "pick", conflicts_with_all = ["main", "create"])]
+        pick: bool,
+
+        /// Open the worktree in a tmux window (or session, see `tmux_mode`) instead of changing directory
+        #[arg(long = "tmux")]
+        tmux: bool,
+
+        /// Fetch the remote (or all remotes) with --prune before resolving the branch
+        #[arg(long = "fetch")]
+        fetch: bool,
+
+        /// Run `git pull --ff-only` in an existing worktree before switching to it
+        #[arg(long = "pull")]
+        pull: bool,
+
+        /// With --pull, exit with code 8 when the worktree can't be fast-forwarded
+        #[arg(long = "strict", requires = "pull")]
+        strict: bool,
+
+        /// Create the branch under the name given, without `branch_prefix`
+        #[arg(long = "no-prefix", requires = "create")]
+        no_prefix: bool,
+
+        /// Move the current worktree's uncommitted changes, untracked files included, to the new worktree
+        #[arg(long = "take-changes", requires = "create")]
+        take_changes: bool,
+
+        /// Create the worktree without checking out files (`git worktree add --no-checkout`)
+        #[arg(long = "no-checkout")]
+        no_checkout: bool,
+
+        /// Create the branch tracking this remote branch, e.g. upstream/feature
+        #[arg(
+            long = "track",
+            value_name = "REMOTE_BRANCH",
+            conflicts_with_all = ["create", "main", "remote", "pick"],
+            value_parser = non_blank
+        )]
+        track: Option<String>,
+
+        /// Print the path instead of changing to it; handled by the shell wrapper, and a no-op
+        /// when gwtree runs directly
+        #[arg(long = "no-cd", visible_alias = "print")]
+        no_cd: bool,
+
+        /// Print the path, branch, repository and whether the worktree was created as JSON; errors
+        /// are printed as JSON on stderr
+        #[arg(long = "json", conflicts_with = "tmux")]
+        json: bool,
+
+        /// Print a `cd` command for `eval "$(gwtree sw --eval <branch>)"` instead of the path, in
+        /// POSIX shell syntax or with `--eval=fish` in fish syntax
+        #[arg(
+            long = "eval",
+            value_name = "SHELL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "sh",
+            conflicts_with_all = ["json", "tmux", "no_cd"]
+        )]
+        eval: Option<crate::ui::EvalShell>,
     },
 
-    /// Remove a worktree by branch name
+    /// Remove a worktree by branch name or directory id
+    ///
+    /// Removes the worktree of a branch, a path inside it or its directory id after asking for
+    /// confirmation. When you're inside that worktree, the shell wrapper moves you to the main
+    /// worktree. `-b` also deletes the branch if it was merged, and `--gone` removes every worktree
+    /// whose upstream branch was deleted. The main worktree and locked worktrees are only removed
+    /// with `--allow-main` and `--force`.
     Rm {
-        /// Branch name of the worktree to remove
-        branch: String,
+        /// Branch name, path (e.g. `.`) or directory id prefix (see `gwt ls --ids`) of the worktree to remove; `-` reads branch names from stdin
+        #[arg(value_parser = non_blank, required_unless_present = "gone")]
+        branch: Option<String>,
+
+        /// Remove every worktree whose upstream branch was deleted, after one confirmation
+        #[arg(long = "gone", conflicts_with = "branch")]
+        gone: bool,
 
         /// Delete the branch after removing the worktree
         #[arg(short = 'b', long = "delete-branch")]
@@ -64,30 +245,603 @@ pub enum Commands {
         #[arg(short = 'B', long = "force-delete-branch")]
         force_delete_branch: bool,
 
+        /// Also delete the branch from its remote (`git push <remote> --delete`)
+        #[arg(long = "delete-remote", conflicts_with = "gone")]
+        delete_remote: bool,
+
         /// Skip confirmation prompt
         #[arg(short = 'y', long = "yes")]
         skip_confirmation: bool,
+
+        /// Remove the worktree even if it is locked
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+
+        /// Allow removing the main worktree
+        #[arg(long = "allow-main")]
+        allow_main: bool,
+
+        /// Don't run `post_remove_hook` from the config
+        #[arg(long = "no-hooks")]
+        no_hooks: bool,
+
+        /// Print a `cd` command to the main worktree for `eval` instead of its path, when the
+        /// removed worktree is the current one; `--eval=fish` for fish syntax
+        #[arg(
+            long = "eval",
+            value_name = "SHELL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "sh"
+        )]
+        eval: Option<crate::ui::EvalShell>,
     },
 
-    /// Output shell integration code for a given shell (bash, zsh, fish)
+    /// Output shell integration code for a given shell (bash, zsh, fish, powershell, nushell), or a starship module
+    ///
+    /// Prints code that defines a `gwt` shell function around `gwtree`, so `gwt sw`, `gwt rm` and
+    /// `gwt home` can change the shell's directory, and that sets up tab completion. Add
+    /// `eval "$(gwtree init)"` (or your shell's equivalent) to your shell's startup file.
     Init {
-        /// Shell name
-        shell: String,
+        /// Shell name (detected from $SHELL or the parent process when omitted)
+        shell: Option<String>,
+
+        /// Name or path of the gwtree binary to call (defaults to the running executable)
+        #[arg(long = "binary-name")]
+        binary_name: Option<String>,
+
+        /// Name of the shell function to define, e.g. `wt`
+        #[arg(long = "function-name", default_value = "gwt")]
+        function_name: String,
+
+        /// Stay in the current directory after `rm`, even when it was the removed worktree
+        #[arg(long = "no-cd-on-rm")]
+        no_cd_on_rm: bool,
+
+        /// Run `gwtree ls` after `sw` changed directory
+        #[arg(long = "ls-after-switch")]
+        ls_after_switch: bool,
     },
 
     /// Print current worktree and branch information
+    ///
+    /// Prints the branch checked out in the current worktree, or `(detached)`, and the worktree's
+    /// root directory.
     #[command(alias = "c")]
     Current,
 
     /// Generate shell completion scripts
+    ///
+    /// Prints a completion script for the shell, or with `--install` writes it to the shell's
+    /// standard per-user completion directory. The code from `gwtree init` already includes
+    /// completion, so this is only needed without the shell wrapper.
     Completion {
         /// Shell to generate completions for
         #[arg(value_enum)]
         shell: ShellType,
+
+        /// Name or path of the gwtree binary to call (defaults to the running executable)
+        #[arg(long = "binary-name")]
+        binary_name: Option<String>,
+
+        /// Write the script to the shell's standard completion directory instead of stdout
+        #[arg(long = "install")]
+        install: bool,
+
+        /// Overwrite an existing completion file when installing
+        #[arg(long = "force", requires = "install")]
+        force: bool,
     },
 
     /// Switch to the home worktree (original repository)
-    Home,
+    ///
+    /// Changes the shell into the main worktree of the current repository, from any of its
+    /// worktrees or subdirectories. Its path is printed on stdout for the shell wrapper.
+    #[command(alias = "main")]
+    Home {
+        /// Print a `cd` command for `eval "$(gwtree home --eval)"` instead of the path;
+        /// `--eval=fish` for fish syntax
+        #[arg(
+            long = "eval",
+            value_name = "SHELL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "sh"
+        )]
+        eval: Option<crate::ui::EvalShell>,
+    },
+
+    /// Run a command in one or all worktrees
+    ///
+    /// Runs the command after `--` with the worktree as its working directory and `GWT_BRANCH` and
+    /// `GWT_PATH` set. With `--all`, each output line is prefixed with the branch name, and gwt
+    /// stops at the first failure unless `--keep-going` is given.
+    Exec {
+        /// Branch, path or directory id prefix of the worktree to run the command in
+        #[arg(
+            short = 'b',
+            long = "branch",
+            conflicts_with = "all",
+            required_unless_present = "all",
+            value_parser = non_blank
+        )]
+        branch: Option<String>,
+
+        /// Run the command in every worktree
+        #[arg(short = 'a', long = "all")]
+        all: bool,
+
+        /// Keep going after a failure and summarize failures at the end
+        #[arg(short = 'k', long = "keep-going")]
+        keep_going: bool,
+
+        /// Number of worktrees to run the command in at the same time
+        #[arg(short = 'j', long = "jobs", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+
+        /// Command to run, followed by its arguments (after `--`)
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Run a command in the worktree of a branch without switching to it
+    ///
+    /// Runs the command after `--` in the branch's worktree, with the terminal attached and
+    /// `GWT_BRANCH` and `GWT_PATH` set, and exits with its status. The shell stays where it is.
+    /// With `--create`, a branch without a worktree gets one first, as with `gwt sw`.
+    Run {
+        /// Branch, path or directory id prefix of the worktree to run the command in
+        #[arg(value_parser = non_blank)]
+        branch: String,
+
+        /// Create the worktree if the branch doesn't have one yet
+        #[arg(short = 'c', long = "create")]
+        create: bool,
+
+        /// Command to run, followed by its arguments (after `--`)
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Clone a repository under the worktree root (prints the worktree path on success)
+    ///
+    /// Clones to `<worktree_root>/<name>/.repo` and changes the shell into the clone. With the
+    /// `clone_bare` config option the clone is bare, with a worktree for the default branch.
+    Clone {
+        /// Repository URL or path
+        url: String,
+
+        /// Directory name under the worktree root (defaults to the repository name)
+        name: Option<String>,
+    },
+
+    /// Lock a worktree so `git worktree prune` never removes it
+    ///
+    /// Locked worktrees survive `git worktree prune`, e.g. while they live on a drive that isn't
+    /// always mounted. `gwt ls` tags them, and `gwt rm` refuses to remove them without `--force`.
+    Lock {
+        /// Branch name (or directory id prefix) of the worktree to lock
+        #[arg(value_parser = non_blank)]
+        branch: String,
+
+        /// Why the worktree is locked (shown by `gwt ls`)
+        #[arg(long = "reason")]
+        reason: Option<String>,
+    },
+
+    /// Unlock a worktree locked with `gwt lock`
+    ///
+    /// Lets `git worktree prune` and `gwt rm` remove the worktree again.
+    Unlock {
+        /// Branch name (or directory id prefix) of the worktree to unlock
+        #[arg(value_parser = non_blank)]
+        branch: String,
+    },
+
+    /// Check out a pull request into its own worktree as branch `pr/<number>`
+    ///
+    /// Fetches `pull/<number>/head` from the remote into the branch `pr/<number>` and changes the
+    /// shell into its worktree, creating it if needed. Running it again fast-forwards the branch to
+    /// the latest PR head; local commits on the branch are never overwritten. Set `pr_refspec` for
+    /// GitLab merge requests.
+    Pr {
+        /// Pull request (or merge request) number
+        number: u32,
+
+        /// Remote to fetch the pull request from
+        #[arg(long, default_value = "origin")]
+        remote: String,
+    },
+
+    /// Rename a branch and move its worktree to the matching path
+    ///
+    /// Renames the branch with `git branch -m` and moves its worktree to the path `gwt sw` uses for
+    /// the new name, uncommitted changes included. The shell wrapper follows the worktree when
+    /// you're inside it.
+    Mv {
+        /// Branch, path or directory id prefix of the worktree to rename
+        #[arg(value_parser = non_blank)]
+        old: String,
+
+        /// New branch name
+        #[arg(value_parser = non_blank)]
+        new: String,
+    },
+
+    /// Remove worktrees that haven't been switched to for a while
+    ///
+    /// Removes the current repository's worktrees not switched to within the duration, after
+    /// listing them and asking for confirmation. The main worktree and the one you're in are never
+    /// removed, and locked or dirty worktrees are kept without `--force`. Branches are kept.
+    Clean {
+        /// Remove worktrees last used longer ago than this, e.g. 30d, 2w or 12h
+        #[arg(long = "older-than", value_parser = parse_duration)]
+        older_than: Duration,
+
+        /// Only remove worktrees whose branch was merged into the main branch
+        #[arg(long = "merged-only")]
+        merged_only: bool,
+
+        /// Show what would be removed without removing anything
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long = "yes")]
+        skip_confirmation: bool,
+
+        /// Also remove locked worktrees and worktrees with uncommitted changes
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+    },
+
+    /// Create worktrees for every remote branch matching a pattern
+    ///
+    /// Fetches the remote and creates a worktree, with a tracking branch when needed, for every
+    /// remote branch matching the pattern. Patterns follow `git for-each-ref`. With `--prune`,
+    /// worktrees of matching branches deleted on the remote are removed.
+    Sync {
+        /// Branch name pattern, e.g. 'release/*' (quote it so the shell doesn't expand it)
+        #[arg(value_parser = non_blank)]
+        pattern: String,
+
+        /// Remote to fetch and read branches from
+        #[arg(short = 'r', long = "remote", default_value = "origin")]
+        remote: String,
+
+        /// Also remove worktrees of matching branches deleted on the remote
+        #[arg(long = "prune")]
+        prune: bool,
+
+        /// Skip the confirmation prompt of --prune
+        #[arg(short = 'y', long = "yes")]
+        skip_confirmation: bool,
+    },
+
+    /// Write the current repository's worktrees to a manifest
+    ///
+    /// Prints a TOML (or JSON) manifest with the branch, upstream, path under the worktree root
+    /// and lock of every linked worktree, to recreate them with `gwt import` in another clone,
+    /// e.g. on a new machine. Detached worktrees are left out.
+    Export {
+        /// Manifest format
+        #[arg(long, value_enum, default_value_t)]
+        format: worktree::ManifestFormat,
+
+        /// Write the manifest to this file instead of stdout
+        #[arg(short = 'o', long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Recreate the worktrees listed in a manifest from `gwt export`
+    ///
+    /// Fetches every remote, then creates a worktree for each branch in the manifest, with a
+    /// tracking branch when it only exists on a remote, and locks the worktrees that were locked.
+    /// Prints what happened to each branch. Branches found nowhere are reported as missing without
+    /// failing the import.
+    Import {
+        /// Manifest file, TOML or JSON
+        file: PathBuf,
+    },
+
+    /// Move worktrees from an old worktree root to the configured one
+    ///
+    /// Moves the current repository's worktrees into the configured `worktree_root` with
+    /// `git worktree move`, at the paths `gwt sw` would use. Run it after changing `worktree_root`.
+    /// Locked worktrees and those with a missing directory are skipped.
+    Migrate {
+        /// Old worktree root; detected from the worktrees' paths when omitted
+        #[arg(long, value_name = "PATH")]
+        from: Option<PathBuf>,
+
+        /// Show what would be moved without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Reconnect worktrees whose directories moved or whose git links broke
+    ///
+    /// Runs `git worktree repair` for every worktree of the current repository, and re-registers
+    /// worktrees whose directory moved along with the worktree root, e.g. after the store was
+    /// copied to a new disk.
+    Repair,
+
+    /// Open a worktree in the editor (`editor_command`, `$VISUAL` or `$EDITOR`)
+    ///
+    /// Runs the editor on the worktree of the branch, or on the current worktree. Terminal editors
+    /// take over the terminal until you quit them.
+    Open {
+        /// Branch whose worktree to open (defaults to the current worktree)
+        #[arg(value_parser = non_blank)]
+        branch: Option<String>,
+
+        /// Create the worktree (and the branch, if needed) when it doesn't exist
+        #[arg(short = 'b', long = "create", requires = "branch")]
+        create: bool,
+    },
+
+    /// List recently used worktrees across all repositories
+    ///
+    /// Lists the worktrees most recently switched to with `gwt sw`, newest first, with how long ago
+    /// each was used. The history is kept in `~/.gwt/history`.
+    Recent {
+        /// Number of worktrees to show
+        #[arg(short = 'n', long = "count", default_value_t = 10)]
+        count: usize,
+
+        /// Choose one interactively and print its path (the shell wrapper changes to it)
+        #[arg(short = 'p', long = "pick")]
+        pick: bool,
+    },
+
+    /// Check the configuration, git, the worktree store and the shell wrapper
+    ///
+    /// Prints a pass, warn or fail line for the config file, the worktree root, the git version,
+    /// orphaned store directories, missing worktree directories and the shell wrapper, and exits
+    /// with 1 when a check fails.
+    Doctor {
+        /// Print machine-readable JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// Show the version and build of gwt and the git it runs
+    ///
+    /// Prints the gwt version, the commit and date it was built from, and the git executable gwt
+    /// runs with its version, for bug reports. Values the build couldn't determine are `unknown`.
+    Version {
+        /// Print machine-readable JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// Fetch once per repository and report which worktrees' upstreams moved
+    ///
+    /// Runs `git fetch --all --prune` once for the current repository, since worktrees share their
+    /// repository's objects, and reports for each worktree whether its upstream was updated, is up
+    /// to date or is gone. `--all` fetches every repository with worktrees in the store, in
+    /// parallel.
+    Fetch {
+        /// Fetch every repository with worktrees in the store, not just the current one
+        #[arg(short = 'a', long = "all")]
+        all: bool,
+    },
+
+    /// Show the disk usage of each worktree
+    ///
+    /// Measures each worktree of the current repository, largest first, with a total. The objects
+    /// shared through the repository aren't counted, and missing directories are marked.
+    Du {
+        /// Print machine-readable JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// List the repositories with worktrees in the worktree root
+    ///
+    /// Groups the worktree directories under `worktree_root` by the repository they belong to and
+    /// prints each repository's name, number of worktrees and main worktree. Directories that hold
+    /// no worktree of an existing repository are flagged as orphaned.
+    Repos {
+        /// Also show the disk usage of each repository's worktrees
+        #[arg(long = "du")]
+        du: bool,
+
+        /// Print machine-readable JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// Remove all worktrees of a repository from the worktree root
+    ///
+    /// Removes every worktree that a repository listed by `gwt repos` has under `worktree_root`,
+    /// after listing them and asking for confirmation, along with directories left empty. The
+    /// repository's main checkout is never removed. When the repository no longer exists, its
+    /// worktree directories are deleted instead.
+    Forget {
+        /// Repository name as shown by `gwt repos`, or the path of its main worktree
+        repo: String,
+
+        /// Show what would be removed without removing anything
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long = "yes")]
+        skip_confirmation: bool,
+
+        /// Also remove locked worktrees and worktrees with uncommitted changes
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+    },
+
+    /// Summarize the health of all worktrees (read-only, never prompts)
+    ///
+    /// Prints one line per worktree: clean or dirty, commits ahead of and behind the upstream, a
+    /// deleted upstream branch and a missing directory. It's meant for scripts and cron jobs and
+    /// never asks anything.
+    Status {
+        /// Print machine-readable JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+
+    /// Print a short segment for the shell prompt inside a managed worktree
+    ///
+    /// Prints nothing outside the worktree root and never prompts or fails loudly, so it's safe to
+    /// run before every prompt. The segment follows `--format` or the `prompt_format` config
+    /// option.
+    Prompt {
+        /// Template with {branch}, {repo} and {hash} placeholders (overrides `prompt_format`)
+        #[arg(long = "format", conflicts_with = "starship")]
+        format: Option<String>,
+
+        /// Print key=value lines (managed, repo, branch, dirty) for a Starship custom module
+        #[arg(long = "starship")]
+        starship: bool,
+    },
+
+    /// Show which repository, branch and worktree a directory belongs to
+    ///
+    /// Looks the directory up in the repository it belongs to, so it works from anywhere, e.g. for
+    /// a store path from `gwt ls` or a log.
+    Which {
+        /// Directory to look up (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+
+    /// Print the config path, worktree root and repository paths for scripts
+    ///
+    /// Prints `GWT_CONFIG_PATH`, `GWT_WORKTREE_ROOT`, `GWT_REPO_STORE` (where the current
+    /// repository's worktrees go) and `GWT_MAIN_WORKTREE` as `NAME=value` lines, quoted for the
+    /// shell, so `eval "$(gwt env)"` sets them. Outside a repository, the last two are left out.
+    Env {
+        /// Print machine-readable JSON
+        #[arg(long = "json", conflicts_with = "export")]
+        json: bool,
+
+        /// Prefix each line with `export `
+        #[arg(long = "export")]
+        export: bool,
+    },
+
+    /// List or test-run the configured hooks
+    ///
+    /// `gwt hooks list` shows each hook with its command, and `gwt hooks run <name>` runs one
+    /// against an existing worktree with the environment a real trigger would set, so a hook
+    /// can be tried without removing anything.
+    #[command(subcommand)]
+    Hooks(hooks::HooksCommands),
+
+    /// List the command aliases of the config
+    ///
+    /// Aliases are commands of your own, set in the `[aliases]` table of the config: with
+    /// `co = ["sw", "-b"]`, `gwt co feature` runs `gwt sw -b feature`. Built-in commands can't be
+    /// replaced, and an alias can't expand to another alias.
+    #[command(subcommand)]
+    Alias(alias::AliasCommands),
+
+    /// Write man pages or markdown reference pages for every command (for packagers)
+    #[command(hide = true)]
+    GenDocs {
+        /// Page format
+        #[arg(long = "format", value_enum, default_value_t = docs::DocFormat::Man)]
+        format: docs::DocFormat,
+
+        /// Directory to write the pages to
+        #[arg(long = "out", value_name = "DIR")]
+        out: PathBuf,
+    },
+}
+
+impl Commands {
+    /// Whether the command reads the config, which is created interactively
+    /// when missing. Commands that don't touch the worktree root skip it, so
+    /// they work in scripts and prompts on machines gwt was never set up on.
+    pub fn needs_config(&self) -> bool {
+        !matches!(
+            self,
+            // Only ask git
+            Self::Home { .. }
+                | Self::Current
+                | Self::Which { .. }
+                | Self::Version { .. }
+                // Only print shell code or docs
+                | Self::Init { .. }
+                | Self::Completion { .. }
+                | Self::GenDocs { .. }
+                // Writes the config rather than reading it
+                | Self::Config(config::ConfigCommands::Setup)
+                // Meant for shell prompts and cron jobs, so it must never prompt
+                | Self::Status { .. }
+                // Reads the config itself to report problems instead of failing on them
+                | Self::Doctor { .. }
+                | Self::Alias(_)
+        )
+    }
+
+    /// Whether errors should be reported as a JSON object on stderr
+    pub fn json_errors(&self) -> bool {
+        matches!(self, Self::Sw { json: true, .. })
+    }
+
+    /// Shell syntax of the `cd` command to print instead of a path (`--eval`)
+    pub fn eval_shell(&self) -> Option<crate::ui::EvalShell> {
+        match self {
+            Self::Sw { eval, .. } | Self::Rm { eval, .. } | Self::Home { eval } => *eval,
+            _ => None,
+        }
+    }
+
+    /// Whether the command may add, remove, move or lock worktrees, which
+    /// makes the cached worktree list out of date
+    pub fn changes_worktrees(&self) -> bool {
+        matches!(
+            self,
+            Self::Sw { .. }
+                | Self::Rm { .. }
+                | Self::Mv { .. }
+                | Self::Clean { .. }
+                | Self::Forget { .. }
+                | Self::Sync { .. }
+                | Self::Import { .. }
+                | Self::Pr { .. }
+                | Self::Lock { .. }
+                | Self::Unlock { .. }
+                | Self::Migrate { .. }
+                | Self::Repair
+                | Self::Open { .. }
+        )
+    }
+}
+
+/// Reject empty and whitespace-only branch arguments, which git would
+/// otherwise report in confusing ways
+fn non_blank(value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        Err("a branch name can't be empty".to_string())
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Parse a duration such as `30d`, `2w` or `12h`
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("'{value}' needs a unit: h, d or w (e.g. 30d)"))?;
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("'{value}' doesn't start with a number"))?;
+    let unit_secs = match unit {
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(format!("unknown unit '{unit}'; use h, d or w")),
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
 }
 
 #[derive(Clone, Copy, ValueEnum)]
@@ -95,4 +849,44 @@ pub enum ShellType {
     Bash,
     Zsh,
     Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+    #[value(name = "nushell", alias = "nu")]
+    Nushell,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_branch_arguments_are_rejected() {
+        for args in [
+            vec!["gwt", "sw", ""],
+            vec!["gwt", "sw", "-b", "  "],
+            vec!["gwt", "rm", ""],
+            vec!["gwt", "mv", "feature", " "],
+            vec!["gwt", "exec", "--branch", "", "--", "true"],
+        ] {
+            let err = Cli::try_parse_from(&args).err();
+            assert!(
+                err.is_some_and(|e| e.to_string().contains("can't be empty")),
+                "{args:?}"
+            );
+        }
+        assert!(Cli::try_parse_from(["gwt", "sw", "feature/ünïcode"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 3_600)));
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert_eq!(parse_duration("0d"), Ok(Duration::ZERO));
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3mo").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("").is_err());
+    }
 }
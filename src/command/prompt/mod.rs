@@ -0,0 +1,176 @@
+use crate::config::Config;
+use crate::utility::{Git, glyphs, in_store};
+use anyhow::Result;
+use std::env;
+use std::path::Path;
+
+/// What `gwt prompt` knows about the worktree it runs in
+#[derive(Debug, PartialEq, Eq)]
+struct Segment {
+    /// The branch, or the short commit hash on a detached HEAD
+    branch: String,
+    repo: String,
+    hash: String,
+}
+
+/// Print a short segment for the shell prompt, e.g. `⎇ feature/login [repo]`,
+/// when the current directory is inside the worktree root; nothing otherwise.
+/// With `starship`, print `key=value` lines for a Starship custom module
+/// instead (see `starship_lines`).
+///
+/// This runs before every prompt, so it never asks anything and never fails
+/// loudly. Outside the worktree root it only compares paths, without running
+/// git.
+pub fn handle(git: &Git, config: &Config, format: Option<&str>, starship: bool) -> Result<()> {
+    let cwd = env::current_dir().ok();
+    let segment = config
+        .data()
+        .zip(cwd.as_deref())
+        .filter(|(data, cwd)| in_store(cwd, &data.worktree_root))
+        .and_then(|_| read_segment(git));
+
+    if starship {
+        let dirty = segment
+            .as_ref()
+            .zip(cwd.as_deref())
+            .and_then(|(_, cwd)| git.is_dirty(cwd).ok());
+        for line in starship_lines(segment.as_ref(), dirty) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let (Some(segment), Some(data)) = (segment, config.data()) else {
+        return Ok(());
+    };
+    let default_format = format!("{} {{branch}} [{{repo}}]", glyphs().branch);
+    let format = format
+        .or(data.prompt_format.as_deref())
+        .unwrap_or(&default_format);
+    println!("{}", segment.render(format));
+    Ok(())
+}
+
+/// The `--starship` output. Every key is always printed, in this order, so
+/// modules can rely on it: `managed` is `true` inside a worktree under the
+/// worktree root, and the other values are empty when it's `false`.
+fn starship_lines(segment: Option<&Segment>, dirty: Option<bool>) -> Vec<String> {
+    let (branch, repo) = segment.map_or(("", ""), |s| (s.branch.as_str(), s.repo.as_str()));
+    vec![
+        format!("managed={}", segment.is_some()),
+        format!("repo={}", repo),
+        format!("branch={}", branch),
+        format!("dirty={}", dirty.map(|d| d.to_string()).unwrap_or_default()),
+    ]
+}
+
+/// Everything in one git call: the common dir names the repository, and the
+/// symbolic name of HEAD is `HEAD` itself when it's detached
+fn read_segment(git: &Git) -> Option<Segment> {
+    let output = git
+        .run(&[
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-common-dir",
+            "HEAD",
+            "--symbolic-full-name",
+            "HEAD",
+        ])
+        .ok()?;
+    parse_segment(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_segment(output: &str) -> Option<Segment> {
+    let mut lines = output.lines();
+    let common_dir = Path::new(lines.next()?);
+    let head = lines.next()?;
+    let symbolic = lines.next()?;
+
+    // `<repo>/.git`, or `<repo>.git` for a bare repository
+    let repo = match common_dir.file_name()?.to_str()? {
+        ".git" => common_dir.parent()?.file_name()?.to_str()?,
+        name => name.strip_suffix(".git").unwrap_or(name),
+    };
+    let hash = &head[..head.len().min(7)];
+    let branch = symbolic.strip_prefix("refs/heads/").unwrap_or(hash);
+    Some(Segment {
+        branch: branch.to_string(),
+        repo: repo.to_string(),
+        hash: hash.to_string(),
+    })
+}
+
+impl Segment {
+    fn render(&self, format: &str) -> String {
+        format
+            .replace("{branch}", &self.branch)
+            .replace("{repo}", &self.repo)
+            .replace("{hash}", &self.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_segment() {
+        let sha = "f2ca260a459e2e087464c322e05c543fb9fe03ad";
+        assert_eq!(
+            parse_segment(&format!(
+                "/code/gwt/.git\n{sha}\nrefs/heads/feature/login\n"
+            )),
+            Some(Segment {
+                branch: "feature/login".to_string(),
+                repo: "gwt".to_string(),
+                hash: "f2ca260".to_string(),
+            })
+        );
+
+        // Detached, in a bare repository
+        let segment = parse_segment(&format!("/code/gwt.git\n{sha}\nHEAD\n")).unwrap();
+        assert_eq!(segment.branch, "f2ca260");
+        assert_eq!(segment.repo, "gwt");
+
+        assert_eq!(parse_segment("/code/gwt/.git\n"), None);
+    }
+
+    #[test]
+    fn test_starship_lines() {
+        let segment = Segment {
+            branch: "feature/login".to_string(),
+            repo: "gwt".to_string(),
+            hash: "f2ca260".to_string(),
+        };
+        assert_eq!(
+            starship_lines(Some(&segment), Some(true)),
+            [
+                "managed=true",
+                "repo=gwt",
+                "branch=feature/login",
+                "dirty=true"
+            ]
+        );
+        assert_eq!(
+            starship_lines(None, None),
+            ["managed=false", "repo=", "branch=", "dirty="]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let segment = Segment {
+            branch: "feature/login".to_string(),
+            repo: "gwt".to_string(),
+            hash: "f2ca260".to_string(),
+        };
+        assert_eq!(
+            segment.render("⎇ {branch} [{repo}]"),
+            "⎇ feature/login [gwt]"
+        );
+        assert_eq!(
+            segment.render("{repo}:{branch}@{hash} {other}"),
+            "gwt:feature/login@f2ca260 {other}"
+        );
+    }
+}
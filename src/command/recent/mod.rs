@@ -0,0 +1,106 @@
+use crate::config::Config;
+use crate::history::{self, Entry};
+use crate::ui;
+use anyhow::{Context, Result, anyhow};
+use console::{Term, style};
+use dialoguer::Select;
+use std::path::Path;
+
+/// List the most recently visited worktrees across all repositories, or pick
+/// one interactively and print its path for the shell wrapper to cd into
+pub fn handle(config: &Config, count: usize, pick: bool) -> Result<()> {
+    let file = config
+        .history_path()
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+    let entries = history::recent(&file, count)?;
+
+    if entries.is_empty() {
+        ui::info!("No recently used worktrees. `gwt sw` records them.");
+        return Ok(());
+    }
+
+    let lines = render(&entries);
+
+    if !pick {
+        lines.iter().for_each(|line| println!("{}", line));
+        return Ok(());
+    }
+
+    // The prompt goes to stderr so stdout only ever carries the chosen path
+    let selection = Select::with_theme(&ui::theme())
+        .with_prompt("Switch to")
+        .items(&lines)
+        .default(0)
+        .interact_on_opt(&Term::stderr())
+        .map_err(ui::prompt_error)
+        .context("Failed to get selection")?;
+
+    if let Some(index) = selection {
+        println!("{}", entries[index].path.display());
+    }
+    Ok(())
+}
+
+/// `branch  repo  age  path` rows with aligned columns
+fn render(entries: &[Entry]) -> Vec<String> {
+    let repos: Vec<String> = entries.iter().map(|e| repo_name(&e.repo)).collect();
+    let ages: Vec<String> = entries
+        .iter()
+        .map(|e| history::relative_time(e.timestamp))
+        .collect();
+
+    let branch_width = entries.iter().map(|e| e.branch.len()).max().unwrap_or(0);
+    let repo_width = repos.iter().map(String::len).max().unwrap_or(0);
+    let age_width = ages.iter().map(String::len).max().unwrap_or(0);
+
+    entries
+        .iter()
+        .zip(repos.iter().zip(&ages))
+        .map(|(entry, (repo, age))| {
+            format!(
+                "{:<branch_width$}  {:<repo_width$}  {}  {}",
+                entry.branch,
+                repo,
+                style(format!("{:<age_width$}", age)).dim(),
+                style(entry.path.display()).cyan(),
+            )
+        })
+        .collect()
+}
+
+fn repo_name(repo: &Path) -> String {
+    repo.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| repo.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        console::set_colors_enabled(false);
+
+        let entries = [
+            Entry {
+                timestamp: 0,
+                repo: PathBuf::from("/code/api"),
+                branch: "feature-long".to_string(),
+                path: PathBuf::from("/store/1111"),
+            },
+            Entry {
+                timestamp: 0,
+                repo: PathBuf::from("/code/website"),
+                branch: "main".to_string(),
+                path: PathBuf::from("/code/website"),
+            },
+        ];
+
+        let lines = render(&entries);
+        assert!(lines[0].starts_with("feature-long  api      "), "{lines:?}");
+        assert!(lines[1].starts_with("main          website  "), "{lines:?}");
+        assert!(lines[0].ends_with("  /store/1111"));
+    }
+}
@@ -0,0 +1,347 @@
+use super::{Repo, find_worktrees, scan};
+use crate::command::worktree::{dirty_or, remove_empty_parents, repo_name};
+use crate::config::Config;
+use crate::error::GwtError;
+use crate::ui;
+use crate::utility::{Git, GitBackend, Worktree, canonical, pointed_repository};
+use anyhow::{Context, Result, anyhow, bail};
+use console::{Term, style};
+use dialoguer::Confirm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Remove every worktree of the repository `repo` from the worktree root.
+///
+/// `repo` is a name as printed by `gwt repos`, or the path of the
+/// repository's main worktree when several share a name. The main checkout is
+/// never removed, not even when it lives in the store. When the repository
+/// is gone, its leftover worktree directories are deleted instead.
+pub fn forget(
+    config: &Config,
+    repo: &str,
+    dry_run: bool,
+    skip_confirmation: bool,
+    force: bool,
+) -> Result<()> {
+    let root = config
+        .data()
+        .map(|d| d.worktree_root.clone())
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+    let report = scan(&root)?;
+
+    let by_path = canonical(Path::new(repo));
+    let matches: Vec<&Repo> = report
+        .repos
+        .iter()
+        .filter(|r| r.name == repo || r.main_worktree == by_path)
+        .collect();
+    match matches.as_slice() {
+        [found] => forget_live(config, &root, found, dry_run, skip_confirmation, force),
+        [] => {
+            let leftovers = leftovers(&report.orphaned, repo);
+            if leftovers.is_empty() {
+                return Err(GwtError::Usage(format!(
+                    "No repository '{}' in '{}'. `gwt repos` lists them.",
+                    repo,
+                    root.display()
+                ))
+                .into());
+            }
+            forget_gone(config, repo, &leftovers, dry_run, skip_confirmation)
+        }
+        _ => {
+            let paths: Vec<String> = matches
+                .iter()
+                .map(|r| format!("  {}", r.main_worktree.display()))
+                .collect();
+            Err(GwtError::Usage(format!(
+                "Several repositories are named '{}'; pass the path of one instead:\n{}",
+                repo,
+                paths.join("\n")
+            ))
+            .into())
+        }
+    }
+}
+
+fn forget_live(
+    config: &Config,
+    root: &Path,
+    repo: &Repo,
+    dry_run: bool,
+    skip_confirmation: bool,
+    force: bool,
+) -> Result<()> {
+    let git = Git::new().with_cwd(&repo.main_worktree);
+    let root = canonical(root);
+    let worktrees = git.list_worktrees()?;
+    let remove: Vec<&Worktree> = worktrees
+        .iter()
+        .filter(|wt| !wt.is_main() && canonical(wt.path()).starts_with(&root))
+        .collect();
+
+    if repo.main_worktree.starts_with(&root) {
+        ui::info!(
+            "{}",
+            style(format!(
+                "Keeping the main checkout at '{}'; remove it yourself if it's no longer needed.",
+                repo.main_worktree.display()
+            ))
+            .dim()
+        );
+    }
+    if remove.is_empty() {
+        ui::info!("'{}' has no worktrees to remove.", repo.name);
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}",
+        if dry_run {
+            "Would remove:"
+        } else {
+            "Worktrees to remove:"
+        }
+    );
+    for wt in &remove {
+        eprintln!(
+            "  {}  {}",
+            wt.branch().unwrap_or("(detached)"),
+            style(wt.path().display()).cyan()
+        );
+    }
+    if dry_run || !confirm(&repo.name, remove.len(), skip_confirmation)? {
+        return Ok(());
+    }
+
+    let mut progress = ui::Progress::new(remove.len());
+    for wt in &remove {
+        progress.run(&wt.path().display().to_string(), || {
+            remove_one(&git, wt, force)?;
+            remove_empty_parents(config, wt.path());
+            Ok(())
+        });
+    }
+    progress.print_summary();
+
+    let failed = progress.failed();
+    if failed > 0 {
+        bail!(
+            "{} of {} worktrees could not be removed",
+            failed,
+            remove.len()
+        );
+    }
+    Ok(())
+}
+
+/// Delete the worktree directories of a repository that no longer exists.
+/// git can't remove them, and its stale registrations are left for
+/// `git worktree prune` in case the repository was only moved.
+fn forget_gone(
+    config: &Config,
+    repo: &str,
+    leftovers: &[(PathBuf, PathBuf)],
+    dry_run: bool,
+    skip_confirmation: bool,
+) -> Result<()> {
+    ui::warn!(
+        "Repository '{}' no longer exists at '{}'; its worktree directories can only be deleted.",
+        repo,
+        leftovers[0].1.display()
+    );
+    eprintln!(
+        "{}",
+        if dry_run {
+            "Would delete:"
+        } else {
+            "Directories to delete:"
+        }
+    );
+    for (path, _) in leftovers {
+        eprintln!("  {}", style(path.display()).cyan());
+    }
+    if dry_run || !confirm(repo, leftovers.len(), skip_confirmation)? {
+        return Ok(());
+    }
+
+    for (path, _) in leftovers {
+        fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to delete '{}'", path.display()))?;
+        remove_empty_parents(config, path);
+    }
+    ui::info!("Deleted {} worktrees of '{}'.", leftovers.len(), repo);
+    ui::warn!(
+        "If the repository was moved, run `git worktree prune` in it to drop the deleted worktrees."
+    );
+    Ok(())
+}
+
+/// Worktrees under the orphaned directories whose repository, now gone, is
+/// named `repo` (or is at that path), each with that repository's path
+fn leftovers(orphaned: &[PathBuf], repo: &str) -> Vec<(PathBuf, PathBuf)> {
+    let mut leftovers = Vec::new();
+    for dir in orphaned {
+        let mut found = Vec::new();
+        find_worktrees(dir, 1, &mut found);
+        for (path, _) in found {
+            if let Some(repository) = pointed_repository(&path)
+                && (repo_name(&repository) == repo || repository == Path::new(repo))
+            {
+                leftovers.push((path, repository));
+            }
+        }
+    }
+    leftovers
+}
+
+fn confirm(repo: &str, count: usize, skip_confirmation: bool) -> Result<bool> {
+    if skip_confirmation {
+        return Ok(true);
+    }
+    let confirmed = Confirm::new()
+        .with_prompt(format!("Remove {} worktrees of '{}'?", count, repo))
+        .default(false)
+        .interact_on(&Term::stderr())
+        .map_err(ui::prompt_error)
+        .context("Failed to get confirmation")?;
+    if !confirmed {
+        ui::info!("Removal cancelled.");
+    }
+    Ok(confirmed)
+}
+
+fn remove_one(git: &dyn GitBackend, wt: &Worktree, force: bool) -> Result<()> {
+    let path = wt
+        .path()
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    if let Some(reason) = wt.locked() {
+        if !force {
+            return Err(GwtError::WorktreeLocked {
+                path: wt.path().clone(),
+                reason: reason.to_string(),
+            }
+            .into());
+        }
+        git.unlock_worktree(path)
+            .context("Failed to unlock worktree")?;
+    }
+    if force {
+        git.force_remove_worktree(path)
+    } else {
+        git.remove_worktree(path)
+            .map_err(|e| dirty_or(e, wt.path()))
+    }
+    .context("Failed to remove worktree")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+    use crate::command::worktree::test_utils::temp_config;
+
+    /// Repositories `api` (worktrees `a1`, `nested/a2`, locked `a3`) and `web`
+    /// (worktree `w1`) next to the worktree root
+    fn setup() -> (tempfile::TempDir, Config, PathBuf) {
+        let (dir, config) = temp_config();
+        let root = config.data().unwrap().worktree_root.canonicalize().unwrap();
+        sh(
+            dir.path(),
+            "git init -q -b main api && git -C api commit -q --allow-empty -m init && \
+             git init -q -b main web && git -C web commit -q --allow-empty -m init && \
+             git -C api worktree add -q ../wt-root/a1 -b a1 && \
+             git -C api worktree add -q ../wt-root/nested/a2 -b a2 && \
+             git -C api worktree add -q --lock ../wt-root/a3 -b a3 && \
+             git -C web worktree add -q ../wt-root/w1 -b w1",
+        );
+        (dir, config, root)
+    }
+
+    #[test]
+    fn test_forget_removes_the_repositorys_worktrees() {
+        let (dir, config, root) = setup();
+
+        forget(&config, "api", true, true, false).unwrap();
+        assert!(root.join("a1").exists(), "dry run removed a worktree");
+
+        let err = forget(&config, "api", false, true, false).unwrap_err();
+        assert!(err.to_string().contains("1 of 3"), "{err}");
+        assert!(!root.join("a1").exists());
+        assert!(!root.join("nested").exists());
+        assert!(root.join("a3").exists(), "locked worktree removed");
+        assert!(root.join("w1").exists());
+        assert!(dir.path().join("api/.git").exists());
+
+        forget(&config, "api", false, true, true).unwrap();
+        assert!(!root.join("a3").exists());
+        let report = scan(&root).unwrap();
+        let names: Vec<_> = report.repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["web"]);
+    }
+
+    #[test]
+    fn test_forget_unknown_repository() {
+        let (_dir, config, _root) = setup();
+        let err = forget(&config, "nope", false, true, false).unwrap_err();
+        assert!(matches!(GwtError::find(&err), Some(GwtError::Usage(_))));
+        assert!(err.to_string().contains("gwt repos"), "{err}");
+    }
+
+    #[test]
+    fn test_forget_ambiguous_name_takes_a_path() {
+        let (dir, config, root) = temp_config_with_two_apis();
+        let err = forget(&config, "api", false, true, false).unwrap_err();
+        assert!(err.to_string().contains("Several repositories"), "{err}");
+
+        let work = dir.path().join("work/api");
+        forget(&config, work.to_str().unwrap(), false, true, false).unwrap();
+        assert!(!root.join("1111").exists());
+        assert!(root.join("2222").exists());
+    }
+
+    fn temp_config_with_two_apis() -> (tempfile::TempDir, Config, PathBuf) {
+        let (dir, config) = temp_config();
+        let root = config.data().unwrap().worktree_root.canonicalize().unwrap();
+        sh(
+            dir.path(),
+            "git init -q -b main work/api && git -C work/api commit -q --allow-empty -m init && \
+             git init -q -b main fork/api && git -C fork/api commit -q --allow-empty -m init && \
+             git -C work/api worktree add -q ../../wt-root/1111 -b one && \
+             git -C fork/api worktree add -q ../../wt-root/2222 -b two",
+        );
+        (dir, config, root)
+    }
+
+    #[test]
+    fn test_forget_keeps_a_clone_in_the_store() {
+        let (_dir, config) = temp_config();
+        let root = config.data().unwrap().worktree_root.canonicalize().unwrap();
+        sh(
+            &root,
+            "git init -q -b main proj/.repo && \
+             git -C proj/.repo commit -q --allow-empty -m init && \
+             git -C proj/.repo worktree add -q ../feature -b feature",
+        );
+
+        forget(&config, "proj", false, true, false).unwrap();
+        assert!(!root.join("proj/feature").exists());
+        assert!(root.join("proj/.repo/.git").exists());
+    }
+
+    #[test]
+    fn test_forget_deletes_worktrees_of_a_deleted_repository() {
+        let (dir, config, root) = setup();
+        fs::remove_dir_all(dir.path().join("api")).unwrap();
+
+        forget(&config, "api", true, true, false).unwrap();
+        assert!(root.join("a1").exists(), "dry run deleted a directory");
+
+        forget(&config, "api", false, true, false).unwrap();
+        for name in ["a1", "nested", "a3"] {
+            assert!(!root.join(name).exists(), "{name} left behind");
+        }
+        assert!(root.join("w1").exists());
+    }
+}
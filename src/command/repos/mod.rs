@@ -0,0 +1,355 @@
+use super::du::{dir_size, human_size};
+use super::worktree::repo_name;
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{canonical, repository_path, worktree_common_dir};
+use anyhow::{Context, Result, anyhow};
+use console::style;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+mod forget;
+
+pub use forget::forget;
+
+/// How far below the worktree root worktrees are looked for, enough for
+/// `group_by_remote` layouts with GitLab subgroups
+const MAX_DEPTH: usize = 6;
+
+/// A repository with worktrees in the store, as printed by `gwt repos`
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Repo {
+    name: String,
+    /// The repository's main worktree, or the repository itself when it's bare
+    main_worktree: PathBuf,
+    /// Its worktrees under the worktree root
+    worktrees: Vec<PathBuf>,
+    /// Disk usage of those worktrees; only measured with `--du`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    repos: Vec<Repo>,
+    /// Directories under the worktree root without a worktree of an existing
+    /// repository
+    orphaned: Vec<PathBuf>,
+}
+
+pub fn handle(config: &Config, du: bool, json: bool) -> Result<()> {
+    let root = config
+        .data()
+        .map(|d| d.worktree_root.clone())
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+
+    let mut report = scan(&root)?;
+    if du {
+        measure(&mut report.repos);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if report.repos.is_empty() && report.orphaned.is_empty() {
+        ui::info!("No repositories in '{}'.", root.display());
+        return Ok(());
+    }
+    for line in render(&report) {
+        println!("{}", line);
+    }
+    if !report.orphaned.is_empty() {
+        ui::warn!(
+            "{} orphaned directories belong to no existing repository. Remove them with `rm -rf <path>` once you're sure nothing in them is needed.",
+            report.orphaned.len()
+        );
+    }
+    Ok(())
+}
+
+/// Group the worktrees under `root` by repository.
+///
+/// Like `scan_store`, a worktree's repository is found by following its `.git`
+/// pointer to the common dir. Directories that aren't worktrees are searched
+/// for nested ones, as laid out by `path_template` or `group_by_remote`.
+fn scan(root: &Path) -> Result<Report> {
+    let mut repos: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    let mut orphaned = Vec::new();
+
+    let dirs = subdirectories(root)
+        .with_context(|| format!("Failed to read worktree root '{}'", root.display()))?;
+    for dir in dirs {
+        let mut found = Vec::new();
+        find_worktrees(&dir, 1, &mut found);
+        if !found.iter().any(|(_, common_dir)| common_dir.is_some()) {
+            orphaned.push(dir);
+            continue;
+        }
+        for (path, common_dir) in found {
+            match common_dir {
+                Some(common_dir) => repos.entry(common_dir).or_default().push(path),
+                None => orphaned.push(path),
+            }
+        }
+    }
+
+    let mut repos: Vec<Repo> = repos
+        .into_iter()
+        .map(|(common_dir, mut worktrees)| {
+            worktrees.sort();
+            let main_worktree = repository_path(&common_dir).to_path_buf();
+            Repo {
+                name: repo_name(&main_worktree),
+                main_worktree,
+                worktrees,
+                bytes: None,
+            }
+        })
+        .collect();
+    repos.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.main_worktree.cmp(&b.main_worktree))
+    });
+    orphaned.sort();
+    Ok(Report { repos, orphaned })
+}
+
+/// Main worktrees of the repositories with worktrees under `root`, in the
+/// order `gwt repos` lists them
+pub(crate) fn repository_paths(root: &Path) -> Result<Vec<PathBuf>> {
+    Ok(scan(root)?
+        .repos
+        .into_iter()
+        .map(|repo| repo.main_worktree)
+        .collect())
+}
+
+/// The worktrees under `root` whose repository is gone or no longer has them
+/// registered, and the directories that hold no worktree at all. Unlike
+/// `gwt repos`, orphaned worktrees nested in a directory are listed one by one.
+pub(crate) fn orphaned_paths(root: &Path) -> Result<Vec<PathBuf>> {
+    let dirs = subdirectories(root)
+        .with_context(|| format!("Failed to read worktree root '{}'", root.display()))?;
+    let mut orphaned = Vec::new();
+    for dir in dirs {
+        let mut found = Vec::new();
+        find_worktrees(&dir, 1, &mut found);
+        if found.is_empty() {
+            orphaned.push(dir);
+        }
+        orphaned.extend(
+            found
+                .into_iter()
+                .filter(|(_, common_dir)| common_dir.is_none())
+                .map(|(path, _)| path),
+        );
+    }
+    orphaned.sort();
+    Ok(orphaned)
+}
+
+/// Collect the worktrees at or below `dir`, each with the common dir of its
+/// repository, or `None` when that repository is gone
+fn find_worktrees(dir: &Path, depth: usize, found: &mut Vec<(PathBuf, Option<PathBuf>)>) {
+    let dot_git = dir.join(".git");
+    let common_dir = if dot_git.is_file() {
+        Some(worktree_common_dir(dir))
+    } else if dot_git.is_dir() {
+        // A regular clone, e.g. the `.repo` of `gwt clone`
+        Some(Some(canonical(&dot_git)))
+    } else if dir.join("HEAD").is_file() && dir.join("objects").is_dir() {
+        // The bare repository of `gwt clone` with `clone_bare`
+        Some(Some(canonical(dir)))
+    } else {
+        None
+    };
+    if let Some(common_dir) = common_dir {
+        found.push((dir.to_path_buf(), common_dir));
+        return;
+    }
+
+    if depth < MAX_DEPTH {
+        for sub in subdirectories(dir).unwrap_or_default() {
+            find_worktrees(&sub, depth + 1, found);
+        }
+    }
+}
+
+/// Directories directly in `dir`; symlinks aren't followed
+fn subdirectories(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Measure each repository's worktrees on a thread of its own, like `gwt du`
+fn measure(repos: &mut [Repo]) {
+    thread::scope(|scope| {
+        for repo in repos.iter_mut() {
+            scope.spawn(move || {
+                repo.bytes = Some(repo.worktrees.iter().filter_map(|p| dir_size(p)).sum());
+            });
+        }
+    });
+}
+
+fn render(report: &Report) -> Vec<String> {
+    let counts: Vec<String> = report
+        .repos
+        .iter()
+        .map(|r| match r.worktrees.len() {
+            1 => "1 worktree".to_string(),
+            n => format!("{} worktrees", n),
+        })
+        .collect();
+    let sizes: Vec<String> = report
+        .repos
+        .iter()
+        .map(|r| r.bytes.map(human_size).unwrap_or_default())
+        .collect();
+    let name_width = report.repos.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    let count_width = counts.iter().map(String::len).max().unwrap_or(0);
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+
+    let mut lines: Vec<String> = report
+        .repos
+        .iter()
+        .zip(counts.iter().zip(&sizes))
+        .map(|(repo, (count, size))| {
+            let size = if size_width > 0 {
+                format!("{:>size_width$}  ", size)
+            } else {
+                String::new()
+            };
+            format!(
+                "{:<name_width$}  {:<count_width$}  {}{}",
+                repo.name,
+                count,
+                size,
+                style(repo.main_worktree.display()).cyan()
+            )
+        })
+        .collect();
+    lines.extend(
+        report
+            .orphaned
+            .iter()
+            .map(|path| format!("{}  {}", style("[orphaned]").red(), path.display())),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+
+    #[test]
+    fn test_scan_groups_worktrees_by_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        let root = dir.join("store");
+        sh(
+            &dir,
+            "git init -q -b main api && git -C api commit -q --allow-empty -m init && \
+             git init -q -b main web && git -C web commit -q --allow-empty -m init && \
+             mkdir -p store/nested && \
+             git -C api worktree add -q ../store/a1 -b a1 && \
+             git -C api worktree add -q ../store/nested/a2 -b a2 && \
+             git -C web worktree add -q ../store/w1 -b w1 && \
+             git -C web worktree add -q ../store/gone -b gone && \
+             rm -rf web/.git/worktrees/gone && \
+             mkdir -p store/empty/dir",
+        );
+
+        let report = scan(&root).unwrap();
+        let summary: Vec<_> = report
+            .repos
+            .iter()
+            .map(|r| {
+                (
+                    r.name.as_str(),
+                    r.main_worktree.clone(),
+                    r.worktrees.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "api",
+                    dir.join("api"),
+                    vec![root.join("a1"), root.join("nested/a2")]
+                ),
+                ("web", dir.join("web"), vec![root.join("w1")]),
+            ]
+        );
+        assert_eq!(report.orphaned, [root.join("empty"), root.join("gone")]);
+    }
+
+    #[test]
+    fn test_scan_finds_clones_in_the_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        let root = dir.join("store");
+        sh(
+            &dir,
+            "git init -q -b main store/proj/.repo && \
+             git -C store/proj/.repo commit -q --allow-empty -m init && \
+             git -C store/proj/.repo worktree add -q ../feature -b feature && \
+             git init -q --bare store/bare/.repo",
+        );
+
+        let report = scan(&root).unwrap();
+        let names: Vec<_> = report
+            .repos
+            .iter()
+            .map(|r| (r.name.as_str(), r.worktrees.len()))
+            .collect();
+        assert_eq!(names, [("bare", 1), ("proj", 2)]);
+        assert!(report.orphaned.is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let report = Report {
+            repos: vec![
+                Repo {
+                    name: "api".to_string(),
+                    main_worktree: PathBuf::from("/src/api"),
+                    worktrees: vec![PathBuf::from("/store/a1"), PathBuf::from("/store/a2")],
+                    bytes: Some(2048),
+                },
+                Repo {
+                    name: "website".to_string(),
+                    main_worktree: PathBuf::from("/src/website"),
+                    worktrees: vec![PathBuf::from("/store/w1")],
+                    bytes: Some(10),
+                },
+            ],
+            orphaned: vec![PathBuf::from("/store/old")],
+        };
+        console::set_colors_enabled(false);
+        assert_eq!(
+            render(&report),
+            [
+                "api      2 worktrees  2.0 KiB  /src/api",
+                "website  1 worktree      10 B  /src/website",
+                "[orphaned]  /store/old",
+            ]
+        );
+    }
+}
@@ -1,77 +1,445 @@
-use anyhow::{Result, anyhow};
+use crate::ui;
+use anyhow::{Result, anyhow, bail};
 
-pub fn handle(shell: &str) -> Result<()> {
-    println!("{}", generate_init(shell)?);
+const DEFAULT_BINARY_NAME: &str = "gwtree";
+const DEFAULT_FUNCTION_NAME: &str = "gwt";
+const SUPPORTED_SHELLS: &str = "bash, zsh, fish, powershell, nushell";
+const DETECTABLE_SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell", "pwsh", "nushell", "nu"];
+
+/// How the generated shell function behaves, set with the `gwtree init` flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitOptions {
+    /// Name of the shell function
+    pub function_name: String,
+    /// Change directory after `rm`, to the main worktree when the current
+    /// one was removed
+    pub cd_on_rm: bool,
+    /// Run `gwtree ls` after `sw` changed directory
+    pub ls_after_switch: bool,
+}
+
+impl Default for InitOptions {
+    fn default() -> Self {
+        Self {
+            function_name: DEFAULT_FUNCTION_NAME.to_string(),
+            cd_on_rm: true,
+            ls_after_switch: false,
+        }
+    }
+}
+
+impl InitOptions {
+    fn validate(&self) -> Result<()> {
+        let name = &self.function_name;
+        // The name is pasted into shell code unquoted
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            bail!(
+                "Invalid function name '{name}'. Use letters, digits, '_' and '-', starting with a letter or '_'."
+            );
+        }
+        Ok(())
+    }
+
+    /// Only the bash, zsh and fish functions can change how `rm` and `sw` behave
+    fn require_default_behavior(&self, shell: &str) -> Result<()> {
+        if !self.cd_on_rm || self.ls_after_switch {
+            bail!(
+                "--no-cd-on-rm and --ls-after-switch aren't supported for {shell}. Supported: bash, zsh, fish"
+            );
+        }
+        Ok(())
+    }
+}
+
+pub fn handle(shell: Option<&str>, binary_name: Option<&str>, options: &InitOptions) -> Result<()> {
+    let binary = resolve_binary_name(binary_name)?;
+
+    let shell = match shell {
+        Some(shell) => shell.to_string(),
+        None => {
+            let shell_env = std::env::var("SHELL").ok();
+            let shell = detect_shell(shell_env.as_deref(), parent_process_name().as_deref())
+                .ok_or_else(|| {
+                    anyhow!("Unable to detect your shell. Pass it explicitly. Supported: {SUPPORTED_SHELLS}")
+                })?;
+            // stderr only, so `eval "$(gwtree init)"` still receives just the script
+            ui::info!("Detected shell: {shell}");
+            shell
+        }
+    };
+
+    println!("{}", generate_init(&shell, &binary, options)?);
     Ok(())
 }
 
-fn generate_init(shell: &str) -> Result<String> {
-    match shell {
-        "bash" => Ok(r#"gwt() {
-    if [ "$1" = "switch" ] || [ "$1" = "sw" ]; then
-        for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
-                command gwtree "$@"
-                return
-            fi
-        done
-        local result
-        result=$(command gwtree sw "${@:2}")
-        local exit_code=$?
-        if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            else
-                printf "%s\n" "$result"
-            fi
+/// Name of the binary the generated shell code should call.
+///
+/// Defaults to the file name of the running executable, so installs under a
+/// different name (`gwt-bin`, a cargo alias, ...) keep working. A bare name is
+/// preferred over the full path so the generated code survives reinstalls to a
+/// different location.
+pub(crate) fn resolve_binary_name(binary_name: Option<&str>) -> Result<String> {
+    let name = match binary_name {
+        Some(name) => name.to_string(),
+        None => std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .map(|name| name.strip_suffix(".exe").unwrap_or(&name).to_string())
+            .unwrap_or_else(|| DEFAULT_BINARY_NAME.to_string()),
+    };
+
+    // The name is pasted into shell code unquoted
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-+/\\:~".contains(c));
+    if !valid {
+        bail!("Invalid binary name '{name}'. Use a name or path without spaces or quotes.");
+    }
+
+    Ok(name)
+}
+
+/// Pick the shell to generate integration code for when none was given.
+///
+/// `$SHELL` is consulted first, then the name of the parent process. When
+/// neither is a supported shell the `$SHELL` value is returned anyway so the
+/// caller reports it as unsupported.
+fn detect_shell(shell_env: Option<&str>, parent_process: Option<&str>) -> Option<String> {
+    let from_env = shell_env.and_then(shell_name);
+    let supported = |name: &&str| DETECTABLE_SHELLS.contains(name);
+
+    from_env
+        .filter(supported)
+        .or_else(|| parent_process.and_then(shell_name).filter(supported))
+        .or(from_env)
+        .map(String::from)
+}
+
+/// Normalize a shell path or process name: `/usr/local/bin/fish` -> `fish`,
+/// `-zsh` (login shell) -> `zsh`, `pwsh.exe` -> `pwsh`
+fn shell_name(path: &str) -> Option<&str> {
+    let name = path.trim().rsplit(['/', '\\']).next()?;
+    let name = name.trim_start_matches('-');
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(unix)]
+fn parent_process_name() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    if let Ok(comm) = std::fs::read_to_string(format!("/proc/{ppid}/comm")) {
+        return Some(comm.trim().to_string());
+    }
+
+    // No procfs (macOS, BSD)
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(unix))]
+fn parent_process_name() -> Option<String> {
+    None
+}
+
+/// `gwtree` in the generated code is an invocation of the binary and becomes
+/// `binary`; `gwt` is the shell function and becomes `options.function_name`.
+fn generate_init(shell: &str, binary: &str, options: &InitOptions) -> Result<String> {
+    options.validate()?;
+
+    let script = match shell {
+        "bash" => format!("{}\n{}", posix_function(options), BASH_COMPLETION),
+        "zsh" => format!("{}\n{}", posix_function(options), ZSH_COMPLETION),
+        "fish" => format!("{}\n{}", fish_function(options), FISH_COMPLETION),
+        "powershell" | "pwsh" => {
+            options.require_default_behavior(shell)?;
+            POWERSHELL.to_string()
+        }
+        "nushell" | "nu" => {
+            options.require_default_behavior(shell)?;
+            NUSHELL.to_string()
+        }
+        // Not a shell: a module to paste into starship.toml
+        "starship" => {
+            if *options != InitOptions::default() {
+                bail!(
+                    "--function-name, --no-cd-on-rm and --ls-after-switch don't apply to the starship module"
+                );
+            }
+            return Ok(STARSHIP.replace(DEFAULT_BINARY_NAME, binary));
+        }
+        _ => bail!("Unsupported shell '{shell}'. Supported: {SUPPORTED_SHELLS}"),
+    };
+
+    Ok(substitute_names(&script, binary, &options.function_name))
+}
+
+/// Replace the words `gwtree` and `gwt` in `script`, leaving names that only
+/// contain them (`_gwt_completions`, `GWT_SHELL_WRAPPER`, `gwt-managed`) alone
+fn substitute_names(script: &str, binary: &str, function_name: &str) -> String {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+
+    let mut out = String::with_capacity(script.len());
+    let mut rest = script;
+    while let Some(start) = rest.find(is_word) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c| !is_word(c)).unwrap_or(rest.len());
+        out.push_str(match &rest[..end] {
+            DEFAULT_BINARY_NAME => binary,
+            DEFAULT_FUNCTION_NAME => function_name,
+            word => word,
+        });
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The `gwt` function for bash and zsh
+fn posix_function(options: &InitOptions) -> String {
+    let ls = if options.ls_after_switch {
+        "\n                command gwtree ls || true"
+    } else {
+        ""
+    };
+
+    let mut branches = vec![posix_branch(
+        r#"[ "$1" = "switch" ] || [ "$1" = "sw" ]"#,
+        r#"sw "${@:2}""#,
+        true,
+        ls,
+        true,
+    )];
+    if options.cd_on_rm {
+        branches.push(posix_branch(
+            r#"[ "$1" = "remove" ] || [ "$1" = "rm" ]"#,
+            r#"rm "${@:2}""#,
+            false,
+            "",
+            false,
+        ));
+    }
+    branches.extend([
+        posix_branch(r#"[ "$1" = "mv" ]"#, r#"mv "${@:2}""#, false, "", false),
+        posix_branch(
+            r#"[ "$1" = "home" ] || [ "$1" = "main" ]"#,
+            r#"home "${@:2}""#,
+            true,
+            "",
+            false,
+        ),
+        posix_branch(
+            r#"[ "$1" = "recent" ] || [ "$1" = "clone" ] || [ "$1" = "pr" ]"#,
+            r#""$@""#,
+            true,
+            "",
+            false,
+        ),
+    ]);
+
+    format!(
+        r#"export GWT_SHELL_WRAPPER=1
+
+gwt() {{
+    if {}    else
+        # An alias of the config runs through this function again, so one
+        # for `sw` changes directory too
+        local expanded
+        expanded=$(command gwtree alias expand sh -- "$@" 2>/dev/null)
+        if [ -n "$expanded" ]; then
+            eval "gwt $expanded"
         else
-            printf "%s\n" "$result" >&2
-            return $exit_code
+            command gwtree "$@"
         fi
-    elif [ "$1" = "remove" ] || [ "$1" = "rm" ]; then
+    fi
+}}
+"#,
+        branches.join("    elif ")
+    )
+}
+
+/// One `if`/`elif` branch of [`posix_function`]: run gwtree with `args` when
+/// `test` matches and change to the directory it prints. Any other output is
+/// printed when `print_other` is set; `after_cd` runs after changing directory.
+/// With `no_cd`, a `--no-cd` or `--print` argument is taken out and the
+/// directory printed instead of changed to. With `--help`, `--json` or
+/// `--eval`, gwtree runs untouched, as its output is no directory.
+fn posix_branch(test: &str, args: &str, print_other: bool, after_cd: &str, no_cd: bool) -> String {
+    let other = if print_other {
+        "            else\n                printf \"%s\\n\" \"$result\"\n"
+    } else {
+        ""
+    };
+    let (declare, check, strip, print) = if no_cd {
+        (
+            "\n        local no_cd=",
+            r#"
+            if [ "$arg" = "--no-cd" ] || [ "$arg" = "--print" ]; then
+                no_cd=1
+            fi"#,
+            r#"
+        if [ -n "$no_cd" ]; then
+            for arg in "$@"; do
+                shift
+                if [ "$arg" != "--no-cd" ] && [ "$arg" != "--print" ]; then
+                    set -- "$@" "$arg"
+                fi
+            done
+        fi"#,
+            "[ -n \"$no_cd\" ]; then\n                printf \"%s\\n\" \"$result\"\n            elif ",
+        )
+    } else {
+        ("", "", "", "")
+    };
+    format!(
+        r#"{test}; then{declare}
         for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
+            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ] || [ "$arg" = "--json" ] || [ "${{arg%%=*}}" = "--eval" ]; then
                 command gwtree "$@"
                 return
-            fi
-        done
+            fi{check}
+        done{strip}
         local result
-        result=$(command gwtree rm "${@:2}")
+        result=$(command gwtree {args})
         local exit_code=$?
         if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            fi
+            if {print}[ -d "$result" ]; then
+                cd -- "$result" || return 1{after_cd}
+{other}            fi
         else
             printf "%s\n" "$result" >&2
             return $exit_code
         fi
-    elif [ "$1" = "home" ]; then
-        for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
-                command gwtree "$@"
-                return
-            fi
-        done
-        local result
-        result=$(command gwtree home)
-        local exit_code=$?
-        if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            else
-                printf "%s\n" "$result"
-            fi
+"#
+    )
+}
+
+/// The `gwt` function for fish
+fn fish_function(options: &InitOptions) -> String {
+    let ls = if options.ls_after_switch {
+        "\n            command gwtree ls"
+    } else {
+        ""
+    };
+
+    let mut branches = vec![fish_branch(
+        r#"test "$argv[1]" = "switch" -o "$argv[1]" = "sw""#,
+        "sw $argv[2..-1]",
+        true,
+        ls,
+        true,
+    )];
+    if options.cd_on_rm {
+        branches.push(fish_branch(
+            r#"test "$argv[1]" = "remove" -o "$argv[1]" = "rm""#,
+            "rm $argv[2..-1]",
+            false,
+            "",
+            false,
+        ));
+    }
+    branches.extend([
+        fish_branch(
+            r#"test "$argv[1]" = "mv""#,
+            "mv $argv[2..-1]",
+            false,
+            "",
+            false,
+        ),
+        fish_branch(
+            r#"test "$argv[1]" = "home" -o "$argv[1]" = "main""#,
+            "home $argv[2..-1]",
+            true,
+            "",
+            false,
+        ),
+        fish_branch(
+            r#"test "$argv[1]" = "recent" -o "$argv[1]" = "clone" -o "$argv[1]" = "pr""#,
+            "$argv",
+            true,
+            "",
+            false,
+        ),
+    ]);
+
+    format!(
+        r#"set -gx GWT_SHELL_WRAPPER 1
+
+function gwt
+    if {}    else
+        # An alias of the config runs through this function again, so one
+        # for `sw` changes directory too
+        set -l expanded (command gwtree alias expand fish -- $argv 2>/dev/null)
+        if test -n "$expanded"
+            eval gwt $expanded
         else
-            printf "%s\n" "$result" >&2
+            command gwtree $argv
+        end
+    end
+end
+"#,
+        branches.join("    else if ")
+    )
+}
+
+/// One `if`/`else if` branch of [`fish_function`], like [`posix_branch`]
+fn fish_branch(test: &str, args: &str, print_other: bool, after_cd: &str, no_cd: bool) -> String {
+    let other = if print_other {
+        "        else if test -n \"$result\"\n            printf \"%s\\n\" \"$result\"\n"
+    } else {
+        ""
+    };
+    let (strip, print) = if no_cd {
+        (
+            r#"
+        set -l no_cd 0
+        if contains -- --no-cd $argv; or contains -- --print $argv
+            set no_cd 1
+            set argv (string match -v -- --no-cd $argv | string match -v -- --print)
+        end"#,
+            r#"
+        if test $no_cd -eq 1
+            test -n "$result"; and printf "%s\n" "$result"
+            return 0
+        end"#,
+        )
+    } else {
+        ("", "")
+    };
+    format!(
+        r#"{test}
+        for arg in $argv
+            if test "$arg" = "--help" -o "$arg" = "-h" -o "$arg" = "--json"; or string match -q -- '--eval*' $arg
+                command gwtree $argv
+                return $status
+            end
+        end{strip}
+        set -l result (command gwtree {args} | string collect)
+        set -l exit_code $pipestatus[1]
+        if test $exit_code -ne 0
+            test -n "$result"; and printf "%s\n" "$result" >&2
             return $exit_code
-        fi
-    else
-        command gwtree "$@"
-    fi
+        end{print}
+        if test -d "$result"
+            cd "$result"; or return 1{after_cd}
+{other}        end
+        return 0
+"#
+    )
 }
 
-# Tab completion for gwt
+const BASH_COMPLETION: &str = r#"# Tab completion for gwt
 _gwt_completions() {
     local cur prev
     COMPREPLY=()
@@ -79,104 +447,59 @@ _gwt_completions() {
     prev="${COMP_WORDS[COMP_CWORD-1]}"
 
     # Top-level commands
-    local commands="config ls sw rm init current completion home"
+    local commands="config ls sw rm init current completion home exec run status open mv lock unlock doctor recent clone pr fetch repair migrate sync export import clean du repos forget which env hooks prompt version alias"
 
     if [ "$COMP_CWORD" -eq 1 ]; then
-        COMPREPLY=($(compgen -W "$commands" -- "$cur"))
+        local aliases
+        aliases=$(command gwtree alias list --raw 2>/dev/null)
+        COMPREPLY=($(compgen -W "$commands $aliases" -- "$cur"))
         return
     fi
 
-    # Complete branch names for 'sw' and 'rm' commands
+    # Complete branch names for commands that take a worktree
     case "${COMP_WORDS[1]}" in
         sw|switch)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--no-cd" -- "$cur"))
+                return
+            fi
             local branches
-            branches=$(command gwtree ls --raw 2>/dev/null)
+            branches=$(command gwtree ls --raw --cached 2>/dev/null)
             COMPREPLY=($(compgen -W "$branches" -- "$cur"))
             ;;
-        rm|remove)
+        run)
+            if [ "$COMP_CWORD" -eq 2 ]; then
+                local branches
+                branches=$(command gwtree ls --raw --cached 2>/dev/null)
+                COMPREPLY=($(compgen -W "$branches" -- "$cur"))
+            fi
+            ;;
+        rm|remove|mv|lock|unlock)
             local branches
-            branches=$(command gwtree ls --raw 2>/dev/null)
+            branches=$(command gwtree ls --raw --cached --no-main 2>/dev/null)
             COMPREPLY=($(compgen -W "$branches" -- "$cur"))
             ;;
         init|completion)
-            COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur"))
+            COMPREPLY=($(compgen -W "bash zsh fish powershell nushell" -- "$cur"))
             ;;
         config)
             COMPREPLY=($(compgen -W "view setup" -- "$cur"))
             ;;
+        hooks)
+            COMPREPLY=($(compgen -W "list run" -- "$cur"))
+            ;;
+        alias)
+            COMPREPLY=($(compgen -W "list" -- "$cur"))
+            ;;
     esac
 }
 
 complete -F _gwt_completions gwt
-"#
-        .to_string()),
-        "zsh" => Ok(r#"gwt() {
-    if [ "$1" = "switch" ] || [ "$1" = "sw" ]; then
-        for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
-                command gwtree "$@"
-                return
-            fi
-        done
-        local result
-        result=$(command gwtree sw "${@:2}")
-        local exit_code=$?
-        if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            else
-                printf "%s\n" "$result"
-            fi
-        else
-            printf "%s\n" "$result" >&2
-            return $exit_code
-        fi
-    elif [ "$1" = "remove" ] || [ "$1" = "rm" ]; then
-        for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
-                command gwtree "$@"
-                return
-            fi
-        done
-        local result
-        result=$(command gwtree rm "${@:2}")
-        local exit_code=$?
-        if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            fi
-        else
-            printf "%s\n" "$result" >&2
-            return $exit_code
-        fi
-    elif [ "$1" = "home" ]; then
-        for arg in "$@"; do
-            if [ "$arg" = "--help" ] || [ "$arg" = "-h" ]; then
-                command gwtree "$@"
-                return
-            fi
-        done
-        local result
-        result=$(command gwtree home)
-        local exit_code=$?
-        if [ $exit_code -eq 0 ]; then
-            if [ -d "$result" ]; then
-                cd "$result" || return 1
-            else
-                printf "%s\n" "$result"
-            fi
-        else
-            printf "%s\n" "$result" >&2
-            return $exit_code
-        fi
-    else
-        command gwtree "$@"
-    fi
-}
+"#;
 
-# Tab completion for gwt (zsh)
+const ZSH_COMPLETION: &str = r#"# Tab completion for gwt (zsh)
 _gwt() {
-    local -a commands branches shells config_commands
+    local -a commands aliases branches ids shells config_commands hooks_commands alias_commands
     commands=(
         'config:Configure gwt'
         'ls:List all worktrees'
@@ -186,16 +509,67 @@ _gwt() {
         'current:Print current worktree and branch information'
         'completion:Generate shell completion scripts'
         'home:Switch to the home worktree (original repository)'
+        'exec:Run a command in one or all worktrees'
+        'run:Run a command in the worktree of a branch'
+        'status:Summarize the health of all worktrees'
+        'open:Open a worktree in the editor'
+        'mv:Rename a branch and move its worktree'
+        'lock:Lock a worktree against pruning'
+        'unlock:Unlock a worktree'
+        'doctor:Diagnose configuration and store problems'
+        'recent:List and jump to recently used worktrees'
+        'clone:Clone a repository under the worktree root'
+        'pr:Check out a pull request into its own worktree'
+        'fetch:Fetch repositories and report upstream changes'
+        'repair:Reconnect moved or broken worktrees'
+        'migrate:Move worktrees to the configured worktree root'
+        'sync:Create worktrees for remote branches matching a pattern'
+        'export:Write the worktrees to a manifest'
+        'import:Recreate the worktrees of a manifest'
+        'clean:Remove worktrees unused for a while'
+        'du:Show the disk usage of each worktree'
+        'repos:List the repositories with worktrees in the worktree root'
+        'forget:Remove all worktrees of a repository from the worktree root'
+        'which:Show which repository, branch and worktree a directory belongs to'
+        'env:Print gwt paths for scripts'
+        'hooks:List or test-run the configured hooks'
+        'prompt:Print a short segment for the shell prompt'
+        'version:Show the version and build of gwt and the git it runs'
+        'alias:List the command aliases of the config'
     )
-    shells=('bash' 'zsh' 'fish')
+    shells=('bash' 'zsh' 'fish' 'powershell' 'nushell')
     config_commands=('view' 'setup')
+    hooks_commands=('list' 'run')
+    alias_commands=('list')
 
     if (( CURRENT == 2 )); then
         _describe 'command' commands
+        aliases=(${(f)"$(command gwtree alias list --raw 2>/dev/null)"})
+        _describe 'alias' aliases
     else
         case "${words[2]}" in
-            sw|switch|rm|remove)
-                branches=(${(f)"$(command gwtree ls --raw 2>/dev/null)"})
+            sw|switch)
+                if [[ $PREFIX == -* ]]; then
+                    compadd -- --no-cd
+                    return
+                fi
+                branches=(${(f)"$(command gwtree ls --raw --cached 2>/dev/null)"})
+                _describe 'branch' branches
+                ;;
+            run)
+                if (( CURRENT == 3 )); then
+                    branches=(${(f)"$(command gwtree ls --raw --cached 2>/dev/null)"})
+                    _describe 'branch' branches
+                fi
+                ;;
+            rm|remove)
+                branches=(${(f)"$(command gwtree ls --raw --cached --no-main 2>/dev/null)"})
+                ids=(${(f)"$(command gwtree ls --raw --cached --ids 2>/dev/null)"})
+                _describe 'branch' branches
+                _describe 'worktree id' ids
+                ;;
+            mv|lock|unlock)
+                branches=(${(f)"$(command gwtree ls --raw --cached --no-main 2>/dev/null)"})
                 _describe 'branch' branches
                 ;;
             init|completion)
@@ -204,77 +578,30 @@ _gwt() {
             config)
                 _describe 'subcommand' config_commands
                 ;;
+            hooks)
+                _describe 'subcommand' hooks_commands
+                ;;
+            alias)
+                _describe 'subcommand' alias_commands
+                ;;
         esac
     fi
 }
 
 compdef _gwt gwt
-"#
-        .to_string()),
-        "fish" => Ok(r#"function gwt
-    if test "$argv[1]" = "switch" -o "$argv[1]" = "sw"
-        for arg in $argv
-            if test "$arg" = "--help" -o "$arg" = "-h"
-                command gwtree $argv
-                return
-            end
-        end
-        set result (command gwtree sw $argv[2..-1])
-        set exit_code $status
-        if test $exit_code -eq 0
-            if test -d "$result"
-                cd "$result" || return 1
-            else
-                printf "%s\n" $result
-            end
-        else
-            printf "%s\n" $result >&2
-            return $exit_code
-        end
-    else if test "$argv[1]" = "remove" -o "$argv[1]" = "rm"
-        for arg in $argv
-            if test "$arg" = "--help" -o "$arg" = "-h"
-                command gwtree $argv
-                return
-            end
-        end
-        set result (command gwtree rm $argv[2..-1])
-        set exit_code $status
-        if test $exit_code -eq 0
-            if test -d "$result"
-                cd "$result" || return 1
-            end
-        else
-            printf "%s\n" $result >&2
-            return $exit_code
-        end
-    else if test "$argv[1]" = "home"
-        for arg in $argv
-            if test "$arg" = "--help" -o "$arg" = "-h"
-                command gwtree $argv
-                return
-            end
-        end
-        set result (command gwtree home)
-        set exit_code $status
-        if test $exit_code -eq 0
-            if test -d "$result"
-                cd "$result" || return 1
-            else
-                printf "%s\n" $result
-            end
-        else
-            printf "%s\n" $result >&2
-            return $exit_code
-        end
-    else
-        command gwtree $argv
-    end
-end
+"#;
 
-# Tab completion for gwt (fish)
+const FISH_COMPLETION: &str = r#"# Tab completion for gwt (fish)
 function __gwt_branches
-    command gwtree ls --raw 2>/dev/null
+    command gwtree ls --raw --cached 2>/dev/null
+end
+
+function __gwt_removable_branches
+    command gwtree ls --raw --cached --no-main 2>/dev/null
+end
+
+function __gwt_ids
+    command gwtree ls --raw --cached --ids 2>/dev/null
 end
 
 function __gwt_needs_command
@@ -284,7 +611,7 @@ end
 
 function __gwt_using_command
     set -l cmd (commandline -opc)
-    test (count $cmd) -gt 1; and test "$cmd[2]" = "$argv[1]"
+    test (count $cmd) -gt 1; and contains -- "$cmd[2]" $argv
 end
 
 # Disable file completions for gwt
@@ -299,87 +626,1037 @@ complete -c gwt -n '__gwt_needs_command' -a 'init' -d 'Output shell integration
 complete -c gwt -n '__gwt_needs_command' -a 'current' -d 'Print current worktree and branch information'
 complete -c gwt -n '__gwt_needs_command' -a 'completion' -d 'Generate shell completion scripts'
 complete -c gwt -n '__gwt_needs_command' -a 'home' -d 'Switch to the home worktree (original repository)'
+complete -c gwt -n '__gwt_needs_command' -a 'exec' -d 'Run a command in one or all worktrees'
+complete -c gwt -n '__gwt_needs_command' -a 'run' -d 'Run a command in the worktree of a branch'
+complete -c gwt -n '__gwt_needs_command' -a 'status' -d 'Summarize the health of all worktrees'
+complete -c gwt -n '__gwt_needs_command' -a 'open' -d 'Open a worktree in the editor'
+complete -c gwt -n '__gwt_needs_command' -a 'mv' -d 'Rename a branch and move its worktree'
+complete -c gwt -n '__gwt_needs_command' -a 'lock' -d 'Lock a worktree against pruning'
+complete -c gwt -n '__gwt_needs_command' -a 'unlock' -d 'Unlock a worktree'
+complete -c gwt -n '__gwt_needs_command' -a 'doctor' -d 'Diagnose configuration and store problems'
+complete -c gwt -n '__gwt_needs_command' -a 'recent' -d 'List and jump to recently used worktrees'
+complete -c gwt -n '__gwt_needs_command' -a 'clone' -d 'Clone a repository under the worktree root'
+complete -c gwt -n '__gwt_needs_command' -a 'pr' -d 'Check out a pull request into its own worktree'
+complete -c gwt -n '__gwt_needs_command' -a 'fetch' -d 'Fetch repositories and report upstream changes'
+complete -c gwt -n '__gwt_needs_command' -a 'repair' -d 'Reconnect moved or broken worktrees'
+complete -c gwt -n '__gwt_needs_command' -a 'migrate' -d 'Move worktrees to the configured worktree root'
+complete -c gwt -n '__gwt_needs_command' -a 'sync' -d 'Create worktrees for remote branches matching a pattern'
+complete -c gwt -n '__gwt_needs_command' -a 'export' -d 'Write the worktrees to a manifest'
+complete -c gwt -n '__gwt_needs_command' -a 'import' -d 'Recreate the worktrees of a manifest'
+complete -c gwt -n '__gwt_needs_command' -a 'clean' -d 'Remove worktrees unused for a while'
+complete -c gwt -n '__gwt_needs_command' -a 'du' -d 'Show the disk usage of each worktree'
+complete -c gwt -n '__gwt_needs_command' -a 'repos' -d 'List the repositories with worktrees in the worktree root'
+complete -c gwt -n '__gwt_needs_command' -a 'forget' -d 'Remove all worktrees of a repository from the worktree root'
+complete -c gwt -n '__gwt_needs_command' -a 'which' -d 'Show which repository, branch and worktree a directory belongs to'
+complete -c gwt -n '__gwt_needs_command' -a 'env' -d 'Print gwt paths for scripts'
+complete -c gwt -n '__gwt_needs_command' -a 'hooks' -d 'List or test-run the configured hooks'
+complete -c gwt -n '__gwt_needs_command' -a 'prompt' -d 'Print a short segment for the shell prompt'
+complete -c gwt -n '__gwt_needs_command' -a 'version' -d 'Show the version and build of gwt and the git it runs'
+complete -c gwt -n '__gwt_needs_command' -a 'alias' -d 'List the command aliases of the config'
+complete -c gwt -n '__gwt_needs_command' -a '(command gwtree alias list --raw 2>/dev/null)' -d 'alias'
 
-# Branch completions for sw and rm
+# Branch completions for commands that take a worktree
 complete -c gwt -n '__gwt_using_command sw' -a '(__gwt_branches)' -d 'branch'
-complete -c gwt -n '__gwt_using_command rm' -a '(__gwt_branches)' -d 'branch'
+complete -c gwt -n '__gwt_using_command run' -a '(__gwt_branches)' -d 'branch'
+complete -c gwt -n '__gwt_using_command sw' -l no-cd -d 'Print the path instead of changing to it'
+complete -c gwt -n '__gwt_using_command rm' -a '(__gwt_removable_branches)' -d 'branch'
+complete -c gwt -n '__gwt_using_command rm' -a '(__gwt_ids)' -d 'worktree id'
+complete -c gwt -n '__gwt_using_command mv lock unlock' -a '(__gwt_removable_branches)' -d 'branch'
 
 # Shell completions for init and completion
-complete -c gwt -n '__gwt_using_command init' -a 'bash zsh fish'
-complete -c gwt -n '__gwt_using_command completion' -a 'bash zsh fish'
+complete -c gwt -n '__gwt_using_command init' -a 'bash zsh fish powershell nushell'
+complete -c gwt -n '__gwt_using_command completion' -a 'bash zsh fish powershell nushell'
 
 # Config subcommands
 complete -c gwt -n '__gwt_using_command config' -a 'view setup'
-"#
-        .to_string()),
-        _ => Err(anyhow!(
-            "Unsupported shell '{shell}'. Supported: bash, zsh, fish",
-        )),
+
+# Hooks subcommands
+complete -c gwt -n '__gwt_using_command hooks' -a 'list run'
+
+# Alias subcommands
+complete -c gwt -n '__gwt_using_command alias' -a 'list'
+"#;
+
+const POWERSHELL: &str = r#"$env:GWT_SHELL_WRAPPER = '1'
+
+function gwt {
+    if ($args.Count -gt 0 -and @('switch', 'sw', 'remove', 'rm', 'mv', 'home', 'main', 'recent', 'clone', 'pr') -contains $args[0]) {
+        foreach ($arg in $args) {
+            if ($arg -eq '--help' -or $arg -eq '-h' -or $arg -eq '--json' -or $arg -like '--eval*') {
+                & gwtree @args
+                return
+            }
+        }
+        $subcommand = switch ($args[0]) {
+            'switch' { 'sw' }
+            'remove' { 'rm' }
+            'main' { 'home' }
+            default { $args[0] }
+        }
+        $rest = @($args | Select-Object -Skip 1)
+        $noCd = $subcommand -eq 'sw' -and ($rest -contains '--no-cd' -or $rest -contains '--print')
+        if ($noCd) {
+            $rest = @($rest | Where-Object { $_ -notin @('--no-cd', '--print') })
+        }
+        $result = (& gwtree $subcommand @rest) -join "`n"
+        $exitCode = $LASTEXITCODE
+        if ($exitCode -eq 0) {
+            if ($noCd) {
+                if ($result) {
+                    Write-Output $result
+                }
+            } elseif ($result -and (Test-Path -LiteralPath $result -PathType Container)) {
+                Set-Location -LiteralPath $result
+            } elseif ($result -and $subcommand -notin @('rm', 'mv')) {
+                Write-Output $result
+            }
+        } else {
+            if ($result) {
+                [Console]::Error.WriteLine($result)
+            }
+            $global:LASTEXITCODE = $exitCode
+        }
+    } else {
+        # An alias of the config runs through this function again, so one
+        # for `sw` changes directory too
+        $expanded = @(& gwtree alias expand lines -- @args 2>$null)
+        if ($expanded.Count -gt 0) {
+            gwt @expanded
+        } else {
+            & gwtree @args
+        }
+    }
+}
+
+# Tab completion for gwt (PowerShell)
+Register-ArgumentCompleter -Native -CommandName 'gwt' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $commands = @('config', 'ls', 'sw', 'rm', 'init', 'current', 'completion', 'home', 'exec', 'run', 'status', 'open', 'mv', 'lock', 'unlock', 'doctor', 'recent', 'clone', 'pr', 'fetch', 'repair', 'migrate', 'sync', 'export', 'import', 'clean', 'du', 'repos', 'forget', 'which', 'env', 'hooks', 'prompt', 'version', 'alias')
+    $elements = $commandAst.CommandElements
+    $completingCommand = $elements.Count -lt 2 -or
+        ($elements.Count -eq 2 -and $wordToComplete -ne '')
+
+    $candidates = if ($completingCommand) {
+        @($commands) + @(gwtree alias list --raw 2>$null)
+    } else {
+        switch ($elements[1].ToString()) {
+            { $_ -in @('sw', 'switch') } { @(gwtree ls --raw --cached 2>$null) + '--no-cd' }
+            'run' { gwtree ls --raw --cached 2>$null }
+            { $_ -in @('rm', 'remove', 'mv', 'lock', 'unlock') } { gwtree ls --raw --cached --no-main 2>$null }
+            { $_ -in @('init', 'completion') } { @('bash', 'zsh', 'fish', 'powershell', 'nushell') }
+            'config' { @('view', 'setup') }
+            'hooks' { @('list', 'run') }
+            'alias' { @('list') }
+        }
+    }
+
+    $candidates |
+        Where-Object { $_ -like "$wordToComplete*" } |
+        ForEach-Object {
+            [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+        }
+}
+"#;
+
+const NUSHELL: &str = r#"$env.GWT_SHELL_WRAPPER = "1"
+
+# Arguments reach gwtree as a list, so a branch name containing spaces only
+# needs quoting once (gwt sw "wip stuff") and is never word-split or
+# glob-expanded the way an unquoted variable would be in POSIX shells.
+def --env --wrapped gwt [...args: string@"nu-complete gwt"] {
+    let subcommand = ($args.0? | default "")
+    if $subcommand in [switch sw remove rm mv home main recent clone pr] {
+        if ("--help" in $args) or ("-h" in $args) or ("--json" in $args) or ($args | any {|a| $a starts-with "--eval" }) {
+            ^gwtree ...$args
+            return
+        }
+        let sub = match $subcommand {
+            "switch" => "sw"
+            "remove" => "rm"
+            "main" => "home"
+            _ => $subcommand
+        }
+        let no_cd = ($sub == "sw") and (("--no-cd" in $args) or ("--print" in $args))
+        let rest = if $no_cd {
+            $args | skip 1 | where {|arg| $arg not-in ["--no-cd" "--print"] }
+        } else {
+            $args | skip 1
+        }
+        let result = (do --ignore-errors { ^gwtree $sub ...$rest } | str trim)
+        let exit_code = $env.LAST_EXIT_CODE
+        if $exit_code != 0 {
+            error make --unspanned { msg: $"gwtree ($sub) failed with exit code ($exit_code)" }
+        }
+        if $no_cd {
+            if $result != "" {
+                print $result
+            }
+        } else if ($result != "") and ($result | path exists) and (($result | path type) == "dir") {
+            cd $result
+        } else if ($sub not-in [rm mv]) and ($result != "") {
+            print $result
+        }
+    } else {
+        # An alias of the config runs through this function again, so one
+        # for `sw` changes directory too
+        let expanded = (do --ignore-errors { ^gwtree alias expand lines -- ...$args } | lines)
+        if ($expanded | is-empty) {
+            ^gwtree ...$args
+        } else {
+            gwt ...$expanded
+        }
+    }
+}
+
+# Tab completion for gwt (nushell)
+def "nu-complete gwt" [context: string] {
+    let words = ($context | split row -r '\s+')
+    if ($words | length) <= 2 {
+        [config ls sw rm init current completion home exec run status open mv lock unlock doctor recent clone pr fetch repair migrate sync export import clean du repos forget which env hooks prompt version alias] | append (^gwtree alias list --raw | lines)
+    } else {
+        match ($words | get 1) {
+            "sw" | "switch" => (^gwtree ls --raw --cached | lines | append "--no-cd")
+            "run" => (^gwtree ls --raw --cached | lines)
+            "rm" | "remove" | "mv" | "lock" | "unlock" => (^gwtree ls --raw --cached --no-main | lines)
+            "init" | "completion" => [bash zsh fish powershell nushell]
+            "config" => [view setup]
+            "hooks" => [list run]
+            "alias" => [list]
+            _ => []
+        }
     }
 }
+"#;
+
+const STARSHIP: &str = r#"# gwt module for Starship: paste into ~/.config/starship.toml.
+# `gwtree prompt --starship` prints managed=, repo=, branch= and dirty= lines.
+[custom.gwt]
+description = "Branch of the gwt-managed worktree"
+when = "gwtree prompt --starship | grep -qx managed=true"
+command = "gwtree prompt --starship | sed -n 's/^branch=//p'"
+shell = ["sh"]
+symbol = "⎇ "
+style = "bold purple"
+format = "[$symbol$output]($style) "
+"#;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn generate_bash_init_contains_function() {
-        let s = generate_init("bash").unwrap();
+        let s = generate_init("bash", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("gwt() {"));
         assert!(s.contains("command gwtree"));
         assert!(s.contains(r#"[ "$1" = "switch" ] || [ "$1" = "sw" ]"#));
         assert!(s.contains(r#"[ "$1" = "remove" ] || [ "$1" = "rm" ]"#));
+        assert!(s.contains(r#"[ "$1" = "home" ] || [ "$1" = "main" ]"#));
+        assert!(s.contains(r#"result=$(command gwtree home "${@:2}")"#));
     }
 
     #[test]
     fn generate_bash_init_contains_completion() {
-        let s = generate_init("bash").unwrap();
+        let s = generate_init("bash", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("_gwt_completions"));
         assert!(s.contains("complete -F _gwt_completions gwt"));
-        assert!(s.contains("gwtree ls --raw"));
+        assert!(s.contains("gwtree ls --raw --cached"));
     }
 
     #[test]
     fn generate_zsh_init_contains_function() {
-        let s = generate_init("zsh").unwrap();
+        let s = generate_init("zsh", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("gwt() {"));
         assert!(s.contains("command gwtree"));
         assert!(s.contains(r#"[ "$1" = "switch" ] || [ "$1" = "sw" ]"#));
         assert!(s.contains(r#"[ "$1" = "remove" ] || [ "$1" = "rm" ]"#));
+        assert!(s.contains(r#"[ "$1" = "home" ] || [ "$1" = "main" ]"#));
+        assert!(s.contains(r#"result=$(command gwtree home "${@:2}")"#));
     }
 
     #[test]
     fn generate_zsh_init_contains_completion() {
-        let s = generate_init("zsh").unwrap();
+        let s = generate_init("zsh", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("_gwt()"));
         assert!(s.contains("compdef _gwt gwt"));
-        assert!(s.contains("gwtree ls --raw"));
+        assert!(s.contains("gwtree ls --raw --cached"));
     }
 
     #[test]
     fn generate_fish_init_contains_function() {
-        let s = generate_init("fish").unwrap();
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("function gwt"));
         assert!(s.contains("command gwtree"));
         assert!(s.contains(r#"test "$argv[1]" = "switch" -o "$argv[1]" = "sw""#));
         assert!(s.contains(r#"test "$argv[1]" = "remove" -o "$argv[1]" = "rm""#));
+        assert!(s.contains(r#"test "$argv[1]" = "home" -o "$argv[1]" = "main""#));
+        assert!(s.contains("command gwtree home $argv[2..-1]"));
+    }
+
+    #[test]
+    fn generate_init_marks_wrapper_as_installed() {
+        // `gwt doctor` looks for this variable
+        for (shell, line) in [
+            ("bash", "export GWT_SHELL_WRAPPER=1"),
+            ("zsh", "export GWT_SHELL_WRAPPER=1"),
+            ("fish", "set -gx GWT_SHELL_WRAPPER 1"),
+            ("powershell", "$env:GWT_SHELL_WRAPPER = '1'"),
+            ("nushell", "$env.GWT_SHELL_WRAPPER = \"1\""),
+        ] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(s.lines().any(|l| l == line), "{shell}");
+        }
+    }
+
+    #[test]
+    fn generate_init_expands_aliases() {
+        for (shell, line) in [
+            (
+                "bash",
+                r#"expanded=$(command gwtree alias expand sh -- "$@" 2>/dev/null)"#,
+            ),
+            (
+                "zsh",
+                r#"expanded=$(command gwtree alias expand sh -- "$@" 2>/dev/null)"#,
+            ),
+            (
+                "fish",
+                "set -l expanded (command gwtree alias expand fish -- $argv 2>/dev/null)",
+            ),
+            (
+                "powershell",
+                "$expanded = @(& gwtree alias expand lines -- @args 2>$null)",
+            ),
+            (
+                "nushell",
+                "let expanded = (do --ignore-errors { ^gwtree alias expand lines -- ...$args } | lines)",
+            ),
+        ] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(s.lines().any(|l| l.trim() == line), "{shell}");
+        }
+
+        // The expansion goes through the wrapper again
+        let s = generate_init("powershell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("        gwt @expanded\n"));
+        let s = generate_init("nushell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("        gwt ...$expanded\n"));
+    }
+
+    #[test]
+    fn generate_init_substitutes_binary_name() {
+        for shell in ["bash", "zsh", "fish"] {
+            let s = generate_init(shell, "gwt-bin", &InitOptions::default()).unwrap();
+            assert!(!s.contains("gwtree"), "{shell}");
+            assert!(s.contains("command gwt-bin sw"), "{shell}");
+            assert!(s.contains("command gwt-bin ls --raw --cached"), "{shell}");
+        }
+
+        // The wrapper keeps its name even when the binary is called gwt
+        let s = generate_init("bash", "gwt", &InitOptions::default()).unwrap();
+        assert!(s.contains("\ngwt() {"));
+        assert!(s.contains(r#"result=$(command gwt sw "${@:2}")"#));
+        let s = generate_init("fish", "gwt", &InitOptions::default()).unwrap();
+        assert!(s.contains("\nfunction gwt\n"));
+        assert!(s.contains("set -l result (command gwt sw $argv[2..-1] | string collect)"));
+    }
+
+    #[test]
+    fn generate_starship_module() {
+        let s = generate_init("starship", "gwt-bin", &InitOptions::default()).unwrap();
+        assert!(s.contains("[custom.gwt]"));
+        assert!(s.contains(r#"when = "gwt-bin prompt --starship | grep -qx managed=true""#));
+        assert!(s.contains("command = \"gwt-bin prompt --starship | sed -n 's/^branch=//p'\""));
+        assert!(!s.contains("GWT_SHELL_WRAPPER"));
+    }
+
+    #[test]
+    fn test_resolve_binary_name() {
+        assert_eq!(resolve_binary_name(Some("gwt")).unwrap(), "gwt");
+        assert_eq!(
+            resolve_binary_name(Some("/opt/gwt/bin/gwtree")).unwrap(),
+            "/opt/gwt/bin/gwtree"
+        );
+        assert!(resolve_binary_name(Some("my gwt")).is_err());
+        assert!(resolve_binary_name(Some("gwt;rm")).is_err());
+        assert!(resolve_binary_name(Some("")).is_err());
+        assert!(!resolve_binary_name(None).unwrap().is_empty());
+    }
+
+    fn options(function_name: &str, cd_on_rm: bool, ls_after_switch: bool) -> InitOptions {
+        InitOptions {
+            function_name: function_name.to_string(),
+            cd_on_rm,
+            ls_after_switch,
+        }
+    }
+
+    #[test]
+    fn generate_init_with_function_name() {
+        let wt = options("wt", true, false);
+        let s = generate_init("bash", "gwtree", &wt).unwrap();
+        assert!(s.contains("\nwt() {"));
+        assert!(!s.contains("gwt() {"));
+        assert!(s.contains("complete -F _gwt_completions wt\n"));
+        assert!(s.contains("command gwtree sw"));
+        assert!(s.contains("export GWT_SHELL_WRAPPER=1"));
+
+        let s = generate_init("zsh", "gwtree", &wt).unwrap();
+        assert!(s.contains("\nwt() {"));
+        assert!(s.contains("compdef _gwt wt\n"));
+
+        let s = generate_init("fish", "gwtree", &wt).unwrap();
+        assert!(s.contains("\nfunction wt\n"));
+        assert!(s.contains("complete -c wt -f"));
+        assert!(!s.contains("complete -c gwt "));
+        assert!(s.contains("function __gwt_branches"));
+
+        let s = generate_init("powershell", "gwtree", &wt).unwrap();
+        assert!(s.contains("function wt {"));
+        assert!(s.contains("-CommandName 'wt'"));
+
+        let s = generate_init("nushell", "gwtree", &wt).unwrap();
+        assert!(s.contains(r#"def --env --wrapped wt [...args: string@"nu-complete wt"]"#));
+        assert!(s.contains(r#"def "nu-complete wt" [context: string]"#));
+
+        // The binary is substituted independently of the function name
+        let s = generate_init("bash", "gwt", &options("gwtree", true, false)).unwrap();
+        assert!(s.contains("\ngwtree() {"));
+        assert!(s.contains(r#"result=$(command gwt sw "${@:2}")"#));
+    }
+
+    #[test]
+    fn generate_init_sw_no_cd() {
+        for shell in ["bash", "zsh", "fish", "powershell", "nushell"] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(s.contains("--no-cd"), "{shell}");
+            assert!(s.contains("--print"), "{shell}");
+        }
+        // Only `sw` takes the flag out
+        let s = generate_init("bash", "gwtree", &InitOptions::default()).unwrap();
+        assert_eq!(s.matches("local no_cd=").count(), 1);
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert_eq!(s.matches("set -l no_cd 0").count(), 1);
+    }
+
+    #[test]
+    fn generate_init_rejects_invalid_function_name() {
+        for name in ["", "1wt", "my wt", "wt;rm", "$wt"] {
+            let result = generate_init("bash", "gwtree", &options(name, true, false));
+            assert!(result.is_err(), "{name}");
+        }
+        assert!(generate_init("starship", "gwtree", &options("wt", true, false)).is_err());
+    }
+
+    #[test]
+    fn generate_init_cd_on_rm() {
+        for shell in ["bash", "zsh"] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(
+                s.contains(r#"result=$(command gwtree rm "${@:2}")"#),
+                "{shell}"
+            );
+
+            let s = generate_init(shell, "gwtree", &options("gwt", false, false)).unwrap();
+            assert!(!s.contains(r#"[ "$1" = "rm" ]"#), "{shell}");
+            assert!(!s.contains("command gwtree rm"), "{shell}");
+            // Other commands still change directory
+            assert!(
+                s.contains(r#"result=$(command gwtree mv "${@:2}")"#),
+                "{shell}"
+            );
+            assert!(
+                s.contains(r#"result=$(command gwtree sw "${@:2}")"#),
+                "{shell}"
+            );
+        }
+
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("(command gwtree rm $argv[2..-1] | string collect)"));
+        let s = generate_init("fish", "gwtree", &options("gwt", false, false)).unwrap();
+        assert!(!s.contains(r#""$argv[1]" = "rm""#));
+        assert!(!s.contains("command gwtree rm"));
+        assert!(s.contains("(command gwtree mv $argv[2..-1] | string collect)"));
+
+        for shell in ["powershell", "nushell"] {
+            assert!(generate_init(shell, "gwtree", &options("gwt", false, false)).is_err());
+        }
+    }
+
+    #[test]
+    fn generate_init_ls_after_switch() {
+        for shell in ["bash", "zsh"] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(!s.contains("command gwtree ls ||"), "{shell}");
+
+            let s = generate_init(shell, "gwtree", &options("gwt", true, true)).unwrap();
+            assert!(
+                s.contains(
+                    "cd -- \"$result\" || return 1\n                command gwtree ls || true\n"
+                ),
+                "{shell}"
+            );
+            // Only after `sw`
+            assert_eq!(s.matches("command gwtree ls ||").count(), 1, "{shell}");
+        }
+
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert!(!s.contains("command gwtree ls\n"));
+        let s = generate_init("fish", "gwtree", &options("gwt", true, true)).unwrap();
+        assert!(s.contains("cd \"$result\"; or return 1\n            command gwtree ls\n"));
+        assert_eq!(s.matches("command gwtree ls\n").count(), 1);
+
+        for shell in ["powershell", "nushell"] {
+            assert!(generate_init(shell, "gwtree", &options("gwt", true, true)).is_err());
+        }
+    }
+
+    #[test]
+    fn generate_init_rm_completion_excludes_main() {
+        for shell in ["bash", "zsh", "fish", "powershell", "nushell"] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            assert!(s.contains("gwtree ls --raw --cached --no-main"), "{shell}");
+        }
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("rm' -a '(__gwt_removable_branches)'"));
+    }
+
+    #[test]
+    fn generate_zsh_init_completes_ids_for_rm() {
+        let s = generate_init("zsh", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("gwtree ls --raw --cached --ids"));
+        assert!(s.contains("_describe 'worktree id' ids"));
+    }
+
+    #[test]
+    fn generate_fish_init_completes_ids_for_rm() {
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("gwtree ls --raw --cached --ids"));
+        assert!(s.contains("-a '(__gwt_ids)' -d 'worktree id'"));
     }
 
     #[test]
     fn generate_fish_init_contains_completion() {
-        let s = generate_init("fish").unwrap();
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
         assert!(s.contains("__gwt_branches"));
         assert!(s.contains("complete -c gwt"));
-        assert!(s.contains("gwtree ls --raw"));
+        assert!(s.contains("gwtree ls --raw --cached"));
+    }
+
+    #[test]
+    fn generate_fish_init_preserves_exit_status() {
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("set -l exit_code $pipestatus[1]"));
+        assert!(s.contains("return $exit_code"));
+        assert!(s.contains(r#"cd "$result"; or return 1"#));
+        assert!(s.contains("return 0"));
+    }
+
+    #[test]
+    fn generate_init_passes_arguments_and_result_verbatim() {
+        for shell in ["bash", "zsh"] {
+            let s = generate_init(shell, "gwtree", &InitOptions::default()).unwrap();
+            for cmd in ["sw", "rm", "mv", "home"] {
+                let capture = format!(r#"result=$(command gwtree {cmd} "${{@:2}}")"#);
+                assert!(s.contains(&capture), "{shell} {cmd}");
+            }
+            assert!(s.contains(r#"result=$(command gwtree "$@")"#), "{shell}");
+            assert!(s.contains(r#"[ -d "$result" ]"#), "{shell}");
+            assert!(s.contains(r#"cd -- "$result" || return 1"#), "{shell}");
+            assert!(!s.contains(r#"cd "$result""#), "{shell}");
+            assert!(s.contains(r#"printf "%s\n" "$result" >&2"#), "{shell}");
+        }
+
+        // A command substitution would split the output into a list at newlines
+        let s = generate_init("fish", "gwtree", &InitOptions::default()).unwrap();
+        for cmd in ["sw", "rm", "mv", "home"] {
+            let capture = format!("(command gwtree {cmd} $argv[2..-1] | string collect)");
+            assert!(s.contains(&capture), "{cmd}");
+        }
+        assert!(s.contains("(command gwtree $argv | string collect)"));
+        assert_eq!(
+            s.matches("set -l exit_code $pipestatus[1]").count(),
+            s.matches("| string collect)").count()
+        );
+        assert!(!s.contains(r#"printf "%s\n" $result"#));
+    }
+
+    /// Returns true when `shell` can be executed on this machine.
+    fn shell_available(shell: &str) -> bool {
+        std::process::Command::new(shell)
+            .arg("--version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    }
+
+    /// Write the generated init script and a stub `gwtree` binary into a temp
+    /// directory. The stub prints `target` for `sw`, `home` and `rm`, `listed`
+    /// for `ls`, and fails with exit code 3 for the branch `missing`. The
+    /// alias `co` expands to `sw "it's"`. It writes its arguments to `args`,
+    /// one per line.
+    fn setup_wrapper_env(shell: &str, target: &Path) -> tempfile::TempDir {
+        setup_wrapper_env_with(shell, target, &InitOptions::default())
+    }
+
+    fn setup_wrapper_env_with(
+        shell: &str,
+        target: &Path,
+        options: &InitOptions,
+    ) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+
+        let stub = format!(
+            r#"#!/bin/sh
+printf '%s\n' "$@" > "$(dirname "$0")/../args"
+if [ "$1" = "sw" ] && [ "$2" = "missing" ]; then
+    echo "Branch 'missing' doesn't exist." >&2
+    exit 3
+fi
+if [ "$1" = "sw" ] || [ "$1" = "home" ] || [ "$1" = "mv" ] || [ "$1" = "recent" ] || [ "$1" = "rm" ]; then
+    echo "{}"
+    exit 0
+fi
+if [ "$1" = "ls" ]; then
+    echo listed
+    exit 0
+fi
+if [ "$1" = "alias" ] && [ "$5" = "co" ]; then
+    echo "'sw' 'it'\\''s'"
+fi
+if [ "$1" = "alias" ]; then
+    exit 0
+fi
+exit 1
+"#,
+            target.display()
+        );
+        let stub_path = bin.join("gwtree");
+        std::fs::write(&stub_path, stub).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&stub_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        std::fs::write(
+            dir.path().join("init"),
+            generate_init(shell, "gwtree", options).unwrap(),
+        )
+        .unwrap();
+        dir
+    }
+
+    fn run_in_shell(shell: &str, env_dir: &Path, script: &str) -> std::process::Output {
+        let path = format!(
+            "{}:{}",
+            env_dir.join("bin").display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        std::process::Command::new(shell)
+            .arg("-c")
+            .arg(format!(
+                "source {}; {}",
+                env_dir.join("init").display(),
+                script
+            ))
+            .env("PATH", path)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn fish_wrapper_sw_changes_directory_before_chained_command() {
+        if !shell_available("fish") {
+            eprintln!("fish not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("fish", &target_path);
+
+        let output = run_in_shell("fish", env_dir.path(), "gwt sw foo; and pwd");
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.trim(), target_path.display().to_string());
+    }
+
+    #[test]
+    fn fish_wrapper_sw_propagates_exit_status() {
+        if !shell_available("fish") {
+            eprintln!("fish not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let env_dir = setup_wrapper_env("fish", target.path());
+
+        let output = run_in_shell(
+            "fish",
+            env_dir.path(),
+            "gwt sw missing; echo status=$status",
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.trim(), "status=3");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Branch 'missing' doesn't exist."));
+    }
+
+    /// `gwt sw 'wip stuff'` hands the branch to the binary as one argument and
+    /// changes to a worktree whose path has a space in it
+    fn assert_sw_with_spaces(shell: &str) {
+        if !shell_available(shell) {
+            eprintln!("{shell} not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap().join("my worktree");
+        std::fs::create_dir(&target_path).unwrap();
+        let env_dir = setup_wrapper_env(shell, &target_path);
+
+        let output = run_in_shell(shell, env_dir.path(), "gwt sw 'wip stuff' '*' && pwd");
+        assert!(output.status.success(), "{shell}: {output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.trim(), target_path.display().to_string(), "{shell}");
+        let args = std::fs::read_to_string(env_dir.path().join("args")).unwrap();
+        assert_eq!(args, "sw\nwip stuff\n*\n", "{shell}");
+    }
+
+    #[test]
+    fn bash_wrapper_sw_passes_branch_with_spaces() {
+        assert_sw_with_spaces("bash");
+    }
+
+    #[test]
+    fn zsh_wrapper_sw_passes_branch_with_spaces() {
+        assert_sw_with_spaces("zsh");
+    }
+
+    #[test]
+    fn fish_wrapper_sw_passes_branch_with_spaces() {
+        assert_sw_with_spaces("fish");
+    }
+
+    fn assert_sw_no_cd(shell: &str) {
+        if !shell_available(shell) {
+            eprintln!("{shell} not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env(shell, &target_path);
+
+        for flag in ["--no-cd", "--print"] {
+            let script = if shell == "fish" {
+                format!("cd /; and gwt sw {flag} 'wip stuff'; and pwd")
+            } else {
+                format!("cd / && gwt sw 'wip stuff' {flag} && pwd")
+            };
+            let output = run_in_shell(shell, env_dir.path(), &script);
+            assert!(output.status.success(), "{shell}: {output:?}");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert_eq!(stdout, format!("{}\n/\n", target_path.display()), "{shell}");
+            let args = std::fs::read_to_string(env_dir.path().join("args")).unwrap();
+            assert_eq!(args, "sw\nwip stuff\n", "{shell} {flag}");
+        }
+    }
+
+    #[test]
+    fn bash_wrapper_sw_no_cd_prints_path() {
+        assert_sw_no_cd("bash");
+    }
+
+    #[test]
+    fn bash_wrapper_sw_json_passes_through() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("bash", &target_path);
+
+        let output = run_in_shell("bash", env_dir.path(), "cd / && gwt sw foo --json && pwd");
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout, format!("{}\n/\n", target_path.display()));
+        let args = std::fs::read_to_string(env_dir.path().join("args")).unwrap();
+        assert_eq!(args, "sw\nfoo\n--json\n");
+    }
+
+    #[test]
+    fn bash_wrapper_sw_eval_passes_through() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("bash", &target_path);
+
+        let output = run_in_shell(
+            "bash",
+            env_dir.path(),
+            "cd / && gwt sw foo --eval=fish && pwd",
+        );
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout, format!("{}\n/\n", target_path.display()));
+        let args = std::fs::read_to_string(env_dir.path().join("args")).unwrap();
+        assert_eq!(args, "sw\nfoo\n--eval=fish\n");
+    }
+
+    fn assert_alias_changes_directory(shell: &str) {
+        if !shell_available(shell) {
+            eprintln!("{shell} not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env(shell, &target_path);
+
+        let script = match shell {
+            "fish" => "gwt co; and pwd",
+            _ => "gwt co && pwd",
+        };
+        let output = run_in_shell(shell, env_dir.path(), script);
+        assert!(output.status.success(), "{shell}: {output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.trim(), target_path.display().to_string(), "{shell}");
+        let args = std::fs::read_to_string(env_dir.path().join("args")).unwrap();
+        assert_eq!(args, "sw\nit's\n", "{shell}");
+    }
+
+    #[test]
+    fn bash_wrapper_alias_changes_directory() {
+        assert_alias_changes_directory("bash");
+    }
+
+    #[test]
+    fn fish_wrapper_alias_changes_directory() {
+        assert_alias_changes_directory("fish");
+    }
+
+    #[test]
+    fn zsh_wrapper_sw_no_cd_prints_path() {
+        assert_sw_no_cd("zsh");
+    }
+
+    #[test]
+    fn fish_wrapper_sw_no_cd_prints_path() {
+        assert_sw_no_cd("fish");
+    }
+
+    #[test]
+    fn bash_wrapper_home_changes_directory() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("bash", &target_path);
+
+        for cmd in ["home", "main"] {
+            let output = run_in_shell("bash", env_dir.path(), &format!("gwt {cmd} && pwd"));
+            assert!(output.status.success());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert_eq!(stdout.trim(), target_path.display().to_string());
+        }
+    }
+
+    #[test]
+    fn bash_wrapper_mv_and_recent_change_directory() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("bash", &target_path);
+
+        for cmd in ["mv old new", "recent --pick"] {
+            let output = run_in_shell("bash", env_dir.path(), &format!("gwt {cmd} && pwd"));
+            assert!(output.status.success());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert_eq!(stdout.trim(), target_path.display().to_string(), "{cmd}");
+        }
+    }
+
+    #[test]
+    fn bash_wrapper_options() {
+        if !shell_available("bash") {
+            eprintln!("bash not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env_with("bash", &target_path, &options("wt", false, true));
+
+        let output = run_in_shell("bash", env_dir.path(), "wt sw foo && pwd");
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout, format!("listed\n{}\n", target_path.display()));
+
+        // `rm` passes straight through: the path is printed, not changed to
+        let output = run_in_shell("bash", env_dir.path(), "cd / && wt rm foo && pwd");
+        assert!(output.status.success(), "{output:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout, format!("{}\n/\n", target_path.display()));
+    }
+
+    #[test]
+    fn fish_wrapper_home_changes_directory() {
+        if !shell_available("fish") {
+            eprintln!("fish not installed, skipping");
+            return;
+        }
+
+        let target = tempfile::tempdir().unwrap();
+        let target_path = target.path().canonicalize().unwrap();
+        let env_dir = setup_wrapper_env("fish", &target_path);
+
+        for cmd in ["home", "main"] {
+            let output = run_in_shell("fish", env_dir.path(), &format!("gwt {cmd}; and pwd"));
+            assert!(output.status.success());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert_eq!(stdout.trim(), target_path.display().to_string());
+        }
+    }
+
+    #[test]
+    fn generate_powershell_init_contains_function() {
+        let s = generate_init("powershell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("function gwt {"));
+        assert!(s.contains("& gwtree @args"));
+        assert!(s.contains(
+            "@('switch', 'sw', 'remove', 'rm', 'mv', 'home', 'main', 'recent', 'clone', 'pr') -contains $args[0]"
+        ));
+        assert!(s.contains("$arg -eq '--help' -or $arg -eq '-h'"));
+        assert!(s.contains("Set-Location -LiteralPath $result"));
+        assert!(s.contains("$global:LASTEXITCODE = $exitCode"));
+    }
+
+    #[test]
+    fn generate_powershell_init_contains_completion() {
+        let s = generate_init("powershell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("Register-ArgumentCompleter -Native -CommandName 'gwt'"));
+        assert!(s.contains("gwtree ls --raw --cached"));
+    }
+
+    #[test]
+    fn generate_pwsh_alias_matches_powershell() {
+        assert_eq!(
+            generate_init("pwsh", "gwtree", &InitOptions::default()).unwrap(),
+            generate_init("powershell", "gwtree", &InitOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn generate_nushell_init_contains_function() {
+        let s = generate_init("nushell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("def --env --wrapped gwt [...args: string@\"nu-complete gwt\"]"));
+        assert!(s.contains("^gwtree ...$args"));
+        assert!(s.contains("if $subcommand in [switch sw remove rm mv home main recent clone pr]"));
+        assert!(s.contains(r#"("--help" in $args) or ("-h" in $args)"#));
+        assert!(s.contains("cd $result"));
+    }
+
+    #[test]
+    fn generate_nushell_init_contains_completion() {
+        let s = generate_init("nushell", "gwtree", &InitOptions::default()).unwrap();
+        assert!(s.contains("def \"nu-complete gwt\" [context: string]"));
+        assert!(s.contains("^gwtree ls --raw --cached | lines"));
+    }
+
+    #[test]
+    fn generate_nu_alias_matches_nushell() {
+        assert_eq!(
+            generate_init("nu", "gwtree", &InitOptions::default()).unwrap(),
+            generate_init("nushell", "gwtree", &InitOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn detect_shell_from_shell_env() {
+        assert_eq!(
+            detect_shell(Some("/usr/local/bin/fish"), None).as_deref(),
+            Some("fish")
+        );
+        assert_eq!(
+            detect_shell(Some("/bin/zsh"), Some("bash")).as_deref(),
+            Some("zsh")
+        );
+        assert_eq!(detect_shell(Some("-bash"), None).as_deref(), Some("bash"));
+        assert_eq!(
+            detect_shell(Some("C:\\Program Files\\pwsh.exe"), None).as_deref(),
+            Some("pwsh")
+        );
+
+        for (env, expected) in [
+            ("/usr/local/bin/fish", "function gwt"),
+            ("/bin/bash", "complete -F _gwt_completions gwt"),
+            ("/usr/bin/zsh", "compdef _gwt gwt"),
+            ("/opt/homebrew/bin/nu", "def --env --wrapped gwt"),
+        ] {
+            let shell = detect_shell(Some(env), None).unwrap();
+            assert!(
+                generate_init(&shell, "gwtree", &InitOptions::default())
+                    .unwrap()
+                    .contains(expected),
+                "{env}"
+            );
+        }
+    }
+
+    #[test]
+    fn detect_shell_falls_back_to_parent_process() {
+        assert_eq!(detect_shell(None, Some("zsh")).as_deref(), Some("zsh"));
+        assert_eq!(
+            detect_shell(Some(""), Some("-fish")).as_deref(),
+            Some("fish")
+        );
+        assert_eq!(
+            detect_shell(Some("/bin/tcsh"), Some("bash")).as_deref(),
+            Some("bash")
+        );
+    }
+
+    #[test]
+    fn detect_shell_unknown_reports_unsupported() {
+        let shell = detect_shell(Some("/bin/tcsh"), Some("sshd")).unwrap();
+        assert_eq!(shell, "tcsh");
+        assert_eq!(
+            generate_init(&shell, "gwtree", &InitOptions::default())
+                .unwrap_err()
+                .to_string(),
+            "Unsupported shell 'tcsh'. Supported: bash, zsh, fish, powershell, nushell"
+        );
+
+        assert_eq!(detect_shell(None, None), None);
+        assert_eq!(detect_shell(None, Some("sshd")), None);
     }
 
     #[test]
     fn generate_init_unsupported_shell() {
-        let result = generate_init("powershell");
+        let result = generate_init("tcsh", "gwtree", &InitOptions::default());
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
-            "Unsupported shell 'powershell'. Supported: bash, zsh, fish"
+            "Unsupported shell 'tcsh'. Supported: bash, zsh, fish, powershell, nushell"
         );
     }
 }
@@ -0,0 +1,248 @@
+use crate::utility::{Git, Tracking, Worktree, glyphs};
+use anyhow::Result;
+use console::{Alignment, measure_text_width, pad_str, style};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread;
+
+/// Health of a single worktree, as printed by `gwt status`
+#[derive(Debug, Serialize)]
+struct WorktreeStatus {
+    branch: Option<String>,
+    path: PathBuf,
+    head: String,
+    /// The worktree the command was run from
+    current: bool,
+    /// The directory no longer exists (`git worktree prune` would remove it)
+    missing: bool,
+    /// Uncommitted changes; `None` when the directory is missing or git failed
+    dirty: Option<bool>,
+    upstream: Option<String>,
+    ahead: u32,
+    behind: u32,
+    /// An upstream is configured but the remote branch no longer exists
+    upstream_gone: bool,
+}
+
+pub fn handle(git: &Git, json: bool) -> Result<()> {
+    let worktrees: Vec<Worktree> = git.list_worktrees()?.iter().cloned().collect();
+    let tracking = git.branch_tracking()?;
+    let current = git.git_toplevel().ok();
+
+    let statuses = collect(git, &worktrees, &tracking, current.as_ref());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+    } else {
+        for line in render(&statuses) {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check every worktree. The per-worktree `git status` calls are the slow part,
+/// so they run concurrently; results are returned in `worktrees` order.
+fn collect(
+    git: &Git,
+    worktrees: &[Worktree],
+    tracking: &HashMap<String, Tracking>,
+    current: Option<&PathBuf>,
+) -> Vec<WorktreeStatus> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = worktrees
+            .iter()
+            .map(|wt| {
+                let missing = !wt.path().is_dir();
+                let dirty = (!missing).then(|| scope.spawn(move || git.is_dirty(wt.path())));
+                (wt, missing, dirty)
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|(wt, missing, dirty)| {
+                let dirty = dirty.and_then(|h| h.join().ok()).and_then(|r| r.ok());
+                let tracking = wt
+                    .branch()
+                    .and_then(|b| tracking.get(b))
+                    .cloned()
+                    .unwrap_or_default();
+
+                WorktreeStatus {
+                    branch: wt.branch().map(String::from),
+                    path: wt.path().clone(),
+                    head: wt.head().to_string(),
+                    current: current == Some(wt.path()),
+                    missing,
+                    dirty,
+                    upstream: tracking.upstream,
+                    ahead: tracking.ahead,
+                    behind: tracking.behind,
+                    upstream_gone: tracking.gone,
+                }
+            })
+            .collect()
+    })
+}
+
+fn render(statuses: &[WorktreeStatus]) -> Vec<String> {
+    let branches: Vec<String> = statuses
+        .iter()
+        .map(|s| match &s.branch {
+            Some(branch) => branch.clone(),
+            None => format!("(detached {})", &s.head[..s.head.len().min(7)]),
+        })
+        .collect();
+    let tags: Vec<String> = statuses.iter().map(tags).collect();
+
+    let branch_width = branches.iter().map(|b| b.len()).max().unwrap_or(0);
+    let tags_width = tags
+        .iter()
+        .map(|t| measure_text_width(t))
+        .max()
+        .unwrap_or(0);
+
+    statuses
+        .iter()
+        .zip(branches.iter().zip(&tags))
+        .map(|(status, (branch, tags))| {
+            let marker = if status.current { "*" } else { " " };
+            format!(
+                "{} {:<branch_width$} {} {}",
+                marker,
+                branch,
+                pad_str(tags, tags_width, Alignment::Left, None),
+                style(status.path.display()).cyan(),
+            )
+        })
+        .collect()
+}
+
+/// Styled summary of a worktree's problems, e.g. `dirty ↑2 ↓1 gone`
+fn tags(status: &WorktreeStatus) -> String {
+    let mut tags = vec![match (status.missing, status.dirty) {
+        (true, _) => style("missing").red().to_string(),
+        (false, Some(true)) => style("dirty").red().to_string(),
+        (false, Some(false)) => style("clean").green().to_string(),
+        (false, None) => style("unknown").yellow().to_string(),
+    }];
+
+    let glyphs = glyphs();
+    if status.ahead > 0 {
+        tags.push(format!("{}{}", glyphs.ahead, status.ahead));
+    }
+    if status.behind > 0 {
+        tags.push(format!("{}{}", glyphs.behind, status.behind));
+    }
+    if status.upstream_gone {
+        tags.push(style("gone").red().to_string());
+    }
+
+    tags.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
+
+    /// Three worktrees: a clean main, a dirty feature branch that's ahead of its
+    /// upstream, and a branch whose upstream and directory are both gone
+    fn setup() -> (tempfile::TempDir, Vec<Worktree>, HashMap<String, Tracking>) {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        let feature = dir.path().join("feature");
+        std::fs::create_dir_all(&main).unwrap();
+        std::fs::create_dir_all(&feature).unwrap();
+
+        let worktrees = vec![
+            Worktree::new(main, "aaaaaaa1".to_string(), Some("main".to_string())),
+            Worktree::new(feature, "bbbbbbb2".to_string(), Some("feature".to_string())),
+            Worktree::new(
+                dir.path().join("stale"),
+                "ccccccc3".to_string(),
+                Some("stale".to_string()),
+            ),
+        ];
+        let tracking = HashMap::from([
+            (
+                "feature".to_string(),
+                Tracking {
+                    upstream: Some("origin/feature".to_string()),
+                    ahead: 2,
+                    ..Default::default()
+                },
+            ),
+            (
+                "stale".to_string(),
+                Tracking {
+                    upstream: Some("origin/stale".to_string()),
+                    gone: true,
+                    ..Default::default()
+                },
+            ),
+        ]);
+        (dir, worktrees, tracking)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_statuses() {
+        let (_dir, worktrees, tracking) = setup();
+        let (mock_git, _mock_dir) = create_mock_git_script(
+            r#"#!/bin/sh
+if [ "$3" = "status" ]; then
+    case "$2" in
+        */feature) echo " M src/lib.rs" ;;
+    esac
+    exit 0
+fi
+exit 1
+"#,
+        );
+
+        let git = Git::with_program(&mock_git);
+        let statuses = collect(&git, &worktrees, &tracking, Some(worktrees[0].path()));
+
+        assert!(statuses[0].current);
+        assert_eq!(statuses[0].dirty, Some(false));
+        assert_eq!(statuses[0].upstream, None);
+
+        assert!(!statuses[1].current);
+        assert_eq!(statuses[1].dirty, Some(true));
+        assert_eq!(statuses[1].ahead, 2);
+
+        assert!(statuses[2].missing);
+        assert_eq!(statuses[2].dirty, None);
+        assert!(statuses[2].upstream_gone);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_render_and_json() {
+        let (_dir, worktrees, tracking) = setup();
+        let (mock_git, _mock_dir) = create_mock_git_script("#!/bin/sh\nexit 0\n");
+
+        let git = Git::with_program(&mock_git);
+        let statuses = collect(&git, &worktrees, &tracking, None);
+
+        let lines: Vec<String> = render(&statuses)
+            .iter()
+            .map(|l| console::strip_ansi_codes(l).into_owned())
+            .collect();
+        assert!(lines[0].starts_with("  main    clean "), "{lines:?}");
+        assert!(lines[1].contains("feature clean") && lines[1].contains('2'));
+        assert!(lines[2].contains("stale   missing gone"), "{lines:?}");
+
+        let json: serde_json::Value = serde_json::to_value(&statuses).unwrap();
+        assert_eq!(json[1]["branch"], "feature");
+        assert_eq!(json[1]["ahead"], 2);
+        assert_eq!(json[2]["missing"], true);
+        assert_eq!(json[2]["dirty"], serde_json::Value::Null);
+        assert_eq!(json[2]["upstream_gone"], true);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::utility::Git;
+use anyhow::Result;
+use serde::Serialize;
+
+/// Version of gwt, and the commit and date it was built from (`unknown` when
+/// the build couldn't tell)
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Build {
+    pub version: &'static str,
+    pub commit: &'static str,
+    pub build_date: &'static str,
+}
+
+/// This build of gwt, as embedded by build.rs
+pub const BUILD: Build = Build {
+    version: env!("CARGO_PKG_VERSION"),
+    commit: env!("GWT_BUILD_COMMIT"),
+    build_date: env!("GWT_BUILD_DATE"),
+};
+
+impl Build {
+    /// One-line description, e.g. `0.4.0 (1b9d857a1c2e, built 2026-10-15)`
+    pub fn summary(&self) -> String {
+        format!(
+            "{} ({}, built {})",
+            self.version, self.commit, self.build_date
+        )
+    }
+}
+
+/// What `gwt version --json` prints
+#[derive(Debug, Serialize)]
+struct Report {
+    #[serde(flatten)]
+    build: &'static Build,
+    git: GitReport,
+}
+
+/// The git gwt runs, per `GWT_GIT`
+#[derive(Debug, Serialize)]
+struct GitReport {
+    executable: String,
+    /// `git --version` output; `None` when git couldn't be run
+    version: Option<String>,
+}
+
+pub fn handle(git: &Git, json: bool) -> Result<()> {
+    let report = Report {
+        build: &BUILD,
+        git: GitReport {
+            executable: shell_words::join(git.executable()),
+            version: git.version().ok().map(|v| v.raw),
+        },
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for line in render(&report) {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn render(report: &Report) -> Vec<String> {
+    vec![
+        format!("gwt {}", report.build.version),
+        format!("commit:     {}", report.build.commit),
+        format!("build date: {}", report.build.build_date),
+        format!(
+            "git:        {} ({})",
+            report.git.version.as_deref().unwrap_or("unknown"),
+            report.git.executable
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static UNKNOWN: Build = Build {
+        version: "0.4.0",
+        commit: "unknown",
+        build_date: "unknown",
+    };
+
+    #[test]
+    fn test_render() {
+        let report = Report {
+            build: &UNKNOWN,
+            git: GitReport {
+                executable: "git -c core.fsmonitor=false".to_string(),
+                version: Some("git version 2.43.0".to_string()),
+            },
+        };
+        assert_eq!(
+            render(&report),
+            [
+                "gwt 0.4.0",
+                "commit:     unknown",
+                "build date: unknown",
+                "git:        git version 2.43.0 (git -c core.fsmonitor=false)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_without_git() {
+        let report = Report {
+            build: &UNKNOWN,
+            git: GitReport {
+                executable: "/no/such/git".to_string(),
+                version: None,
+            },
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["version"], "0.4.0");
+        assert_eq!(value["commit"], "unknown");
+        assert_eq!(value["build_date"], "unknown");
+        assert_eq!(value["git"]["executable"], "/no/such/git");
+        assert!(value["git"]["version"].is_null());
+    }
+
+    #[test]
+    fn test_build_is_embedded() {
+        assert_eq!(BUILD.version, env!("CARGO_PKG_VERSION"));
+        assert!(!BUILD.commit.is_empty());
+        assert!(BUILD.build_date == "unknown" || BUILD.build_date.len() == 10);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::error::GwtError;
+use crate::utility::{Git, GitBackend};
+use anyhow::{Context, Result, bail};
+use console::style;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The worktree a directory belongs to
+#[derive(Debug, PartialEq, Eq)]
+struct Location {
+    /// Name of the repository's main worktree directory
+    repo: String,
+    branch: Option<String>,
+    head: String,
+    root: PathBuf,
+    /// Where the directory is inside the worktree; empty at its root
+    relative: PathBuf,
+}
+
+/// Print the repository, branch and worktree of `path` (the current
+/// directory by default), e.g. for an opaque store path from `gwt ls`
+pub fn handle(git: &Git, path: Option<&Path>) -> Result<()> {
+    let path = path.unwrap_or(Path::new("."));
+    let path = fs::canonicalize(path)
+        .with_context(|| format!("Failed to resolve '{}'", path.display()))?;
+    // Ask the repository the path is in, not the one gwt runs in
+    let dir = if path.is_dir() {
+        path.clone()
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    };
+    let git = git.clone().with_cwd(dir);
+
+    let location = match describe(&git, &path) {
+        Err(e) if matches!(GwtError::find(&e), Some(GwtError::NotARepository { .. })) => None,
+        location => location?,
+    };
+    let Some(location) = location else {
+        bail!("'{}' is not a gwt-managed worktree", path.display());
+    };
+
+    let branch = match &location.branch {
+        Some(branch) => style(branch.clone()).green(),
+        None => style(format!(
+            "(detached at {})",
+            &location.head[..location.head.len().min(7)]
+        ))
+        .yellow(),
+    };
+    let relative = if location.relative.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        &location.relative
+    };
+    println!("Repo:     {}", location.repo);
+    println!("Branch:   {}", branch);
+    println!("Worktree: {}", style(location.root.display()).cyan());
+    println!("Path:     {}", relative.display());
+    Ok(())
+}
+
+/// The registered worktree containing `path`, the innermost one if worktrees
+/// are nested; `None` when `path` isn't inside any of them
+fn describe(git: &dyn GitBackend, path: &Path) -> Result<Option<Location>> {
+    let worktrees = git.list_worktrees()?;
+    let repo = worktrees
+        .iter()
+        .next()
+        .and_then(|main| main.path().file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let found = worktrees
+        .iter()
+        .map(|wt| {
+            let root = fs::canonicalize(wt.path()).unwrap_or_else(|_| wt.path().clone());
+            (wt, root)
+        })
+        .filter(|(_, root)| path.starts_with(root))
+        .max_by_key(|(_, root)| root.components().count());
+
+    Ok(found.map(|(wt, root)| Location {
+        repo,
+        branch: wt.branch().map(str::to_string),
+        head: wt.head().to_string(),
+        relative: path.strip_prefix(&root).unwrap_or(path).to_path_buf(),
+        root,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::FakeGit;
+
+    fn git() -> FakeGit {
+        FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/repo/69fa950d", "feature")
+            .with_detached_worktree("/store/repo/77aa0000")
+    }
+
+    #[test]
+    fn test_describe_walks_up_to_the_worktree_root() {
+        let location = describe(&git(), Path::new("/store/repo/69fa950d/src/bin"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            location,
+            Location {
+                repo: "repo".to_string(),
+                branch: Some("feature".to_string()),
+                head: "1".repeat(40),
+                root: PathBuf::from("/store/repo/69fa950d"),
+                relative: PathBuf::from("src/bin"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_describe_main_and_detached_worktrees() {
+        let main = describe(&git(), Path::new("/path/to/repo"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(main.branch.as_deref(), Some("main"));
+        assert_eq!(main.relative, PathBuf::new());
+
+        let detached = describe(&git(), Path::new("/store/repo/77aa0000"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(detached.branch, None);
+    }
+
+    #[test]
+    fn test_describe_outside_worktrees() {
+        assert_eq!(describe(&git(), Path::new("/store/repo")).unwrap(), None);
+        // A sibling sharing a name prefix isn't inside
+        assert_eq!(
+            describe(&git(), Path::new("/store/repo/69fa950d-old")).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ui;
+use crate::utility::GitBackend;
+use anyhow::{Context, Result};
+use console::style;
+use std::path::{Path, PathBuf};
+
+/// Message of the stash `gwt sw --take-changes` carries changes in
+const STASH_MESSAGE: &str = "gwt-carry";
+
+/// Uncommitted changes stashed away from the worktree they were made in
+pub(super) struct Carried {
+    from: PathBuf,
+    stash: String,
+}
+
+/// Stash the current worktree's changes, untracked files included, so they
+/// can be applied in a new worktree. `None` when there's nothing to carry.
+pub(super) fn stash_changes(git: &dyn GitBackend) -> Result<Option<Carried>> {
+    let from = git
+        .git_toplevel()
+        .context("--take-changes must be run inside a worktree of the repository")?;
+    let Some(stash) = git
+        .stash_push(&from, STASH_MESSAGE)
+        .context("Failed to stash the current changes")?
+    else {
+        ui::info!("{}", style("No changes to take along.").dim());
+        return Ok(None);
+    };
+    ui::info!("Stashed the changes in '{}'.", from.display());
+    Ok(Some(Carried { from, stash }))
+}
+
+impl Carried {
+    /// Apply the changes in the worktree at `path` and drop the stash. When
+    /// that fails, the stash is kept so nothing is lost.
+    pub(super) fn apply_to(self, git: &dyn GitBackend, path: &Path) {
+        if let Err(e) = git.stash_apply(path, &self.stash) {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Failed to apply your changes in '{}': {:#}\nThey're kept in the stash ({}); resolve the conflicts there, or run `git stash apply {}` once the worktree is clean.",
+                    path.display(),
+                    e,
+                    self.stash,
+                    self.stash
+                ))
+                .red()
+            );
+            return;
+        }
+        ui::info!("Moved your changes to '{}'.", path.display());
+        self.drop_stash(git);
+    }
+
+    /// Put the changes back where they came from, after the new worktree
+    /// couldn't be created
+    pub(super) fn restore(self, git: &dyn GitBackend) {
+        if let Err(e) = git.stash_apply(&self.from, &self.stash) {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Failed to restore your changes in '{}': {:#}\nThey're kept in the stash ({}).",
+                    self.from.display(),
+                    e,
+                    self.stash
+                ))
+                .red()
+            );
+            return;
+        }
+        self.drop_stash(git);
+    }
+
+    fn drop_stash(&self, git: &dyn GitBackend) {
+        if let Err(e) = git.stash_drop(&self.stash) {
+            ui::warn!("Failed to drop stash {}: {:#}", self.stash, e);
+        }
+    }
+}
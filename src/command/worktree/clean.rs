@@ -0,0 +1,298 @@
+use super::{dirty_or, resolve_main_branch, warn_on_meta_error};
+use crate::config::Config;
+use crate::meta;
+use crate::ui;
+use crate::utility::{GitBackend, MergeState, Worktree, unix_now};
+use anyhow::{Context, Result, anyhow, bail};
+use console::{Term, style};
+use dialoguer::Confirm;
+use std::time::Duration;
+
+/// Why `gwt clean` leaves an old worktree alone
+#[derive(Debug, PartialEq, Eq)]
+enum Keep {
+    Current,
+    Locked,
+    Dirty,
+    NotMerged,
+}
+
+impl Keep {
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::Current => "current worktree",
+            Self::Locked => "locked; use --force to remove it anyway",
+            Self::Dirty => "uncommitted changes; use --force to remove it anyway",
+            Self::NotMerged => "not merged",
+        }
+    }
+}
+
+/// A worktree last used longer ago than the cutoff
+struct Stale<'a> {
+    worktree: &'a Worktree,
+    last_active: u64,
+    keep: Option<Keep>,
+}
+
+/// Remove the linked worktrees of the current repository that haven't been
+/// switched to within `older_than` (see `gwt ls --age`).
+///
+/// The main worktree and the current one are never removed; locked and dirty
+/// ones only with `force`. With `merged_only`, only worktrees whose branch
+/// was merged into the main branch are considered. Branches are kept.
+pub fn clean(
+    git: &dyn GitBackend,
+    config: &Config,
+    older_than: Duration,
+    merged_only: bool,
+    dry_run: bool,
+    skip_confirmation: bool,
+    force: bool,
+) -> Result<()> {
+    config.ensure_worktree_root()?;
+    let data = config.data().ok_or_else(|| anyhow!("Config not loaded"))?;
+
+    let worktrees = git.list_worktrees()?;
+    let cutoff = unix_now().saturating_sub(older_than.as_secs());
+    let linked: Vec<&Worktree> = worktrees.iter().filter(|wt| !wt.is_main()).collect();
+    let ages = meta::last_active(&data.worktree_root, linked.iter().map(|wt| wt.path()));
+    let current = git.git_toplevel().ok();
+    let base = if merged_only {
+        Some(resolve_main_branch(git, config)?)
+    } else {
+        None
+    };
+
+    let mut stale = Vec::new();
+    for wt in linked {
+        // No age means the directory is gone; that's for `git worktree prune`
+        let Some(&last_active) = ages.get(wt.path()).filter(|t| **t < cutoff) else {
+            continue;
+        };
+        let keep = if current.as_ref() == Some(wt.path()) {
+            Some(Keep::Current)
+        } else if wt.locked().is_some() && !force {
+            Some(Keep::Locked)
+        } else if !force && git.is_dirty(wt.path()).unwrap_or(true) {
+            Some(Keep::Dirty)
+        } else if let Some(base) = &base
+            && !is_merged(git, config, wt, base)?
+        {
+            Some(Keep::NotMerged)
+        } else {
+            None
+        };
+        stale.push(Stale {
+            worktree: wt,
+            last_active,
+            keep,
+        });
+    }
+
+    let (remove, keep): (Vec<&Stale>, Vec<&Stale>) = stale.iter().partition(|s| s.keep.is_none());
+    for s in &keep {
+        ui::info!(
+            "{}",
+            style(format!(
+                "Keeping {} ({}): {}",
+                s.worktree.branch().unwrap_or("(detached)"),
+                s.worktree.path().display(),
+                s.keep.as_ref().map_or("", Keep::reason)
+            ))
+            .dim()
+        );
+    }
+    if remove.is_empty() {
+        ui::info!("No worktrees to clean up.");
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}",
+        if dry_run {
+            "Would remove:"
+        } else {
+            "Worktrees to remove:"
+        }
+    );
+    for s in &remove {
+        eprintln!(
+            "  {:<4}  {}  {}",
+            meta::short_age(s.last_active),
+            s.worktree.branch().unwrap_or("(detached)"),
+            style(s.worktree.path().display()).cyan()
+        );
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !skip_confirmation {
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Remove {} worktrees?", remove.len()))
+            .default(false)
+            .interact_on(&Term::stderr())
+            .map_err(ui::prompt_error)
+            .context("Failed to get confirmation")?;
+        if !confirmed {
+            ui::info!("Removal cancelled.");
+            return Ok(());
+        }
+    }
+
+    let mut progress = ui::Progress::new(remove.len());
+    for s in &remove {
+        progress.run(&s.worktree.path().display().to_string(), || {
+            remove_one(git, s.worktree, force)?;
+            warn_on_meta_error(meta::record_removed(&data.worktree_root, s.worktree.path()));
+            Ok(())
+        });
+    }
+    progress.print_summary();
+
+    let failed = progress.failed();
+    if failed > 0 {
+        bail!(
+            "{} of {} worktrees could not be removed",
+            failed,
+            remove.len()
+        );
+    }
+    Ok(())
+}
+
+fn is_merged(git: &dyn GitBackend, config: &Config, wt: &Worktree, base: &str) -> Result<bool> {
+    let Some(branch) = wt.branch().filter(|b| *b != base) else {
+        return Ok(false);
+    };
+    let detection = config
+        .data()
+        .map(|d| d.merged_detection)
+        .unwrap_or_default();
+    Ok(git.merge_state(branch, base, detection)? != MergeState::NotMerged)
+}
+
+fn remove_one(git: &dyn GitBackend, wt: &Worktree, force: bool) -> Result<()> {
+    let path = wt
+        .path()
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    if wt.locked().is_some() {
+        git.unlock_worktree(path)
+            .context("Failed to unlock worktree")?;
+    }
+    if force {
+        git.force_remove_worktree(path)
+    } else {
+        git.remove_worktree(path)
+            .map_err(|e| dirty_or(e, wt.path()))
+    }
+    .context("Failed to remove worktree")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::utility::FakeGit;
+    use std::path::PathBuf;
+
+    /// Worktree directories in the store, all last used long ago except `fresh`
+    fn store(config: &Config) -> PathBuf {
+        let root = config.data().unwrap().worktree_root.clone();
+        for name in ["old", "fresh", "locked", "dirty", "feature"] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            if name != "fresh" {
+                let file = std::fs::File::open(root.join(name)).unwrap();
+                file.set_modified(
+                    std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000),
+                )
+                .unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn test_clean_removes_only_old_unprotected_worktrees() {
+        let (_dir, config) = temp_config();
+        let root = store(&config);
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree(root.join("old"), "old")
+            .with_worktree(root.join("fresh"), "fresh")
+            .with_locked_worktree(root.join("locked"), "locked", "")
+            .with_worktree(root.join("dirty"), "dirty")
+            .with_dirty(root.join("dirty"));
+
+        clean(
+            &git,
+            &config,
+            Duration::from_secs(86_400),
+            false,
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            git.calls(),
+            [format!("remove_worktree {}", root.join("old").display())]
+        );
+    }
+
+    #[test]
+    fn test_clean_force_removes_locked_and_dirty() {
+        let (_dir, config) = temp_config();
+        let root = store(&config);
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_locked_worktree(root.join("locked"), "locked", "")
+            .with_worktree(root.join("dirty"), "dirty")
+            .with_dirty(root.join("dirty"));
+
+        clean(
+            &git,
+            &config,
+            Duration::from_secs(86_400),
+            false,
+            false,
+            true,
+            true,
+        )
+        .unwrap();
+
+        let locked = root.join("locked").display().to_string();
+        let dirty = root.join("dirty").display().to_string();
+        assert_eq!(
+            git.calls(),
+            [
+                format!("unlock_worktree {locked}"),
+                format!("force_remove_worktree {locked}"),
+                format!("force_remove_worktree {dirty}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clean_merged_only_and_dry_run() {
+        let (_dir, config) = temp_config();
+        let root = store(&config);
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree(root.join("old"), "old")
+            .with_worktree(root.join("feature"), "feature")
+            .with_merge_state("feature", MergeState::Merged);
+        let day = Duration::from_secs(86_400);
+
+        clean(&git, &config, day, true, true, true, false).unwrap();
+        assert!(git.calls().is_empty());
+
+        clean(&git, &config, day, true, false, true, false).unwrap();
+        assert_eq!(
+            git.calls(),
+            [format!(
+                "remove_worktree {}",
+                root.join("feature").display()
+            )]
+        );
+    }
+}
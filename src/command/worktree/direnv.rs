@@ -0,0 +1,82 @@
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{ExternalOptions, OutputMode, run_external};
+use anyhow::{Result, bail};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// How long `direnv allow` may run unless `direnv.timeout_secs` says otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run `direnv allow` in a new worktree that has an `.envrc`, when
+/// `direnv_allow` is set. This is a convenience, so failing never fails the
+/// creation of the worktree.
+pub(super) fn allow_new_worktree(config: &Config, path: &Path) {
+    let Some(data) = config.data().filter(|d| d.direnv_allow) else {
+        return;
+    };
+    if let Err(e) = allow("direnv", path, data.direnv.timeout(Some(DEFAULT_TIMEOUT))) {
+        ui::warn!("Failed to allow .envrc: {:#}", e);
+    }
+}
+
+fn allow(program: &str, path: &Path, timeout: Option<Duration>) -> Result<()> {
+    if !path.join(".envrc").is_file() {
+        return Ok(());
+    }
+    ui::info!("Running `direnv allow` in '{}'", path.display());
+
+    let mut cmd = Command::new(program);
+    cmd.arg("allow").arg(path);
+    let opts = ExternalOptions::new()
+        .output(OutputMode::Capture)
+        .timeout(timeout);
+    let output = run_external(cmd, &opts)?;
+    if !output.status.success() {
+        bail!("direnv allow failed: {}", output.stderr.trim_end());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_allow_only_with_envrc() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("direnv.log");
+        let (direnv, _script_dir) =
+            create_mock_git_script(&format!("#!/bin/sh\necho \"$*\" >> '{}'\n", log.display()));
+        let direnv = direnv.to_str().unwrap();
+        let wt = dir.path().join("wt");
+        std::fs::create_dir_all(&wt).unwrap();
+
+        allow(direnv, &wt, None).unwrap();
+        assert!(!log.exists());
+
+        std::fs::write(wt.join(".envrc"), "export FOO=1\n").unwrap();
+        allow(direnv, &wt, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            format!("allow {}\n", wt.display())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_allow_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".envrc"), "").unwrap();
+        let (direnv, _script_dir) =
+            create_mock_git_script("#!/bin/sh\necho 'permission denied' >&2\nexit 1\n");
+
+        let err = allow(direnv.to_str().unwrap(), dir.path(), None).unwrap_err();
+        assert_eq!(err.to_string(), "direnv allow failed: permission denied");
+        assert!(allow("gwt-no-such-direnv", dir.path(), None).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::command::clone::REPO_DIR_NAME;
+use anyhow::{Result, bail};
+use std::path::{Component, Path, PathBuf};
+
+/// What the placeholders of the `path_template` config option stand for
+pub(super) struct PathValues<'a> {
+    pub repo: &'a str,
+    pub branch: &'a str,
+    pub hash: &'a str,
+}
+
+/// Render `template` into the path of a worktree relative to the worktree
+/// root, substituting `{repo}`, `{branch}`, `{branch_slug}` and `{hash}`.
+///
+/// Fails on unknown placeholders, on templates that would put every branch in
+/// the same directory, and when the result leaves the worktree root through
+/// `..` or an absolute path.
+pub(super) fn render_path_template(
+    template: &str,
+    values: &PathValues,
+    nest_slashes: bool,
+) -> Result<PathBuf> {
+    let mut rendered = String::new();
+    let mut per_branch = false;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            bail!("Unclosed placeholder in path_template '{}'", template);
+        };
+        let name = &rest[start + 1..start + len];
+        match name {
+            "repo" => rendered.push_str(values.repo),
+            "branch" => rendered.push_str(values.branch),
+            "branch_slug" => rendered.push_str(&branch_slug(values.branch, nest_slashes)),
+            "hash" => rendered.push_str(values.hash),
+            _ => bail!(
+                "Unknown placeholder '{{{}}}' in path_template '{}'. Use {{repo}}, {{branch}}, {{branch_slug}} or {{hash}}.",
+                name,
+                template
+            ),
+        }
+        per_branch |= name != "repo";
+        rest = &rest[start + len + 1..];
+    }
+    rendered.push_str(rest);
+
+    if !per_branch {
+        bail!(
+            "path_template '{}' needs {{branch}}, {{branch_slug}} or {{hash}} to give each branch its own directory",
+            template
+        );
+    }
+
+    let path = PathBuf::from(&rendered);
+    if !stays_inside(&path) {
+        bail!(
+            "path_template '{}' puts branch '{}' at '{}', outside the worktree root",
+            template,
+            values.branch,
+            rendered
+        );
+    }
+    Ok(path)
+}
+
+/// The part of the path `template` renders to that every branch of `repo`
+/// shares: the directories before the first one with a per-branch
+/// placeholder. Empty when worktrees sit directly in the worktree root.
+pub(super) fn render_repo_dir(template: &str, repo: &str, nest_slashes: bool) -> Result<PathBuf> {
+    // Fails the same way as creating a worktree would
+    let values = PathValues {
+        repo,
+        branch: "branch",
+        hash: "hash",
+    };
+    render_path_template(template, &values, nest_slashes)?;
+
+    let per_branch = ["{branch}", "{branch_slug}", "{hash}"]
+        .iter()
+        .filter_map(|p| template.find(p))
+        .min()
+        .unwrap_or(template.len());
+    let shared = template[..per_branch]
+        .rfind('/')
+        .map_or("", |end| &template[..end]);
+    let shared = PathBuf::from(shared.replace("{repo}", repo));
+    Ok(shared
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect())
+}
+
+/// Whether joining `path` to a directory names something below it: only
+/// plain components, and at least one of them
+fn stays_inside(path: &Path) -> bool {
+    let mut components = path.components().filter(|c| *c != Component::CurDir);
+    components.clone().next().is_some() && components.all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// `branch` with anything but letters, digits, `.`, `_` and `-` replaced by
+/// `-`. Slashes become directories with `nest_slashes` and `-` without.
+fn branch_slug(branch: &str, nest_slashes: bool) -> String {
+    branch
+        .chars()
+        .map(|c| match c {
+            '/' if nest_slashes => '/',
+            c if c.is_ascii_alphanumeric() || "._-".contains(c) => c,
+            _ => '-',
+        })
+        .collect()
+}
+
+/// Name of the repository for `{repo}`: the directory of its main worktree,
+/// or the directory around it for a `gwt clone` (`<name>/.repo`)
+pub(crate) fn repo_name(main_worktree: &Path) -> String {
+    let dir = match main_worktree.file_name() {
+        Some(name) if name == REPO_DIR_NAME => main_worktree.parent().unwrap_or(main_worktree),
+        _ => main_worktree,
+    };
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Owner and repository name from a remote URL, e.g. `("troydai", "gwt")`
+/// for `git@github.com:troydai/gwt.git`. The owner keeps GitLab subgroups
+/// (`group/subgroup`). `None` for local paths and URLs without an owner.
+pub(crate) fn parse_remote_url(url: &str) -> Option<(String, String)> {
+    let url = url.trim();
+    let path = match url.split_once("://") {
+        Some(("file", _)) => return None,
+        // ssh://git@host:22/owner/repo, https://host/owner/repo
+        Some((_, rest)) => rest.split_once('/')?.1,
+        // git@host:owner/repo; a `/` before the `:` makes it a local path
+        None => {
+            let (host, path) = url.split_once(':')?;
+            if host.is_empty() || host.contains('/') {
+                return None;
+            }
+            path
+        }
+    };
+
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.iter().any(|s| *s == "." || *s == "..") {
+        return None;
+    }
+    let (repo, owner) = segments.split_last()?;
+    if owner.is_empty() {
+        return None;
+    }
+    Some((owner.join("/"), repo.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, branch: &str, nest_slashes: bool) -> Result<PathBuf> {
+        let values = PathValues {
+            repo: "api",
+            branch,
+            hash: "0123456789abcdef",
+        };
+        render_path_template(template, &values, nest_slashes)
+    }
+
+    #[test]
+    fn test_render_path_template() {
+        assert_eq!(
+            render("{repo}/{branch}", "feature/login", false).unwrap(),
+            PathBuf::from("api/feature/login")
+        );
+        assert_eq!(
+            render("{repo}-{branch_slug}", "feature/login", false).unwrap(),
+            PathBuf::from("api-feature-login")
+        );
+        assert_eq!(
+            render("{repo}/{branch_slug}", "feature/login", true).unwrap(),
+            PathBuf::from("api/feature/login")
+        );
+        assert_eq!(
+            render("{repo}/{hash}", "feature/login", false).unwrap(),
+            PathBuf::from("api/0123456789abcdef")
+        );
+        assert_eq!(
+            render("./wt_{branch_slug}", "fix it#2", false).unwrap(),
+            PathBuf::from("./wt_fix-it-2")
+        );
+    }
+
+    #[test]
+    fn test_render_path_template_rejects_traversal() {
+        for template in [
+            "../{branch}",
+            "{repo}/../../{branch}",
+            "/tmp/{branch}",
+            "{repo}/..{hash}/../..",
+        ] {
+            let err = render(template, "feature", false).unwrap_err();
+            assert!(
+                err.to_string().contains("outside the worktree root"),
+                "{template}: {err}"
+            );
+        }
+        // A branch can't climb out either, whatever git allows in its name
+        assert!(render("{branch}", "../escape", false).is_err());
+        assert!(render("{branch_slug}", "../escape", true).is_err());
+        assert!(render(".", "feature", false).is_err());
+    }
+
+    #[test]
+    fn test_render_path_template_rejects_bad_placeholders() {
+        let err = render("{repo}/{name}", "feature", false).unwrap_err();
+        assert!(
+            err.to_string().contains("Unknown placeholder '{name}'"),
+            "{err}"
+        );
+        assert!(render("{repo}/{branch", "feature", false).is_err());
+        // Every branch would get the same directory
+        let err = render("{repo}", "feature", false).unwrap_err();
+        assert!(err.to_string().contains("its own directory"), "{err}");
+    }
+
+    #[test]
+    fn test_render_repo_dir() {
+        for (template, expected) in [
+            ("{repo}/{branch}", "api"),
+            ("{repo}/wt/{hash}", "api/wt"),
+            ("{repo}-{branch_slug}", ""),
+            ("{hash}", ""),
+            ("./{repo}/{hash}", "api"),
+            ("all/{repo}/{branch_slug}/src", "all/api"),
+        ] {
+            assert_eq!(
+                render_repo_dir(template, "api", false).unwrap(),
+                PathBuf::from(expected),
+                "{template}"
+            );
+        }
+        assert!(render_repo_dir("../{repo}/{branch}", "api", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_url() {
+        for (url, expected) in [
+            (
+                "https://github.com/troydai/gwt.git",
+                Some(("troydai", "gwt")),
+            ),
+            ("https://github.com/troydai/gwt", Some(("troydai", "gwt"))),
+            ("https://github.com/troydai/gwt/", Some(("troydai", "gwt"))),
+            ("git@github.com:troydai/gwt.git", Some(("troydai", "gwt"))),
+            ("github.com:fork-owner/gwt", Some(("fork-owner", "gwt"))),
+            (
+                "https://gitlab.com/group/subgroup/project.git",
+                Some(("group/subgroup", "project")),
+            ),
+            (
+                "git@gitlab.com:group/sub/deeper/project.git",
+                Some(("group/sub/deeper", "project")),
+            ),
+            (
+                "ssh://git@github.com/troydai/gwt.git",
+                Some(("troydai", "gwt")),
+            ),
+            (
+                "ssh://git@example.com:2222/team/api.git",
+                Some(("team", "api")),
+            ),
+            ("https://user@bitbucket.org/team/api", Some(("team", "api"))),
+            ("git@example.com:gwt.git", None),
+            ("https://example.com/gwt", None),
+            ("/srv/git/team/api.git", None),
+            ("./team/api", None),
+            ("file:///srv/git/team/api.git", None),
+            ("git@example.com:team/../api", None),
+            ("", None),
+        ] {
+            let expected = expected.map(|(o, r)| (o.to_string(), r.to_string()));
+            assert_eq!(parse_remote_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_repo_name() {
+        assert_eq!(repo_name(Path::new("/src/api")), "api");
+        assert_eq!(repo_name(Path::new("/store/api/.repo")), "api");
+    }
+}
@@ -1,56 +1,804 @@
+use super::repo_name;
+use crate::cache::{self, Lookup};
+use crate::command::repos::{orphaned_paths, repository_paths};
 use crate::config::Config;
-use crate::utility::{BranchRenderMode, Git};
-use anyhow::Result;
+use crate::manager::WorktreeManager;
+use crate::meta;
+use crate::ui;
+use crate::utility::{
+    BranchRenderMode, Git, GitBackend, IdColumn, MAX_BRANCH_WIDTH, RenderOptions, Tracking,
+    Worktrees, canonical, pointed_repository,
+};
+use anyhow::{Result, anyhow};
+use console::{Term, measure_text_width, style};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Narrowest branch column `list.max_branch_width` may ask for
+const MIN_MAX_BRANCH_WIDTH: usize = 8;
+/// Narrowest branch column before paths are elided instead
+const MIN_BRANCH_WIDTH: usize = 12;
+/// Narrowest elided path before falling back to two lines per worktree
+const MIN_PATH_WIDTH: usize = 24;
+/// Terminals narrower than this get two lines per worktree right away
+const NARROW_TERMINAL_WIDTH: usize = 60;
+/// Marker and commit columns with their separators, and the space after the
+/// branch column
+const FIXED_COLUMNS_WIDTH: usize = 11;
+
+/// How long `--cached` trusts the cache unless `completion_cache_secs` is set
+const DEFAULT_CACHE_TTL_SECS: u64 = 5;
+
+/// Flags of `gwt ls`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ListOptions {
+    pub full: bool,
+    pub raw: bool,
+    pub cached: bool,
+    pub no_main: bool,
+    pub with_paths: bool,
+    pub ids: bool,
+    pub status: bool,
+    pub age: bool,
+    pub orphans: bool,
+    pub json: bool,
+    /// List every repository with worktrees in the store
+    pub all: bool,
+    pub group_by: Option<GroupBy>,
+}
+
+/// How `gwt ls --all --group-by` groups the worktrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    Repo,
+}
+
+/// A directory of `gwt ls --orphans`, with what the store metadata recorded
+/// about it
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Orphan {
+    path: PathBuf,
+    branch: Option<String>,
+    /// Main worktree of the repository it was created from
+    repo: Option<PathBuf>,
+    /// Seconds since the Unix epoch
+    created_at: Option<u64>,
+}
+
+/// One worktree in `gwt ls --json`
+#[derive(Debug, Serialize)]
+struct ListEntry<'a> {
+    branch: Option<&'a str>,
+    path: &'a Path,
+    head: &'a str,
+    /// The worktree the command was run from
+    current: bool,
+    /// The repository's main worktree
+    is_main: bool,
+    locked: bool,
+    /// The branch's upstream is configured but no longer exists on the remote
+    gone: bool,
+    /// The worktree's directory exists
+    exists: bool,
+    /// Repository of the worktree, in `gwt ls --all` without `--group-by`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo: Option<&'a str>,
+}
+
+/// The worktrees of one repository in `gwt ls --all`
+struct RepoGroup {
+    /// Repository name, or the main worktree's path when another repository
+    /// in the listing has the same name
+    label: String,
+    main_worktree: PathBuf,
+    members: Members,
+}
 
-pub fn list(config: &Config, full: bool, raw: bool) -> Result<()> {
+enum Members {
+    Worktrees {
+        worktrees: Worktrees,
+        tracking: HashMap<String, Tracking>,
+    },
+    /// Directories left in the store by a repository that is gone or no
+    /// longer knows them
+    Orphaned(Vec<Orphan>),
+}
+
+/// A group's entries in `gwt ls --all --group-by repo --json`
+#[derive(Serialize)]
+#[serde(untagged)]
+enum GroupEntries<'a> {
+    Worktrees(Vec<ListEntry<'a>>),
+    Orphaned(&'a [Orphan]),
+}
+
+/// Worktrees rendered with one layout, each with the upstream state of its
+/// repository's branches
+struct Listing<'a> {
+    worktrees: &'a Worktrees,
+    tracking: &'a HashMap<String, Tracking>,
+}
+
+pub fn list<G: GitBackend>(manager: &WorktreeManager<G>, options: &ListOptions) -> Result<()> {
+    let (git, config) = (manager.git(), manager.config());
     config.ensure_worktree_root()?;
 
-    let git = Git::new();
-    let mut worktrees = git.list_worktrees()?;
+    let worktree_root = config
+        .data()
+        .map(|d| &d.worktree_root)
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+
+    if options.orphans {
+        return list_orphans(worktree_root, options.json);
+    }
+    if options.all {
+        return list_all(git, config, worktree_root, options);
+    }
+
+    let cache_file = cache_file(git, config);
+    let mut worktrees = match cache_file.as_deref().filter(|_| options.cached) {
+        Some(file) => match cache::load(file, cache_ttl(config)) {
+            Lookup::Fresh(worktrees) => worktrees,
+            Lookup::Stale(worktrees) => {
+                refresh_in_background();
+                worktrees
+            }
+            Lookup::Missing => list_and_cache(manager, cache_file.as_deref())?,
+        },
+        None => list_and_cache(manager, cache_file.as_deref())?,
+    };
 
     // Sort worktrees by branch name alphabetically
     // Detached worktrees (None) come after named branches
     worktrees.sort_by_branch();
 
-    if raw {
-        // --raw is used for tab completion
+    // One stat per worktree, shared by every kind of output below
+    let missing: HashSet<PathBuf> = worktrees
+        .iter()
+        .map(|wt| wt.path())
+        .filter(|path| !path.exists())
+        .cloned()
+        .collect();
+
+    if options.raw && options.ids {
+        // --raw --ids is used for tab completion of worktree ids
         worktrees
             .iter()
-            .filter_map(|br| {
-                br.branch()
-                    .filter(|br| !br.is_empty())
-                    .map(|br| br.to_string())
-            })
+            .filter(|wt| !missing.contains(wt.path()))
+            .filter_map(|wt| wt.id(worktree_root))
+            .for_each(|id| println!("{}", id));
+        return Ok(());
+    }
+
+    if options.raw {
+        // --raw is used for tab completion
+        completion_lines(&worktrees, &missing, options.no_main, options.with_paths)
+            .into_iter()
             .for_each(|s| println!("{}", s));
         return Ok(());
     }
 
     let current_worktree = git.git_toplevel().ok();
-    let render_option = if full {
-        BranchRenderMode::Full
+    let tracking = if options.status || options.json {
+        git.branch_tracking()?
     } else {
-        let max_branch_width = worktrees.max_branch_width();
-        BranchRenderMode::Truncated(max_branch_width)
+        HashMap::new()
     };
 
-    worktrees
+    if options.json {
+        let entries = json_entries(
+            &worktrees,
+            current_worktree.as_ref(),
+            &tracking,
+            &missing,
+            None,
+        );
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let listing = Listing {
+        worktrees: &worktrees,
+        tracking: &tracking,
+    };
+    let limit = branch_width_limit(
+        options.full,
+        config.data().and_then(|d| d.list.max_branch_width),
+    );
+    render_rows(
+        &[listing],
+        &current_worktree,
+        &missing,
+        worktree_root,
+        limit,
+        options,
+    )
+    .iter()
+    .for_each(|s| println!("{}", s));
+
+    warn_missing(&missing);
+    Ok(())
+}
+
+fn warn_missing(missing: &HashSet<PathBuf>) {
+    if !missing.is_empty() {
+        ui::warn!(
+            "Worktrees tagged [missing] have no directory; run `git worktree prune` to clean them up, or `gwt repair` if the worktree store moved."
+        );
+    }
+}
+
+/// The rows of `gwt ls` for the worktrees of `listings`, fitted to the
+/// terminal together. `limit` is the widest branch column, or `None` for the
+/// two-line layout.
+fn render_rows(
+    listings: &[Listing],
+    current: &Option<PathBuf>,
+    missing: &HashSet<PathBuf>,
+    root: &Path,
+    limit: Option<usize>,
+    options: &ListOptions,
+) -> Vec<String> {
+    let ids = options.ids.then(|| IdColumn {
+        root,
+        width: listings
+            .iter()
+            .map(|l| l.worktrees.max_id_width(root))
+            .max()
+            .unwrap_or(0),
+    });
+    let layout = if let Some(max_branch_width) = limit {
+        let rows: Vec<RowWidths> = listings
+            .iter()
+            .flat_map(|l| {
+                l.worktrees.iter().map(move |wt| RowWidths {
+                    branch: measure_text_width(wt.branch().unwrap_or("(detached)")),
+                    path: measure_text_width(&wt.path().display().to_string()),
+                    tags: wt.tags_width(options.status.then_some(l.tracking), Some(missing)),
+                })
+            })
+            .collect();
+        let fixed = FIXED_COLUMNS_WIDTH
+            + ids.map_or(0, |ids| ids.width + 1)
+            + if options.age { 5 } else { 0 };
+        let terminal_width = Term::stdout()
+            .size_checked()
+            .map(|(_, cols)| usize::from(cols));
+        choose_layout(terminal_width, max_branch_width, fixed, &rows)
+    } else {
+        Layout::TwoLine
+    };
+    let (branch_mode, path_width) = match layout {
+        Layout::Columns {
+            branch_width,
+            path_width,
+        } => (BranchRenderMode::Truncated(branch_width), path_width),
+        Layout::TwoLine => (BranchRenderMode::Full, None),
+    };
+    let ages = options.age.then(|| {
+        meta::last_active(
+            root,
+            listings
+                .iter()
+                .flat_map(|l| l.worktrees.iter().map(|wt| wt.path())),
+        )
+    });
+
+    listings
         .iter()
-        .map(|wt| wt.render(&current_worktree, render_option))
-        .for_each(|s| println!("{}", s));
+        .flat_map(|l| {
+            let render_options = RenderOptions {
+                branch_mode,
+                ids,
+                tracking: options.status.then_some(l.tracking),
+                ages: ages.as_ref(),
+                path_width,
+                missing: Some(missing),
+            };
+            l.worktrees
+                .iter()
+                .map(|wt| wt.render(current, &render_options))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Print the worktrees of every repository with worktrees in the store, and
+/// of the current one. With `--group-by repo`, each repository gets a header,
+/// a layout of its own and a count, and repositories that left only orphaned
+/// directories in the store are listed with those.
+fn list_all(
+    git: &dyn GitBackend,
+    config: &Config,
+    root: &Path,
+    options: &ListOptions,
+) -> Result<()> {
+    let groups = collect_groups(git, root, options.status || options.json)?;
+    let current_worktree = git.git_toplevel().ok();
+    let missing: HashSet<PathBuf> = groups
+        .iter()
+        .filter_map(RepoGroup::listing)
+        .flat_map(|l| l.worktrees.iter())
+        .map(|wt| wt.path())
+        .filter(|path| !path.exists())
+        .cloned()
+        .collect();
+    let grouped = options.group_by == Some(GroupBy::Repo);
+
+    if options.json {
+        let current = current_worktree.as_ref();
+        let json = if grouped {
+            let groups: BTreeMap<&str, GroupEntries> = groups
+                .iter()
+                .map(|group| {
+                    let entries = match &group.members {
+                        Members::Worktrees {
+                            worktrees,
+                            tracking,
+                        } => GroupEntries::Worktrees(json_entries(
+                            worktrees, current, tracking, &missing, None,
+                        )),
+                        Members::Orphaned(orphans) => GroupEntries::Orphaned(orphans),
+                    };
+                    (group.label.as_str(), entries)
+                })
+                .collect();
+            serde_json::to_string_pretty(&groups)?
+        } else {
+            let entries: Vec<ListEntry> = groups
+                .iter()
+                .filter_map(|group| Some((group.label.as_str(), group.listing()?)))
+                .flat_map(|(label, l)| {
+                    json_entries(l.worktrees, current, l.tracking, &missing, Some(label))
+                })
+                .collect();
+            serde_json::to_string_pretty(&entries)?
+        };
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if groups.is_empty() {
+        ui::info!("No repositories in '{}'.", root.display());
+        return Ok(());
+    }
+    let limit = branch_width_limit(
+        options.full,
+        config.data().and_then(|d| d.list.max_branch_width),
+    );
+    if grouped {
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            render_group(group, &current_worktree, &missing, root, limit, options)
+                .iter()
+                .for_each(|s| println!("{}", s));
+        }
+    } else {
+        let listings: Vec<Listing> = groups.iter().filter_map(RepoGroup::listing).collect();
+        render_rows(&listings, &current_worktree, &missing, root, limit, options)
+            .iter()
+            .for_each(|s| println!("{}", s));
+    }
+
+    warn_missing(&missing);
+    Ok(())
+}
+
+/// The repositories with worktrees under `root` and the current repository,
+/// then those known only from orphaned directories, sorted by name. A
+/// repository git fails to list is left out with a warning, and orphaned
+/// directories whose repository can't be told are left to `--orphans`.
+fn collect_groups(
+    git: &dyn GitBackend,
+    root: &Path,
+    with_tracking: bool,
+) -> Result<Vec<RepoGroup>> {
+    let current = git.get_main_worktree().ok().map(|wt| canonical(wt.path()));
+    let mut repos = repository_paths(root)?;
+    if let Some(current) = &current
+        && !repos.contains(current)
+    {
+        repos.push(current.clone());
+    }
+
+    let mut found: Vec<(PathBuf, Members)> = Vec::new();
+    for main_worktree in repos {
+        let members = if current.as_ref() == Some(&main_worktree) {
+            list_members(git, with_tracking)
+        } else {
+            list_members(&Git::new().with_cwd(&main_worktree), with_tracking)
+        };
+        match members {
+            Ok(members) => found.push((main_worktree, members)),
+            Err(e) => ui::warn!(
+                "Failed to list the worktrees of '{}': {:#}",
+                main_worktree.display(),
+                e
+            ),
+        }
+    }
+
+    let mut orphaned: BTreeMap<PathBuf, Vec<Orphan>> = BTreeMap::new();
+    for orphan in find_orphans(root)? {
+        let Some(repo) = orphan
+            .repo
+            .clone()
+            .or_else(|| pointed_repository(&orphan.path))
+        else {
+            continue;
+        };
+        let repo = canonical(&repo);
+        if !found
+            .iter()
+            .any(|(main_worktree, _)| *main_worktree == repo)
+        {
+            orphaned.entry(repo).or_default().push(orphan);
+        }
+    }
+    found.extend(
+        orphaned
+            .into_iter()
+            .map(|(repo, orphans)| (repo, Members::Orphaned(orphans))),
+    );
+
+    found
+        .sort_by_cached_key(|(main_worktree, _)| (repo_name(main_worktree), main_worktree.clone()));
+    let names: Vec<String> = found.iter().map(|(path, _)| repo_name(path)).collect();
+    Ok(found
+        .into_iter()
+        .zip(&names)
+        .map(|((main_worktree, members), name)| RepoGroup {
+            label: if names.iter().filter(|n| *n == name).count() > 1 {
+                main_worktree.display().to_string()
+            } else {
+                name.clone()
+            },
+            main_worktree,
+            members,
+        })
+        .collect())
+}
+
+fn list_members(git: &dyn GitBackend, with_tracking: bool) -> Result<Members> {
+    let mut worktrees = git.list_worktrees()?;
+    worktrees.sort_by_branch();
+    let tracking = if with_tracking {
+        git.branch_tracking()?
+    } else {
+        HashMap::new()
+    };
+    Ok(Members::Worktrees {
+        worktrees,
+        tracking,
+    })
+}
+
+impl RepoGroup {
+    fn listing(&self) -> Option<Listing<'_>> {
+        match &self.members {
+            Members::Worktrees {
+                worktrees,
+                tracking,
+            } => Some(Listing {
+                worktrees,
+                tracking,
+            }),
+            Members::Orphaned(_) => None,
+        }
+    }
+}
+
+/// A repository's header, its rows fitted to the terminal on their own, and
+/// a count
+fn render_group(
+    group: &RepoGroup,
+    current: &Option<PathBuf>,
+    missing: &HashSet<PathBuf>,
+    root: &Path,
+    limit: Option<usize>,
+    options: &ListOptions,
+) -> Vec<String> {
+    let mut header = style(&group.label).bold().to_string();
+    let path = group.main_worktree.display().to_string();
+    if group.label != path {
+        header = format!("{}  {}", header, style(path).dim());
+    }
+    let (rows, count) = match &group.members {
+        Members::Worktrees {
+            worktrees,
+            tracking,
+        } => {
+            let listing = Listing {
+                worktrees,
+                tracking,
+            };
+            let rows = render_rows(&[listing], current, missing, root, limit, options);
+            let count = match worktrees.len() {
+                1 => "1 worktree".to_string(),
+                n => format!("{} worktrees", n),
+            };
+            (rows, count)
+        }
+        Members::Orphaned(orphans) => {
+            header = format!("{} {}", header, style("(orphaned)").red());
+            let count = match orphans.len() {
+                1 => "1 orphaned directory".to_string(),
+                n => format!("{} orphaned directories", n),
+            };
+            (render_orphans(orphans), count)
+        }
+    };
+
+    let mut lines = vec![header];
+    lines.extend(rows);
+    lines.push(style(count).dim().to_string());
+    lines
+}
 
+/// Print the orphaned directories under `root`. Without store metadata, or
+/// with a corrupt file, their branches are simply unknown.
+fn list_orphans(root: &Path, json: bool) -> Result<()> {
+    let orphans = find_orphans(root)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&orphans)?);
+        return Ok(());
+    }
+    if orphans.is_empty() {
+        ui::info!("No orphaned directories in '{}'.", root.display());
+        return Ok(());
+    }
+    for line in render_orphans(&orphans) {
+        println!("{}", line);
+    }
+    ui::info!("Remove them with `rm -rf <path>` once you're sure nothing in them is needed.");
     Ok(())
 }
 
+fn find_orphans(root: &Path) -> Result<Vec<Orphan>> {
+    let paths = orphaned_paths(root)?;
+    let mut entries = meta::entries(root, paths.iter());
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let entry = entries.remove(&path);
+            Orphan {
+                branch: entry.as_ref().map(|e| e.branch.clone()),
+                repo: entry.as_ref().and_then(|e| e.repo.clone()),
+                created_at: entry.map(|e| e.created_at),
+                path,
+            }
+        })
+        .collect())
+}
+
+fn render_orphans(orphans: &[Orphan]) -> Vec<String> {
+    let width = orphans
+        .iter()
+        .map(|o| measure_text_width(&o.path.display().to_string()))
+        .max()
+        .unwrap_or(0);
+    orphans
+        .iter()
+        .map(|o| {
+            let path = o.path.display().to_string();
+            let branch = match &o.branch {
+                Some(branch) => style(branch.clone()).green(),
+                None => style("(unknown branch)".to_string()).dim(),
+            };
+            let repo = o
+                .repo
+                .as_ref()
+                .map(|repo| format!("  {}", style(format!("from {}", repo.display())).dim()))
+                .unwrap_or_default();
+            format!("{:<width$}  {}{}", path, branch, repo)
+        })
+        .collect()
+}
+
+/// How `gwt ls` lays out its rows
+#[derive(Debug, PartialEq, Eq)]
+enum Layout {
+    /// One line per worktree with a branch column `branch_width` wide, and
+    /// longer paths elided in the middle to `path_width`
+    Columns {
+        branch_width: usize,
+        path_width: Option<usize>,
+    },
+    /// Branch and path on lines of their own, as with `--full`
+    TwoLine,
+}
+
+/// What one row of `gwt ls` needs, in terminal columns
+#[derive(Debug, Clone, Copy)]
+struct RowWidths {
+    branch: usize,
+    path: usize,
+    /// Tags after the path, e.g. ` [locked]`
+    tags: usize,
+}
+
+/// Widest branch column for `gwt ls`, from the `list.max_branch_width`
+/// setting. `None` means no truncation at all: the two-line layout `--full`
+/// asks for whatever the setting says.
+fn branch_width_limit(full: bool, configured: Option<usize>) -> Option<usize> {
+    match configured {
+        _ if full => None,
+        None => Some(MAX_BRANCH_WIDTH),
+        Some(0) => None,
+        Some(width) if width < MIN_MAX_BRANCH_WIDTH => {
+            ui::warn!(
+                "list.max_branch_width = {} is too narrow; using {}.",
+                width,
+                MIN_MAX_BRANCH_WIDTH
+            );
+            Some(MIN_MAX_BRANCH_WIDTH)
+        }
+        Some(width) => Some(width),
+    }
+}
+
+/// Fit the rows into a terminal `terminal_width` columns wide, or lay them
+/// out untruncated when the output isn't a terminal. `fixed` is the width of
+/// everything but the branch, path and tags.
+///
+/// The branch column shrinks first, down to `MIN_BRANCH_WIDTH`; then paths
+/// are elided in the middle, down to `MIN_PATH_WIDTH`. Below that, and on
+/// narrow terminals, each worktree gets two lines.
+fn choose_layout(
+    terminal_width: Option<usize>,
+    max_branch_width: usize,
+    fixed: usize,
+    rows: &[RowWidths],
+) -> Layout {
+    let widest_branch = rows.iter().map(|r| r.branch).max().unwrap_or(0);
+    let Some(width) = terminal_width else {
+        // Pipes get everything, for scripts and grep
+        return Layout::Columns {
+            branch_width: widest_branch,
+            path_width: None,
+        };
+    };
+    if width < NARROW_TERMINAL_WIDTH {
+        return Layout::TwoLine;
+    }
+
+    let widest_rest = rows.iter().map(|r| r.path + r.tags).max().unwrap_or(0);
+    let room = width.saturating_sub(fixed + widest_rest);
+    let branch_width = widest_branch
+        .min(max_branch_width)
+        .min(room.max(MIN_BRANCH_WIDTH));
+    if fixed + branch_width + widest_rest <= width {
+        return Layout::Columns {
+            branch_width,
+            path_width: None,
+        };
+    }
+
+    let widest_tags = rows.iter().map(|r| r.tags).max().unwrap_or(0);
+    let path_width = width.saturating_sub(fixed + branch_width + widest_tags);
+    if path_width < MIN_PATH_WIDTH {
+        return Layout::TwoLine;
+    }
+    Layout::Columns {
+        branch_width,
+        path_width: Some(path_width),
+    }
+}
+
+/// The cache of the repository gwt runs in; `None` when the cache is turned
+/// off or the repository can't be told
+pub fn cache_file(git: &dyn GitBackend, config: &Config) -> Option<PathBuf> {
+    if cache_ttl(config).is_zero() {
+        return None;
+    }
+    let common_dir = git.git_common_dir().ok()?;
+    Some(cache::file_for(&config.cache_dir()?, &common_dir))
+}
+
+fn cache_ttl(config: &Config) -> Duration {
+    Duration::from_secs(
+        config
+            .data()
+            .and_then(|d| d.completion_cache_secs)
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS),
+    )
+}
+
+/// List the worktrees and cache the result. Failing to write the cache never
+/// fails the listing.
+fn list_and_cache<G: GitBackend>(
+    manager: &WorktreeManager<G>,
+    cache_file: Option<&Path>,
+) -> Result<Worktrees> {
+    let worktrees = manager.list()?;
+    if let Some(file) = cache_file {
+        let _ = cache::store(file, &worktrees);
+    }
+    Ok(worktrees)
+}
+
+/// Serve the stale list now and let a detached `gwt ls --raw` rewrite the
+/// cache for the next completion
+fn refresh_in_background() {
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let _ = Command::new(exe)
+        .args(["ls", "--raw"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+fn json_entries<'a>(
+    worktrees: &'a Worktrees,
+    current: Option<&PathBuf>,
+    tracking: &HashMap<String, Tracking>,
+    missing: &HashSet<PathBuf>,
+    repo: Option<&'a str>,
+) -> Vec<ListEntry<'a>> {
+    worktrees
+        .iter()
+        .map(|wt| ListEntry {
+            branch: wt.branch(),
+            path: wt.path(),
+            head: wt.head(),
+            current: current == Some(wt.path()),
+            is_main: wt.is_main(),
+            locked: wt.locked().is_some(),
+            gone: wt
+                .branch()
+                .and_then(|b| tracking.get(b))
+                .is_some_and(|t| t.gone),
+            exists: !missing.contains(wt.path()),
+            repo,
+        })
+        .collect()
+}
+
+/// Lines printed by `--raw` for shell completion: branch names, or
+/// `branch<TAB>path` pairs with `with_paths`. With `no_main` (when completing
+/// `gwt rm`), the main worktree is left out. Worktrees whose directory is
+/// `missing` are never offered, as there's nothing to change to.
+fn completion_lines(
+    worktrees: &Worktrees,
+    missing: &HashSet<PathBuf>,
+    no_main: bool,
+    with_paths: bool,
+) -> Vec<String> {
+    worktrees
+        .iter()
+        .filter(|wt| !missing.contains(wt.path()))
+        .filter(|wt| !(no_main && wt.is_main()))
+        .filter_map(|wt| {
+            let branch = wt.branch().filter(|br| !br.is_empty())?;
+            Some(if with_paths {
+                format!("{}\t{}", branch, wt.path().display())
+            } else {
+                branch.to_string()
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::command::worktree::test_utils::{ENV_LOCK, create_mock_git_script};
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
     use crate::config::{Config, ConfigData};
+    use crate::utility::Git;
     use std::path::PathBuf;
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
     "worktree list --porcelain")
@@ -77,28 +825,24 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(result.is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_with_detached() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
     "worktree list --porcelain")
@@ -125,28 +869,24 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(result.is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_sorted_alphabetically() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // The worktrees should be sorted by branch name alphabetically regardless of which one is active
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
@@ -174,29 +914,25 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // The list function should succeed and sort by branch name (feature-branch before main)
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(result.is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_no_current_worktree() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test when git_toplevel fails (e.g., not in any worktree)
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
@@ -224,29 +960,25 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // The list function should still succeed even if we can't detect current worktree
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(result.is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_in_dangling_worktree_directory() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test the specific scenario where user is in a dangling worktree directory:
         // - The directory exists on disk
         // - git_toplevel fails because it's not a valid git worktree (orphaned/deleted)
@@ -282,33 +1014,29 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // The list function should gracefully handle dangling directory scenario
         // It will list all valid worktrees, with none marked as active
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(
             result.is_ok(),
             "list should succeed even in dangling worktree directory"
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_dangling_vs_valid_path_matching() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test edge case: dangling directory path is similar to a valid worktree path
         // but git_toplevel returns a different path (or fails)
         // This ensures we don't accidentally match on path similarity
@@ -341,33 +1069,29 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // When current_worktree doesn't match any valid worktree path,
         // no worktree should be marked as active
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(
             result.is_ok(),
             "list should succeed when current path doesn't match any worktree"
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_sorted_alphabetically_by_branch() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test that worktrees are sorted alphabetically by branch name
         // when there's no active worktree
         let script = r#"#!/bin/sh
@@ -406,33 +1130,29 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // The list function should succeed and sort alphabetically
         // Expected order: apple, charlie, main, zebra
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(
             result.is_ok(),
             "list should succeed with alphabetical sorting"
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_detached_sorted_last() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test that detached worktrees appear after named branches
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
@@ -468,13 +1188,10 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
@@ -482,20 +1199,19 @@ esac
         // The list function should sort named branches first (alphabetically),
         // then detached worktrees
         // Expected order: apple, zebra, (detached), (detached)
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(
             result.is_ok(),
             "list should succeed with detached worktrees last"
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_truncate_long_branch_names() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test that branch names longer than MAX_BRANCH_WIDTH characters are truncated by default
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
@@ -523,32 +1239,28 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // Test without --full flag (should truncate)
-        let result = list(&config, false, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions::default(),
+        );
         assert!(
             result.is_ok(),
             "list should succeed with truncated branch names"
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_full_flag_shows_complete_branch_names() {
-        let _guard = ENV_LOCK.lock().unwrap();
         // Test that --full flag shows complete branch names without truncation
         let script = r#"#!/bin/sh
 case "$1 $2 $3" in
@@ -576,23 +1288,370 @@ esac
         let wt_root = _dir.path().join("wt-root");
         std::fs::create_dir_all(&wt_root).unwrap();
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: wt_root,
+                ..Default::default()
             },
             PathBuf::from("/tmp/config"),
         );
 
         // Test with --full flag (should not truncate)
-        let result = list(&config, true, false);
+        let result = list(
+            &WorktreeManager::new(config, Git::with_program(&mock_git)),
+            &ListOptions {
+                full: true,
+                ..Default::default()
+            },
+        );
         assert!(result.is_ok(), "list should succeed with full branch names");
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+    #[cfg(unix)]
+    #[test]
+    fn test_list_worktrees_with_ids() {
+        let (mock_git, _dir) = create_mock_git_script("");
+        let wt_root = _dir.path().join("wt-root");
+        std::fs::create_dir_all(&wt_root).unwrap();
+
+        let script = format!(
+            r#"#!/bin/sh
+case "$1 $2 $3" in
+    "worktree list --porcelain")
+        echo "worktree /path/to/main
+HEAD abc123def456789
+branch refs/heads/main
+
+worktree {}/3f2a9c1d86b47897
+HEAD def456abc789012
+branch refs/heads/feature-branch"
+        exit 0
+        ;;
+    "rev-parse --show-toplevel")
+        echo "/path/to/main"
+        exit 0
+        ;;
+    *)
+        echo "unexpected args: $@" >&2
+        exit 1
+        ;;
+esac
+"#,
+            wt_root.display()
+        );
+        std::fs::write(&mock_git, script).unwrap();
+
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: wt_root,
+                ..Default::default()
+            },
+            PathBuf::from("/tmp/config"),
+        );
+
+        let manager = WorktreeManager::new(config, Git::with_program(&mock_git));
+        let ids = ListOptions {
+            ids: true,
+            ..Default::default()
+        };
+        assert!(list(&manager, &ids).is_ok());
+        assert!(list(&manager, &ListOptions { full: true, ..ids }).is_ok());
+        assert!(list(&manager, &ListOptions { raw: true, ..ids }).is_ok());
+    }
+
+    fn rows(widths: &[(usize, usize)]) -> Vec<RowWidths> {
+        widths
+            .iter()
+            .map(|&(branch, path)| RowWidths {
+                branch,
+                path,
+                tags: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_choose_layout_fits_wide_terminals() {
+        let rows = rows(&[(40, 40), (4, 30)]);
+        assert_eq!(
+            choose_layout(Some(200), MAX_BRANCH_WIDTH, 11, &rows),
+            Layout::Columns {
+                branch_width: MAX_BRANCH_WIDTH,
+                path_width: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_choose_layout_shrinks_branches_then_paths() {
+        let rows = rows(&[(30, 40), (4, 30)]);
+        // 11 + 30 + 40 = 81 doesn't fit 70; the branch column gives way first
+        assert_eq!(
+            choose_layout(Some(70), MAX_BRANCH_WIDTH, 11, &rows),
+            Layout::Columns {
+                branch_width: 19,
+                path_width: None,
+            }
+        );
+        // Down to MIN_BRANCH_WIDTH, then the paths are elided
+        assert_eq!(
+            choose_layout(Some(60), MAX_BRANCH_WIDTH, 11, &rows),
+            Layout::Columns {
+                branch_width: MIN_BRANCH_WIDTH,
+                path_width: Some(37),
+            }
+        );
+    }
+
+    #[test]
+    fn test_choose_layout_falls_back_to_two_lines() {
+        let rows = rows(&[(30, 40)]);
+        assert_eq!(
+            choose_layout(Some(59), MAX_BRANCH_WIDTH, 11, &rows),
+            Layout::TwoLine
+        );
+        // Wide enough in general, but the id and age columns leave no room
+        assert_eq!(
+            choose_layout(Some(60), MAX_BRANCH_WIDTH, 30, &rows),
+            Layout::TwoLine
+        );
+    }
+
+    #[test]
+    fn test_branch_width_limit() {
+        assert_eq!(branch_width_limit(false, None), Some(MAX_BRANCH_WIDTH));
+        assert_eq!(branch_width_limit(false, Some(20)), Some(20));
+        assert_eq!(
+            branch_width_limit(false, Some(3)),
+            Some(MIN_MAX_BRANCH_WIDTH)
+        );
+        // 0 never truncates
+        assert_eq!(branch_width_limit(false, Some(0)), None);
+        // --full beats the setting
+        assert_eq!(branch_width_limit(true, Some(20)), None);
+        assert_eq!(branch_width_limit(true, None), None);
+    }
+
+    #[test]
+    fn test_choose_layout_honors_max_branch_width() {
+        let rows = rows(&[(30, 40)]);
+        assert_eq!(
+            choose_layout(Some(200), 10, 11, &rows),
+            Layout::Columns {
+                branch_width: 10,
+                path_width: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_choose_layout_untruncated_without_terminal() {
+        let rows = rows(&[(50, 200), (4, 30)]);
+        assert_eq!(
+            choose_layout(None, MAX_BRANCH_WIDTH, 11, &rows),
+            Layout::Columns {
+                branch_width: 50,
+                path_width: None,
+            }
+        );
+    }
+
+    fn completion_test_worktrees() -> Worktrees {
+        use crate::utility::Worktree;
+
+        Worktrees::new(vec![
+            Worktree::new(
+                PathBuf::from("/path/to/repo"),
+                "abc".to_string(),
+                Some("main".to_string()),
+            )
+            .into_main(),
+            Worktree::new(
+                PathBuf::from("/path/to/feature"),
+                "def".to_string(),
+                Some("feature".to_string()),
+            ),
+            Worktree::new(PathBuf::from("/path/to/detached"), "123".to_string(), None),
+        ])
+    }
+
+    #[test]
+    fn test_completion_lines_excludes_main() {
+        let worktrees = completion_test_worktrees();
+        assert_eq!(
+            completion_lines(&worktrees, &HashSet::new(), false, false),
+            vec!["main", "feature"]
+        );
+        assert_eq!(
+            completion_lines(&worktrees, &HashSet::new(), true, false),
+            vec!["feature"]
+        );
+    }
+
+    #[test]
+    fn test_completion_lines_skip_missing_directories() {
+        let worktrees = completion_test_worktrees();
+        let missing = HashSet::from([PathBuf::from("/path/to/feature")]);
+        assert_eq!(
+            completion_lines(&worktrees, &missing, false, false),
+            vec!["main"]
+        );
+    }
+
+    #[test]
+    fn test_completion_lines_with_paths() {
+        let worktrees = completion_test_worktrees();
+        assert_eq!(
+            completion_lines(&worktrees, &HashSet::new(), false, true),
+            vec!["main\t/path/to/repo", "feature\t/path/to/feature"]
+        );
+        assert_eq!(
+            completion_lines(&worktrees, &HashSet::new(), true, true),
+            vec!["feature\t/path/to/feature"]
+        );
+    }
+
+    #[test]
+    fn test_json_entries_report_gone_upstreams() {
+        use crate::utility::FakeGit;
+
+        let git = FakeGit::new("/repo", "main")
+            .with_worktree("/store/merged", "merged")
+            .with_worktree("/store/local", "local")
+            .with_upstream("merged", "origin/merged", true);
+        let worktrees = git.list_worktrees().unwrap();
+        let tracking = git.branch_tracking().unwrap();
+
+        let missing = HashSet::from([PathBuf::from("/store/local")]);
+        let entries = json_entries(
+            &worktrees,
+            Some(&PathBuf::from("/repo")),
+            &tracking,
+            &missing,
+            None,
+        );
+        let json = serde_json::to_value(&entries).unwrap();
+
+        assert_eq!(json[0]["branch"], "main");
+        assert_eq!(json[0]["current"], true);
+        assert_eq!(json[0]["is_main"], true);
+        assert_eq!(json[1]["branch"], "merged");
+        assert_eq!(json[1]["is_main"], false);
+        assert_eq!(json[1]["gone"], true);
+        // No upstream configured is not the same as gone
+        assert_eq!(json[2]["gone"], false);
+        assert_eq!(json[2]["path"], "/store/local");
+        assert_eq!(json[1]["exists"], true);
+        assert_eq!(json[2]["exists"], false);
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        // A worktree whose repository was deleted, one git no longer knows
+        // about, and a stray directory
+        let gone = root.join("3f2a9c1d86b47897");
+        std::fs::create_dir_all(&gone).unwrap();
+        std::fs::write(
+            gone.join(".git"),
+            "gitdir: /no/such/repo/.git/worktrees/x\n",
+        )
+        .unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join(".git/worktrees")).unwrap();
+        let pruned = root.join("api/feature");
+        std::fs::create_dir_all(&pruned).unwrap();
+        let gitdir = repo.path().join(".git/worktrees/feature");
+        std::fs::write(
+            pruned.join(".git"),
+            format!("gitdir: {}\n", gitdir.display()),
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("stray")).unwrap();
+        meta::record_created(root, &gone, "feature-login", Path::new("/src/api")).unwrap();
+
+        let orphans = find_orphans(root).unwrap();
+        let paths: Vec<&Path> = orphans.iter().map(|o| o.path.as_path()).collect();
+        assert_eq!(paths, [gone.as_path(), &pruned, &root.join("stray")]);
+        assert_eq!(orphans[0].branch.as_deref(), Some("feature-login"));
+        assert_eq!(orphans[0].repo.as_deref(), Some(Path::new("/src/api")));
+        assert_eq!(orphans[1].branch, None);
+
+        console::set_colors_enabled(false);
+        let lines = render_orphans(&orphans);
+        assert!(
+            lines[0].ends_with("feature-login  from /src/api"),
+            "{lines:?}"
+        );
+        assert!(lines[2].ends_with("(unknown branch)"), "{lines:?}");
+
+        // A corrupt metadata file only loses the branch names
+        std::fs::write(root.join(".gwt-meta.toml"), "not [valid toml").unwrap();
+        let orphans = find_orphans(root).unwrap();
+        assert_eq!(orphans.len(), 3);
+        assert!(orphans.iter().all(|o| o.branch.is_none()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        let root = dir.join("store");
+        // `web` has a worktree in the store, `api` only its main worktree,
+        // and `old` was deleted after adding one
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "for r in web api old; do \
+                   git init -q -b main $r && \
+                   git -C $r -c user.name=t -c user.email=t@e commit -q --allow-empty -m init; \
+                 done && \
+                 git -C web worktree add -q ../store/w1 -b w1 && \
+                 git -C old worktree add -q ../store/o1 -b o1 && \
+                 rm -rf old",
+            )
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let current = Git::new().with_cwd(dir.join("api"));
+        let groups = collect_groups(&current, &root, false).unwrap();
+        let summary: Vec<(&str, &Path, usize)> = groups
+            .iter()
+            .map(|g| {
+                let count = match &g.members {
+                    Members::Worktrees { worktrees, .. } => worktrees.len(),
+                    Members::Orphaned(orphans) => orphans.len(),
+                };
+                (g.label.as_str(), g.main_worktree.as_path(), count)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("api", dir.join("api").as_path(), 1),
+                ("old", dir.join("old").as_path(), 1),
+                ("web", dir.join("web").as_path(), 2),
+            ]
+        );
+        assert!(matches!(groups[1].members, Members::Orphaned(_)));
+
+        console::set_colors_enabled(false);
+        let lines = render_group(
+            &groups[1],
+            &None,
+            &HashSet::new(),
+            &root,
+            Some(MAX_BRANCH_WIDTH),
+            &ListOptions::default(),
+        );
+        assert_eq!(
+            lines[0],
+            format!("old  {} (orphaned)", dir.join("old").display())
+        );
+        assert_eq!(lines[2], "1 orphaned directory");
     }
 }
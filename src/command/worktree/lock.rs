@@ -0,0 +1,40 @@
+use super::resolve_worktree;
+use crate::config::Config;
+use crate::ui;
+use crate::utility::GitBackend;
+use anyhow::{Context, Result, anyhow};
+
+/// Lock a worktree so `git worktree prune` never drops it, e.g. while it
+/// lives on removable or network storage
+pub fn lock(
+    git: &dyn GitBackend,
+    config: &Config,
+    target: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    let worktree = resolve_worktree(git, config, target)?;
+    let path = worktree
+        .path()
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+
+    git.lock_worktree(path, reason)
+        .context("Failed to lock worktree")?;
+
+    ui::info!("Worktree at '{}' locked.", worktree.path().display());
+    Ok(())
+}
+
+pub fn unlock(git: &dyn GitBackend, config: &Config, target: &str) -> Result<()> {
+    let worktree = resolve_worktree(git, config, target)?;
+    let path = worktree
+        .path()
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+
+    git.unlock_worktree(path)
+        .context("Failed to unlock worktree")?;
+
+    ui::info!("Worktree at '{}' unlocked.", worktree.path().display());
+    Ok(())
+}
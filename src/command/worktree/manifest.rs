@@ -0,0 +1,370 @@
+use super::{CreateRollback, create_worktree, repo_name};
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{AddWorktreeOptions, GitBackend, slash_path, validate_branch_name};
+use anyhow::{Context, Result, anyhow, bail};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version written by `gwt export`; newer manifests are refused
+const MANIFEST_VERSION: u32 = 1;
+
+/// Format of `gwt export`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ManifestFormat {
+    #[default]
+    Toml,
+    Json,
+}
+
+/// The linked worktrees of a repository, as written by `gwt export`
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Name of the repository the worktrees were exported from
+    repo: String,
+    #[serde(default, rename = "worktree")]
+    worktrees: Vec<Entry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    branch: String,
+    /// Upstream the branch tracks, e.g. `origin/feature`; `gwt import`
+    /// creates a missing branch from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    /// Where the worktree was, relative to the worktree root; unset for
+    /// worktrees outside it. For reference only: `gwt import` puts worktrees
+    /// where `gwt sw` would.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    locked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lock_reason: Option<String>,
+}
+
+/// What `gwt import` did for one branch
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Created(PathBuf),
+    /// The branch already has a worktree
+    Skipped(PathBuf),
+    /// The branch exists neither locally nor on any remote
+    Missing,
+    Failed(String),
+}
+
+/// Write the current repository's linked worktrees as a manifest to
+/// `output`, or to stdout
+pub fn export(
+    git: &dyn GitBackend,
+    config: &Config,
+    format: ManifestFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let manifest = collect(git, config)?;
+    let content = match format {
+        ManifestFormat::Toml => {
+            toml::to_string_pretty(&manifest).context("Failed to serialize the manifest")?
+        }
+        ManifestFormat::Json => format!("{}\n", serde_json::to_string_pretty(&manifest)?),
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            ui::info!(
+                "Exported {} worktrees to '{}'",
+                manifest.worktrees.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+fn collect(git: &dyn GitBackend, config: &Config) -> Result<Manifest> {
+    let root = config
+        .data()
+        .map(|d| d.worktree_root.as_path())
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+    let main = git.get_main_worktree()?;
+    let tracking = git.branch_tracking()?;
+
+    let worktrees = git
+        .list_worktrees()?
+        .iter()
+        .filter(|wt| !wt.is_main())
+        // A detached worktree has no branch to recreate it from
+        .filter_map(|wt| {
+            let branch = wt.branch()?;
+            Some(Entry {
+                branch: branch.to_string(),
+                base: tracking.get(branch).and_then(|t| t.upstream.clone()),
+                path: wt.path().strip_prefix(root).ok().and_then(slash_path),
+                locked: wt.locked().is_some(),
+                lock_reason: wt.locked().filter(|r| !r.is_empty()).map(String::from),
+            })
+        })
+        .collect();
+    Ok(Manifest {
+        version: MANIFEST_VERSION,
+        repo: repo_name(main.path()),
+        worktrees,
+    })
+}
+
+/// Create a worktree for every branch in the manifest at `file`, after
+/// fetching every remote. Branches that exist nowhere are reported without
+/// failing the import; other failures make it exit non-zero once every
+/// branch was tried.
+pub fn import(git: &dyn GitBackend, config: &Config, file: &Path) -> Result<()> {
+    config.ensure_worktree_root()?;
+
+    let content =
+        fs::read_to_string(file).with_context(|| format!("Failed to read '{}'", file.display()))?;
+    let manifest =
+        parse(&content).with_context(|| format!("Invalid manifest '{}'", file.display()))?;
+    let repo = repo_name(git.get_main_worktree()?.path());
+    if manifest.repo != repo {
+        ui::warn!(
+            "The manifest was exported from '{}', not '{}'.",
+            manifest.repo,
+            repo
+        );
+    }
+
+    // Offline, the branches at hand are still worth importing
+    for remote in git.remotes().context("Failed to list remotes")? {
+        ui::info!("Fetching '{}'...", remote);
+        if let Err(e) = git.fetch_remote(&remote) {
+            ui::warn!("Failed to fetch '{}': {:#}", remote, e);
+        }
+    }
+
+    let results: Vec<(String, Outcome)> = manifest
+        .worktrees
+        .iter()
+        .map(|entry| {
+            let outcome = import_one(git, config, entry)
+                .unwrap_or_else(|e| Outcome::Failed(format!("{:#}", e)));
+            (entry.branch.clone(), outcome)
+        })
+        .collect();
+
+    if results.is_empty() {
+        ui::info!("The manifest lists no worktrees.");
+        return Ok(());
+    }
+    for line in render(&results) {
+        println!("{}", line);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        bail!(
+            "{} of {} branches could not be imported",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// A manifest in either format `gwt export` writes
+fn parse(content: &str) -> Result<Manifest> {
+    let manifest: Manifest = if content.trim_start().starts_with('{') {
+        serde_json::from_str(content)?
+    } else {
+        toml::from_str(content)?
+    };
+    if manifest.version > MANIFEST_VERSION {
+        bail!(
+            "manifest version {} is newer than this gwt supports ({})",
+            manifest.version,
+            MANIFEST_VERSION
+        );
+    }
+    Ok(manifest)
+}
+
+fn import_one(git: &dyn GitBackend, config: &Config, entry: &Entry) -> Result<Outcome> {
+    let branch = entry.branch.as_str();
+    validate_branch_name(branch)?;
+    if let Some(wt) = git.find_worktree_by_branch(branch)? {
+        return Ok(Outcome::Skipped(wt.path().clone()));
+    }
+
+    let mut rollback = CreateRollback::new(git);
+    if !git.branch_exists(branch)? {
+        let Some(remote_branch) = remote_branch(git, branch, entry.base.as_deref())? else {
+            return Ok(Outcome::Missing);
+        };
+        git.create_branch_from_remote(branch, &remote_branch)?;
+        rollback.branch_created(branch);
+    }
+    let path = create_worktree(
+        git,
+        config,
+        branch,
+        false,
+        &AddWorktreeOptions::default(),
+        &mut rollback,
+    )?;
+    rollback.commit();
+
+    if entry.locked {
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+        if let Err(e) = git.lock_worktree(path_str, entry.lock_reason.as_deref()) {
+            ui::warn!("Failed to lock the worktree of '{}': {:#}", branch, e);
+        }
+    }
+    Ok(Outcome::Created(path))
+}
+
+/// The remote branch to create `branch` from: its recorded upstream, or the
+/// only remote that has a branch of that name. Several candidates without
+/// an upstream to choose by are an error.
+fn remote_branch(git: &dyn GitBackend, branch: &str, base: Option<&str>) -> Result<Option<String>> {
+    if let Some(base) = base.filter(|b| git.remote_branch_exists(b).unwrap_or(false)) {
+        return Ok(Some(base.to_string()));
+    }
+    let mut matches = git.find_remote_branches_by_name(branch)?;
+    match matches.len() {
+        0 | 1 => Ok(matches.pop()),
+        _ => bail!(
+            "found on several remotes: {}; create it with `gwt sw {} --remote <name>`",
+            matches.join(", "),
+            branch
+        ),
+    }
+}
+
+fn render(results: &[(String, Outcome)]) -> Vec<String> {
+    let branch_width = results.iter().map(|(b, _)| b.len()).max().unwrap_or(0);
+    results
+        .iter()
+        .map(|(branch, outcome)| {
+            let (label, detail) = match outcome {
+                Outcome::Created(path) => (style("created").green(), path.display().to_string()),
+                Outcome::Skipped(path) => (
+                    style("skipped").dim(),
+                    format!("already at {}", path.display()),
+                ),
+                Outcome::Missing => (
+                    style("missing").yellow(),
+                    "not found locally or on any remote".to_string(),
+                ),
+                Outcome::Failed(reason) => (style("failed").red(), reason.clone()),
+            };
+            format!("  {:<7}  {:<branch_width$}  {}", label, branch, detail)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::utility::FakeGit;
+
+    #[test]
+    fn test_collect() {
+        let (_dir, config) = temp_config();
+        let root = config.data().unwrap().worktree_root.clone();
+        let git = FakeGit::new("/src/api", "main")
+            .with_worktree(root.join("3f2a9c1d86b47897"), "feature")
+            .with_locked_worktree("/elsewhere/usb", "release", "on a USB drive")
+            .with_detached_worktree_at(root.join("detached"), "abc123")
+            .with_upstream("feature", "origin/feature", false);
+
+        let manifest = collect(&git, &config).unwrap();
+        assert_eq!(manifest.repo, "api");
+        assert_eq!(
+            manifest.worktrees,
+            [
+                Entry {
+                    branch: "feature".to_string(),
+                    base: Some("origin/feature".to_string()),
+                    path: Some("3f2a9c1d86b47897".to_string()),
+                    locked: false,
+                    lock_reason: None,
+                },
+                Entry {
+                    branch: "release".to_string(),
+                    base: None,
+                    path: None,
+                    locked: true,
+                    lock_reason: Some("on a USB drive".to_string()),
+                },
+            ]
+        );
+
+        // Both formats read back to the same manifest
+        let toml = toml::to_string_pretty(&manifest).unwrap();
+        assert_eq!(parse(&toml).unwrap(), manifest);
+        let json = serde_json::to_string_pretty(&manifest).unwrap();
+        assert_eq!(parse(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_parse_refuses_newer_versions() {
+        let err = parse("version = 2\nrepo = 'api'\n").unwrap_err();
+        assert!(err.to_string().contains("newer"), "{err}");
+    }
+
+    #[test]
+    fn test_import_one() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/src/api", "main")
+            .with_worktree("/store/present", "present")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("origin/renamed");
+        let entry = |branch: &str, base: Option<&str>, locked: bool| Entry {
+            branch: branch.to_string(),
+            base: base.map(String::from),
+            path: None,
+            locked,
+            lock_reason: None,
+        };
+
+        assert_eq!(
+            import_one(&git, &config, &entry("present", None, false)).unwrap(),
+            Outcome::Skipped(PathBuf::from("/store/present"))
+        );
+        assert_eq!(
+            import_one(&git, &config, &entry("gone", None, false)).unwrap(),
+            Outcome::Missing
+        );
+
+        let created = import_one(&git, &config, &entry("feature", None, true)).unwrap();
+        let Outcome::Created(path) = created else {
+            panic!("{created:?}");
+        };
+        let calls = git.calls();
+        assert!(calls.contains(&"create_branch_from_remote feature origin/feature".to_string()));
+        assert!(calls.contains(&format!("lock_worktree {} ", path.display())));
+
+        // The recorded upstream wins over a lookup by name
+        import_one(
+            &git,
+            &config,
+            &entry("local", Some("origin/renamed"), false),
+        )
+        .unwrap();
+        assert!(
+            git.calls()
+                .contains(&"create_branch_from_remote local origin/renamed".to_string())
+        );
+    }
+}
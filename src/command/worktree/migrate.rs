@@ -0,0 +1,232 @@
+use super::{compute_worktree_hash, create_dirs};
+use crate::config::Config;
+use crate::ui::{self, Outcome};
+use crate::utility::{GitBackend, Worktree, slash_path};
+use anyhow::{Result, anyhow, bail};
+use console::style;
+use std::path::{Path, PathBuf};
+
+/// A worktree `gwt migrate` moves, or leaves where it is and why
+#[derive(Debug, PartialEq, Eq)]
+struct Planned {
+    branch: String,
+    from: PathBuf,
+    to: PathBuf,
+    skip: Option<&'static str>,
+}
+
+/// Move the current repository's worktrees from an old worktree root to the
+/// configured one with `git worktree move`, keeping the directory layout `gwt
+/// sw` uses, so worktrees created before `worktree_root` changed are found
+/// again.
+///
+/// Without `from`, worktrees gwt created elsewhere are detected by their
+/// directory name, the hash `gwt sw` derives from the repository and branch.
+pub fn migrate(
+    git: &dyn GitBackend,
+    config: &Config,
+    from: Option<&Path>,
+    dry_run: bool,
+) -> Result<()> {
+    let root = config
+        .data()
+        .map(|d| d.worktree_root.clone())
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+    if !dry_run {
+        config.ensure_worktree_root()?;
+    }
+
+    let toplevel = git.get_main_worktree()?.path().clone();
+    let worktrees = git.list_worktrees()?;
+    let planned = plan(worktrees.iter().skip(1), &toplevel, &root, from);
+
+    if planned.is_empty() {
+        ui::info!("No worktrees to migrate to '{}'.", root.display());
+        return Ok(());
+    }
+
+    if dry_run {
+        for p in &planned {
+            match p.skip {
+                Some(reason) => println!(
+                    "  {:<7}  {}  {}: {}",
+                    style("skipped").yellow(),
+                    p.branch,
+                    p.from.display(),
+                    reason
+                ),
+                None => println!(
+                    "  {:<7}  {}  {} -> {}",
+                    style("move").green(),
+                    p.branch,
+                    p.from.display(),
+                    p.to.display()
+                ),
+            }
+        }
+        let skipped = planned.iter().filter(|p| p.skip.is_some()).count();
+        ui::info!("{} to move, {} skipped.", planned.len() - skipped, skipped);
+        return Ok(());
+    }
+
+    let mut progress = ui::Progress::new(planned.len());
+    for p in &planned {
+        progress.run_with(&p.branch, || match p.skip {
+            Some(reason) => Ok(Outcome::Skipped(reason.to_string())),
+            None => move_one(git, &p.from, &p.to).map(|()| Outcome::Succeeded),
+        });
+    }
+    progress.print_summary();
+
+    let failed = progress.failed();
+    if failed > 0 {
+        bail!(
+            "{} of {} worktrees could not be moved",
+            failed,
+            planned.len()
+        );
+    }
+    Ok(())
+}
+
+/// The linked worktrees to move into `root`: those under `from` when given,
+/// otherwise those outside `root` named the way `gwt sw` names them
+fn plan<'a>(
+    worktrees: impl Iterator<Item = &'a Worktree>,
+    toplevel: &Path,
+    root: &Path,
+    from: Option<&Path>,
+) -> Vec<Planned> {
+    let repo = slash_path(toplevel).unwrap_or_else(|| toplevel.to_string_lossy().into_owned());
+    worktrees
+        .filter_map(|wt| {
+            let path = wt.path();
+            let name = path.file_name()?.to_str()?;
+            let branch = wt.branch();
+            let created_by_gwt = branch.is_some_and(|b| compute_worktree_hash(&repo, b) == name);
+            let selected = match from {
+                Some(from) => path.starts_with(from),
+                None => created_by_gwt && path.parent() != Some(root),
+            };
+            if !selected || path.starts_with(root) {
+                return None;
+            }
+
+            let to = match branch {
+                Some(b) => root.join(compute_worktree_hash(&repo, b)),
+                None => root.join(name),
+            };
+            let skip = if wt.locked().is_some() {
+                Some("locked; unlock it with `gwt unlock` first")
+            } else if !path.exists() {
+                Some("directory not found; try `gwt repair`")
+            } else if to.exists() {
+                Some("target directory already exists")
+            } else {
+                None
+            };
+            Some(Planned {
+                branch: branch.unwrap_or("(detached)").to_string(),
+                from: path.clone(),
+                to,
+                skip,
+            })
+        })
+        .collect()
+}
+
+fn move_one(git: &dyn GitBackend, from: &Path, to: &Path) -> Result<()> {
+    let (Some(from), Some(to_str)) = (from.to_str(), to.to_str()) else {
+        bail!("invalid path");
+    };
+    // `git worktree move` doesn't create missing parent directories
+    if let Some(parent) = to.parent() {
+        create_dirs(parent)?;
+    }
+    git.move_worktree(from, to_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::Git;
+
+    fn gwt_worktree(store: &Path, branch: &str) -> Worktree {
+        Worktree::new(
+            store.join(compute_worktree_hash("/repo", branch)),
+            "1".repeat(40),
+            Some(branch.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_plan_detects_worktrees_under_old_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        let new = dir.path().join("new");
+        let moving = gwt_worktree(&old, "feature");
+        std::fs::create_dir_all(moving.path()).unwrap();
+        let worktrees = [
+            moving,
+            gwt_worktree(&old, "gone"),
+            gwt_worktree(&old, "locked").with_lock(String::new()),
+            gwt_worktree(&new, "current"),
+            // Not created by gwt, so only moved when asked with --from
+            Worktree::new(old.join("scratch"), "1".repeat(40), Some("scratch".into())),
+        ];
+
+        let planned = plan(worktrees.iter(), Path::new("/repo"), &new, None);
+
+        let summary: Vec<_> = planned
+            .iter()
+            .map(|p| (p.branch.as_str(), p.skip))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("feature", None),
+                ("gone", Some("directory not found; try `gwt repair`")),
+                ("locked", Some("locked; unlock it with `gwt unlock` first")),
+            ]
+        );
+        assert_eq!(
+            planned[0].to,
+            new.join(compute_worktree_hash("/repo", "feature"))
+        );
+
+        let planned = plan(worktrees.iter(), Path::new("/repo"), &new, Some(&old));
+        assert_eq!(planned.len(), 4);
+        assert_eq!(
+            planned[3].to,
+            new.join(compute_worktree_hash("/repo", "scratch"))
+        );
+    }
+
+    #[test]
+    fn test_migrate_moves_worktree_with_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(
+                "git init -q -b main repo && \
+                 git -C repo -c user.name=t -c user.email=t@e commit -q --allow-empty -m init && \
+                 git -C repo worktree add -q ../old/wt -b feature",
+            )
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let git = Git::new().with_cwd(dir.path().join("repo"));
+        let to = dir.path().join("new/nested/wt");
+
+        move_one(&git, &dir.path().join("old/wt"), &to).unwrap();
+
+        assert!(to.join(".git").is_file());
+        let worktrees = git.list_worktrees().unwrap();
+        assert!(
+            worktrees
+                .iter()
+                .any(|wt| wt.path().ends_with("new/nested/wt"))
+        );
+    }
+}
@@ -1,44 +1,234 @@
+mod carry;
+mod clean;
+mod direnv;
+mod layout;
 mod list;
-
-pub use list::list;
-
-use crate::config::Config;
-use crate::utility::Git;
+mod lock;
+mod manifest;
+mod migrate;
+mod open;
+mod pick;
+mod pr;
+mod rename;
+mod repair;
+mod sync;
+mod tmux;
+
+pub use clean::clean;
+pub(crate) use layout::repo_name;
+pub use list::{GroupBy, ListOptions, cache_file, list};
+pub use lock::{lock, unlock};
+pub use manifest::{ManifestFormat, export, import};
+pub use migrate::migrate;
+pub use open::open;
+pub use pick::{pick_branch, picker};
+pub use pr::pr;
+pub use rename::rename;
+pub use repair::repair;
+pub use sync::sync;
+
+use crate::config::{BranchMatch, Config, ConfigData, PreSwitchCheck};
+use crate::error::GwtError;
+use crate::history;
+use crate::hooks::{self, Hook};
+use crate::manager::WorktreeManager;
+use crate::meta;
+use crate::ui;
+use crate::utility::{
+    AddWorktreeOptions, GitBackend, MergeState, Tmux, Worktree, in_store, slash_path,
+    validate_branch_name,
+};
 use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
 use sha1::{Digest, Sha1};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
 
 use console::{Term, style};
 use dialoguer::Confirm;
 
-pub fn switch(
-    config: &Config,
+/// Lock file in the worktree root, taken while a worktree is created
+const LOCK_FILE_NAME: &str = ".gwt.lock";
+
+/// Flags of `gwt sw`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SwitchOptions<'a> {
+    /// Create the branch if it doesn't exist
+    pub create: bool,
+    /// Switch to the main branch instead of a named one
+    pub main: bool,
+    /// Remote to take the branch from when several have it
+    pub remote: Option<&'a str>,
+    /// Add a worktree even if the branch is checked out in another one
+    pub force: bool,
+    /// Open the worktree in tmux instead of printing its path
+    pub tmux: bool,
+    /// Create the worktree without checking out any files
+    pub no_checkout: bool,
+    /// Create the branch tracking this remote branch instead of looking it up
+    pub track: Option<&'a str>,
+    /// Fetch the remote (or all remotes) before resolving the branch
+    pub fetch: bool,
+    /// Fast-forward an existing worktree to its upstream before switching
+    pub pull: bool,
+    /// Fail when `pull` can't fast-forward instead of only warning
+    pub strict: bool,
+    /// Move the current worktree's uncommitted changes to the new worktree
+    pub take_changes: bool,
+    /// Create the branch without `branch_prefix`
+    pub no_prefix: bool,
+    /// Print the result as a JSON object instead of the bare path
+    pub json: bool,
+}
+
+/// Flags of `gwt rm`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RemoveOptions {
+    /// Delete the branch after removing the worktree, if it was merged
+    pub delete_branch: bool,
+    /// Delete the branch even if it wasn't merged
+    pub force_delete_branch: bool,
+    /// Delete the branch's upstream from its remote too
+    pub delete_remote: bool,
+    /// Don't ask for confirmation
+    pub skip_confirmation: bool,
+    /// Remove the worktree even if it is locked
+    pub force: bool,
+    /// Remove the main worktree too
+    pub allow_main: bool,
+    /// Don't run `post_remove_hook`
+    pub no_hooks: bool,
+}
+
+/// Where `switch_worktree` ended up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Switched {
+    pub branch: String,
+    pub path: PathBuf,
+    /// The branch is the one checked out where gwt runs, and `path` is that
+    /// worktree; nothing was done
+    pub already_current: bool,
+    /// The worktree didn't exist before
+    pub created: bool,
+    /// False when `pull` was asked for but couldn't fast-forward
+    pub pulled: bool,
+}
+
+/// What `gwt sw --json` prints
+#[derive(Serialize)]
+struct SwitchReport<'a> {
+    path: &'a Path,
+    branch: &'a str,
+    created: bool,
+    repo: String,
+}
+
+/// `gwt sw`: switch to the worktree of `branch`, creating it if needed, and
+/// print its path for the shell wrapper (or open it in tmux)
+pub fn switch<G: GitBackend>(
+    manager: &WorktreeManager<G>,
     branch: Option<&str>,
-    create: bool,
-    use_main: bool,
-    remote: Option<&str>,
+    options: &SwitchOptions,
 ) -> Result<()> {
-    config.ensure_worktree_root()?;
+    let (git, config) = (manager.git(), manager.config());
+    let switched = manager.switch_worktree(branch, options)?;
+    if options.json {
+        // Scripts want the path either way, so being there already isn't an error
+        let report = SwitchReport {
+            path: &switched.path,
+            branch: &switched.branch,
+            created: switched.created,
+            repo: repo_name(git.get_main_worktree()?.path()),
+        };
+        println!("{}", serde_json::to_string(&report)?);
+    } else if switched.already_current {
+        ui::warn!("You are already on branch '{}'.", switched.branch);
+        std::process::exit(1);
+    }
+
+    if options.tmux {
+        if Tmux::is_active() {
+            return tmux::open_in_tmux(
+                &tmux::tmux(config),
+                git,
+                config,
+                &switched.branch,
+                &switched.path,
+            );
+        }
+        ui::warn!("Not inside tmux; changing directory instead.");
+    }
+    if !options.json {
+        ui::print_cd(&switched.path);
+    }
+    if !switched.pulled && options.strict {
+        return Err(GwtError::NotFastForwarded(switched.path).into());
+    }
+    Ok(())
+}
 
-    let git = Git::new();
+/// Find or create the worktree of `branch` (the main branch with
+/// `options.main`) and record the visit. Progress goes to stderr; `tmux` and
+/// `strict` are left to the caller.
+pub fn switch_worktree(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: Option<&str>,
+    options: &SwitchOptions,
+) -> Result<Switched> {
+    let SwitchOptions {
+        create,
+        main: use_main,
+        remote,
+        force,
+        no_checkout,
+        track,
+        fetch,
+        pull,
+        take_changes,
+        no_prefix,
+        ..
+    } = *options;
+    config.ensure_worktree_root()?;
 
     // Resolve the branch name based on the flag
     let target_branch = if use_main {
-        resolve_main_branch(&git)?
+        resolve_main_branch(git, config)?
     } else {
-        branch
-            .ok_or_else(|| anyhow!("Branch name is required"))?
-            .to_string()
+        let branch =
+            branch.ok_or_else(|| GwtError::Usage("Branch name is required".to_string()))?;
+        match branch_prefix(git, config)? {
+            Some(prefix) if create && !no_prefix && !branch.contains('/') => {
+                format!("{}{}", prefix, branch)
+            }
+            _ => branch.to_string(),
+        }
     };
 
+    validate_branch_name(&target_branch)?;
+    let target_branch = canonical_branch(git, config, &target_branch)?;
+
     if git.get_current_branch().is_ok_and(|c| c == target_branch) {
-        eprintln!(
-            "{}",
-            style(format!("You are already on branch '{}'.", target_branch)).yellow()
-        );
-        std::process::exit(1);
+        return Ok(Switched {
+            branch: target_branch,
+            path: git.git_toplevel()?,
+            already_current: true,
+            created: false,
+            pulled: true,
+        });
+    }
+
+    // --take-changes brings the changes along, so they aren't left behind
+    if !take_changes {
+        check_leaving(git, config, force)?;
+    }
+
+    let fetch_on_create = config.data().is_some_and(|d| d.fetch_on_create)
+        && git.find_worktree_by_branch(&target_branch)?.is_none();
+    if fetch || fetch_on_create {
+        fetch_remotes(git, remote)?;
     }
 
     // Check if local branch exists or if we should look for a remote branch
@@ -46,48 +236,364 @@ pub fn switch(
         .branch_exists(&target_branch)
         .context("Failed to check if branch exists")?;
 
-    let final_branch = if !exists_locally && !create {
+    // Deletes any branch created below if we fail before the worktree exists
+    let mut rollback = CreateRollback::new(git);
+
+    if exists_locally && track.is_some() {
+        bail!(
+            "Branch '{}' already exists; --track only applies when creating a branch",
+            target_branch
+        );
+    }
+
+    let final_branch = if !exists_locally && !create && track.is_none() {
         // Try to resolve from remote
-        handle_remote_branch(&git, &target_branch, remote)?
+        let branch = match handle_remote_branch(git, &target_branch, remote) {
+            Ok(branch) => branch,
+            Err(e)
+                if matches!(
+                    GwtError::find(&e),
+                    Some(GwtError::BranchNotFoundAnywhere(_))
+                ) =>
+            {
+                // Not a branch at all: maybe a commit a detached worktree has checked out
+                let Some(worktree) = git.find_detached_worktree(&target_branch)? else {
+                    return Err(e);
+                };
+                record_visit(git, config, &target_branch, worktree.path());
+                return Ok(Switched {
+                    branch: target_branch,
+                    path: worktree.path().clone(),
+                    already_current: false,
+                    created: false,
+                    pulled: true,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        rollback.branch_created(&branch);
+        branch
     } else {
         target_branch
     };
 
-    let wt_path = git
+    let existing = git
         .list_worktrees()?
         .iter()
         .find(|wt| wt.branch().is_some_and(|v| v == final_branch))
-        .map(|wt| wt.path().clone())
-        .map_or_else(
-            || create_worktree_and_print_path(&git, config, &final_branch, create),
-            Ok,
-        )?;
+        .map(|wt| wt.path().clone());
+    // --force asks for a checkout of our own even when the branch is checked
+    // out elsewhere; one at our path is still reused
+    let mut pulled = true;
+    let wt_path = match existing {
+        Some(path) if !force || path == compute_target_path(git, config, &final_branch)? => {
+            if !path.exists() {
+                bail!(
+                    "The worktree for branch '{}' is registered at '{}', which doesn't exist. Run `gwt repair` if the worktree store moved, or `git worktree prune` to forget it.",
+                    final_branch,
+                    path.display()
+                );
+            }
+            if take_changes {
+                bail!(
+                    "Branch '{}' already has a worktree at '{}'; --take-changes only moves changes to a new worktree",
+                    final_branch,
+                    path.display()
+                );
+            }
+            if pull {
+                pulled = pull_worktree(git, &final_branch, &path)?;
+            }
+            path
+        }
+        _ => {
+            let add = AddWorktreeOptions {
+                force,
+                no_checkout,
+                track,
+            };
+            let carried = if take_changes {
+                carry::stash_changes(git)?
+            } else {
+                None
+            };
+            match create_worktree(git, config, &final_branch, create, &add, &mut rollback) {
+                Ok(path) => {
+                    if let Some(carried) = carried {
+                        carried.apply_to(git, &path);
+                    }
+                    path
+                }
+                Err(e) => {
+                    if let Some(carried) = carried {
+                        carried.restore(git);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    };
+    let created = rollback.added_worktree();
+    rollback.commit();
+
+    record_visit(git, config, &final_branch, &wt_path);
+    Ok(Switched {
+        branch: final_branch,
+        path: wt_path,
+        already_current: false,
+        created,
+        pulled,
+    })
+}
+
+/// Fast-forward the worktree of `branch` at `path` to its upstream. Returns
+/// false, after a warning, when that failed; the switch goes ahead anyway.
+fn pull_worktree(git: &dyn GitBackend, branch: &str, path: &Path) -> Result<bool> {
+    let tracking = git.branch_tracking()?;
+    match tracking.get(branch) {
+        Some(t) if t.gone => {
+            ui::info!(
+                "{}",
+                style(format!(
+                    "The upstream of '{}' is gone; not pulling.",
+                    branch
+                ))
+                .dim()
+            );
+            return Ok(true);
+        }
+        Some(t) if t.upstream.is_some() => {}
+        _ => {
+            ui::info!(
+                "{}",
+                style(format!("'{}' has no upstream; not pulling.", branch)).dim()
+            );
+            return Ok(true);
+        }
+    }
+
+    ui::info!("Pulling '{}' in '{}'...", branch, path.display());
+    if let Err(e) = git.pull_ff_only(path) {
+        eprintln!(
+            "{}",
+            style(format!(
+                "Warning: '{}' couldn't be fast-forwarded and is unchanged: {:#}",
+                branch, e
+            ))
+            .yellow()
+            .bold()
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// `branch` spelled like the local branch it names. With `branch_match =
+/// "icase"`, a name that doesn't exist as typed resolves to the one local
+/// branch matching it ignoring case, so its path and `worktree add` use the
+/// branch's own casing. Names without a match are returned unchanged.
+fn canonical_branch(git: &dyn GitBackend, config: &Config, branch: &str) -> Result<String> {
+    let icase = config
+        .data()
+        .is_some_and(|d| d.branch_match == BranchMatch::Icase);
+    if !icase || git.branch_exists(branch)? {
+        return Ok(branch.to_string());
+    }
+
+    let lower = branch.to_lowercase();
+    let matches: Vec<String> = git
+        .local_branches()?
+        .into_iter()
+        .filter(|b| b.to_lowercase() == lower)
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(branch.to_string()),
+        [found] => {
+            ui::info!("Using branch '{}' for '{}'", found, branch);
+            Ok(found.clone())
+        }
+        _ => Err(GwtError::Usage(format!(
+            "'{}' matches several branches that differ only by case: {}",
+            branch,
+            matches.join(", ")
+        ))
+        .into()),
+    }
+}
+
+/// Prefix of the branches `gwt sw -b` creates: `gwt.branchPrefix` from the
+/// repository's git config, or `branch_prefix` from gwt's
+fn branch_prefix(git: &dyn GitBackend, config: &Config) -> Result<Option<String>> {
+    if let Some(prefix) = git.config_value("gwt.branchPrefix")? {
+        return Ok(Some(prefix));
+    }
+    Ok(config
+        .data()
+        .and_then(|d| d.branch_prefix.clone())
+        .filter(|p| !p.is_empty()))
+}
+
+/// Apply `pre_switch_check` to the worktree being left: refuse (unless
+/// `force`) or warn when it's under the worktree root and has uncommitted
+/// changes. Anywhere else, e.g. in the main checkout, there's nothing to check.
+fn check_leaving(git: &dyn GitBackend, config: &Config, force: bool) -> Result<()> {
+    let Some(data) = config.data() else {
+        return Ok(());
+    };
+    if data.pre_switch_check == PreSwitchCheck::Off {
+        return Ok(());
+    }
+    let Ok(current) = git.git_toplevel() else {
+        return Ok(());
+    };
+    if !in_store(&current, &data.worktree_root) {
+        return Ok(());
+    }
+    let files = git.dirty_files(&current)?;
+    if files == 0 {
+        return Ok(());
+    }
+
+    if data.pre_switch_check == PreSwitchCheck::BlockDirty && !force {
+        return Err(GwtError::LeavingDirtyWorktree {
+            path: current,
+            files,
+        }
+        .into());
+    }
+    ui::warn!(
+        "Leaving {} uncommitted {} behind in '{}'.",
+        files,
+        if files == 1 { "file" } else { "files" },
+        current.display()
+    );
+    Ok(())
+}
 
-    println!("{}", wt_path.display());
+/// Fetch `remote`, or every remote, so branches are resolved against fresh
+/// remote refs. A repository without remotes has nothing to fetch.
+fn fetch_remotes(git: &dyn GitBackend, remote: Option<&str>) -> Result<()> {
+    let remotes = match remote {
+        Some(remote) => vec![remote.to_string()],
+        None => git.remotes().context("Failed to list remotes")?,
+    };
+    for remote in &remotes {
+        ui::info!("Fetching '{}'...", remote);
+        git.fetch_prune(remote)
+            .with_context(|| format!("Failed to fetch '{}'", remote))?;
+    }
     Ok(())
 }
 
+/// Remember the switch for `gwt recent` and `gwt ls --age`. History is a
+/// convenience, so failing to write it never fails the switch.
+fn record_visit(git: &dyn GitBackend, config: &Config, branch: &str, path: &Path) {
+    let main = git.get_main_worktree().ok();
+    if let Some(d) = config.data() {
+        warn_on_meta_error(meta::record_switch(
+            &d.worktree_root,
+            path,
+            branch,
+            main.as_ref().map(|m| m.path().as_path()),
+        ));
+    }
+    let (Some(file), Some(main)) = (config.history_path(), main) else {
+        return;
+    };
+    let entry = history::Entry::now(main.path().clone(), branch.to_string(), path.to_path_buf());
+    if let Err(e) = history::record(&file, entry) {
+        ui::warn!("Failed to update history: {}", e);
+    }
+}
+
+pub(crate) fn warn_on_meta_error(result: Result<()>) {
+    if let Err(e) = result {
+        ui::warn!("Failed to update worktree metadata: {}", e);
+    }
+}
+
+/// Take the advisory lock of the worktree root, held until the returned file
+/// is dropped. Creating worktrees under it keeps concurrent `gwt sw` runs for
+/// the same branch from both adding one.
+fn lock_store(config: &Config) -> Result<fs::File> {
+    let root = config
+        .data()
+        .map(|d| &d.worktree_root)
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+    let path = root.join(LOCK_FILE_NAME);
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open lock file '{}'", path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock '{}'", path.display()))?;
+    Ok(file)
+}
+
 fn create_worktree_and_print_path(
-    git: &Git,
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: &str,
+    create: bool,
+    rollback: &mut CreateRollback,
+) -> Result<PathBuf> {
+    create_worktree(
+        git,
+        config,
+        branch,
+        create,
+        &AddWorktreeOptions::default(),
+        rollback,
+    )
+}
+
+/// Create the worktree for `branch`; `add.force` lets git check out a branch
+/// that is already checked out in another worktree, and with `add.track` git
+/// creates the branch from that remote branch.
+///
+/// Without `force`, a branch git reports as checked out elsewhere leads to that
+/// checkout instead of an error, since that's where the user wants to be.
+fn create_worktree(
+    git: &dyn GitBackend,
     config: &Config,
     branch: &str,
     create: bool,
+    add: &AddWorktreeOptions,
+    rollback: &mut CreateRollback,
 ) -> Result<PathBuf> {
+    validate_branch_name(branch)?;
+
+    let _lock = lock_store(config)?;
+    let target_path = compute_target_path(git, config, branch)?;
+    // Another gwt may have created the worktree while this one waited for the lock
+    if let Some(wt) = git
+        .find_worktree_by_branch(branch)?
+        .filter(|wt| !add.force || *wt.path() == target_path)
+    {
+        ui::info!(
+            "Branch '{}' got a worktree at '{}' in the meantime; switching there.",
+            branch,
+            wt.path().display()
+        );
+        return Ok(wt.path().clone());
+    }
+
     let exists = git
         .branch_exists(branch)
         .context("Failed to check if branch exists")?;
-    if !exists {
+    if !exists && add.track.is_none() {
         if create {
             git.create_branch(branch)
                 .context(format!("Failed to create branch '{}'", branch))?;
-            eprintln!("Branch '{}' created.", branch);
+            rollback.branch_created(branch);
+            ui::info!("Branch '{}' created.", branch);
         } else {
-            bail!("Branch '{}' doesn't exist.", branch);
+            return Err(GwtError::BranchNotFound(branch.to_string()).into());
         }
     }
 
-    let target_path = compute_target_path(git, config, branch)?;
-
     // If the target path already exists but is not a valid worktree, fail with instructions
     // (we know it's not a valid worktree because we didn't find it in list_worktrees)
     if target_path.exists() {
@@ -103,41 +609,291 @@ fn create_worktree_and_print_path(
     }
 
     if let Some(parent) = target_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+        rollback.dirs_created(create_dirs(parent)?);
     }
 
     let target_path_str = target_path
         .to_str()
         .ok_or_else(|| anyhow!("Invalid target path"))?;
 
-    git.add_worktree(target_path_str, branch)
-        .context("Failed to add worktree")?;
+    rollback.worktree_started(&target_path);
+    if let Err(e) = git.add_worktree(target_path_str, branch, add) {
+        if let Some(path) = checked_out_elsewhere(git, branch, &e) {
+            // git refuses before creating anything, so there's nothing to undo
+            rollback.worktree_not_added();
+            ui::info!(
+                "Branch '{}' is already checked out at '{}'; switching there instead. Use --force for a second checkout.",
+                branch,
+                path.display()
+            );
+            return Ok(path);
+        }
+        // git may have created the branch before the checkout failed
+        if add.track.is_some() && git.branch_exists(branch).unwrap_or(false) {
+            rollback.branch_created(branch);
+        }
+        return Err(e).context("Failed to add worktree");
+    }
+    if add.track.is_some() {
+        rollback.branch_created(branch);
+    }
 
-    eprintln!(
+    ui::info!(
         "Created worktree for branch '{}' at '{}'",
         branch,
         target_path.display()
     );
+    if let (Some(d), Ok(main)) = (config.data(), git.get_main_worktree()) {
+        warn_on_meta_error(meta::record_created(
+            &d.worktree_root,
+            &target_path,
+            branch,
+            main.path(),
+        ));
+    }
+    direnv::allow_new_worktree(config, &target_path);
 
     Ok(target_path)
 }
 
+/// Where `branch` is checked out, when `error` is git refusing to add a
+/// worktree because of that checkout
+fn checked_out_elsewhere(
+    git: &dyn GitBackend,
+    branch: &str,
+    error: &anyhow::Error,
+) -> Option<PathBuf> {
+    let Some(GwtError::GitFailure { stderr, .. }) = GwtError::find(error) else {
+        return None;
+    };
+    // "is already used by worktree at" since git 2.42
+    let marker = [
+        "is already checked out at '",
+        "is already used by worktree at '",
+    ]
+    .into_iter()
+    .find_map(|m| stderr.split_once(m).map(|(_, rest)| rest))?;
+
+    // The checkout list_worktrees missed the first time may show up now;
+    // otherwise trust the path in git's message
+    if let Ok(Some(worktree)) = git.find_worktree_by_branch(branch) {
+        return Some(worktree.path().clone());
+    }
+    let path = PathBuf::from(marker.split('\'').next()?);
+    path.is_dir().then_some(path)
+}
+
+/// Create `dir` and its missing parents, returning the directories that were
+/// created, outermost first
+fn create_dirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|d| !d.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+    Ok(missing.into_iter().rev().collect())
+}
+
+/// Remove the directories between a removed worktree at `path` and the
+/// worktree root that are now empty, like `<repo>/` of a `path_template` layout
+pub(crate) fn remove_empty_parents(config: &Config, path: &Path) {
+    let Some(root) = config.data().map(|d| &d.worktree_root) else {
+        return;
+    };
+    for dir in path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root) && dir != root)
+    {
+        // Stops at the first directory that still holds something
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+/// Undoes the side effects of a failed `gwt sw`, so that a retry doesn't trip
+/// over a branch, directory or worktree registration the user never
+/// consciously created.
+///
+/// Unless `commit` is called, dropping the guard removes whatever `git worktree
+/// add` left behind, the directories this invocation created and the branch it
+/// created. Branches and directories that existed beforehand are never
+/// touched, and the created branch can't have commits of its own because its
+/// worktree was never added.
+struct CreateRollback<'a> {
+    git: &'a dyn GitBackend,
+    created_branch: Option<String>,
+    /// Outermost first
+    created_dirs: Vec<PathBuf>,
+    worktree: Option<PathBuf>,
+}
+
+impl<'a> CreateRollback<'a> {
+    fn new(git: &'a dyn GitBackend) -> Self {
+        Self {
+            git,
+            created_branch: None,
+            created_dirs: Vec::new(),
+            worktree: None,
+        }
+    }
+
+    /// Record that this invocation created `branch`
+    fn branch_created(&mut self, branch: &str) {
+        self.created_branch = Some(branch.to_string());
+    }
+
+    /// Record directories this invocation created
+    fn dirs_created(&mut self, dirs: Vec<PathBuf>) {
+        self.created_dirs.extend(dirs);
+    }
+
+    /// Record that `git worktree add` is about to run for `path`, which
+    /// didn't exist before
+    fn worktree_started(&mut self, path: &Path) {
+        self.worktree = Some(path.to_path_buf());
+    }
+
+    /// `git worktree add` refused without touching anything
+    fn worktree_not_added(&mut self) {
+        self.worktree = None;
+    }
+
+    /// Whether this operation added a worktree, rather than finding one
+    fn added_worktree(&self) -> bool {
+        self.worktree.is_some()
+    }
+
+    /// The operation succeeded, keep everything
+    fn commit(mut self) {
+        self.created_branch = None;
+        self.created_dirs.clear();
+        self.worktree = None;
+    }
+
+    /// Best effort: a failed `git worktree add` may have left a partial
+    /// checkout or a registration behind
+    fn remove_partial_worktree(&self, path: &Path) {
+        if path.exists() {
+            if let Some(path_str) = path.to_str() {
+                let _ = self.git.force_remove_worktree(path_str);
+            }
+            if path.exists() {
+                let _ = fs::remove_dir_all(path);
+            }
+            ui::info!(
+                "Removed the partially created worktree at '{}'.",
+                path.display()
+            );
+        }
+        let _ = self.git.prune_worktrees();
+    }
+}
+
+impl Drop for CreateRollback<'_> {
+    fn drop(&mut self) {
+        // The worktree goes first: git won't delete a branch that is checked out
+        if let Some(path) = self.worktree.take() {
+            self.remove_partial_worktree(&path);
+        }
+        // Innermost first; `remove_dir` leaves anything that isn't empty alone
+        for dir in self.created_dirs.drain(..).rev() {
+            let _ = fs::remove_dir(dir);
+        }
+
+        let Some(branch) = self.created_branch.take() else {
+            return;
+        };
+
+        match self.git.delete_branch(&branch, true) {
+            Ok(()) => ui::info!("Deleted branch '{}' created by this command.", branch),
+            Err(e) => ui::warn!(
+                "Failed to delete branch '{}' created by this command: {}",
+                branch,
+                e
+            ),
+        }
+    }
+}
+
+/// `gwt rm`: remove the worktree of `target`, a branch, a worktree directory
+/// or a directory id. Returns the main worktree's path when the current
+/// directory is inside the removed worktree, for the shell to change to.
 pub fn remove(
+    git: &dyn GitBackend,
     config: &Config,
-    branch: &str,
-    delete_branch: bool,
-    force_delete_branch: bool,
-    skip_confirmation: bool,
-) -> Result<()> {
+    target: &str,
+    options: &RemoveOptions,
+) -> Result<Option<PathBuf>> {
+    let &RemoveOptions {
+        delete_branch,
+        force_delete_branch,
+        delete_remote,
+        skip_confirmation,
+        force,
+        allow_main,
+        no_hooks,
+    } = options;
     config.ensure_worktree_root()?;
 
-    let git = Git::new();
+    // Find the worktree for this branch (or directory id)
+    let worktree = resolve_worktree(git, config, target)?;
+    let branch = worktree.branch().unwrap_or("(detached)");
 
-    // Find the worktree for this branch
-    let worktree = git
-        .find_worktree_by_branch(branch)?
-        .ok_or_else(|| anyhow!("No worktree found for branch '{}'", branch))?;
+    if worktree.is_main() && !allow_main {
+        return Err(GwtError::Usage(format!(
+            "'{}' is the main worktree of the repository. Use --allow-main to remove it anyway.",
+            worktree.path().display()
+        ))
+        .into());
+    }
+
+    if (delete_branch || force_delete_branch || delete_remote) && worktree.branch().is_none() {
+        bail!(
+            "Worktree at '{}' is detached; there is no branch to delete.",
+            worktree.path().display()
+        );
+    }
+
+    // A lock protects the worktree from `git worktree prune`; honor it here too
+    if let Some(reason) = worktree.locked().filter(|_| !force) {
+        return Err(GwtError::WorktreeLocked {
+            path: worktree.path().clone(),
+            reason: reason.to_string(),
+        }
+        .into());
+    }
+
+    // `git branch -d` only knows about regular merges. Check ourselves, so a
+    // squash-merged branch can still be deleted after an explicit warning and
+    // an unmerged one gets a better explanation than git's.
+    let merge = if delete_branch && !force_delete_branch {
+        merged_into(git, config, branch)?
+    } else {
+        None
+    };
+    let squash_base = merge
+        .as_ref()
+        .filter(|(_, state)| *state == MergeState::SquashMerged)
+        .map(|(base, _)| base);
+    if let Some(base) = squash_base {
+        ui::warn!(
+            "Branch '{}' is {} into '{}' and will be force-deleted.",
+            branch,
+            MergeState::SquashMerged,
+            base
+        );
+    }
+
+    // Look the upstream up now; it is gone with the local branch
+    let remote = if delete_remote {
+        remote_to_delete_from(git, branch)?
+    } else {
+        None
+    };
 
     let worktree_path = worktree.path();
 
@@ -145,8 +901,9 @@ pub fn remove(
     let current_dir = env::current_dir().context("Failed to get current directory")?;
     let need_to_switch = current_dir.starts_with(worktree_path);
 
-    // Get the main worktree path if we need to switch (but don't print yet)
-    let main_path = if need_to_switch {
+    // The main worktree path, if we need to switch or to run the hook in it
+    let run_hook = !no_hooks && Hook::PostRemove.command(config).is_some();
+    let main_path = if need_to_switch || run_hook {
         Some(git.get_main_worktree()?.path().clone())
     } else {
         None
@@ -154,139 +911,497 @@ pub fn remove(
 
     // Request confirmation unless skipped
     if !skip_confirmation {
-        let prompt = format!(
-            "Remove worktree at '{}' for branch '{}'?",
+        let mut prompt = format!(
+            "Remove worktree at '{}' for branch '{}'",
             worktree_path.display(),
             branch
         );
+        if let Some(remote) = &remote {
+            prompt.push_str(&format!(
+                " and delete remote branch '{}/{}'",
+                remote, branch
+            ));
+        }
+        prompt.push('?');
 
         let confirmed = Confirm::new()
             .with_prompt(prompt)
             .default(false)
             .interact_on(&Term::stderr())
+            .map_err(ui::prompt_error)
             .context("Failed to get confirmation")?;
 
         if !confirmed {
-            eprintln!("Removal cancelled.");
-            return Ok(());
+            ui::info!("Removal cancelled.");
+            return Ok(None);
         }
     }
 
-    // Print the main worktree path so the shell wrapper can cd to it (only after confirmation)
-    if let Some(path) = main_path {
-        println!("{}", path.display());
-    }
-
     // Remove the worktree
     let worktree_path_str = worktree_path
         .to_str()
         .ok_or_else(|| anyhow!("Invalid worktree path"))?;
 
-    git.remove_worktree(worktree_path_str)
-        .context("Failed to remove worktree")?;
+    if let Some(reason) = worktree.locked() {
+        git.unlock_worktree(worktree_path_str)
+            .context("Failed to unlock worktree")?;
+        if let Err(e) = git.remove_worktree(worktree_path_str) {
+            // Leave the worktree as protected as we found it
+            let reason = Some(reason).filter(|r| !r.is_empty());
+            if let Err(relock) = git.lock_worktree(worktree_path_str, reason) {
+                ui::warn!("Failed to lock the worktree again: {}", relock);
+            }
+            return Err(dirty_or(e, worktree_path)).context("Failed to remove worktree");
+        }
+    } else {
+        git.remove_worktree(worktree_path_str)
+            .map_err(|e| dirty_or(e, worktree_path))
+            .context("Failed to remove worktree")?;
+    }
+
+    ui::info!("Worktree for branch '{}' removed.", branch);
+    if let Some(d) = config.data() {
+        warn_on_meta_error(meta::record_removed(&d.worktree_root, worktree_path));
+    }
+    remove_empty_parents(config, worktree_path);
+    if let Some(main_path) = main_path.as_ref().filter(|_| run_hook) {
+        hooks::post_remove(
+            config,
+            main_path,
+            worktree.branch().unwrap_or_default(),
+            worktree_path,
+        );
+    }
 
-    eprintln!("Worktree for branch '{}' removed.", branch);
+    if let Some((base, MergeState::NotMerged)) = &merge {
+        return Err(GwtError::BranchNotMerged {
+            branch: branch.to_string(),
+            base: base.clone(),
+        }
+        .into());
+    }
 
     // Delete the branch if requested
     if delete_branch || force_delete_branch {
-        git.delete_branch(branch, force_delete_branch)
+        git.delete_branch(branch, force_delete_branch || squash_base.is_some())
             .context("Failed to delete branch")?;
-        eprintln!("Branch '{}' deleted.", branch);
+        ui::info!("Branch '{}' deleted.", branch);
     }
 
-    Ok(())
+    // The local cleanup stands even if this fails, e.g. without network
+    if let Some(remote) = remote {
+        git.delete_remote_branch(&remote, branch).with_context(|| {
+            format!(
+                "Failed to delete remote branch '{}/{}'; the worktree was removed",
+                remote, branch
+            )
+        })?;
+        ui::info!("Remote branch '{}/{}' deleted.", remote, branch);
+    }
+
+    Ok(main_path.filter(|_| need_to_switch))
 }
 
-fn handle_remote_branch(
-    git: &Git,
-    local_branch: &str,
-    remote_name_override: Option<&str>,
-) -> Result<String> {
-    let full_remote_path = if let Some(remote_name) = remote_name_override {
-        let path = format!("{}/{}", remote_name, local_branch);
-        if !git.remote_branch_exists(&path)? {
-            bail!("Remote branch '{}' does not exist.", path);
-        }
-        path
-    } else {
-        // Smart lookup like 'git checkout'
-        let matches = git.find_remote_branches_by_name(local_branch)?;
-        if matches.is_empty() {
-            bail!(
-                "Branch '{}' not found locally or in any remote.",
-                local_branch
-            );
-        } else if matches.len() > 1 {
-            bail!(
-                "Ambiguous branch name '{}'. Found in multiple remotes: {}. Please specify the remote using --remote (e.g., --remote {})",
-                local_branch,
-                matches.join(", "),
-                matches[0].split('/').next().unwrap_or("origin")
-            );
-        }
-        matches[0].clone()
+/// The remote to delete `branch` from: the remote of its upstream, when that
+/// is a remote branch of the same name that still exists. Anything else is
+/// skipped with a warning, so `--delete-remote` never deletes a shared branch
+/// such as `origin/main` that a feature branch happens to track.
+fn remote_to_delete_from(git: &dyn GitBackend, branch: &str) -> Result<Option<String>> {
+    let skip = |message: String| {
+        ui::warn!("{}", message);
+        Ok(None)
     };
 
-    git.create_branch_from_remote(local_branch, &full_remote_path)
-        .context(format!(
-            "Failed to create local branch '{}' from remote '{}'",
-            local_branch, full_remote_path
-        ))?;
-
-    eprintln!(
-        "Created local branch '{}' tracking remote '{}'.",
-        local_branch, full_remote_path
-    );
-
-    Ok(local_branch.to_string())
-}
-
-fn compute_target_path(git: &Git, config: &Config, branch: &str) -> Result<PathBuf> {
-    let toplevel = git.git_toplevel().context("Failed to get git toplevel")?;
-    let repo_path = toplevel
-        .to_str()
-        .ok_or_else(|| anyhow!("invalid toplevel path"))?;
+    let tracking = git.branch_tracking()?.remove(branch).unwrap_or_default();
+    let Some(upstream) = tracking.upstream else {
+        return skip(format!(
+            "Branch '{}' has no upstream; no remote branch to delete.",
+            branch
+        ));
+    };
+    if tracking.gone {
+        ui::info!(
+            "{}",
+            style(format!("Remote branch '{}' is already gone.", upstream)).dim()
+        );
+        return Ok(None);
+    }
 
-    let hash = compute_worktree_hash(repo_path, branch);
-    let worktree_root = config
-        .data()
-        .map(|d| &d.worktree_root)
-        .ok_or_else(|| anyhow!("Config not loaded"))?;
-    let target_path = worktree_root.join(hash);
-    Ok(target_path)
+    // Remote names may contain `/`, so match against the configured remotes
+    let remote = git
+        .remotes()?
+        .into_iter()
+        .filter(|r| upstream.starts_with(&format!("{}/", r)))
+        .max_by_key(String::len);
+    match remote {
+        Some(remote) if upstream[remote.len() + 1..] == *branch => Ok(Some(remote)),
+        Some(_) => skip(format!(
+            "Branch '{}' tracks '{}', not a remote branch of its own; leaving it alone.",
+            branch, upstream
+        )),
+        None => skip(format!(
+            "Upstream '{}' of branch '{}' is not a remote branch; nothing to delete.",
+            upstream, branch
+        )),
+    }
 }
 
-fn compute_worktree_hash(repo_name: &str, branch_name: &str) -> String {
-    let mut hasher = Sha1::new();
-    hasher.update(format!("{repo_name}|{branch_name}"));
-    let digest = hasher.finalize();
-    format!("{digest:x}")[0..16].to_string()
-}
+/// Remove every linked worktree whose branch tracks a remote branch that no
+/// longer exists, typically because its pull request was merged. Branches
+/// without an upstream are never considered gone.
+///
+/// Asks once for the whole list, then removes each worktree like `gwt rm -y`
+/// would, carrying on past failures. Returns the main worktree's path when the
+/// current directory was inside a removed worktree.
+pub fn remove_gone(
+    git: &dyn GitBackend,
+    config: &Config,
+    options: &RemoveOptions,
+) -> Result<Option<PathBuf>> {
+    let &RemoveOptions {
+        skip_confirmation,
+        force,
+        ..
+    } = options;
+    config.ensure_worktree_root()?;
 
-fn resolve_main_branch(git: &Git) -> Result<String> {
-    // Check if 'main' exists first
-    if git.branch_exists("main")? {
-        return Ok("main".to_string());
+    let tracking = git.branch_tracking()?;
+    let worktrees = git.list_worktrees()?;
+    // The main worktree is never removed here
+    let (gone, locked): (Vec<&Worktree>, Vec<&Worktree>) = worktrees
+        .iter()
+        .filter(|wt| !wt.is_main())
+        .filter(|wt| {
+            wt.branch()
+                .and_then(|b| tracking.get(b))
+                .is_some_and(|t| t.gone)
+        })
+        .partition(|wt| force || wt.locked().is_none());
+
+    for wt in &locked {
+        ui::warn!(
+            "Skipping locked worktree for branch '{}'; use --force to remove it anyway.",
+            wt.branch().unwrap_or_default()
+        );
     }
-
-    // Fall back to 'master'
-    if git.branch_exists("master")? {
-        return Ok("master".to_string());
+    if gone.is_empty() {
+        ui::info!("No worktrees with a gone upstream branch.");
+        return Ok(None);
     }
 
-    bail!("Neither 'main' nor 'master' branch exists")
-}
-
-// Helper functions
+    eprintln!("Worktrees whose upstream branch is gone:");
+    for wt in &gone {
+        eprintln!(
+            "  {}  {}",
+            wt.branch().unwrap_or_default(),
+            style(wt.path().display()).cyan()
+        );
+    }
+    if !skip_confirmation {
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Remove {} worktrees?", gone.len()))
+            .default(false)
+            .interact_on(&Term::stderr())
+            .map_err(ui::prompt_error)
+            .context("Failed to get confirmation")?;
+        if !confirmed {
+            ui::info!("Removal cancelled.");
+            return Ok(None);
+        }
+    }
+
+    let branches: Vec<&str> = gone
+        .iter()
+        .map(|wt| wt.branch().unwrap_or_default())
+        .collect();
+    remove_each(git, config, &branches, options)
+}
+
+/// Remove the worktrees of the branches read from stdin, one per line, as in
+/// `git branch --merged | grep feature/ | gwt rm - -b`. stdin holds the list,
+/// so there is no confirmation. Returns the main worktree's path when the
+/// current directory was inside a removed worktree.
+pub fn remove_from_stdin(
+    git: &dyn GitBackend,
+    config: &Config,
+    options: &RemoveOptions,
+) -> Result<Option<PathBuf>> {
+    let branches = read_branch_list(std::io::stdin().lock()).context("Failed to read stdin")?;
+    if branches.is_empty() {
+        ui::info!("No branches given on stdin.");
+        return Ok(None);
+    }
+    let branches: Vec<&str> = branches.iter().map(String::as_str).collect();
+    remove_each(git, config, &branches, options)
+}
+
+/// Branch names, one per line. Empty lines and `#` comments are skipped, and
+/// so are the `*` and `+` markers `git branch` puts before checked out
+/// branches.
+fn read_branch_list(input: impl BufRead) -> Result<Vec<String>> {
+    let mut branches = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let branch = line
+            .strip_prefix("* ")
+            .or_else(|| line.strip_prefix("+ "))
+            .unwrap_or(line);
+        branches.push(branch.trim_start().to_string());
+    }
+    Ok(branches)
+}
+
+/// Remove the worktree of each branch like `gwt rm -y` would, carrying on
+/// past failures. Returns the main worktree's path when the current directory
+/// was inside a removed worktree.
+fn remove_each(
+    git: &dyn GitBackend,
+    config: &Config,
+    branches: &[&str],
+    options: &RemoveOptions,
+) -> Result<Option<PathBuf>> {
+    let options = RemoveOptions {
+        skip_confirmation: true,
+        ..*options
+    };
+    let mut progress = ui::Progress::new(branches.len());
+    let mut cd = None;
+    for branch in branches {
+        progress.run(branch, || {
+            if let Some(path) = remove(git, config, branch, &options)? {
+                cd = Some(path);
+            }
+            Ok(())
+        });
+    }
+    progress.print_summary();
+
+    let failed = progress.failed();
+    if failed > 0 {
+        bail!(
+            "{} of {} worktrees could not be removed",
+            failed,
+            branches.len()
+        );
+    }
+    Ok(cd)
+}
+
+/// Report git refusing to remove a worktree with local changes as
+/// `WorktreeDirty`, so scripts can tell it from other git failures
+pub(crate) fn dirty_or(error: anyhow::Error, path: &Path) -> anyhow::Error {
+    match GwtError::find(&error) {
+        Some(GwtError::GitFailure { stderr, .. })
+            if stderr.contains("contains modified or untracked files") =>
+        {
+            GwtError::WorktreeDirty(path.to_path_buf()).into()
+        }
+        _ => error,
+    }
+}
+
+/// The main branch and whether `branch` was merged into it, according to the
+/// configured `merged_detection` mode; `None` when there is no main branch to
+/// compare with, or `branch` is the main branch
+fn merged_into(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: &str,
+) -> Result<Option<(String, MergeState)>> {
+    let Ok(base) = resolve_main_branch(git, config) else {
+        return Ok(None);
+    };
+    if base == branch {
+        return Ok(None);
+    }
+
+    let detection = config
+        .data()
+        .map(|d| d.merged_detection)
+        .unwrap_or_default();
+    let state = git.merge_state(branch, &base, detection)?;
+    Ok(Some((base, state)))
+}
+
+/// Resolve a user supplied target to an existing worktree: a branch, a
+/// directory inside a worktree (e.g. `.`), or a directory id prefix (see
+/// `gwt ls --ids`), in that order. A branch that happens to look like an id
+/// still wins.
+pub(crate) fn resolve_worktree(
+    git: &dyn GitBackend,
+    config: &Config,
+    target: &str,
+) -> Result<Worktree> {
+    if let Some(worktree) = git.find_worktree_by_branch(target)? {
+        return Ok(worktree);
+    }
+
+    if Path::new(target).is_dir() {
+        let dir = fs::canonicalize(target)
+            .with_context(|| format!("Failed to resolve path '{}'", target))?;
+        // The innermost worktree, in case one is nested in another
+        return git
+            .list_worktrees()?
+            .iter()
+            .filter(|wt| fs::canonicalize(wt.path()).is_ok_and(|p| dir.starts_with(p)))
+            .max_by_key(|wt| wt.path().components().count())
+            .cloned()
+            .ok_or_else(|| {
+                GwtError::Usage(format!(
+                    "'{}' isn't inside a worktree of this repository",
+                    target
+                ))
+                .into()
+            });
+    }
+
+    let worktree_root = config
+        .data()
+        .map(|d| &d.worktree_root)
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+
+    git.list_worktrees()?
+        .find_by_id_prefix(target, worktree_root)?
+        .cloned()
+        .ok_or_else(|| GwtError::WorktreeNotFound(target.to_string()).into())
+}
+
+fn handle_remote_branch(
+    git: &dyn GitBackend,
+    local_branch: &str,
+    remote_name_override: Option<&str>,
+) -> Result<String> {
+    let full_remote_path = if let Some(remote_name) = remote_name_override {
+        let path = format!("{}/{}", remote_name, local_branch);
+        if !git.remote_branch_exists(&path)? {
+            return Err(GwtError::RemoteBranchNotFound(path).into());
+        }
+        path
+    } else {
+        // Smart lookup like 'git checkout'
+        let matches = git.find_remote_branches_by_name(local_branch)?;
+        if matches.is_empty() {
+            return Err(GwtError::BranchNotFoundAnywhere(local_branch.to_string()).into());
+        } else if matches.len() > 1 {
+            return Err(GwtError::Usage(format!(
+                "Ambiguous branch name '{}'. Found in multiple remotes: {}. Please specify the remote using --remote (e.g., --remote {})",
+                local_branch,
+                matches.join(", "),
+                matches[0].split('/').next().unwrap_or("origin")
+            ))
+            .into());
+        }
+        matches[0].clone()
+    };
+
+    git.create_branch_from_remote(local_branch, &full_remote_path)
+        .context(format!(
+            "Failed to create local branch '{}' from remote '{}'",
+            local_branch, full_remote_path
+        ))?;
+
+    ui::info!(
+        "Created local branch '{}' tracking remote '{}'.",
+        local_branch,
+        full_remote_path
+    );
+
+    Ok(local_branch.to_string())
+}
+
+/// Where the worktree of `branch` goes under the worktree root: the
+/// `path_template` config option rendered for the repository and branch, or
+/// a directory named by a hash of the two when it's unset.
+///
+/// With `group_by_remote`, `{repo}` is the `<owner>/<repo>` of the origin URL
+/// and the hash directory goes under it, so forks of a project don't mix.
+pub fn compute_target_path(git: &dyn GitBackend, config: &Config, branch: &str) -> Result<PathBuf> {
+    let toplevel = git.git_toplevel().context("Failed to get git toplevel")?;
+    let hash = worktree_hash(&toplevel, branch)?;
+    let data = config.data().ok_or_else(|| anyhow!("Config not loaded"))?;
+    data.check_root_outside(git.get_main_worktree()?.path())?;
+    let Some(template) = path_template(data) else {
+        return Ok(data.worktree_root.join(hash));
+    };
+
+    let repo = layout_repo_name(git, config)?;
+    let values = layout::PathValues {
+        repo: &repo,
+        branch,
+        hash: &hash,
+    };
+    let relative = layout::render_path_template(template, &values, data.nest_slashes)?;
+    Ok(data.worktree_root.join(relative))
+}
+
+/// The directory under the worktree root that holds all worktrees of the
+/// current repository; the worktree root itself for the default layout
+pub fn repo_store(git: &dyn GitBackend, config: &Config) -> Result<PathBuf> {
+    let data = config.data().ok_or_else(|| anyhow!("Config not loaded"))?;
+    let Some(template) = path_template(data) else {
+        return Ok(data.worktree_root.clone());
+    };
+    let repo = layout_repo_name(git, config)?;
+    let relative = layout::render_repo_dir(template, &repo, data.nest_slashes)?;
+    Ok(data.worktree_root.join(relative))
+}
+
+/// What `{repo}` stands for in the current repository: its directory name,
+/// or `<owner>/<name>` from the origin URL with `group_by_remote`
+pub fn layout_repo_name(git: &dyn GitBackend, config: &Config) -> Result<String> {
+    let mut repo = layout::repo_name(git.get_main_worktree()?.path());
+    if config.data().is_some_and(|d| d.group_by_remote) {
+        // Without an origin, fall back to the directory name
+        let origin = git.remote_url("origin")?;
+        if let Some((owner, name)) = origin.as_deref().and_then(layout::parse_remote_url) {
+            repo = format!("{}/{}", owner, name);
+        }
+    }
+    Ok(repo)
+}
+
+/// The template worktree paths follow, or `None` for the plain hash layout
+fn path_template(data: &ConfigData) -> Option<&str> {
+    match (&data.path_template, data.group_by_remote) {
+        (Some(template), _) => Some(template.as_str()),
+        (None, true) => Some("{repo}/{hash}"),
+        (None, false) => None,
+    }
+}
+
+/// The directory name of `branch`'s worktree for the repository at
+/// `toplevel`. The path is hashed with `/` separators, as git prints it, so
+/// Windows paths spelled with backslashes or a `\\?\` prefix hash the same.
+fn worktree_hash(toplevel: &Path, branch: &str) -> Result<String> {
+    let repo_path = slash_path(toplevel).ok_or_else(|| anyhow!("invalid toplevel path"))?;
+    Ok(compute_worktree_hash(&repo_path, branch))
+}
+
+fn compute_worktree_hash(repo_name: &str, branch_name: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("{repo_name}|{branch_name}"));
+    let digest = hasher.finalize();
+    format!("{digest:x}")[0..16].to_string()
+}
+
+fn resolve_main_branch(git: &dyn GitBackend, config: &Config) -> Result<String> {
+    git.default_branch(config.data().and_then(|d| d.default_base_branch.as_deref()))
+}
+
+// Helper functions
 
 #[cfg(test)]
 pub(crate) mod test_utils {
-    use std::path::PathBuf;
-    use std::sync::Mutex;
+    use crate::config::{Config, ConfigData};
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
     use tempfile::tempdir;
 
-    pub(crate) static ENV_LOCK: Mutex<()> = Mutex::new(());
-
+    #[cfg(unix)]
     pub(crate) fn create_mock_git_script(script_content: &str) -> (PathBuf, tempfile::TempDir) {
         let dir = tempdir().unwrap();
         let mock_git = dir.path().join("mock-git");
@@ -300,14 +1415,60 @@ pub(crate) mod test_utils {
 
         (mock_git, dir)
     }
+
+    /// Run `script` with `sh` in `dir`, with a git identity for the commits it
+    /// makes; a failing script fails the test
+    pub(crate) fn sh(dir: &Path, script: &str) {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success(), "setup failed: {script}");
+    }
+
+    /// Config with a fresh worktree root, and the config file (where history
+    /// is kept) in the same temporary directory
+    pub(crate) fn temp_config() -> (tempfile::TempDir, Config) {
+        let dir = tempdir().unwrap();
+        let wt_root = dir.path().join("wt-root");
+        std::fs::create_dir_all(&wt_root).unwrap();
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: wt_root,
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        (dir, config)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::command::worktree::test_utils::{ENV_LOCK, create_mock_git_script};
-    use crate::config::ConfigData;
-    use std::path::PathBuf;
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::utility::FakeGit;
+
+    fn worktree_root(config: &Config) -> PathBuf {
+        config.data().unwrap().worktree_root.clone()
+    }
+
+    /// Entries of the worktree root other than the creation lock
+    fn store_entries(root: &Path) -> usize {
+        std::fs::read_dir(root)
+            .unwrap()
+            .flatten()
+            .filter(|e| e.file_name() != LOCK_FILE_NAME)
+            .count()
+    }
 
     #[test]
     fn test_compute_worktree_hash() {
@@ -318,335 +1479,827 @@ mod tests {
     }
 
     #[test]
-    fn test_compute_target_path() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        // Mock git toplevel
-        let script = r#"#!/bin/sh
-if [ "$1" = "rev-parse" ] && [ "$2" = "--show-toplevel" ]; then
-    echo "/path/to/my-repo"
-    exit 0
-fi
-exit 1
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        let wt_root = _dir.path().join("wt-root");
-        std::fs::create_dir_all(&wt_root).unwrap();
+    fn test_worktree_hash_is_the_same_on_every_platform() {
+        // Existing worktrees are found by this name, so it must never change
+        assert_eq!(
+            worktree_hash(Path::new("/src/api"), "feature").unwrap(),
+            "76507dc225d899e5"
+        );
+        assert_eq!(
+            worktree_hash(Path::new("C:/src/api"), "feature").unwrap(),
+            "7a1c0c21fac8c821"
+        );
+    }
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
+    #[cfg(windows)]
+    #[test]
+    fn test_worktree_hash_ignores_windows_separators() {
+        for path in [r"C:\src\api", r"\\?\C:\src\api", r"C:\src/api"] {
+            assert_eq!(
+                worktree_hash(Path::new(path), "feature").unwrap(),
+                "7a1c0c21fac8c821",
+                "{path}"
+            );
         }
+    }
 
-        let config = Config::Loaded(
-            ConfigData {
-                worktree_root: wt_root.clone(),
-            },
-            PathBuf::from("/tmp/config"),
-        );
+    #[test]
+    fn test_compute_target_path() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/my-repo", "main");
 
-        let git = Git::new();
         let path = compute_target_path(&git, &config, "feature-branch").unwrap();
 
         let hash = compute_worktree_hash("/path/to/my-repo", "feature-branch");
-        let expected_path = wt_root.join(hash);
-        assert_eq!(path, expected_path);
+        assert_eq!(path, worktree_root(&config).join(hash));
+    }
+
+    #[test]
+    fn test_compute_target_path_from_template() {
+        let (_dir, mut config) = temp_config();
+        if let Config::Loaded(data, _) = &mut config {
+            data.path_template = Some("{repo}/{branch_slug}".to_string());
+        }
+        let git = FakeGit::new("/path/to/my-repo", "main");
+
+        let path = compute_target_path(&git, &config, "feature/login").unwrap();
+        assert_eq!(path, worktree_root(&config).join("my-repo/feature-login"));
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
+        if let Config::Loaded(data, _) = &mut config {
+            data.nest_slashes = true;
         }
+        let path = compute_target_path(&git, &config, "feature/login").unwrap();
+        assert_eq!(path, worktree_root(&config).join("my-repo/feature/login"));
+
+        if let Config::Loaded(data, _) = &mut config {
+            data.path_template = Some("../{branch}".to_string());
+        }
+        assert!(compute_target_path(&git, &config, "feature").is_err());
     }
 
     #[test]
-    fn test_create_worktree_and_print_path_with_create() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/heads/new-branch")
-        exit 0
-        ;;
-    "branch new-branch")
-        exit 0
-        ;;
-    "rev-parse --show-toplevel")
-        echo "/path/to/repo"
-        exit 0
-        ;;
-    "worktree add "* )
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        let wt_root = _dir.path().join("wt-root");
-        std::fs::create_dir_all(&wt_root).unwrap();
-
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
+    fn test_compute_target_path_grouped_by_remote() {
+        let (_dir, mut config) = temp_config();
+        if let Config::Loaded(data, _) = &mut config {
+            data.group_by_remote = true;
+        }
+        let root = worktree_root(&config);
+        let hash = compute_worktree_hash("/path/to/gwt", "feature");
+
+        let git = FakeGit::new("/path/to/gwt", "main")
+            .with_remote_url("origin", "git@github.com:fork-owner/gwt.git");
+        let path = compute_target_path(&git, &config, "feature").unwrap();
+        assert_eq!(path, root.join("fork-owner/gwt").join(&hash));
+
+        // No origin: grouped by the directory name
+        let git = FakeGit::new("/path/to/gwt", "main")
+            .with_remote_url("upstream", "https://github.com/troydai/gwt");
+        let path = compute_target_path(&git, &config, "feature").unwrap();
+        assert_eq!(path, root.join("gwt").join(&hash));
+
+        // `{repo}` of a template carries the owner too
+        if let Config::Loaded(data, _) = &mut config {
+            data.path_template = Some("{repo}/{branch}".to_string());
         }
+        let git = FakeGit::new("/path/to/gwt", "main")
+            .with_remote_url("origin", "https://gitlab.com/group/sub/gwt.git");
+        let path = compute_target_path(&git, &config, "feature").unwrap();
+        assert_eq!(path, root.join("group/sub/gwt/feature"));
+    }
 
-        let config = Config::Loaded(
-            ConfigData {
-                worktree_root: wt_root,
+    #[test]
+    fn test_remove_empty_parents() {
+        let (_dir, config) = temp_config();
+        let root = worktree_root(&config);
+        std::fs::create_dir_all(root.join("api/feature")).unwrap();
+        std::fs::create_dir_all(root.join("api/main")).unwrap();
+
+        remove_empty_parents(&config, &root.join("api/feature/login"));
+        assert!(!root.join("api/feature").exists());
+        assert!(root.join("api/main").exists());
+
+        remove_empty_parents(&config, &root.join("api/main/x"));
+        assert!(!root.join("api").exists());
+        assert!(root.exists());
+    }
+
+    #[test]
+    fn test_create_worktree_reuses_one_created_while_waiting() {
+        let (_dir, config) = temp_config();
+        // What a concurrent gwt left behind by the time the lock is taken
+        let git = FakeGit::new("/path/to/repo", "main").with_worktree("/store/other", "feature");
+
+        let mut rollback = CreateRollback::new(&git);
+        let path =
+            create_worktree_and_print_path(&git, &config, "feature", false, &mut rollback).unwrap();
+        rollback.commit();
+
+        assert_eq!(path, PathBuf::from("/store/other"));
+        assert!(git.calls().is_empty(), "calls: {:?}", git.calls());
+        assert!(worktree_root(&config).join(LOCK_FILE_NAME).is_file());
+    }
+
+    #[test]
+    fn test_create_worktree_and_print_path_with_create() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main");
+
+        let mut rollback = CreateRollback::new(&git);
+        let path = create_worktree_and_print_path(&git, &config, "new-branch", true, &mut rollback)
+            .unwrap();
+        rollback.commit();
+
+        assert_eq!(
+            git.calls(),
+            [
+                "create_branch new-branch".to_string(),
+                format!("add_worktree {} new-branch", path.display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_create_rolls_back_branch_when_add_fails() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").failing("add_worktree");
+
+        let manager = WorktreeManager::new(config, git);
+        let result = switch(
+            &manager,
+            Some("new-branch"),
+            &SwitchOptions {
+                create: true,
+                ..Default::default()
             },
-            PathBuf::from("/tmp/config"),
         );
 
-        let git = Git::new();
-        let result = create_worktree_and_print_path(&git, &config, "new-branch", true);
-        assert!(result.is_ok());
+        assert!(result.is_err());
+        let calls = manager.git().calls();
+        assert!(calls[1].starts_with("add_worktree "), "calls: {calls:?}");
+        assert_eq!(
+            calls[2..],
+            ["prune_worktrees", "delete_branch new-branch -D"],
+            "calls: {calls:?}"
+        );
+        assert!(!manager.git().branch_exists("new-branch").unwrap());
+        assert_eq!(store_entries(&worktree_root(manager.config())), 0);
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
+    #[test]
+    fn test_switch_rejects_invalid_branch_name_before_any_change() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main");
+
+        let manager = WorktreeManager::new(config, git);
+        for name in ["foo bar", "a..b", "-rf"] {
+            let err = switch(
+                &manager,
+                Some(name),
+                &SwitchOptions {
+                    create: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+            assert!(
+                matches!(
+                    GwtError::find(&err),
+                    Some(GwtError::InvalidBranchName { .. })
+                ),
+                "{err}"
+            );
         }
+        assert!(
+            manager.git().calls().is_empty(),
+            "calls: {:?}",
+            manager.git().calls()
+        );
+        assert_eq!(store_entries(&worktree_root(manager.config())), 0);
     }
 
     #[test]
-    fn test_resolve_main_branch_when_only_main_exists() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/heads/main")
-        echo "refs/heads/main"
-        exit 0
-        ;;
-    "for-each-ref --format=%(refname) refs/heads/master")
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
+    fn test_switch_creates_unusual_but_valid_branch_names() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main");
+
+        let manager = WorktreeManager::new(config, git);
+        for name in ["feature/login", "naïve-café"] {
+            switch(
+                &manager,
+                Some(name),
+                &SwitchOptions {
+                    create: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            assert!(manager.git().branch_exists(name).unwrap(), "{name}");
         }
+    }
 
-        let git = Git::new();
-        let result = resolve_main_branch(&git);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "main");
+    #[test]
+    fn test_switch_keeps_existing_branch_when_add_fails() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_branch("new-branch")
+            .failing("add_worktree");
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+        let manager = WorktreeManager::new(config, git);
+        let result = switch(&manager, Some("new-branch"), &SwitchOptions::default());
+
+        assert!(result.is_err());
+        let calls = manager.git().calls();
+        assert_eq!(calls.len(), 2, "calls: {calls:?}");
+        assert!(calls[0].starts_with("add_worktree "));
+        assert_eq!(calls[1], "prune_worktrees");
+        assert!(manager.git().branch_exists("new-branch").unwrap());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_resolve_main_branch_when_only_master_exists() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
+    fn test_switch_cleans_up_partial_checkout_when_add_fails() {
+        // `worktree add` gets as far as creating the checkout, then fails
+        let (mock_git, dir) = create_mock_git_script(
+            r#"#!/bin/sh
+echo "$@" >> "$(dirname "$0")/calls.log"
 case "$@" in
-    "for-each-ref --format=%(refname) refs/heads/main")
-        exit 0
-        ;;
-    "for-each-ref --format=%(refname) refs/heads/master")
-        echo "refs/heads/master"
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
+    "branch --show-current") echo main ;;
+    "for-each-ref --format=%(refname) refs/heads/new-branch") echo refs/heads/new-branch ;;
+    "worktree list --porcelain") printf 'worktree /path/to/repo
+HEAD abc
+branch refs/heads/main
+' ;;
+    "rev-parse --show-toplevel") echo /path/to/repo ;;
+    "worktree add "*) mkdir -p "$3" && echo partial > "$3/README" && exit 128 ;;
+    "worktree remove --force "*|"worktree prune") ;;
+    *) exit 1 ;;
 esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
+"#,
+        );
+        let (_config_dir, config) = temp_config();
 
-        let git = Git::new();
-        let result = resolve_main_branch(&git);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "master");
+        let manager = WorktreeManager::new(config, crate::utility::Git::with_program(&mock_git));
+        let result = switch(&manager, Some("new-branch"), &SwitchOptions::default());
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+        assert!(result.is_err());
+        let root = worktree_root(manager.config());
+        assert_eq!(store_entries(&root), 0);
+        let calls = std::fs::read_to_string(dir.path().join("calls.log")).unwrap();
+        let target = compute_worktree_hash("/path/to/repo", "new-branch");
+        assert!(
+            calls.contains(&format!(
+                "worktree remove --force {}\nworktree prune\n",
+                root.join(target).display()
+            )),
+            "{calls}"
+        );
     }
 
     #[test]
-    fn test_resolve_main_branch_when_both_exist_prefer_main() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/heads/main")
-        echo "refs/heads/main"
-        exit 0
-        ;;
-    "for-each-ref --format=%(refname) refs/heads/master")
-        echo "refs/heads/master"
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
+    fn test_switch_redirects_to_branch_checked_out_elsewhere() {
+        let (dir, config) = temp_config();
+        let elsewhere = dir.path().join("elsewhere");
+        std::fs::create_dir_all(&elsewhere).unwrap();
+        // A checkout `git worktree list` didn't report, e.g. a stale registration
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_branch("feature")
+            .failing_with(
+                "add_worktree",
+                &format!(
+                    "fatal: 'feature' is already used by worktree at '{}'",
+                    elsewhere.display()
+                ),
+            );
 
-        let git = Git::new();
-        let result = resolve_main_branch(&git);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "main");
+        let switched =
+            switch_worktree(&git, &config, Some("feature"), &SwitchOptions::default()).unwrap();
+        assert!(!switched.created);
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+        // Nothing was created, so nothing is rolled back
+        let calls = git.calls();
+        assert_eq!(calls.len(), 1, "calls: {calls:?}");
+        assert!(calls[0].starts_with("add_worktree "));
+        let history = std::fs::read_to_string(dir.path().join("history")).unwrap();
+        assert!(
+            history.contains(&format!("\tfeature\t{}", elsewhere.display())),
+            "{history}"
+        );
     }
 
     #[test]
-    fn test_resolve_main_branch_when_neither_exists() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/heads/main")
-        exit 0
-        ;;
-    "for-each-ref --format=%(refname) refs/heads/master")
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
+    fn test_switch_branch_match_icase() {
+        let (dir, exact) = temp_config();
+        let icase = Config::Loaded(
+            ConfigData {
+                worktree_root: exact.data().unwrap().worktree_root.clone(),
+                branch_match: BranchMatch::Icase,
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        let options = SwitchOptions::default();
 
-        let git = Git::new();
-        let result = resolve_main_branch(&git);
-        assert!(result.is_err());
+        let git = FakeGit::new("/path/to/repo", "main").with_branch("Feature-X");
+        let err = switch_worktree(&git, &exact, Some("feature-x"), &options).unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 4);
+
+        let switched = switch_worktree(&git, &icase, Some("feature-x"), &options).unwrap();
+        assert_eq!(switched.branch, "Feature-X");
+        assert_eq!(
+            switched.path,
+            compute_target_path(&git, &icase, "Feature-X").unwrap()
+        );
+        let calls = git.calls();
         assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Neither 'main' nor 'master' branch exists")
+            calls
+                .iter()
+                .any(|c| c.starts_with("add_worktree ") && c.ends_with(" Feature-X")),
+            "calls: {calls:?}"
+        );
+
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_branch("Feature-X")
+            .with_branch("FEATURE-x");
+        let err = switch_worktree(&git, &icase, Some("feature-x"), &options).unwrap_err();
+        assert!(matches!(GwtError::find(&err), Some(GwtError::Usage(_))));
+        assert!(
+            err.to_string()
+                .ends_with("differ only by case: Feature-X, FEATURE-x"),
+            "{err}"
         );
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
+    #[test]
+    fn test_switch_create_applies_branch_prefix() {
+        let (dir, plain) = temp_config();
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: plain.data().unwrap().worktree_root.clone(),
+                branch_prefix: Some("users/troy/".to_string()),
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        let create = SwitchOptions {
+            create: true,
+            ..Default::default()
+        };
+
+        let git = FakeGit::new("/path/to/repo", "main");
+        let switched = switch_worktree(&git, &config, Some("login-fix"), &create).unwrap();
+        assert_eq!(switched.branch, "users/troy/login-fix");
+        assert_eq!(
+            switched.path,
+            compute_target_path(&git, &config, "users/troy/login-fix").unwrap()
+        );
+        assert!(
+            git.calls()
+                .contains(&"create_branch users/troy/login-fix".to_string())
+        );
+
+        // A name with a `/`, --no-prefix and switching without -b keep the name
+        let no_prefix = SwitchOptions {
+            no_prefix: true,
+            ..create
+        };
+        for (branch, options) in [("fix/typo", &create), ("plain", &no_prefix)] {
+            let git = FakeGit::new("/path/to/repo", "main");
+            let switched = switch_worktree(&git, &config, Some(branch), options).unwrap();
+            assert_eq!(switched.branch, branch);
         }
+        let git = FakeGit::new("/path/to/repo", "main").with_branch("existing");
+        let switched =
+            switch_worktree(&git, &config, Some("existing"), &SwitchOptions::default()).unwrap();
+        assert_eq!(switched.branch, "existing");
+
+        // The repository's git config wins over gwt's
+        let git =
+            FakeGit::new("/path/to/repo", "main").with_git_config("gwt.branchPrefix", "team-");
+        let switched = switch_worktree(&git, &config, Some("login-fix"), &create).unwrap();
+        assert_eq!(switched.branch, "team-login-fix");
+        let switched = switch_worktree(&git, &plain, Some("other"), &create).unwrap();
+        assert_eq!(switched.branch, "team-other");
     }
 
     #[test]
-    fn test_handle_remote_branch() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/remotes/*/feature")
-        echo "refs/remotes/origin/feature"
-        exit 0
-        ;;
-    "branch --track feature origin/feature")
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
+    fn test_switch_pre_switch_check_leaving_dirty_worktree() {
+        let (dir, default_config) = temp_config();
+        let root = dir.path().join("wt-root");
+        let current = root.join("current");
+        let other =
+            compute_target_path(&FakeGit::new(&current, "current"), &default_config, "other")
+                .unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        let git = FakeGit::new(&current, "current")
+            .with_worktree(&other, "other")
+            .with_dirty(&current);
+        let config = |check| {
+            Config::Loaded(
+                ConfigData {
+                    worktree_root: root.clone(),
+                    pre_switch_check: check,
+                    ..Default::default()
+                },
+                dir.path().join("config.toml"),
+            )
+        };
+        let options = SwitchOptions::default();
+
+        let err = switch_worktree(
+            &git,
+            &config(PreSwitchCheck::BlockDirty),
+            Some("other"),
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 6);
+        assert!(err.to_string().contains("1 uncommitted file."), "{err}");
+
+        let force = SwitchOptions {
+            force: true,
+            ..Default::default()
+        };
+        for (check, options) in [
+            (PreSwitchCheck::BlockDirty, &force),
+            (PreSwitchCheck::WarnDirty, &options),
+            (PreSwitchCheck::Off, &options),
+        ] {
+            let switched = switch_worktree(&git, &config(check), Some("other"), options).unwrap();
+            assert_eq!(switched.path, other);
         }
 
-        let git = Git::new();
+        // Outside the worktree root there's nothing to check
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: dir.path().join("elsewhere"),
+                pre_switch_check: PreSwitchCheck::BlockDirty,
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        std::fs::create_dir_all(dir.path().join("elsewhere")).unwrap();
+        switch_worktree(&git, &config, Some("other"), &options).unwrap();
+    }
+
+    #[test]
+    fn test_switch_finds_detached_worktree_by_commit() {
+        let (dir, config) = temp_config();
+        let detached = dir.path().join("detached");
+        std::fs::create_dir_all(&detached).unwrap();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_detached_worktree_at(&detached, "abc1234def5678abc1234def5678abc1234def56")
+            .with_detached_worktree_at("/store/other", "abd0000000000000000000000000000000000000");
+
+        let switched =
+            switch_worktree(&git, &config, Some("abc1234"), &SwitchOptions::default()).unwrap();
+        assert_eq!(switched.path, detached);
+        assert!(!switched.created);
+        assert!(git.calls().is_empty());
+
+        let err =
+            switch_worktree(&git, &config, Some("ab"), &SwitchOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("ambiguous"), "{err}");
+
+        let err =
+            switch_worktree(&git, &config, Some("fff"), &SwitchOptions::default()).unwrap_err();
+        assert!(matches!(
+            GwtError::find(&err),
+            Some(GwtError::BranchNotFoundAnywhere(_))
+        ));
+    }
+
+    #[test]
+    fn test_switch_force_adds_second_checkout() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").with_worktree("/elsewhere", "feature");
+
+        let switched = switch_worktree(
+            &git,
+            &config,
+            Some("feature"),
+            &SwitchOptions {
+                force: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let target = compute_target_path(&git, &config, "feature").unwrap();
+        assert!(switched.created);
+        assert_eq!(switched.path, target);
+        assert_eq!(
+            git.calls(),
+            [format!("add_worktree --force {} feature", target.display())]
+        );
+    }
+
+    #[test]
+    fn test_switch_track_creates_branch_from_given_remote() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("upstream/feature");
+        let options = SwitchOptions {
+            track: Some("upstream/feature"),
+            no_checkout: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap();
+
+        let target = compute_target_path(manager.git(), manager.config(), "feature").unwrap();
+        assert_eq!(
+            manager.git().calls(),
+            [format!(
+                "add_worktree --no-checkout --track -b feature {} upstream/feature",
+                target.display()
+            )]
+        );
+
+        // An existing branch already has its upstream
+        let err = switch(&manager, Some("feature"), &options).unwrap_err();
+        assert!(err.to_string().contains("--track only applies"), "{err}");
+    }
+
+    #[test]
+    fn test_switch_fetch_runs_before_resolving_branch() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("upstream/other");
+        let options = SwitchOptions {
+            fetch: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap();
+
+        let calls = manager.git().calls();
+        assert_eq!(
+            calls[..3],
+            [
+                "fetch_prune origin",
+                "fetch_prune upstream",
+                "create_branch_from_remote feature origin/feature"
+            ]
+        );
+
+        // Without remotes there is nothing to fetch
+        let (_dir, config) = temp_config();
+        let manager = WorktreeManager::new(config, FakeGit::new("/path/to/repo", "main"));
+        switch(
+            &manager,
+            Some("local"),
+            &SwitchOptions {
+                create: true,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(manager.git().calls()[0], "create_branch local");
+    }
+
+    #[test]
+    fn test_switch_fetch_failure_aborts() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/feature")
+            .failing_with(
+                "fetch_prune",
+                "fatal: unable to access 'https://example.com/'",
+            );
+        let options = SwitchOptions {
+            fetch: true,
+            remote: Some("origin"),
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        let err = switch(&manager, Some("feature"), &options).unwrap_err();
+
+        assert_eq!(err.to_string(), "Failed to fetch 'origin'");
+        assert!(format!("{err:#}").contains("unable to access"));
+        assert_eq!(manager.git().calls(), ["fetch_prune origin"]);
+    }
+
+    #[test]
+    fn test_switch_pull_fast_forwards_existing_worktree() {
+        let (dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree(dir.path(), "feature")
+            .with_worktree(dir.path().join("wt-root"), "local")
+            .with_upstream("feature", "origin/feature", false);
+        let options = SwitchOptions {
+            pull: true,
+            strict: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap();
+        assert_eq!(
+            manager.git().calls(),
+            [format!("pull_ff_only {}", dir.path().display())]
+        );
+
+        // Without an upstream there is nothing to pull from
+        switch(&manager, Some("local"), &options).unwrap();
+        assert_eq!(manager.git().calls().len(), 1);
+    }
+
+    #[test]
+    fn test_switch_pull_failure_warns_unless_strict() {
+        let (dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree(dir.path(), "feature")
+            .with_upstream("feature", "origin/feature", false)
+            .failing_with(
+                "pull_ff_only",
+                "fatal: Not possible to fast-forward, aborting.",
+            );
+        let options = SwitchOptions {
+            pull: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap();
+
+        let err = switch(
+            &manager,
+            Some("feature"),
+            &SwitchOptions {
+                strict: true,
+                ..options
+            },
+        )
+        .unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 8);
+    }
+
+    #[test]
+    fn test_switch_take_changes_moves_changes_to_new_worktree() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").with_dirty("/path/to/repo");
+        let options = SwitchOptions {
+            create: true,
+            take_changes: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap();
+
+        let target = compute_target_path(manager.git(), manager.config(), "feature").unwrap();
+        assert_eq!(
+            manager.git().calls(),
+            [
+                "stash_push /path/to/repo gwt-carry".to_string(),
+                "create_branch feature".to_string(),
+                format!("add_worktree {} feature", target.display()),
+                format!("stash_apply {} stash1", target.display()),
+                "stash_drop stash1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_take_changes_restores_changes_on_failure() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_dirty("/path/to/repo")
+            .failing("add_worktree");
+        let options = SwitchOptions {
+            create: true,
+            take_changes: true,
+            ..Default::default()
+        };
+
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("feature"), &options).unwrap_err();
+
+        let calls = manager.git().calls();
+        assert!(
+            calls.contains(&"stash_apply /path/to/repo stash1".to_string()),
+            "{calls:?}"
+        );
+        assert_eq!(calls.last().unwrap(), "delete_branch feature -D");
+    }
+
+    #[test]
+    fn test_switch_to_existing_worktree_creates_nothing() {
+        let (dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").with_worktree(dir.path(), "feature");
+
+        let switched =
+            switch_worktree(&git, &config, Some("feature"), &SwitchOptions::default()).unwrap();
+
+        assert!(!switched.created);
+        assert!(git.calls().is_empty(), "calls: {:?}", git.calls());
+    }
+
+    #[test]
+    fn test_switch_to_missing_worktree_suggests_repair() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").with_worktree("/gone/feature", "feature");
+
+        let manager = WorktreeManager::new(config, git);
+        let error = switch(&manager, Some("feature"), &SwitchOptions::default()).unwrap_err();
+
+        assert!(error.to_string().contains("gwt repair"), "{error}");
+        assert!(
+            manager.git().calls().is_empty(),
+            "calls: {:?}",
+            manager.git().calls()
+        );
+    }
+
+    #[test]
+    fn test_resolve_main_branch_prefers_the_configured_branch() {
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_branch("develop")
+            .with_remote_head("origin", "main");
+        let config = Config::Loaded(
+            ConfigData {
+                default_base_branch: Some("develop".to_string()),
+                ..Default::default()
+            },
+            PathBuf::from("/home/me/.gwt/config.toml"),
+        );
+        assert_eq!(resolve_main_branch(&git, &config).unwrap(), "develop");
+    }
+
+    #[test]
+    fn test_resolve_main_branch_follows_origin_head() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "feature")
+            .with_branch("trunk")
+            .with_git_config("init.defaultBranch", "feature")
+            .with_remote_head("origin", "trunk");
+        assert_eq!(resolve_main_branch(&git, &config).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn test_resolve_main_branch_uses_init_default_branch() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "feature")
+            .with_branch("trunk")
+            .with_git_config("init.defaultBranch", "trunk");
+        assert_eq!(resolve_main_branch(&git, &config).unwrap(), "trunk");
+
+        // origin/HEAD naming a branch that doesn't exist locally is skipped
+        let git = git.with_remote_head("origin", "gone");
+        assert_eq!(resolve_main_branch(&git, &config).unwrap(), "trunk");
+    }
+
+    #[test]
+    fn test_resolve_main_branch_falls_back_to_the_main_worktree() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "master")
+            .with_branch("main")
+            .with_git_config("init.defaultBranch", "develop");
+        assert_eq!(resolve_main_branch(&git, &config).unwrap(), "master");
+    }
+
+    #[test]
+    fn test_handle_remote_branch() {
+        let git = FakeGit::new("/path/to/repo", "main").with_remote_branch("origin/feature");
         let result = handle_remote_branch(&git, "feature", None);
-        assert!(result.is_ok());
         assert_eq!(result.unwrap(), "feature");
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+        assert_eq!(
+            git.calls(),
+            ["create_branch_from_remote feature origin/feature"]
+        );
     }
 
     #[test]
     fn test_handle_remote_branch_with_override() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "for-each-ref --format=%(refname) refs/remotes/upstream/feature")
-        echo "refs/remotes/upstream/feature"
-        exit 0
-        ;;
-    "branch --track feature upstream/feature")
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("upstream/feature");
         let result = handle_remote_branch(&git, "feature", Some("upstream"));
-        assert!(result.is_ok());
         assert_eq!(result.unwrap(), "feature");
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+        assert_eq!(
+            git.calls(),
+            ["create_branch_from_remote feature upstream/feature"]
+        );
     }
 
     #[test]
     fn test_handle_remote_branch_ambiguous() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-if [ "$1" = "for-each-ref" ] && [ "$3" = "refs/remotes/*/feature" ]; then
-    echo "refs/remotes/origin/feature"
-    echo "refs/remotes/upstream/feature"
-    exit 0
-fi
-exit 1
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("upstream/feature");
         let result = handle_remote_branch(&git, "feature", None);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Ambiguous"));
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
     #[test]
     fn test_handle_remote_branch_not_found() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-if [ "$1" = "for-each-ref" ] && [ "$3" = "refs/remotes/*/feature" ]; then
-    exit 0
-fi
-exit 1
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = FakeGit::new("/path/to/repo", "main");
         let result = handle_remote_branch(&git, "feature", None);
         assert!(result.is_err());
         assert!(
@@ -655,65 +2308,491 @@ exit 1
                 .to_string()
                 .contains("not found locally or in any remote")
         );
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
     #[test]
     fn test_switch_prefers_local() {
-        let _guard = ENV_LOCK.lock().unwrap();
-        let script = r#"#!/bin/sh
-case "$@" in
-    "branch --show-current")
-        echo "current"
-        exit 0
-        ;;
-    "for-each-ref --format=%(refname) refs/heads/local-branch")
-        echo "refs/heads/local-branch"
-        exit 0
-        ;;
-    "worktree list --porcelain")
-        echo "worktree /path/to/repo"
-        echo "HEAD abc"
-        echo "branch refs/heads/main"
-        exit 0
-        ;;
-    "rev-parse --show-toplevel")
-        echo "/path/to/repo"
-        exit 0
-        ;;
-    "worktree add "* )
-        exit 0
-        ;;
-    *)
-        echo "unexpected args: $@" >&2
-        exit 1
-        ;;
-esac
-"#;
-        let (mock_git, _dir) = create_mock_git_script(script);
-        let wt_root = _dir.path().join("wt-root");
-        std::fs::create_dir_all(&wt_root).unwrap();
+        let (dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "current")
+            .with_branch("local-branch")
+            .with_remote_branch("origin/local-branch");
 
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
+        let manager = WorktreeManager::new(config, git);
+        switch(&manager, Some("local-branch"), &SwitchOptions::default()).unwrap();
 
-        let config = Config::Loaded(
-            ConfigData {
-                worktree_root: wt_root,
+        let calls = manager.git().calls();
+        assert_eq!(calls.len(), 1, "calls: {calls:?}");
+        assert!(calls[0].starts_with("add_worktree "));
+
+        // The switch is remembered for `gwt recent`, next to the config file
+        let history = std::fs::read_to_string(dir.path().join("history")).unwrap();
+        assert!(
+            history.contains("\t/path/to/repo\tlocal-branch\t"),
+            "{history}"
+        );
+    }
+
+    fn resolver_git(wt_root: &Path) -> FakeGit {
+        FakeGit::new("/path/to/repo", "main")
+            .with_worktree(wt_root.join("3f2a9c1d86b47897"), "feature")
+            .with_worktree(wt_root.join("3f2b000000000000"), "3f2a")
+            .with_detached_worktree(wt_root.join("77aa000000000000"))
+    }
+
+    #[test]
+    fn test_resolve_worktree_precedence() {
+        let (_dir, config) = temp_config();
+        let wt_root = worktree_root(&config);
+        let git = resolver_git(&wt_root);
+
+        // Exact branch name
+        let wt = resolve_worktree(&git, &config, "feature").unwrap();
+        assert_eq!(wt.path(), &wt_root.join("3f2a9c1d86b47897"));
+
+        // A branch named like an id prefix beats the id match
+        let wt = resolve_worktree(&git, &config, "3f2a").unwrap();
+        assert_eq!(wt.branch(), Some("3f2a"));
+
+        // Unambiguous id prefix, including detached worktrees
+        let wt = resolve_worktree(&git, &config, "3f2a9").unwrap();
+        assert_eq!(wt.branch(), Some("feature"));
+        let wt = resolve_worktree(&git, &config, "77").unwrap();
+        assert_eq!(wt.branch(), None);
+
+        // Ambiguous id prefix
+        let err = resolve_worktree(&git, &config, "3f").unwrap_err();
+        assert!(err.to_string().contains("Ambiguous worktree id"));
+
+        // Unknown target
+        let err = resolve_worktree(&git, &config, "nope").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("No worktree found for branch 'nope'")
+        );
+    }
+
+    #[test]
+    fn test_resolve_worktree_by_path() {
+        let (dir, config) = temp_config();
+        let wt_root = worktree_root(&config);
+        let detached = wt_root.join("77aa000000000000");
+        std::fs::create_dir_all(detached.join("src")).unwrap();
+        let git = resolver_git(&wt_root);
+
+        let wt = resolve_worktree(&git, &config, detached.to_str().unwrap()).unwrap();
+        assert_eq!(wt.path(), &detached);
+        // A directory inside the worktree, spelled with `..`
+        let inside = detached.join("src/../src");
+        let wt = resolve_worktree(&git, &config, inside.to_str().unwrap()).unwrap();
+        assert_eq!(wt.path(), &detached);
+
+        let err = resolve_worktree(&git, &config, dir.path().to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("isn't inside a worktree"), "{err}");
+
+        remove(
+            &git,
+            &config,
+            detached.to_str().unwrap(),
+            &RemoveOptions {
+                skip_confirmation: true,
+                ..Default::default()
             },
-            PathBuf::from("/tmp/config"),
+        )
+        .unwrap();
+        assert_eq!(
+            git.calls(),
+            [format!("remove_worktree {}", detached.display())]
+        );
+    }
+
+    #[test]
+    fn test_remove_runs_post_remove_hook() {
+        let (dir, _config) = temp_config();
+        let main = dir.path().join("repo");
+        std::fs::create_dir_all(&main).unwrap();
+        let git = FakeGit::new(&main, "main")
+            .with_worktree("/store/one", "one")
+            .with_worktree("/store/two", "two")
+            .with_worktree("/store/three", "three");
+        let config = |hook: &str| {
+            Config::Loaded(
+                ConfigData {
+                    worktree_root: dir.path().join("wt-root"),
+                    post_remove_hook: Some(hook.to_string()),
+                    ..Default::default()
+                },
+                dir.path().join("config.toml"),
+            )
+        };
+        let options = RemoveOptions {
+            skip_confirmation: true,
+            ..Default::default()
+        };
+
+        let log = r#"echo "$GWT_BRANCH $GWT_REMOVED_PATH" >> hook.log"#;
+        remove(&git, &config(log), "one", &options).unwrap();
+        let no_hooks = RemoveOptions {
+            no_hooks: true,
+            ..options
+        };
+        remove(&git, &config(log), "two", &no_hooks).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(main.join("hook.log")).unwrap(),
+            "one /store/one\n"
         );
 
-        // switch(config, branch, create, use_main, remote)
-        let result = switch(&config, Some("local-branch"), false, false, None);
-        assert!(result.is_ok());
+        // The worktree is gone either way
+        remove(&git, &config("exit 1"), "three", &options).unwrap();
+        assert_eq!(git.calls().len(), 3);
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+    #[test]
+    fn test_remove_refuses_main_worktree() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main");
+        let options = RemoveOptions {
+            delete_branch: true,
+            skip_confirmation: true,
+            ..Default::default()
+        };
+
+        let err = remove(&git, &config, "main", &options).unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 2);
+        assert!(err.to_string().contains("--allow-main"), "{err}");
+        assert!(git.calls().is_empty());
+
+        let options = RemoveOptions {
+            delete_branch: false,
+            allow_main: true,
+            ..options
+        };
+        remove(&git, &config, "main", &options).unwrap();
+        assert_eq!(git.calls(), ["remove_worktree /path/to/repo"]);
+    }
+
+    #[test]
+    fn test_remove_detached_with_delete_branch_fails() {
+        let (_dir, config) = temp_config();
+        let git = resolver_git(&worktree_root(&config));
+
+        let err = remove(
+            &git,
+            &config,
+            "77aa",
+            &RemoveOptions {
+                delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("there is no branch to delete"));
+        assert!(git.calls().is_empty());
+    }
+
+    #[test]
+    fn test_remove_delete_branch_checks_merged() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/done", "done")
+            .with_worktree("/store/wip", "wip")
+            .with_worktree("/store/scrap", "scrap")
+            .with_merge_state("done", MergeState::Merged);
+
+        remove(
+            &git,
+            &config,
+            "done",
+            &RemoveOptions {
+                delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Unmerged: the worktree goes, the branch stays
+        let err = remove(
+            &git,
+            &config,
+            "wip",
+            &RemoveOptions {
+                delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 9);
+        assert!(err.to_string().contains("git branch -D wip"), "{err}");
+
+        // -B doesn't ask
+        remove(
+            &git,
+            &config,
+            "scrap",
+            &RemoveOptions {
+                force_delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            git.calls(),
+            [
+                "remove_worktree /store/done",
+                "delete_branch done -d",
+                "remove_worktree /store/wip",
+                "remove_worktree /store/scrap",
+                "delete_branch scrap -D",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_delete_remote_deletes_own_upstream_only() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/feature", "feature")
+            .with_worktree("/store/fix", "fix")
+            .with_worktree("/store/local", "local")
+            .with_remote_branch("origin/feature")
+            .with_upstream("feature", "origin/feature", false)
+            .with_upstream("fix", "origin/main", false)
+            .with_merge_state("feature", MergeState::Merged);
+        let options = RemoveOptions {
+            delete_branch: true,
+            delete_remote: true,
+            skip_confirmation: true,
+            ..Default::default()
+        };
+
+        remove(&git, &config, "feature", &options).unwrap();
+        // Tracking someone else's branch, or nothing at all: only local cleanup
+        let options = RemoveOptions {
+            delete_branch: false,
+            ..options
+        };
+        remove(&git, &config, "fix", &options).unwrap();
+        remove(&git, &config, "local", &options).unwrap();
+
+        assert_eq!(
+            git.calls(),
+            [
+                "remove_worktree /store/feature",
+                "delete_branch feature -d",
+                "delete_remote_branch origin feature",
+                "remove_worktree /store/fix",
+                "remove_worktree /store/local",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_delete_remote_failure_keeps_local_cleanup() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/feature", "feature")
+            .with_remote_branch("origin/feature")
+            .with_upstream("feature", "origin/feature", false)
+            .failing_with("delete_remote_branch", "fatal: unable to access remote");
+        let options = RemoveOptions {
+            force_delete_branch: true,
+            delete_remote: true,
+            skip_confirmation: true,
+            ..Default::default()
+        };
+
+        let err = remove(&git, &config, "feature", &options).unwrap_err();
+        assert_eq!(GwtError::find(&err).unwrap().exit_code(), 5);
+        assert!(
+            format!("{err:#}").contains("unable to access remote"),
+            "{err:#}"
+        );
+        assert_eq!(
+            git.calls(),
+            [
+                "remove_worktree /store/feature",
+                "delete_branch feature -D",
+                "delete_remote_branch origin feature",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_deletes_squash_merged_branch() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/feature", "feature")
+            .with_merge_state("feature", MergeState::SquashMerged);
+
+        remove(
+            &git,
+            &config,
+            "feature",
+            &RemoveOptions {
+                delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            git.calls(),
+            ["remove_worktree /store/feature", "delete_branch feature -D"]
+        );
+    }
+
+    #[test]
+    fn test_remove_locked_worktree_requires_force() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main").with_locked_worktree(
+            "/mnt/usb/wt",
+            "usb",
+            "on the usb drive",
+        );
+
+        let err = remove(
+            &git,
+            &config,
+            "usb",
+            &RemoveOptions {
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("is locked (on the usb drive)"), "{err}");
+        assert!(err.contains("--force"), "{err}");
+        assert!(git.calls().is_empty());
+
+        remove(
+            &git,
+            &config,
+            "usb",
+            &RemoveOptions {
+                skip_confirmation: true,
+                force: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            git.calls(),
+            ["unlock_worktree /mnt/usb/wt", "remove_worktree /mnt/usb/wt"]
+        );
+    }
+
+    #[test]
+    fn test_remove_relocks_when_removal_fails() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_locked_worktree("/mnt/usb/wt", "usb", "on the usb drive")
+            .failing("remove_worktree");
+
+        assert!(
+            remove(
+                &git,
+                &config,
+                "usb",
+                &RemoveOptions {
+                    skip_confirmation: true,
+                    force: true,
+                    ..Default::default()
+                }
+            )
+            .is_err()
+        );
+        assert_eq!(
+            git.calls().last().map(String::as_str),
+            Some("lock_worktree /mnt/usb/wt on the usb drive")
+        );
+        let wt = git.find_worktree_by_branch("usb").unwrap().unwrap();
+        assert_eq!(wt.locked(), Some("on the usb drive"));
+    }
+
+    #[test]
+    fn test_remove_gone_removes_only_gone_upstreams() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/merged", "merged")
+            .with_worktree("/store/active", "active")
+            .with_worktree("/store/local", "local")
+            .with_locked_worktree("/mnt/usb/wt", "usb", "")
+            .with_upstream("main", "origin/main", true)
+            .with_upstream("merged", "origin/merged", true)
+            .with_upstream("active", "origin/active", false)
+            .with_upstream("usb", "origin/usb", true)
+            .with_merge_state("merged", MergeState::Merged);
+
+        remove_gone(
+            &git,
+            &config,
+            &RemoveOptions {
+                delete_branch: true,
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Never the main worktree, a branch without upstream or a locked one
+        assert_eq!(
+            git.calls(),
+            ["remove_worktree /store/merged", "delete_branch merged -d"]
+        );
+    }
+
+    #[test]
+    fn test_read_branch_list() {
+        let input = "  feature\n\n# merged last week\n* main\n+ fix/login\n  spaced  \n";
+        assert_eq!(
+            read_branch_list(input.as_bytes()).unwrap(),
+            ["feature", "main", "fix/login", "spaced"]
+        );
+    }
+
+    #[test]
+    fn test_remove_each_carries_on_past_failures() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/a", "a")
+            .with_worktree("/store/b", "b");
+
+        let err = remove_each(
+            &git,
+            &config,
+            &["a", "missing", "b"],
+            &RemoveOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "1 of 3 worktrees could not be removed");
+        assert_eq!(
+            git.calls(),
+            ["remove_worktree /store/a", "remove_worktree /store/b"]
+        );
+    }
+
+    #[test]
+    fn test_remove_gone_with_nothing_gone() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/active", "active")
+            .with_upstream("active", "origin/active", false);
+
+        remove_gone(
+            &git,
+            &config,
+            &RemoveOptions {
+                skip_confirmation: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(git.calls().is_empty());
     }
 }
@@ -0,0 +1,206 @@
+use super::{CreateRollback, create_worktree_and_print_path, resolve_worktree};
+use crate::config::{Config, ConfigData};
+use crate::error::GwtError;
+use crate::ui;
+use crate::utility::{ExternalOptions, GitBackend, OutputMode, run_external};
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::Command;
+
+/// Open the worktree `branch` (a branch, a path inside the worktree or a
+/// directory id prefix; the current worktree when omitted) in the editor.
+///
+/// With `create`, a missing worktree is created the way `gwt sw -b` would.
+pub fn open(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: Option<&str>,
+    create: bool,
+) -> Result<()> {
+    // Resolve the editor first so a misconfiguration doesn't leave a new worktree behind
+    let editor = editor_command(config.data(), |name| std::env::var(name).ok())?;
+
+    let path = match branch {
+        None => git.git_toplevel().context("Failed to get git toplevel")?,
+        Some(branch) => match resolve_worktree(git, config, branch) {
+            Ok(worktree) => worktree.path().clone(),
+            Err(e) if matches!(GwtError::find(&e), Some(GwtError::WorktreeNotFound(_))) => {
+                if !create {
+                    ui::info!("Use `gwt open -b {}` to create the worktree.", branch);
+                    return Err(e);
+                }
+                config.ensure_worktree_root()?;
+                let mut rollback = CreateRollback::new(git);
+                let path =
+                    create_worktree_and_print_path(git, config, branch, true, &mut rollback)?;
+                rollback.commit();
+                path
+            }
+            Err(e) => return Err(e),
+        },
+    };
+
+    launch(&editor, &path)
+}
+
+/// The editor command split into program and arguments with shell quoting
+/// rules: `editor_command` from the config, then `$VISUAL`, then `$EDITOR`
+fn editor_command(
+    data: Option<&ConfigData>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>> {
+    let command = data
+        .and_then(|d| d.editor_command.clone())
+        .map(|command| ("editor_command", command))
+        .into_iter()
+        .chain(
+            [("$VISUAL", "VISUAL"), ("$EDITOR", "EDITOR")]
+                .into_iter()
+                .filter_map(|(setting, name)| lookup(name).map(|command| (setting, command))),
+        )
+        .find(|(_, command)| !command.trim().is_empty());
+
+    match command {
+        Some((setting, command)) => shell_words::split(&command).map_err(|e| {
+            GwtError::InvalidSetting {
+                setting: setting.to_string(),
+                value: command.clone(),
+                reason: e.to_string(),
+            }
+            .into()
+        }),
+        None => bail!(
+            "No editor configured. Set `editor_command` in the gwt config, or $VISUAL or $EDITOR."
+        ),
+    }
+}
+
+/// Run the editor on `path` with the terminal attached.
+///
+/// The editor's stdio is never captured: terminal editors take over the
+/// terminal until they exit, and launchers of GUI editors (`code`, `subl`, ...)
+/// hand off to the running application and return right away. There is no
+/// timeout, which would kill an interactive session in a terminal editor.
+fn launch(editor: &[String], path: &Path) -> Result<()> {
+    let Some((program, args)) = editor.split_first() else {
+        bail!("Editor command is empty");
+    };
+
+    ui::info!("Opening '{}' with {}", path.display(), program);
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).arg(path);
+    let opts = ExternalOptions::new().output(OutputMode::Inherit);
+    let output = run_external(cmd, &opts)?;
+    if !output.status.success() {
+        bail!("Editor '{}' exited with {}", program, output.status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
+    use crate::utility::FakeGit;
+    use std::path::PathBuf;
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_editor_command_precedence() {
+        let data = ConfigData {
+            editor_command: Some("code --new-window".to_string()),
+            ..Default::default()
+        };
+        let env = [("VISUAL", "nvim"), ("EDITOR", "vi")];
+
+        assert_eq!(
+            editor_command(Some(&data), lookup(&env)).unwrap(),
+            vec!["code", "--new-window"]
+        );
+        assert_eq!(editor_command(None, lookup(&env)).unwrap(), vec!["nvim"]);
+        assert_eq!(
+            editor_command(None, lookup(&[("VISUAL", ""), ("EDITOR", "vi")])).unwrap(),
+            vec!["vi"]
+        );
+        assert!(editor_command(None, lookup(&[])).is_err());
+    }
+
+    #[test]
+    fn test_editor_command_quoting() {
+        let data = ConfigData {
+            editor_command: Some(
+                r#""/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code" --wait"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            editor_command(Some(&data), lookup(&[])).unwrap(),
+            vec![
+                "/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code",
+                "--wait"
+            ]
+        );
+
+        let err =
+            editor_command(None, lookup(&[("EDITOR", "'/opt/my editor/bin/ed")])).unwrap_err();
+        let found = GwtError::find(&err).unwrap();
+        assert!(
+            matches!(found, GwtError::InvalidSetting { setting, .. } if setting == "$EDITOR"),
+            "{err:#}"
+        );
+        assert_eq!(found.exit_code(), 7);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launch_passes_path_to_editor() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("opened");
+        let (editor, _editor_dir) = create_mock_git_script(&format!(
+            "#!/bin/sh\nprintf '%s %s' \"$1\" \"$2\" > '{}'\n",
+            out.display()
+        ));
+
+        launch(
+            &[editor.to_string_lossy().into_owned(), "--flag".to_string()],
+            Path::new("/tmp/worktree"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "--flag /tmp/worktree"
+        );
+    }
+
+    #[test]
+    fn test_open_without_worktree_requires_create() {
+        let git = FakeGit::new("/repo", "main");
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: PathBuf::from("/tmp"),
+                editor_command: Some("true".to_string()),
+                ..Default::default()
+            },
+            PathBuf::from("config.toml"),
+        );
+
+        let err = open(&git, &config, Some("feature"), false).unwrap_err();
+
+        assert!(
+            matches!(GwtError::find(&err), Some(GwtError::WorktreeNotFound(b)) if b == "feature"),
+            "{err:#}"
+        );
+        assert!(git.calls().is_empty());
+    }
+}
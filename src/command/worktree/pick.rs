@@ -0,0 +1,175 @@
+use crate::ui;
+use crate::utility::{
+    ExternalOptions, GitBackend, OutputMode, Worktree, is_not_found, run_external,
+};
+use anyhow::{Context, Result, bail};
+use console::{Term, style};
+use dialoguer::Select;
+use std::process::Command;
+
+/// Picker run by `gwt sw --pick` unless `$GWT_PICKER` names another one
+const DEFAULT_PICKER: &str = "fzf";
+
+/// What running an external picker produced
+#[derive(Debug, PartialEq, Eq)]
+enum Picked {
+    Line(String),
+    Cancelled,
+    /// The picker binary isn't installed
+    Unavailable,
+}
+
+/// The picker command from `$GWT_PICKER`, fzf when unset, split into program
+/// and arguments
+pub fn picker() -> Result<Vec<String>> {
+    let picker = std::env::var("GWT_PICKER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PICKER.to_string());
+    shell_words::split(&picker).with_context(|| format!("Invalid GWT_PICKER '{}'", picker))
+}
+
+/// Let the user pick one of the current repository's worktrees with the
+/// `picker` command, falling back to a built-in list when it isn't installed.
+/// Returns the chosen branch, or `None` when the pick was cancelled.
+pub fn pick_branch(git: &dyn GitBackend, picker: &[String]) -> Result<Option<String>> {
+    let current = git.get_current_branch().ok();
+    let worktrees = git.list_worktrees()?;
+    let candidates: Vec<&Worktree> = worktrees
+        .iter()
+        .filter(|wt| wt.branch().is_some_and(|b| Some(b) != current.as_deref()))
+        .collect();
+    if candidates.is_empty() {
+        bail!("No other worktrees to switch to");
+    }
+    let lines = candidate_lines(&candidates);
+
+    let index = match run_picker(picker, &lines)? {
+        Picked::Line(line) => lines.iter().position(|l| *l == line),
+        Picked::Cancelled => None,
+        Picked::Unavailable => select(&picker[0], &lines)?,
+    };
+    Ok(index.and_then(|i| candidates[i].branch().map(String::from)))
+}
+
+/// `branch  path` lines with the branches padded to one width
+fn candidate_lines(worktrees: &[&Worktree]) -> Vec<String> {
+    let width = worktrees
+        .iter()
+        .filter_map(|wt| wt.branch())
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    worktrees
+        .iter()
+        .map(|wt| {
+            format!(
+                "{:<width$}  {}",
+                wt.branch().unwrap_or_default(),
+                wt.path().display()
+            )
+        })
+        .collect()
+}
+
+/// Feed `lines` to the picker on stdin and read the chosen one from its
+/// stdout. Its stderr stays on the terminal; fzf and friends draw there or on
+/// the tty, so gwt's stdout still only carries the final path.
+fn run_picker(argv: &[String], lines: &[String]) -> Result<Picked> {
+    let (program, args) = argv
+        .split_first()
+        .context("GWT_PICKER doesn't name a program")?;
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    let opts = ExternalOptions::new()
+        .output(OutputMode::CaptureStdout)
+        .input(lines.join("\n"));
+    let output = match run_external(cmd, &opts) {
+        Ok(output) => output,
+        Err(e) if is_not_found(&e) => return Ok(Picked::Unavailable),
+        Err(e) => return Err(e).with_context(|| format!("Failed to run picker '{}'", program)),
+    };
+
+    // fzf exits with 1 when nothing matched and 130 when interrupted
+    match output.stdout.lines().next() {
+        Some(line) if output.status.success() && !line.is_empty() => {
+            Ok(Picked::Line(line.to_string()))
+        }
+        _ => Ok(Picked::Cancelled),
+    }
+}
+
+/// The built-in picker, drawn on stderr so stdout only carries the final path
+fn select(picker: &str, lines: &[String]) -> Result<Option<usize>> {
+    ui::info!(
+        "{}",
+        style(format!(
+            "'{}' not found; using the built-in picker.",
+            picker
+        ))
+        .dim()
+    );
+    Select::with_theme(&ui::theme())
+        .with_prompt("Switch to")
+        .items(lines)
+        .default(0)
+        .interact_on_opt(&Term::stderr())
+        .map_err(ui::prompt_error)
+        .context("Failed to get selection")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::FakeGit;
+
+    fn args(command: &str) -> Vec<String> {
+        shell_words::split(command).unwrap()
+    }
+
+    #[test]
+    fn test_candidate_lines_are_padded() {
+        let git = FakeGit::new("/repo", "main").with_worktree("/store/a", "feature-long");
+        let worktrees = git.list_worktrees().unwrap();
+        let all: Vec<&Worktree> = worktrees.iter().collect();
+
+        assert_eq!(
+            candidate_lines(&all),
+            ["main          /repo", "feature-long  /store/a"]
+        );
+    }
+
+    #[test]
+    fn test_run_picker() {
+        let lines = vec!["main  /repo".to_string(), "dev   /store/a".to_string()];
+
+        assert_eq!(
+            run_picker(&args("sed -n 2p"), &lines).unwrap(),
+            Picked::Line("dev   /store/a".into())
+        );
+        // Nothing selected, or the picker was aborted
+        assert_eq!(
+            run_picker(&args("true"), &lines).unwrap(),
+            Picked::Cancelled
+        );
+        assert_eq!(
+            run_picker(&args("sh -c 'echo main; exit 130'"), &lines).unwrap(),
+            Picked::Cancelled
+        );
+        assert_eq!(
+            run_picker(&args("gwt-no-such-picker"), &lines).unwrap(),
+            Picked::Unavailable
+        );
+    }
+
+    #[test]
+    fn test_pick_branch_uses_the_picker() {
+        let git = FakeGit::new("/repo", "main")
+            .with_worktree("/store/a", "feature")
+            .with_worktree("/store/b", "bugfix");
+
+        let picked = pick_branch(&git, &args("grep bugfix"));
+
+        assert_eq!(picked.unwrap().as_deref(), Some("bugfix"));
+    }
+}
@@ -0,0 +1,235 @@
+use super::{CreateRollback, create_worktree_and_print_path, record_visit};
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{Git, GitBackend};
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+/// Ref GitHub publishes for every pull request; `{number}` is substituted
+pub const DEFAULT_PR_REFSPEC: &str = "pull/{number}/head";
+
+/// Fetch pull request `number` from `remote` into the local branch `pr/<number>`
+/// and switch to its worktree, creating it like `gwt sw -b` would.
+///
+/// Running it again for the same PR fast-forwards the branch (and its
+/// worktree) to the latest PR head.
+pub fn pr(git: &Git, config: &Config, number: u32, remote: &str) -> Result<()> {
+    config.ensure_worktree_root()?;
+
+    let template = config
+        .data()
+        .and_then(|d| d.pr_refspec.as_deref())
+        .unwrap_or(DEFAULT_PR_REFSPEC);
+    let source = template.replace("{number}", &number.to_string());
+    let branch = format!("pr/{}", number);
+
+    if git.run(&["remote", "get-url", remote]).is_err() {
+        bail!(
+            "No remote named '{}'. Add it with `git remote add {} <url>` or pick another with --remote.",
+            remote,
+            remote
+        );
+    }
+
+    ui::info!("Fetching '{}' from '{}'...", source, remote);
+    git.run_streaming(&["fetch", "--no-tags", remote, &source])
+        .with_context(|| {
+            format!(
+                "Failed to fetch PR #{} ('{}') from '{}'. Check the number, or set `pr_refspec` in the config if the remote isn't GitHub (GitLab uses 'merge-requests/{{number}}/head').",
+                number, source, remote
+            )
+        })?;
+    let head = rev_parse(git, "FETCH_HEAD")?;
+
+    let mut rollback = CreateRollback::new(git);
+    let existing = git.find_worktree_by_branch(&branch)?;
+
+    if git
+        .branch_exists(&branch)
+        .context("Failed to check if branch exists")?
+    {
+        let current = rev_parse(git, &branch)?;
+        if current != head {
+            if !git.is_ancestor(&current, &head)? {
+                bail!(
+                    "Local branch '{}' has diverged from PR #{}. Delete or rename it (e.g. `gwt rm -B {}`) and run `gwt pr {}` again.",
+                    branch,
+                    number,
+                    branch,
+                    number
+                );
+            }
+            fast_forward(
+                git,
+                &branch,
+                &head,
+                existing.as_ref().map(|wt| wt.path().as_path()),
+            )?;
+            ui::info!("Updated branch '{}' to the latest PR head.", branch);
+        }
+    } else {
+        git.run(&["branch", &branch, &head])
+            .with_context(|| format!("Failed to create branch '{}'", branch))?;
+        rollback.branch_created(&branch);
+        ui::info!("Branch '{}' created for PR #{}.", branch, number);
+    }
+
+    let wt_path = match existing {
+        Some(wt) => wt.path().clone(),
+        None => create_worktree_and_print_path(git, config, &branch, false, &mut rollback)?,
+    };
+    rollback.commit();
+
+    record_visit(git, config, &branch, &wt_path);
+    println!("{}", wt_path.display());
+    Ok(())
+}
+
+/// Move `branch` forward to `head`. A checked out branch is advanced inside its
+/// worktree so the files follow; git refuses to move it behind the worktree's back.
+fn fast_forward(git: &Git, branch: &str, head: &str, worktree: Option<&Path>) -> Result<()> {
+    match worktree {
+        Some(path) => {
+            git.run_in(path, &["merge", "--ff-only", "--quiet", head])
+                .with_context(|| {
+                    format!(
+                        "Failed to update the worktree of '{}'; commit or stash local changes first",
+                        branch
+                    )
+                })?;
+        }
+        None => {
+            git.run(&["branch", "--force", branch, head])
+                .with_context(|| format!("Failed to update branch '{}'", branch))?;
+        }
+    }
+    Ok(())
+}
+
+fn rev_parse(git: &Git, rev: &str) -> Result<String> {
+    let output = git.run(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{rev}^{{commit}}"),
+    ])?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+    use crate::config::ConfigData;
+    use std::process::Command;
+
+    /// An `origin` publishing PR #7 as `refs/pull/7/head` and a clone of it,
+    /// with a `Git` that runs inside the clone
+    #[cfg(unix)]
+    fn setup() -> (tempfile::TempDir, Git, Config) {
+        let dir = tempfile::tempdir().unwrap();
+        sh(
+            dir.path(),
+            "git init -q -b main origin && cd origin && \
+             git commit -q --allow-empty -m init && \
+             git checkout -q -b contributor && git commit -q --allow-empty -m fix && \
+             git update-ref refs/pull/7/head contributor && git checkout -q main && \
+             cd .. && git clone -q origin repo && mkdir store",
+        );
+
+        let git = Git::new().with_cwd(dir.path().join("repo"));
+
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: dir.path().join("store"),
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        (dir, git, config)
+    }
+
+    /// `None` instead of a panic, so a missing branch fails the assertion
+    fn head_of(git: &Git, rev: &str) -> Option<String> {
+        rev_parse(git, rev).ok()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pr_creates_worktree_and_picks_up_updates() {
+        let (dir, git, config) = setup();
+        let origin = dir.path().join("origin");
+
+        let first = pr(&git, &config, 7, "origin");
+        let worktree = git.find_worktree_by_branch("pr/7").unwrap();
+
+        // The contributor pushes another commit to the PR
+        sh(
+            &origin,
+            "git checkout -q contributor && git commit -q --allow-empty -m more && \
+             git update-ref refs/pull/7/head contributor && git checkout -q main",
+        );
+        let second = pr(&git, &config, 7, "origin");
+        let after = git.find_worktree_by_branch("pr/7").unwrap();
+        let local_head = head_of(&git, "pr/7");
+
+        first.unwrap();
+        second.unwrap();
+        let worktree = worktree.expect("worktree for pr/7");
+        let local_head = local_head.expect("pr/7 exists");
+        assert_eq!(
+            after.map(|wt| wt.path().clone()).as_ref(),
+            Some(worktree.path())
+        );
+        let origin_head = Command::new("git")
+            .args(["-C", origin.to_str().unwrap(), "rev-parse", "contributor"])
+            .output()
+            .unwrap();
+        assert_eq!(
+            local_head,
+            String::from_utf8_lossy(&origin_head.stdout).trim()
+        );
+        // The checked out worktree moved along with the branch
+        let wt_head = Command::new("git")
+            .args(["-C", worktree.path().to_str().unwrap(), "rev-parse", "HEAD"])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&wt_head.stdout).trim(), local_head);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pr_refuses_diverged_branch() {
+        let (dir, git, config) = setup();
+
+        // A local commit on pr/7 that the PR doesn't have
+        sh(
+            &dir.path().join("repo"),
+            "git commit -q --allow-empty -m local && git branch pr/7 && \
+             git reset -q --hard origin/main",
+        );
+        let before = head_of(&git, "pr/7");
+        let result = pr(&git, &config, 7, "origin");
+        let after = head_of(&git, "pr/7");
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("has diverged from PR #7"), "{err}");
+        assert_eq!(before, after);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pr_reports_missing_remote_and_unknown_pr() {
+        let (_dir, git, config) = setup();
+
+        let no_remote = pr(&git, &config, 7, "upstream");
+        let unknown = pr(&git, &config, 99, "origin");
+        let leftover = git.branch_exists("pr/99").unwrap();
+
+        let err = no_remote.unwrap_err().to_string();
+        assert!(err.contains("No remote named 'upstream'"), "{err}");
+        let err = format!("{:#}", unknown.unwrap_err());
+        assert!(err.contains("Failed to fetch PR #99"), "{err}");
+        assert!(!leftover);
+    }
+}
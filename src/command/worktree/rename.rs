@@ -0,0 +1,222 @@
+use super::{compute_target_path, resolve_worktree, warn_on_meta_error};
+use crate::config::Config;
+use crate::meta;
+use crate::ui;
+use crate::utility::{GitBackend, validate_branch_name};
+use anyhow::{Context, Result, anyhow, bail};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Rename the branch of the worktree `old` (a branch, a path inside the
+/// worktree or a directory id prefix) to `new` and move the worktree to the
+/// path `gwt sw` would use for `new`, so paths keep matching their branches.
+///
+/// Prints the new path (keeping the subdirectory) when run from inside the
+/// moved worktree, so the shell wrapper can cd into it.
+pub fn rename(git: &dyn GitBackend, config: &Config, old: &str, new: &str) -> Result<()> {
+    config.ensure_worktree_root()?;
+    validate_branch_name(new)?;
+
+    let worktree = resolve_worktree(git, config, old)?;
+    let old = worktree.branch().ok_or_else(|| {
+        anyhow!(
+            "The worktree at '{}' is detached; there's no branch to rename.",
+            worktree.path().display()
+        )
+    })?;
+    if git.get_main_worktree()?.path() == worktree.path() {
+        bail!(
+            "Branch '{}' is checked out in the main worktree, which can't be moved. Use `git branch -m` to rename it.",
+            old
+        );
+    }
+    if git
+        .branch_exists(new)
+        .context("Failed to check if branch exists")?
+    {
+        bail!("Branch '{}' already exists.", new);
+    }
+
+    let old_path = worktree.path().clone();
+    let new_path = compute_target_path(git, config, new)?;
+    if new_path.exists() {
+        bail!(
+            "Cannot move worktree: directory '{}' already exists.",
+            new_path.display()
+        );
+    }
+    // `git worktree move` doesn't create missing parent directories
+    if let Some(parent) = new_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+
+    let old_path_str = old_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid worktree path"))?;
+    let new_path_str = new_path
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid target path"))?;
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let subdir = current_dir
+        .strip_prefix(&old_path)
+        .ok()
+        .map(Path::to_path_buf);
+
+    git.rename_branch(old, new)
+        .with_context(|| format!("Failed to rename branch '{}' to '{}'", old, new))?;
+
+    if let Err(e) = git.move_worktree(old_path_str, new_path_str) {
+        // Put the old name back so the worktree keeps matching its path
+        if let Err(undo) = git.rename_branch(new, old) {
+            ui::warn!(
+                "Failed to rename branch '{}' back to '{}': {}",
+                new,
+                old,
+                undo
+            );
+        }
+        return Err(e).context("Failed to move worktree");
+    }
+
+    // The branch stays checked out across the rename; make sure git agrees
+    let moved = git.find_worktree_by_branch(new)?;
+    if !moved.is_some_and(|wt| same_path(wt.path(), &new_path)) {
+        ui::warn!(
+            "Branch '{}' is not checked out at '{}' after the move. Check `gwt ls`.",
+            new,
+            new_path.display()
+        );
+    }
+
+    if let Some(d) = config.data() {
+        warn_on_meta_error(meta::record_moved(
+            &d.worktree_root,
+            &old_path,
+            &new_path,
+            new,
+        ));
+    }
+
+    ui::info!(
+        "Renamed branch '{}' to '{}' and moved its worktree to '{}'",
+        old,
+        new,
+        new_path.display()
+    );
+
+    if let Some(subdir) = subdir {
+        println!("{}", new_path.join(subdir).display());
+    }
+
+    Ok(())
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+    use crate::config::ConfigData;
+    use crate::utility::Git;
+    use std::path::PathBuf;
+
+    /// A real repository with a `feature` worktree at its gwt path, and a
+    /// `Git` that runs inside it
+    #[cfg(unix)]
+    fn setup() -> (tempfile::TempDir, Git, Config) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let store = dir.path().join("store");
+        fs::create_dir_all(&repo).unwrap();
+        sh(
+            &repo,
+            "git init -q -b main && git commit -q --allow-empty -m init",
+        );
+
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: store,
+                ..Default::default()
+            },
+            PathBuf::from("config.toml"),
+        );
+        let git = Git::new().with_cwd(&repo);
+        let path = compute_target_path(&git, &config, "feature").unwrap();
+        git.run(&[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            "feature",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        (dir, git, config)
+    }
+
+    fn worktree_path(git: &Git, branch: &str) -> Option<PathBuf> {
+        git.find_worktree_by_branch(branch)
+            .unwrap()
+            .map(|wt| wt.path().clone())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rename_round_trip() {
+        let (_dir, git, config) = setup();
+        let feature_path = compute_target_path(&git, &config, "feature").unwrap();
+        let renamed_path = compute_target_path(&git, &config, "renamed").unwrap();
+        fs::write(feature_path.join("wip.txt"), "uncommitted").unwrap();
+
+        // The worktree can be named by its path as well as its branch
+        let renamed = rename(&git, &config, feature_path.to_str().unwrap(), "renamed");
+        let after_rename = (
+            worktree_path(&git, "renamed"),
+            worktree_path(&git, "feature"),
+            git.branch_exists("feature").unwrap(),
+        );
+        let back = rename(&git, &config, "renamed", "feature");
+        let after_back = worktree_path(&git, "feature");
+
+        renamed.unwrap();
+        assert_eq!(after_rename, (Some(renamed_path.clone()), None, false));
+        back.unwrap();
+        assert_eq!(after_back, Some(feature_path.clone()));
+        assert!(!renamed_path.exists());
+        // Uncommitted work travels with the worktree
+        assert_eq!(
+            fs::read_to_string(feature_path.join("wip.txt")).unwrap(),
+            "uncommitted"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rename_refuses_existing_branch_and_main_worktree() {
+        let (_dir, git, config) = setup();
+        let feature_path = compute_target_path(&git, &config, "feature").unwrap();
+
+        let onto_main = rename(&git, &config, "feature", "main");
+        let from_main = rename(&git, &config, "main", "trunk");
+        let unknown = rename(&git, &config, "nope", "other");
+        let after = worktree_path(&git, "feature");
+
+        let err = onto_main.unwrap_err().to_string();
+        assert!(err.contains("Branch 'main' already exists"), "{err}");
+        let err = from_main.unwrap_err().to_string();
+        assert!(err.contains("main worktree"), "{err}");
+        let err = unknown.unwrap_err().to_string();
+        assert!(err.contains("No worktree found for branch 'nope'"), "{err}");
+        assert_eq!(after, Some(feature_path));
+    }
+}
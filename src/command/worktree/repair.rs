@@ -0,0 +1,176 @@
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{GitBackend, Worktree};
+use anyhow::{Result, anyhow, bail};
+use std::path::{Path, PathBuf};
+
+/// What `gwt repair` found or did for one worktree
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Healthy,
+    /// git fixed broken links between the worktree and the repository
+    Repaired,
+    /// The directory was found under the current worktree root and
+    /// re-registered there
+    Moved(PathBuf),
+    Unrepairable(String),
+}
+
+/// Run `git worktree repair` for every linked worktree of the current
+/// repository, and re-register worktrees whose directory is gone but turns
+/// up under the current worktree root, e.g. after the store moved to another
+/// disk or was restored from a backup.
+pub fn repair(git: &dyn GitBackend, config: &Config) -> Result<()> {
+    let root = config
+        .data()
+        .map(|d| &d.worktree_root)
+        .ok_or_else(|| anyhow!("Config not loaded"))?;
+
+    let worktrees = git.list_worktrees()?;
+    // git always lists the main worktree first
+    let linked: Vec<&Worktree> = worktrees.iter().skip(1).collect();
+    if linked.is_empty() {
+        ui::info!("No linked worktrees to repair.");
+        return Ok(());
+    }
+
+    let mut progress = ui::Progress::new(linked.len());
+    let mut notes = Vec::new();
+    for wt in &linked {
+        let item = wt
+            .branch()
+            .map_or_else(|| wt.path().display().to_string(), str::to_string);
+        progress.run_with(&item, || {
+            Ok(match repair_one(git, wt, root) {
+                Outcome::Healthy => ui::Outcome::Succeeded,
+                Outcome::Repaired => {
+                    notes.push(format!("Repaired the links of '{}'.", wt.path().display()));
+                    ui::Outcome::Succeeded
+                }
+                Outcome::Moved(to) => {
+                    notes.push(format!(
+                        "Registered '{}' at '{}'.",
+                        wt.path().display(),
+                        to.display()
+                    ));
+                    ui::Outcome::Succeeded
+                }
+                Outcome::Unrepairable(reason) => ui::Outcome::Failed(reason),
+            })
+        });
+    }
+    for note in &notes {
+        ui::info!("{}", note);
+    }
+    progress.print_summary();
+
+    let failed = progress.failed();
+    if failed > 0 {
+        bail!(
+            "{} of {} worktrees could not be repaired",
+            failed,
+            linked.len()
+        );
+    }
+    Ok(())
+}
+
+fn repair_one(git: &dyn GitBackend, worktree: &Worktree, root: &Path) -> Outcome {
+    let registered = worktree.path();
+    let target = if registered.exists() {
+        registered.clone()
+    } else if let Some(moved) = relocated(registered, root) {
+        moved
+    } else {
+        return Outcome::Unrepairable(
+            "directory not found; drop the entry with `git worktree prune`".to_string(),
+        );
+    };
+    let Some(target_str) = target.to_str() else {
+        return Outcome::Unrepairable("invalid path".to_string());
+    };
+
+    match git.repair_worktree(target_str) {
+        Err(e) => Outcome::Unrepairable(e.to_string()),
+        Ok(_) if &target != registered => Outcome::Moved(target),
+        Ok(true) => Outcome::Repaired,
+        Ok(false) => Outcome::Healthy,
+    }
+}
+
+/// The directory with the same name under `root`, when a worktree registered
+/// at `path` seems to have moved there along with the rest of the store
+fn relocated(path: &Path, root: &Path) -> Option<PathBuf> {
+    let candidate = root.join(path.file_name()?);
+    (candidate != path && candidate.join(".git").is_file()).then_some(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+    use crate::utility::Git;
+
+    #[test]
+    fn test_repair_follows_moved_store() {
+        let dir = tempfile::tempdir().unwrap();
+        // Three worktrees in `old-store`, which then moves to `new-store`
+        // except for `gone`, which is lost
+        sh(
+            dir.path(),
+            "git init -q -b main repo && git -C repo commit -q --allow-empty -m init && \
+             git -C repo worktree add -q ../old-store/aaaa -b moved && \
+             git -C repo worktree add -q ../old-store/bbbb -b gone && \
+             git -C repo worktree add -q ../here -b healthy && \
+             mv old-store new-store && rm -rf new-store/bbbb",
+        );
+        let git = Git::new().with_cwd(dir.path().join("repo"));
+        let new_store = dir.path().join("new-store");
+
+        let worktrees = git.list_worktrees().unwrap();
+        let mut outcomes: Vec<_> = worktrees
+            .iter()
+            .skip(1)
+            .map(|wt| {
+                (
+                    wt.branch().unwrap().to_string(),
+                    repair_one(&git, wt, &new_store),
+                )
+            })
+            .collect();
+        outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+        let after: Vec<PathBuf> = git
+            .list_worktrees()
+            .unwrap()
+            .iter()
+            .map(|wt| wt.path().clone())
+            .collect();
+
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0].0, "gone");
+        assert!(matches!(outcomes[0].1, Outcome::Unrepairable(_)));
+        assert_eq!(outcomes[1], ("healthy".to_string(), Outcome::Healthy));
+        assert_eq!(outcomes[2].0, "moved");
+        assert_eq!(outcomes[2].1, Outcome::Moved(new_store.join("aaaa")));
+        assert!(
+            after.iter().any(|p| p.ends_with("new-store/aaaa")),
+            "{after:?}"
+        );
+    }
+
+    #[test]
+    fn test_relocated() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("store");
+        std::fs::create_dir_all(root.join("abcd")).unwrap();
+        std::fs::write(root.join("abcd/.git"), "gitdir: /x").unwrap();
+        std::fs::create_dir_all(root.join("nogit")).unwrap();
+
+        assert_eq!(
+            relocated(Path::new("/old/store/abcd"), &root),
+            Some(root.join("abcd"))
+        );
+        assert_eq!(relocated(Path::new("/old/store/nogit"), &root), None);
+        assert_eq!(relocated(&root.join("abcd"), &root), None);
+    }
+}
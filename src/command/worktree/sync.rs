@@ -0,0 +1,246 @@
+use super::{CreateRollback, RemoveOptions, create_worktree, remove};
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{AddWorktreeOptions, GitBackend, ref_pattern_matches};
+use anyhow::{Context, Result, bail};
+use console::{Term, style};
+use dialoguer::Confirm;
+use std::path::PathBuf;
+
+/// What `gwt sync` did for one branch
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Created(PathBuf),
+    Present(PathBuf),
+    /// Removed by `--prune` because its remote branch is gone
+    Removed(PathBuf),
+    Failed(String),
+}
+
+/// Fetch `remote` and make sure every branch on it matching `pattern` (e.g.
+/// `release/*`) has a worktree, creating tracking branches as needed. With
+/// `prune`, worktrees of matching branches whose remote branch was deleted are
+/// removed after a confirmation.
+pub fn sync(
+    git: &dyn GitBackend,
+    config: &Config,
+    pattern: &str,
+    remote: &str,
+    prune: bool,
+    skip_confirmation: bool,
+) -> Result<()> {
+    config.ensure_worktree_root()?;
+
+    git.fetch_remote(remote)
+        .with_context(|| format!("Failed to fetch '{}'", remote))?;
+    let remote_branches = git.remote_branches_matching(remote, pattern)?;
+
+    let mut results: Vec<(String, Outcome)> = remote_branches
+        .iter()
+        .map(|remote_branch| {
+            let branch = &remote_branch[remote.len() + 1..];
+            let outcome = materialize(git, config, branch, remote_branch)
+                .unwrap_or_else(|e| Outcome::Failed(format!("{:#}", e)));
+            (branch.to_string(), outcome)
+        })
+        .collect();
+
+    if prune {
+        results.extend(prune_gone(git, config, pattern, remote, skip_confirmation)?);
+    }
+
+    if results.is_empty() {
+        ui::info!("No branches on '{}' match '{}'.", remote, pattern);
+        return Ok(());
+    }
+    for line in render(&results) {
+        println!("{}", line);
+    }
+
+    let failed = results
+        .iter()
+        .filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        bail!(
+            "{} of {} branches could not be synced",
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+/// Create the worktree of `branch`, and the branch itself tracking
+/// `remote_branch`, unless they already exist
+fn materialize(
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: &str,
+    remote_branch: &str,
+) -> Result<Outcome> {
+    if let Some(wt) = git.find_worktree_by_branch(branch)? {
+        return Ok(Outcome::Present(wt.path().clone()));
+    }
+
+    let mut rollback = CreateRollback::new(git);
+    if !git.branch_exists(branch)? {
+        git.create_branch_from_remote(branch, remote_branch)?;
+        rollback.branch_created(branch);
+    }
+    let path = create_worktree(
+        git,
+        config,
+        branch,
+        false,
+        &AddWorktreeOptions::default(),
+        &mut rollback,
+    )?;
+    rollback.commit();
+    Ok(Outcome::Created(path))
+}
+
+/// Remove the worktrees of branches matching `pattern` whose upstream on
+/// `remote` is gone. The main worktree is never removed.
+fn prune_gone(
+    git: &dyn GitBackend,
+    config: &Config,
+    pattern: &str,
+    remote: &str,
+    skip_confirmation: bool,
+) -> Result<Vec<(String, Outcome)>> {
+    let tracking = git.branch_tracking()?;
+    let worktrees = git.list_worktrees()?;
+    let gone: Vec<(&str, PathBuf)> = worktrees
+        .iter()
+        .filter(|wt| !wt.is_main())
+        .filter_map(|wt| Some((wt.branch()?, wt.path().clone())))
+        .filter(|(branch, _)| {
+            tracking.get(*branch).is_some_and(|t| {
+                t.gone
+                    && t.upstream.as_deref().is_some_and(|upstream| {
+                        upstream
+                            .strip_prefix(remote)
+                            .and_then(|rest| rest.strip_prefix('/'))
+                            .is_some_and(|name| ref_pattern_matches(pattern, name))
+                    })
+            })
+        })
+        .collect();
+    if gone.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !skip_confirmation {
+        eprintln!("Worktrees whose branch was deleted on '{}':", remote);
+        for (branch, path) in &gone {
+            eprintln!("  {}  {}", branch, style(path.display()).cyan());
+        }
+        let confirmed = Confirm::new()
+            .with_prompt(format!("Remove {} worktrees?", gone.len()))
+            .default(false)
+            .interact_on(&Term::stderr())
+            .map_err(ui::prompt_error)
+            .context("Failed to get confirmation")?;
+        if !confirmed {
+            ui::info!("Pruning cancelled.");
+            return Ok(Vec::new());
+        }
+    }
+
+    Ok(gone
+        .into_iter()
+        .map(|(branch, path)| {
+            let options = RemoveOptions {
+                skip_confirmation: true,
+                ..Default::default()
+            };
+            let outcome = match remove(git, config, branch, &options) {
+                Ok(cd) => {
+                    if let Some(main) = cd {
+                        ui::print_cd(&main);
+                    }
+                    Outcome::Removed(path)
+                }
+                Err(e) => Outcome::Failed(format!("{:#}", e)),
+            };
+            (branch.to_string(), outcome)
+        })
+        .collect())
+}
+
+fn render(results: &[(String, Outcome)]) -> Vec<String> {
+    let branch_width = results.iter().map(|(b, _)| b.len()).max().unwrap_or(0);
+    results
+        .iter()
+        .map(|(branch, outcome)| {
+            let (label, detail) = match outcome {
+                Outcome::Created(path) => (style("created").green(), path.display().to_string()),
+                Outcome::Present(path) => (style("present").dim(), path.display().to_string()),
+                Outcome::Removed(path) => (style("removed").yellow(), path.display().to_string()),
+                Outcome::Failed(reason) => (style("failed").red(), reason.clone()),
+            };
+            format!("  {:<7}  {:<branch_width$}  {}", label, branch, detail)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::utility::FakeGit;
+
+    #[test]
+    fn test_sync_creates_missing_worktrees() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/release/1.0")
+            .with_remote_branch("origin/release/2.0")
+            .with_remote_branch("origin/feature")
+            .with_remote_branch("upstream/release/3.0")
+            .with_worktree("/store/r1", "release/1.0");
+
+        sync(&git, &config, "release/*", "origin", false, true).unwrap();
+
+        let calls = git.calls();
+        assert_eq!(calls[0], "fetch_remote origin");
+        assert_eq!(
+            calls[1],
+            "create_branch_from_remote release/2.0 origin/release/2.0"
+        );
+        assert!(calls[2].starts_with("add_worktree "), "{calls:?}");
+        assert!(calls[2].ends_with(" release/2.0"), "{calls:?}");
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[test]
+    fn test_sync_reports_failures_and_rolls_back() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_remote_branch("origin/release/1.0")
+            .failing("add_worktree");
+
+        let err = sync(&git, &config, "release/*", "origin", false, true).unwrap_err();
+
+        assert_eq!(err.to_string(), "1 of 1 branches could not be synced");
+        assert_eq!(git.calls().last().unwrap(), "delete_branch release/1.0 -D");
+    }
+
+    #[test]
+    fn test_sync_prune_removes_gone_matching_worktrees() {
+        let (_dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main")
+            .with_worktree("/store/old", "release/0.9")
+            .with_worktree("/store/feature", "feature")
+            .with_upstream("release/0.9", "origin/release/0.9", true)
+            .with_upstream("feature", "origin/feature", true);
+
+        sync(&git, &config, "release/*", "origin", true, true).unwrap();
+
+        assert_eq!(
+            git.calls(),
+            ["fetch_remote origin", "remove_worktree /store/old"]
+        );
+    }
+}
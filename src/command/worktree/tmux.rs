@@ -0,0 +1,150 @@
+use crate::config::Config;
+use crate::utility::{GitBackend, Tmux, TmuxMode};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long each tmux command may run unless `tmux.timeout_secs` says otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The tmux server of the current session, with the timeout of the config
+pub(super) fn tmux(config: &Config) -> Tmux {
+    let timeout = config.data().map_or(Some(DEFAULT_TIMEOUT), |d| {
+        d.tmux.timeout(Some(DEFAULT_TIMEOUT))
+    });
+    Tmux::new().with_timeout(timeout)
+}
+
+/// Open the worktree of `branch` at `path` in a tmux window, or a session
+/// with `tmux_mode = "session"`, reusing the one opened for it before
+pub(super) fn open_in_tmux(
+    tmux: &Tmux,
+    git: &dyn GitBackend,
+    config: &Config,
+    branch: &str,
+    path: &Path,
+) -> Result<()> {
+    let mode = config.data().map(|d| d.tmux_mode).unwrap_or_default();
+    match mode {
+        TmuxMode::Window => {
+            let name = sanitize(branch);
+            let existing = tmux
+                .list_windows()?
+                .into_iter()
+                .find(|w| w.name == name && w.path.starts_with(path));
+            match existing {
+                Some(window) => tmux.select_window(&window.id),
+                None => tmux.new_window(&name, path),
+            }
+        }
+        TmuxMode::Session => {
+            let main = git.get_main_worktree()?;
+            let repo = main
+                .path()
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let name = sanitize(&format!("{}/{}", repo, branch));
+            if !tmux.has_session(&name) {
+                tmux.new_session(&name, path)?;
+            }
+            tmux.switch_client(&name)
+        }
+    }
+    .context("Failed to open the worktree in tmux")
+}
+
+/// A tmux window or session name for `name`. tmux treats `.` and `:` in
+/// targets as separators, so they can't appear in names.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '.' | ':' => '-',
+            c if c.is_whitespace() => '-',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::command::worktree::test_utils::create_mock_git_script;
+    use crate::command::worktree::test_utils::temp_config;
+    use crate::config::Config;
+    use crate::utility::FakeGit;
+    use std::path::PathBuf;
+
+    /// A fake tmux that logs its arguments and knows one window, `feature`
+    #[cfg(unix)]
+    fn mock_tmux(dir: &Path, window_path: &Path) -> (Tmux, PathBuf, tempfile::TempDir) {
+        let log = dir.join("tmux.log");
+        let (script, script_dir) = create_mock_git_script(&format!(
+            "#!/bin/sh\necho \"$*\" >> '{}'\n\
+             case \"$1\" in\n\
+               list-windows) printf '@1\\tzsh\\t/home\\n@2\\tfeature\\t{}/src\\n' ;;\n\
+               has-session) exit 1 ;;\n\
+             esac\n",
+            log.display(),
+            window_path.display()
+        ));
+        (
+            Tmux::with_program(script.to_str().unwrap()),
+            log,
+            script_dir,
+        )
+    }
+
+    fn set_mode(config: Config, mode: TmuxMode) -> Config {
+        match config {
+            Config::Loaded(mut data, path) => {
+                data.tmux_mode = mode;
+                Config::Loaded(data, path)
+            }
+            other => other,
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("release/1.2"), "release/1-2");
+        assert_eq!(sanitize("my.repo/fix:it now"), "my-repo/fix-it-now");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_window_mode_reuses_window_of_worktree() {
+        let (dir, config) = temp_config();
+        let git = FakeGit::new("/path/to/repo", "main");
+        let (tmux, log, _script_dir) = mock_tmux(dir.path(), Path::new("/store/a"));
+
+        open_in_tmux(&tmux, &git, &config, "feature", Path::new("/store/a")).unwrap();
+        open_in_tmux(&tmux, &git, &config, "fix.1", Path::new("/store/b")).unwrap();
+
+        let calls = std::fs::read_to_string(log).unwrap();
+        let calls: Vec<&str> = calls.lines().filter(|l| !l.starts_with("list")).collect();
+        assert_eq!(
+            calls,
+            ["select-window -t @2", "new-window -c /store/b -n fix-1"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_session_mode_creates_and_switches() {
+        let (dir, config) = temp_config();
+        let config = set_mode(config, TmuxMode::Session);
+        let git = FakeGit::new("/path/to/my.repo", "main");
+        let (tmux, log, _script_dir) = mock_tmux(dir.path(), Path::new("/store/a"));
+
+        open_in_tmux(&tmux, &git, &config, "feature", Path::new("/store/a")).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(log).unwrap(),
+            "has-session -t =my-repo/feature\n\
+             new-session -d -s my-repo/feature -c /store/a\n\
+             switch-client -t =my-repo/feature\n"
+        );
+    }
+}
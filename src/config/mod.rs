@@ -1,25 +1,182 @@
 use crate::command;
+use crate::error::GwtError;
+use crate::ui::{self, Theme};
+use crate::utility::{MergedDetection, TmuxMode, canonical};
 use anyhow::{Result, anyhow, bail};
 use console::Style;
-use dialoguer::{Confirm, Input, theme::ColorfulTheme};
+use dialoguer::{Confirm, Input};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 const DEFAULT_WORKTREE_ROOT: &str = ".gwt_store";
 const CONFIG_DIR_NAME: &str = ".gwt";
 const CONFIG_FILE_NAME: &str = "config.toml";
+const HISTORY_FILE_NAME: &str = "history";
+const CACHE_DIR_NAME: &str = "cache";
 
+// Loaded once per run, so the size of `ConfigData` doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum Config {
     Omit,
     Loaded(ConfigData, PathBuf),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct ConfigData {
     /// Root directory where all git worktrees will be stored
     pub worktree_root: PathBuf,
+    /// Accept a worktree root gwt would refuse otherwise: `/`, the home
+    /// directory, a directory holding the config, or one inside the repository
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_unsafe_root: bool,
+    /// Colors and glyphs of prompts and listings
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub theme: Theme,
+    /// How `gwt rm -b` decides whether a branch was merged
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub merged_detection: MergedDetection,
+    /// Editor used by `gwt open`; falls back to `$VISUAL`, then `$EDITOR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor_command: Option<String>,
+    /// Make `gwt clone` create a bare repository with a worktree for the default branch
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clone_bare: bool,
+    /// Ref fetched by `gwt pr <number>`, with `{number}` substituted;
+    /// defaults to GitHub's `pull/{number}/head`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_refspec: Option<String>,
+    /// Seconds after which a git command gwt runs in the background is killed;
+    /// unset waits forever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_timeout_secs: Option<u64>,
+    /// Make `gwt sw` fetch before creating a worktree, as if `--fetch` was given
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fetch_on_create: bool,
+    /// Run `direnv allow` in new worktrees that have an `.envrc`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub direnv_allow: bool,
+    /// Whether `gwt sw --tmux` opens a window or a session
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub tmux_mode: TmuxMode,
+    /// Template of `gwt prompt`, with `{branch}`, `{repo}` and `{hash}`
+    /// substituted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_format: Option<String>,
+    /// Seconds `gwt ls --raw --cached` serves the worktree list from the
+    /// cache; `0` turns the cache off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_cache_secs: Option<u64>,
+    /// Where new worktrees go under the worktree root, with `{repo}`,
+    /// `{branch}`, `{branch_slug}` and `{hash}` substituted; unset puts each in
+    /// a directory named by the hash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    /// Make `/` in `{branch_slug}` nest directories instead of becoming `-`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nest_slashes: bool,
+    /// Put worktrees under `<owner>/<repo>` from the origin URL, so forks of
+    /// a project are kept apart
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub group_by_remote: bool,
+    /// Shell command run in the main worktree after `gwt rm` removes a
+    /// worktree, with `GWT_BRANCH`, `GWT_REMOVED_PATH` and `GWT_REPO` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_remove_hook: Option<String>,
+    /// Branch that `gwt sw --main` switches to and merges are checked
+    /// against; unset detects it from `origin/HEAD`, `init.defaultBranch` or
+    /// the main worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_base_branch: Option<String>,
+    /// Put in front of the branch `gwt sw -b` creates when its name has no
+    /// `/`, e.g. `users/me/`; `git config gwt.branchPrefix` in a repository
+    /// takes precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_prefix: Option<String>,
+    /// What `gwt sw` does when leaving a dirty worktree of the worktree root
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub pre_switch_check: PreSwitchCheck,
+    /// How `gwt sw` matches the branch name it's given against local branches
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub branch_match: BranchMatch,
+    /// Settings of `gwt ls`, the `[list]` table
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub list: ListConfig,
+    /// How long hooks may run, the `[hooks]` table
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub hooks: TimeoutConfig,
+    /// How long `direnv allow` may run, the `[direnv]` table
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub direnv: TimeoutConfig,
+    /// How long each tmux command of `gwt sw --tmux` may run, the `[tmux]` table
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub tmux: TimeoutConfig,
+    /// Commands of your own, the `[aliases]` table: each name expands to the
+    /// arguments it maps to, e.g. `co = ["sw", "-b"]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ListConfig {
+    /// Widest branch column before names are truncated; `0` never truncates,
+    /// as if `--full` was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_branch_width: Option<usize>,
+}
+
+/// A table of the config limiting how long one kind of external command runs
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TimeoutConfig {
+    /// Seconds after which the command, and everything it started, is killed;
+    /// `0` waits forever
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl TimeoutConfig {
+    /// The configured timeout, or `default` when unset
+    pub fn timeout(&self, default: Option<Duration>) -> Option<Duration> {
+        match self.timeout_secs {
+            None => default,
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+        }
+    }
+}
+
+/// What `gwt sw` does when the worktree it leaves has uncommitted changes
+/// (`pre_switch_check` in the config)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreSwitchCheck {
+    /// Refuse to switch unless `--force` is given
+    BlockDirty,
+    /// Print a warning and switch anyway
+    WarnDirty,
+    /// Don't check
+    #[default]
+    Off,
+}
+
+/// How `gwt sw` finds the local branch it was given (`branch_match` in the
+/// config)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchMatch {
+    /// The name must match exactly
+    #[default]
+    Exact,
+    /// A name that doesn't exist as typed may match one branch ignoring case,
+    /// as on case-insensitive filesystems
+    Icase,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
 }
 
 /// Initialize config - load from file or run interactive setup
@@ -43,8 +200,21 @@ pub fn setup() -> Result<()> {
     let d = prompt_for_config_data(&home)?;
 
     let config_path = config_file_path(&home);
+    d.check_worktree_root(&config_path, &home)?;
+    let previous = read(&config_path).ok();
     d.save(&config_path)?;
-    eprintln!("Configuration saved to {}", config_path.display());
+    ui::info!("Configuration saved to {}", config_path.display());
+
+    if let Some(old_root) = previous
+        .map(|p| p.worktree_root)
+        .filter(|old| *old != d.worktree_root && has_worktrees(old))
+    {
+        ui::warn!(
+            "Worktrees under the previous root '{}' stay there. Run `gwt migrate --from {}` in each repository to move them.",
+            old_root.display(),
+            old_root.display()
+        );
+    }
 
     let config = Config::Loaded(d, config_path);
     config.ensure_worktree_root()?;
@@ -52,32 +222,54 @@ pub fn setup() -> Result<()> {
     Ok(())
 }
 
+/// Whether `root` holds any linked worktree, i.e. a directory with a `.git` file
+fn has_worktrees(root: &Path) -> bool {
+    fs::read_dir(root).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.path().join(".git").is_file())
+    })
+}
+
 fn load_with_home(cmd: &command::Commands, home: &Path) -> Result<Config> {
-    if let command::Commands::Init { .. } = cmd {
+    if !cmd.needs_config() {
         return Ok(Config::Omit);
     }
 
-    if let command::Commands::Config(command::config::ConfigCommands::Setup) = cmd {
-        return Ok(Config::Omit);
+    // Prompt runs before every shell prompt: without a readable config it
+    // quietly prints nothing
+    if let command::Commands::Prompt { .. } = cmd {
+        let config_path = config_file_path(home);
+        return Ok(match read(&config_path) {
+            Ok(data) => Config::Loaded(data, config_path),
+            Err(_) => Config::Omit,
+        });
     }
 
     let config_path = config_file_path(home);
     if config_path.exists() {
-        let content = fs::read_to_string(&config_path)?;
-        let result = toml::from_str::<ConfigData>(&content)?;
+        let result = read(&config_path)?;
+        result.check_worktree_root(&config_path, home)?;
         return Ok(Config::Loaded(result, config_path));
     }
 
     eprintln!("gwt configuration not found at {}", config_path.display());
 
-    let should_create = Confirm::with_theme(&prompt_theme())
+    let should_create = Confirm::with_theme(&ui::theme())
         .with_prompt("Would you like to create a configuration file now?")
         .default(true)
         .interact()
-        .map_err(|e| anyhow!("initialization cancelled: {e}"))?;
+        // Without a terminal to ask on, the config is simply missing
+        .map_err(|e| {
+            if ui::interrupted(&e) {
+                return ui::prompt_error(e);
+            }
+            anyhow::Error::from(GwtError::ConfigMissing)
+                .context(format!("initialization cancelled: {e}"))
+        })?;
 
     if !should_create {
-        bail!("configuration file must be created first.");
+        return Err(GwtError::ConfigMissing.into());
     }
 
     eprintln!(
@@ -91,8 +283,9 @@ fn load_with_home(cmd: &command::Commands, home: &Path) -> Result<Config> {
 
     let d = prompt_for_config_data(home)?;
 
+    d.check_worktree_root(&config_path, home)?;
     d.save(&config_path)?;
-    eprintln!("Configuration saved to {}", config_path.display());
+    ui::info!("Configuration saved to {}", config_path.display());
 
     let config = Config::Loaded(d, config_path);
     config.ensure_worktree_root()?;
@@ -103,18 +296,25 @@ fn load_with_home(cmd: &command::Commands, home: &Path) -> Result<Config> {
 fn prompt_for_config_data(home: &Path) -> Result<ConfigData> {
     eprintln!("Please provide the following configuration:");
 
-    let worktree_root: String = Input::with_theme(&prompt_theme())
+    let worktree_root: String = Input::with_theme(&ui::theme())
         .with_prompt("Worktree root directory")
         .default(default_store_path(home).to_string_lossy().to_string())
         .interact_text()
-        .map_err(|e| anyhow!("initialization cancelled: {e}"))?;
+        .map_err(setup_error)?;
 
     Ok(ConfigData {
         worktree_root: PathBuf::from(worktree_root),
+        ..Default::default()
     })
 }
 
 impl Config {
+    /// The configuration in the file at `path`, read without any prompting
+    pub fn from_file(path: PathBuf) -> Result<Self> {
+        let data = read(&path)?;
+        Ok(Self::Loaded(data, path))
+    }
+
     pub fn config_path(&self) -> Option<&str> {
         match self {
             Self::Omit => None,
@@ -122,6 +322,22 @@ impl Config {
         }
     }
 
+    /// Worktree access history used by `gwt recent`, kept next to the config file
+    pub fn history_path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Omit => None,
+            Self::Loaded(_, path) => Some(path.with_file_name(HISTORY_FILE_NAME)),
+        }
+    }
+
+    /// Directory of the worktree list caches, kept next to the config file
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        match self {
+            Self::Omit => None,
+            Self::Loaded(_, path) => Some(path.with_file_name(CACHE_DIR_NAME)),
+        }
+    }
+
     pub fn data(&self) -> Option<&ConfigData> {
         match self {
             Self::Omit => None,
@@ -129,36 +345,120 @@ impl Config {
         }
     }
 
+    /// Make sure the worktree root is a writable directory, offering to
+    /// create it when it doesn't exist
     pub fn ensure_worktree_root(&self) -> Result<()> {
         let d = self
             .data()
             .ok_or_else(|| anyhow!("config data not loaded"))?;
-
-        if d.worktree_root.exists() {
-            return Ok(());
+        let root = &d.worktree_root;
+
+        // A dangling symlink is there, but `exists` follows it
+        if root.is_symlink() && !root.exists() {
+            let target = fs::read_link(root)?;
+            bail!(
+                "Worktree root '{}' is a symlink to '{}', which doesn't exist.",
+                root.display(),
+                target.display()
+            );
+        }
+        if root.exists() {
+            if !root.is_dir() {
+                bail!(
+                    "Worktree root '{}' is a file, not a directory. Move it away or choose another `worktree_root`.",
+                    root.display()
+                );
+            }
+            return check_writable(root);
         }
 
-        let should_create = Confirm::with_theme(&prompt_theme())
+        let should_create = Confirm::with_theme(&ui::theme())
             .with_prompt(format!(
                 "Worktree root '{}' does not exist. Create it?",
                 d.worktree_root.display()
             ))
             .default(true)
             .interact()
-            .map_err(|e| anyhow!("initialization cancelled: {e}"))?;
+            .map_err(setup_error)?;
 
         if !should_create {
-            bail!("Worktree root must be created before proceed.")
+            return Err(GwtError::WorktreeRootMissing.into());
         }
 
         fs::create_dir_all(&d.worktree_root)?;
-        eprintln!("Created directory: {}", d.worktree_root.display());
+        ui::info!("Created directory: {}", d.worktree_root.display());
 
         Ok(())
     }
 }
 
+/// Permission bits don't tell the whole story (ACLs, read-only mounts), so
+/// try writing a file
+fn check_writable(root: &Path) -> Result<()> {
+    let probe = root.join(format!(".gwt-write-check-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => bail!("Worktree root '{}' is not writable: {}", root.display(), e),
+    }
+}
+
+/// Error of a setup prompt. A prompt cut short by Ctrl-C leaves what was
+/// already saved in place, so the next run picks up from there: a saved config
+/// with a missing worktree root asks to create the root again.
+fn setup_error(error: dialoguer::Error) -> anyhow::Error {
+    if ui::interrupted(&error) {
+        return ui::prompt_error(error);
+    }
+    anyhow!("initialization cancelled: {error}")
+}
+
 impl ConfigData {
+    /// Refuse a worktree root that bulk operations could do real damage in:
+    /// `/`, the home directory itself, or a directory that holds the config
+    /// directory, unless `allow_unsafe_root` is set
+    pub fn check_worktree_root(&self, config_path: &Path, home: &Path) -> Result<()> {
+        let root = canonical(&self.worktree_root);
+        let reason = if root.has_root() && root.parent().is_none() {
+            "is the filesystem root"
+        } else if root == canonical(home) {
+            "is your home directory"
+        } else if config_path
+            .parent()
+            .is_some_and(|dir| canonical(dir).starts_with(&root))
+        {
+            "holds the gwt config directory"
+        } else {
+            return Ok(());
+        };
+        self.unsafe_root(reason.to_string())
+    }
+
+    /// Refuse a worktree root inside the repository at `main_worktree`,
+    /// unless `allow_unsafe_root` is set
+    pub fn check_root_outside(&self, main_worktree: &Path) -> Result<()> {
+        if !canonical(&self.worktree_root).starts_with(canonical(main_worktree)) {
+            return Ok(());
+        }
+        self.unsafe_root(format!(
+            "is inside the repository at '{}'",
+            main_worktree.display()
+        ))
+    }
+
+    fn unsafe_root(&self, reason: String) -> Result<()> {
+        if self.allow_unsafe_root {
+            return Ok(());
+        }
+        Err(GwtError::UnsafeWorktreeRoot {
+            root: self.worktree_root.clone(),
+            reason,
+        }
+        .into())
+    }
+
     /// Save config to the provided path
     /// Creates the config directory if it doesn't exist
     pub fn save(&self, path: &Path) -> Result<()> {
@@ -174,6 +474,17 @@ impl ConfigData {
     }
 }
 
+/// Path of the config file for the current user, whether or not it exists
+pub fn file_path() -> Result<PathBuf> {
+    Ok(config_file_path(&home_dir()?))
+}
+
+/// Read and parse the config file at `path` without any prompting
+pub fn read(path: &Path) -> Result<ConfigData> {
+    let content = fs::read_to_string(path)?;
+    Ok(toml::from_str::<ConfigData>(&content)?)
+}
+
 /// Returns the path to the config file (~/.gwt/config.toml)
 fn config_file_path(home: &Path) -> PathBuf {
     home.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME)
@@ -189,16 +500,6 @@ fn home_dir() -> Result<PathBuf> {
     })
 }
 
-fn prompt_theme() -> ColorfulTheme {
-    ColorfulTheme {
-        prompt_style: Style::new().cyan().bright(),
-        prompt_prefix: Style::new().cyan().bright().apply_to("?".to_string()),
-        success_prefix: Style::new().green().bright().apply_to("✔".to_string()),
-        values_style: Style::new().cyan().bright(),
-        ..ColorfulTheme::default()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +512,7 @@ mod tests {
 
         let data = ConfigData {
             worktree_root: PathBuf::from("/tmp/gwt_test"),
+            ..Default::default()
         };
 
         let config_path = config_file_path(&home);
@@ -227,6 +529,19 @@ mod tests {
             create: false,
             main: false,
             remote: None,
+            force: false,
+            pick: false,
+            tmux: false,
+            no_checkout: false,
+            track: None,
+            fetch: false,
+            pull: false,
+            strict: false,
+            take_changes: false,
+            no_cd: false,
+            json: false,
+            eval: None,
+            no_prefix: false,
         };
         let loaded = load_with_home(&cmd, &home).unwrap();
         match loaded {
@@ -237,10 +552,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_load_resumes_a_setup_cut_short() {
+        // Setup saved the config, then was interrupted before creating the root
+        let home = tempdir().unwrap();
+        let data = ConfigData {
+            worktree_root: home.path().join("never-created"),
+            ..Default::default()
+        };
+        data.save(&config_file_path(home.path())).unwrap();
+
+        // Loading doesn't start over; the root is asked about when it's needed
+        let config = load_with_home(
+            &crate::command::Commands::Ls {
+                full: false,
+                raw: false,
+                cached: false,
+                no_main: false,
+                with_paths: false,
+                ids: false,
+                status: false,
+                age: false,
+                orphans: false,
+                json: false,
+                all: false,
+                group_by: None,
+            },
+            home.path(),
+        )
+        .unwrap();
+        assert_eq!(config.data(), Some(&data));
+    }
+
     #[test]
     fn test_config_omit_for_init() {
         let cmd = crate::command::Commands::Init {
-            shell: "bash".to_string(),
+            shell: Some("bash".to_string()),
+            binary_name: None,
+            function_name: "gwt".to_string(),
+            no_cd_on_rm: false,
+            ls_after_switch: false,
         };
         let home = PathBuf::from("/tmp");
         let config = load_with_home(&cmd, &home).unwrap();
@@ -249,6 +600,15 @@ mod tests {
         assert!(config.data().is_none());
     }
 
+    #[test]
+    fn test_config_omit_for_home() {
+        // A home with no config would otherwise start the interactive setup
+        let home = tempdir().unwrap();
+        let config =
+            load_with_home(&crate::command::Commands::Home { eval: None }, home.path()).unwrap();
+        assert_eq!(config, Config::Omit);
+    }
+
     #[test]
     fn test_ensure_worktree_root_exists() {
         let dir = tempdir().unwrap();
@@ -258,10 +618,151 @@ mod tests {
         let config = Config::Loaded(
             ConfigData {
                 worktree_root: root,
+                ..Default::default()
             },
             PathBuf::from("config.toml"),
         );
 
         assert!(config.ensure_worktree_root().is_ok());
     }
+
+    #[test]
+    fn test_ensure_worktree_root_rejects_files_and_dangling_links() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        let config = |root: &Path| {
+            Config::Loaded(
+                ConfigData {
+                    worktree_root: root.to_path_buf(),
+                    ..Default::default()
+                },
+                PathBuf::from("config.toml"),
+            )
+        };
+
+        let err = config(&file).ensure_worktree_root().unwrap_err();
+        assert!(err.to_string().contains("is a file"), "{err}");
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(dir.path().join("gone"), &link).unwrap();
+            let err = config(&link).ensure_worktree_root().unwrap_err();
+            assert!(err.to_string().contains("which doesn't exist"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_check_worktree_root() {
+        let home = tempdir().unwrap();
+        let config_path = config_file_path(home.path());
+        let check = |root: &Path, allow: bool| {
+            ConfigData {
+                worktree_root: root.to_path_buf(),
+                allow_unsafe_root: allow,
+                ..Default::default()
+            }
+            .check_worktree_root(&config_path, home.path())
+        };
+
+        assert!(check(&default_store_path(home.path()), false).is_ok());
+        assert!(check(&home.path().join("code/worktrees"), false).is_ok());
+        for (root, reason) in [
+            (Path::new("/"), "is the filesystem root"),
+            (home.path(), "is your home directory"),
+            (
+                home.path().parent().unwrap(),
+                "holds the gwt config directory",
+            ),
+            (
+                &home.path().join(CONFIG_DIR_NAME),
+                "holds the gwt config directory",
+            ),
+        ] {
+            let err = check(root, false).unwrap_err();
+            assert!(matches!(
+                GwtError::find(&err),
+                Some(GwtError::UnsafeWorktreeRoot { .. })
+            ));
+            assert!(err.to_string().contains(reason), "{err}");
+            assert!(check(root, true).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_check_root_outside() {
+        let data = ConfigData {
+            worktree_root: PathBuf::from("/src/api/.worktrees"),
+            ..Default::default()
+        };
+        let err = data.check_root_outside(Path::new("/src/api")).unwrap_err();
+        assert!(err.to_string().contains("inside the repository"), "{err}");
+        assert!(data.check_root_outside(Path::new("/src/web")).is_ok());
+        // A sibling that merely shares the prefix isn't inside
+        assert!(data.check_root_outside(Path::new("/src/ap")).is_ok());
+    }
+
+    #[test]
+    fn test_list_table_round_trip() {
+        let data: ConfigData =
+            toml::from_str("worktree_root = \"/tmp\"\n\n[list]\nmax_branch_width = 0\n").unwrap();
+        assert_eq!(data.list.max_branch_width, Some(0));
+        assert!(toml::to_string(&data).unwrap().contains("[list]"));
+
+        // The table is left out until something is set in it
+        let data: ConfigData = toml::from_str("worktree_root = \"/tmp\"\n").unwrap();
+        assert!(!toml::to_string(&data).unwrap().contains("[list]"));
+    }
+
+    #[test]
+    fn test_timeout_tables() {
+        let data: ConfigData = toml::from_str(
+            "worktree_root = \"/tmp\"\n\n[hooks]\ntimeout_secs = 5\n\n[tmux]\ntimeout_secs = 0\n",
+        )
+        .unwrap();
+        let default = Some(Duration::from_secs(30));
+        assert_eq!(data.hooks.timeout(default), Some(Duration::from_secs(5)));
+        assert_eq!(data.tmux.timeout(default), None);
+        assert_eq!(data.direnv.timeout(default), default);
+        let written = toml::to_string(&data).unwrap();
+        assert!(written.contains("[hooks]") && !written.contains("[direnv]"));
+    }
+
+    #[test]
+    fn test_windows_paths_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let data = ConfigData {
+            worktree_root: PathBuf::from(r"C:\Users\me\.gwt_store"),
+            ..Default::default()
+        };
+        data.save(&path).unwrap();
+        assert_eq!(read(&path).unwrap().worktree_root, data.worktree_root);
+
+        // Hand-written configs quote backslashes either way
+        for line in [
+            r"worktree_root = 'C:\Users\me\.gwt_store'",
+            r#"worktree_root = "C:\\Users\\me\\.gwt_store""#,
+        ] {
+            let parsed: ConfigData = toml::from_str(line).unwrap();
+            assert_eq!(parsed.worktree_root, data.worktree_root, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_has_worktrees() {
+        let dir = tempdir().unwrap();
+        assert!(!has_worktrees(dir.path()));
+        assert!(!has_worktrees(&dir.path().join("missing")));
+
+        fs::create_dir_all(dir.path().join("abcd")).unwrap();
+        assert!(!has_worktrees(dir.path()));
+        fs::write(
+            dir.path().join("abcd/.git"),
+            "gitdir: /repo/.git/worktrees/abcd",
+        )
+        .unwrap();
+        assert!(has_worktrees(dir.path()));
+    }
 }
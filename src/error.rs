@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+
+/// Failures scripts may want to tell apart. `main` exits with `exit_code()`
+/// for them; everything else exits with 1.
+///
+/// Variants usually travel inside an `anyhow::Error`, possibly under added
+/// context, so look for them with `GwtError::find`.
+#[derive(Debug, thiserror::Error)]
+pub enum GwtError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("Invalid branch name '{name}': {reason}.")]
+    InvalidBranchName { name: String, reason: &'static str },
+    #[error("not inside a git repository (cwd: {})", .cwd.display())]
+    NotARepository { cwd: PathBuf },
+    #[error("Branch '{0}' doesn't exist.")]
+    BranchNotFound(String),
+    #[error("Branch '{0}' not found locally or in any remote.")]
+    BranchNotFoundAnywhere(String),
+    #[error("Remote branch '{0}' does not exist.")]
+    RemoteBranchNotFound(String),
+    #[error("No worktree found for branch '{0}'")]
+    WorktreeNotFound(String),
+    #[error(
+        "Worktree at '{0}' contains modified or untracked files. Commit or stash them, or remove it with `git worktree remove --force`."
+    )]
+    WorktreeDirty(PathBuf),
+    #[error(
+        "Worktree at '{path}' has {files} uncommitted {}. Commit or stash them before switching away, or pass --force.",
+        if *.files == 1 { "file" } else { "files" }
+    )]
+    LeavingDirtyWorktree { path: PathBuf, files: usize },
+    #[error("Worktree at '{path}' is locked{}. Use --force to remove it anyway.", lock_suffix(.reason))]
+    WorktreeLocked { path: PathBuf, reason: String },
+    #[error("configuration file must be created first.")]
+    ConfigMissing,
+    #[error("Worktree root must be created before proceed.")]
+    WorktreeRootMissing,
+    #[error(
+        "Refusing to use '{}' as the worktree root: it {reason}. Choose another `worktree_root`, or set `allow_unsafe_root = true` in the config to use it anyway.",
+        .root.display()
+    )]
+    UnsafeWorktreeRoot { root: PathBuf, reason: String },
+    #[error("Invalid {setting} '{value}': {reason}.")]
+    InvalidSetting {
+        setting: String,
+        value: String,
+        reason: String,
+    },
+    #[error("git error: {args} failed with {status}{}", detail(.stderr))]
+    GitFailure {
+        args: String,
+        status: String,
+        stderr: String,
+    },
+    #[error("git error: {args} timed out after {secs}s")]
+    GitTimedOut { args: String, secs: u64 },
+    #[error(
+        "Branch '{branch}' is not merged into '{base}' and was kept. Delete it with `git branch -D {branch}` if you don't need it."
+    )]
+    BranchNotMerged { branch: String, base: String },
+    #[error("Worktree at '{0}' couldn't be fast-forwarded to its upstream.")]
+    NotFastForwarded(PathBuf),
+    #[error("git executable '{0}' not found; install git or set GWT_GIT")]
+    GitNotFound(String),
+    #[error("Cancelled.")]
+    Cancelled,
+}
+
+impl GwtError {
+    /// A git command run in `cwd` exited unsuccessfully. Running outside a
+    /// repository is reported as such, so scripts can check for it without
+    /// parsing stderr.
+    pub fn git_failure(cwd: &Path, args: String, status: String, stderr: String) -> Self {
+        if stderr.contains("not a git repository") {
+            Self::NotARepository {
+                cwd: cwd.to_path_buf(),
+            }
+        } else {
+            Self::GitFailure {
+                args,
+                status,
+                stderr,
+            }
+        }
+    }
+
+    /// Documented in the README's "Exit codes" section
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) | Self::InvalidBranchName { .. } => 2,
+            Self::NotARepository { .. } => 3,
+            Self::BranchNotFound(_)
+            | Self::BranchNotFoundAnywhere(_)
+            | Self::RemoteBranchNotFound(_)
+            | Self::WorktreeNotFound(_) => 4,
+            Self::GitFailure { .. } | Self::GitTimedOut { .. } => 5,
+            Self::WorktreeDirty(_)
+            | Self::LeavingDirtyWorktree { .. }
+            | Self::WorktreeLocked { .. } => 6,
+            Self::ConfigMissing
+            | Self::WorktreeRootMissing
+            | Self::UnsafeWorktreeRoot { .. }
+            | Self::InvalidSetting { .. } => 7,
+            Self::NotFastForwarded(_) => 8,
+            Self::BranchNotMerged { .. } => 9,
+            // What shells return for a command that doesn't exist
+            Self::GitNotFound(_) => 127,
+            // What shells return for a command killed by SIGINT
+            Self::Cancelled => 130,
+        }
+    }
+
+    /// Stable snake_case name of the variant, the `error_kind` of JSON errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Usage(_) => "usage",
+            Self::InvalidBranchName { .. } => "invalid_branch_name",
+            Self::NotARepository { .. } => "not_a_repository",
+            Self::BranchNotFound(_) => "branch_not_found",
+            Self::BranchNotFoundAnywhere(_) => "branch_not_found_anywhere",
+            Self::RemoteBranchNotFound(_) => "remote_branch_not_found",
+            Self::WorktreeNotFound(_) => "worktree_not_found",
+            Self::WorktreeDirty(_) => "worktree_dirty",
+            Self::LeavingDirtyWorktree { .. } => "leaving_dirty_worktree",
+            Self::WorktreeLocked { .. } => "worktree_locked",
+            Self::ConfigMissing => "config_missing",
+            Self::WorktreeRootMissing => "worktree_root_missing",
+            Self::UnsafeWorktreeRoot { .. } => "unsafe_worktree_root",
+            Self::InvalidSetting { .. } => "invalid_setting",
+            Self::GitFailure { .. } => "git_failure",
+            Self::GitTimedOut { .. } => "git_timed_out",
+            Self::BranchNotMerged { .. } => "branch_not_merged",
+            Self::NotFastForwarded(_) => "not_fast_forwarded",
+            Self::GitNotFound(_) => "git_not_found",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// The outermost `GwtError` in `error`'s chain
+    pub fn find(error: &anyhow::Error) -> Option<&GwtError> {
+        error.chain().find_map(|e| e.downcast_ref::<GwtError>())
+    }
+}
+
+/// `error` as a one-line JSON object for commands run with `--json`:
+/// `error_kind` is [`GwtError::kind`], or `other` for errors without a kind
+pub fn to_json(error: &anyhow::Error) -> String {
+    let kind = GwtError::find(error);
+    serde_json::json!({
+        "error": format!("{:#}", error),
+        "error_kind": kind.map_or("other", GwtError::kind),
+        "exit_code": kind.map_or(1, GwtError::exit_code),
+    })
+    .to_string()
+}
+
+fn detail(stderr: &str) -> String {
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", stderr)
+    }
+}
+
+fn lock_suffix(reason: &str) -> String {
+    if reason.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_find_looks_through_context() {
+        let error = Err::<(), _>(GwtError::BranchNotFound("feature".into()))
+            .context("Failed to switch")
+            .unwrap_err();
+        let found = GwtError::find(&error).unwrap();
+        assert_eq!(found.exit_code(), 4);
+        assert_eq!(found.to_string(), "Branch 'feature' doesn't exist.");
+
+        assert!(GwtError::find(&anyhow::anyhow!("plain")).is_none());
+    }
+
+    #[test]
+    fn test_locked_message() {
+        let locked = |reason: &str| GwtError::WorktreeLocked {
+            path: PathBuf::from("/wt"),
+            reason: reason.to_string(),
+        };
+        assert_eq!(
+            locked("on usb").to_string(),
+            "Worktree at '/wt' is locked (on usb). Use --force to remove it anyway."
+        );
+        assert_eq!(
+            locked("").to_string(),
+            "Worktree at '/wt' is locked. Use --force to remove it anyway."
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let error = Err::<(), _>(GwtError::WorktreeNotFound("feature".into()))
+            .context("Failed to remove")
+            .unwrap_err();
+        let json: serde_json::Value = serde_json::from_str(&to_json(&error)).unwrap();
+        assert_eq!(json["error_kind"], "worktree_not_found");
+        assert_eq!(json["exit_code"], 4);
+        assert_eq!(
+            json["error"],
+            "Failed to remove: No worktree found for branch 'feature'"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&anyhow::anyhow!("plain"))).unwrap();
+        assert_eq!(json["error_kind"], "other");
+        assert_eq!(json["exit_code"], 1);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::utility::{unix_now, write_atomic};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of entries kept in the history file
+const MAX_ENTRIES: usize = 200;
+
+/// One visit of a worktree through `gwt sw`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Main worktree of the repository
+    pub repo: PathBuf,
+    pub branch: String,
+    pub path: PathBuf,
+}
+
+impl Entry {
+    pub fn now(repo: PathBuf, branch: String, path: PathBuf) -> Self {
+        Self {
+            timestamp: unix_now(),
+            repo,
+            branch,
+            path,
+        }
+    }
+
+    /// `timestamp<TAB>repo<TAB>branch<TAB>path`. Branch names can't contain
+    /// tabs, and the path comes last so a tab in it survives.
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.timestamp,
+            self.repo.display(),
+            self.branch,
+            self.path.display()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(4, '\t');
+        Some(Self {
+            timestamp: fields.next()?.parse().ok()?,
+            repo: PathBuf::from(fields.next()?),
+            branch: fields.next()?.to_string(),
+            path: PathBuf::from(fields.next().filter(|p| !p.is_empty())?),
+        })
+    }
+}
+
+/// Append `entry` to the history file, keeping only the newest entries
+pub fn record(file: &Path, entry: Entry) -> Result<()> {
+    let mut entries = load(file)?;
+    entries.push(entry);
+    let excess = entries.len().saturating_sub(MAX_ENTRIES);
+    save(file, &entries[excess..])
+}
+
+/// The last `count` distinct worktrees, newest first.
+///
+/// Entries whose directory no longer exists are dropped here and pruned from
+/// the file, so the history cleans itself up as worktrees are removed.
+pub fn recent(file: &Path, count: usize) -> Result<Vec<Entry>> {
+    let entries = load(file)?;
+    let (existing, gone): (Vec<Entry>, Vec<Entry>) =
+        entries.into_iter().partition(|e| e.path.is_dir());
+    if !gone.is_empty() {
+        save(file, &existing)?;
+    }
+
+    let mut seen = HashSet::new();
+    Ok(existing
+        .into_iter()
+        .rev()
+        .filter(|e| seen.insert(e.path.clone()))
+        .take(count)
+        .collect())
+}
+
+fn load(file: &Path) -> Result<Vec<Entry>> {
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read history file '{}'", file.display()))?;
+    Ok(content.lines().filter_map(Entry::from_line).collect())
+}
+
+fn save(file: &Path, entries: &[Entry]) -> Result<()> {
+    let content: String = entries.iter().map(|e| e.to_line() + "\n").collect();
+    write_atomic(file, content)
+        .with_context(|| format!("Failed to write history file '{}'", file.display()))
+}
+
+/// Human readable age of `timestamp`, e.g. "5 minutes ago"
+pub fn relative_time(timestamp: u64) -> String {
+    relative_time_from(timestamp, unix_now())
+}
+
+fn relative_time_from(timestamp: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(timestamp);
+    let (value, unit) = match elapsed {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (elapsed / 60, "minute"),
+        3_600..86_400 => (elapsed / 3_600, "hour"),
+        86_400..604_800 => (elapsed / 86_400, "day"),
+        _ => (elapsed / 604_800, "week"),
+    };
+    let plural = if value == 1 { "" } else { "s" };
+    format!("{value} {unit}{plural} ago")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, branch: &str, path: &Path) -> Entry {
+        Entry {
+            timestamp,
+            repo: PathBuf::from("/code/repo"),
+            branch: branch.to_string(),
+            path: path.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_entry_line_round_trip() {
+        let e = entry(1700000000, "feature/x", Path::new("/store/with\ttab"));
+        assert_eq!(Entry::from_line(&e.to_line()), Some(e));
+        assert_eq!(Entry::from_line("garbage"), None);
+        assert_eq!(Entry::from_line("1\t/repo\tbranch\t"), None);
+    }
+
+    #[test]
+    fn test_record_caps_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(".gwt/history");
+
+        for i in 0..(MAX_ENTRIES as u64 + 5) {
+            record(&file, entry(i, "b", dir.path())).unwrap();
+        }
+
+        let entries = load(&file).unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].timestamp, 5);
+        assert_eq!(entries.last().unwrap().timestamp, MAX_ENTRIES as u64 + 4);
+    }
+
+    #[test]
+    fn test_recent_dedupes_and_prunes_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("history");
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+
+        for e in [
+            entry(1, "a", &a),
+            entry(2, "gone", &dir.path().join("gone")),
+            entry(3, "b", &b),
+            entry(4, "a", &a),
+        ] {
+            record(&file, e).unwrap();
+        }
+
+        let recent_entries = recent(&file, 10).unwrap();
+        let branches: Vec<_> = recent_entries.iter().map(|e| e.branch.as_str()).collect();
+        assert_eq!(branches, ["a", "b"]);
+        assert_eq!(recent_entries[0].timestamp, 4);
+
+        // The missing worktree was pruned from the file, duplicates are kept
+        let remaining: Vec<_> = load(&file).unwrap().iter().map(|e| e.timestamp).collect();
+        assert_eq!(remaining, [1, 3, 4]);
+
+        assert_eq!(recent(&file, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(relative_time_from(100, 130), "just now");
+        assert_eq!(relative_time_from(0, 60), "1 minute ago");
+        assert_eq!(relative_time_from(0, 7_200), "2 hours ago");
+        assert_eq!(relative_time_from(0, 86_400 * 3), "3 days ago");
+        assert_eq!(relative_time_from(0, 604_800 * 5), "5 weeks ago");
+        // Clock skew doesn't produce nonsense
+        assert_eq!(relative_time_from(200, 100), "just now");
+    }
+}
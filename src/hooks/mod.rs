@@ -0,0 +1,232 @@
+use crate::command::worktree::repo_name;
+use crate::config::Config;
+use crate::ui;
+use crate::utility::{ExternalOptions, OutputMode, run_external};
+use anyhow::{Context, Result, bail};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// How long a hook may run unless `hooks.timeout_secs` says otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A point where gwt runs a user command from the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// After `gwt rm` removed a worktree
+    PostRemove,
+}
+
+impl Hook {
+    pub const ALL: &[Hook] = &[Hook::PostRemove];
+
+    /// Name used by `gwt hooks`
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PostRemove => "post_remove",
+        }
+    }
+
+    /// Key of the hook's command in the config
+    pub fn config_key(self) -> &'static str {
+        match self {
+            Hook::PostRemove => "post_remove_hook",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Hook> {
+        Hook::ALL
+            .iter()
+            .copied()
+            .find(|hook| hook.name() == name || hook.config_key() == name)
+    }
+
+    /// The command configured for the hook, if any
+    pub fn command(self, config: &Config) -> Option<&str> {
+        let data = config.data()?;
+        let command = match self {
+            Hook::PostRemove => data.post_remove_hook.as_deref(),
+        };
+        command.filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// Where a hook runs, the environment it gets and how long it may take
+#[derive(Debug, PartialEq, Eq)]
+pub struct Invocation {
+    pub cwd: PathBuf,
+    pub env: Vec<(&'static str, OsString)>,
+    /// After this, the hook and everything it started are killed
+    pub timeout: Option<Duration>,
+}
+
+impl Invocation {
+    /// `post_remove` runs in the main worktree since the removed one is gone.
+    /// `--allow-main` may have removed the main worktree itself, which leaves
+    /// the worktree root.
+    pub fn post_remove(
+        config: &Config,
+        main_worktree: &Path,
+        branch: &str,
+        removed: &Path,
+    ) -> Self {
+        let cwd = match config.data() {
+            Some(d) if !main_worktree.is_dir() => d.worktree_root.clone(),
+            _ => main_worktree.to_path_buf(),
+        };
+        Self {
+            cwd,
+            timeout: config.data().map_or(Some(DEFAULT_TIMEOUT), |d| {
+                d.hooks.timeout(Some(DEFAULT_TIMEOUT))
+            }),
+            env: vec![
+                ("GWT_BRANCH", branch.into()),
+                ("GWT_REMOVED_PATH", removed.into()),
+                ("GWT_REPO", repo_name(main_worktree).into()),
+            ],
+        }
+    }
+}
+
+/// Run `post_remove_hook` after the worktree at `removed` was removed. The
+/// removal already happened, so a failing hook only prints a warning.
+pub fn post_remove(config: &Config, main_worktree: &Path, branch: &str, removed: &Path) {
+    let Some(command) = Hook::PostRemove.command(config) else {
+        return;
+    };
+    let invocation = Invocation::post_remove(config, main_worktree, branch, removed);
+    if let Err(e) = run(Hook::PostRemove, command, &invocation) {
+        ui::warn!("{:#}", e);
+    }
+}
+
+/// Run `command` with the shell. Its stdout goes to stderr like the rest of
+/// gwt's messages, so the shell wrapper only ever sees the path to change to.
+pub fn run(hook: Hook, command: &str, invocation: &Invocation) -> Result<()> {
+    ui::info!("Running {}", hook.config_key());
+
+    let mut cmd = shell(command);
+    cmd.current_dir(&invocation.cwd);
+    let opts = invocation
+        .env
+        .iter()
+        .fold(ExternalOptions::new(), |opts, (key, value)| {
+            opts.env(*key, value)
+        })
+        .output(OutputMode::Stream)
+        .timeout(invocation.timeout);
+    let output =
+        run_external(cmd, &opts).with_context(|| format!("{} failed", hook.config_key()))?;
+    if !output.status.success() {
+        bail!("{} failed: {}", hook.config_key(), output.status);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigData;
+
+    fn config_with_hook(root: &Path, hook: &str) -> Config {
+        Config::Loaded(
+            ConfigData {
+                worktree_root: root.to_path_buf(),
+                post_remove_hook: Some(hook.to_string()),
+                ..Default::default()
+            },
+            PathBuf::from("/home/me/.gwt/config.toml"),
+        )
+    }
+
+    #[test]
+    fn test_post_remove_runs_in_main_worktree_with_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("api");
+        std::fs::create_dir_all(&main).unwrap();
+        let config = config_with_hook(
+            dir.path(),
+            r#"printf '%s|%s|%s' "$GWT_BRANCH" "$GWT_REMOVED_PATH" "$GWT_REPO" > out"#,
+        );
+
+        post_remove(&config, &main, "feature", Path::new("/store/feature"));
+        let out = std::fs::read_to_string(main.join("out")).unwrap();
+        assert_eq!(out, "feature|/store/feature|api");
+
+        // Without the main worktree, the hook runs in the worktree root
+        let gone = dir.path().join("gone");
+        let invocation = Invocation::post_remove(&config, &gone, "x", &gone);
+        assert_eq!(invocation.cwd, dir.path());
+    }
+
+    #[test]
+    fn test_failing_hook_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let invocation = Invocation {
+            cwd: dir.path().to_path_buf(),
+            env: Vec::new(),
+            timeout: None,
+        };
+        let err = run(Hook::PostRemove, "exit 3", &invocation).unwrap_err();
+        assert!(
+            err.to_string().starts_with("post_remove_hook failed"),
+            "{err}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_hook_is_killed() {
+        let dir = tempfile::tempdir().unwrap();
+        let invocation = Invocation {
+            cwd: dir.path().to_path_buf(),
+            env: Vec::new(),
+            timeout: Some(Duration::from_millis(200)),
+        };
+        let start = std::time::Instant::now();
+        let err = run(Hook::PostRemove, "sleep 30", &invocation).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        let err = format!("{err:#}");
+        assert!(
+            err.starts_with("post_remove_hook failed") && err.contains("timed out"),
+            "{err}"
+        );
+
+        // The default applies unless the config sets one
+        let config = config_with_hook(dir.path(), "true");
+        let invocation = Invocation::post_remove(&config, dir.path(), "x", dir.path());
+        assert_eq!(invocation.timeout, Some(DEFAULT_TIMEOUT));
+    }
+
+    #[test]
+    fn test_hook_names() {
+        assert_eq!(Hook::from_name("post_remove"), Some(Hook::PostRemove));
+        assert_eq!(Hook::from_name("post_remove_hook"), Some(Hook::PostRemove));
+        assert_eq!(Hook::from_name("pre_switch"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            Hook::PostRemove.command(&config_with_hook(dir.path(), "make clean")),
+            Some("make clean")
+        );
+        assert_eq!(
+            Hook::PostRemove.command(&config_with_hook(dir.path(), "  ")),
+            None
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Git worktree management behind the `gwtree` binary.
+//!
+//! [`WorktreeManager`] offers the operations of `gwt sw`, `gwt rm` and
+//! `gwt ls` to other tools. It works on a [`Config`], usually the user's
+//! `~/.gwt/config.toml`, and a [`GitBackend`], usually [`Git`] running in the
+//! repository at hand.
+//!
+//! ```no_run
+//! use gwt::{Config, Git, WorktreeManager};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let config = Config::from_file(gwt::config::file_path()?)?;
+//! let manager = WorktreeManager::new(config, Git::new().with_cwd("/code/my-app"));
+//! println!("{}", manager.target_path("feature/login")?.display());
+//! # Ok(())
+//! # }
+//! ```
+
+mod history;
+mod hooks;
+mod manager;
+mod meta;
+
+// The command line layer of the binary; not a stable API
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod command;
+
+pub mod config;
+pub mod error;
+pub mod ui;
+pub mod utility;
+
+pub use command::worktree::{RemoveOptions, SwitchOptions, Switched};
+pub use config::{Config, ConfigData};
+pub use error::GwtError;
+pub use manager::WorktreeManager;
+pub use utility::{Git, GitBackend, Worktree, Worktrees};
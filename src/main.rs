@@ -1,39 +1,309 @@
-mod command;
-mod config;
-mod utility;
-
-use crate::command::{Cli, Commands};
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use gwt::command::{Cli, Commands};
+use gwt::{WorktreeManager, cache, command, config, error, ui, utility};
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    // Aliases of the config are expanded before parsing, so clap reports
+    // mistakes against the command they expand to
+    let args: Vec<_> = std::env::args_os().collect();
+    let args = command::alias::expand(&args, &command::alias::configured()).unwrap_or_else(|e| {
+        Cli::command()
+            .error(clap::error::ErrorKind::InvalidSubcommand, e)
+            .exit()
+    });
+    let cli = Cli::parse_from(args);
+    let json_errors = cli.command.json_errors();
+    match run(cli) {
+        Err(e) if json_errors => {
+            eprintln!("{}", error::to_json(&e));
+            std::process::exit(error::GwtError::find(&e).map_or(1, |e| e.exit_code()));
+        }
+        Err(e) => match error::GwtError::find(&e) {
+            // The same single line whichever git command found out first
+            Some(error::GwtError::Cancelled) => {
+                eprintln!("Cancelled.");
+                std::process::exit(130);
+            }
+            Some(gwt_error @ error::GwtError::NotARepository { .. }) => {
+                eprintln!("gwt: {}", gwt_error);
+                std::process::exit(gwt_error.exit_code());
+            }
+            Some(gwt_error) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(gwt_error.exit_code());
+            }
+            None => Err(e),
+        },
+        ok => ok,
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    utility::set_verbose(cli.verbose);
+    ui::set_quiet(cli.quiet);
+    ui::set_eval(cli.command.eval_shell());
     let config = config::load(&cli.command)?;
+    ui::set_theme(
+        config.data().map_or(ui::Theme::Default, |d| d.theme),
+        cli.color,
+    );
+    utility::set_git_timeout(
+        config
+            .data()
+            .and_then(|d| d.git_timeout_secs)
+            .map(std::time::Duration::from_secs),
+    );
 
-    match cli.command {
-        Commands::Config(config_command) => command::config::handle(&config, &config_command),
-        Commands::Ls { full, raw } => command::worktree::list(&config, full, raw),
+    // `gwt sw`, `gwt rm` and `gwt ls` go through the same manager as library
+    // users; the other commands take its git and config
+    let manager = WorktreeManager::new(config, utility::Git::new());
+    let (git, config) = (manager.git(), manager.config());
+    // Commands that change worktrees drop the list cached for completion. Its
+    // file is found up front, as `gwt rm` may remove the directory gwt runs in.
+    let stale_cache = cli
+        .command
+        .changes_worktrees()
+        .then(|| command::worktree::cache_file(git, config))
+        .flatten();
+    let result = match cli.command {
+        Commands::Config(config_command) => command::config::handle(config, &config_command),
+        Commands::Alias(alias_command) => command::alias::handle(&alias_command),
+        Commands::Hooks(hooks_command) => command::hooks::handle(git, config, &hooks_command),
+        Commands::Ls {
+            full,
+            raw,
+            cached,
+            no_main,
+            with_paths,
+            ids,
+            status,
+            age,
+            orphans,
+            json,
+            all,
+            group_by,
+        } => command::worktree::list(
+            &manager,
+            &command::worktree::ListOptions {
+                full,
+                raw,
+                cached,
+                no_main,
+                with_paths,
+                ids,
+                status,
+                age,
+                orphans,
+                json,
+                all,
+                group_by,
+            },
+        ),
+        Commands::Sw {
+            pick: true,
+            remote,
+            force,
+            tmux,
+            no_checkout,
+            fetch,
+            pull,
+            strict,
+            json,
+            ..
+        } => match command::worktree::pick_branch(git, &command::worktree::picker()?)? {
+            Some(branch) => command::worktree::switch(
+                &manager,
+                Some(&branch),
+                &command::worktree::SwitchOptions {
+                    remote: remote.as_deref(),
+                    force,
+                    tmux,
+                    no_checkout,
+                    fetch,
+                    pull,
+                    strict,
+                    json,
+                    ..Default::default()
+                },
+            ),
+            None => Ok(()),
+        },
         Commands::Sw {
             branch,
             create,
             main,
             remote,
-        } => command::worktree::switch(&config, branch.as_deref(), create, main, remote.as_deref()),
+            force,
+            pick: false,
+            tmux,
+            no_checkout,
+            track,
+            fetch,
+            pull,
+            strict,
+            take_changes,
+            no_prefix,
+            no_cd: _,
+            json,
+            eval: _,
+        } => command::worktree::switch(
+            &manager,
+            branch.as_deref(),
+            &command::worktree::SwitchOptions {
+                create,
+                main,
+                remote: remote.as_deref(),
+                force,
+                tmux,
+                no_checkout,
+                track: track.as_deref(),
+                fetch,
+                pull,
+                strict,
+                take_changes,
+                no_prefix,
+                json,
+            },
+        ),
         Commands::Rm {
             branch,
             delete_branch,
             force_delete_branch,
+            delete_remote,
             skip_confirmation,
-        } => command::worktree::remove(
-            &config,
-            &branch,
-            delete_branch,
-            force_delete_branch,
+            force,
+            allow_main,
+            no_hooks,
+            ..
+        } => {
+            let options = command::worktree::RemoveOptions {
+                delete_branch,
+                force_delete_branch,
+                delete_remote,
+                skip_confirmation,
+                force,
+                allow_main,
+                no_hooks,
+            };
+            let removed = match branch {
+                Some(branch) if branch == "-" => manager.remove_from_stdin(&options),
+                Some(branch) => manager.remove(&branch, &options),
+                None => manager.remove_gone(&options),
+            };
+            removed.map(|cd| {
+                if let Some(main) = cd {
+                    ui::print_cd(&main);
+                }
+            })
+        }
+        Commands::Init {
+            shell,
+            binary_name,
+            function_name,
+            no_cd_on_rm,
+            ls_after_switch,
+        } => {
+            let options = command::shell::InitOptions {
+                function_name,
+                cd_on_rm: !no_cd_on_rm,
+                ls_after_switch,
+            };
+            command::shell::handle(shell.as_deref(), binary_name.as_deref(), &options)
+        }
+        Commands::Current => command::current::handle(git),
+        Commands::Completion {
+            shell,
+            binary_name,
+            install,
+            force,
+        } => command::completion::handle(shell, binary_name.as_deref(), install, force),
+        Commands::Home { .. } => command::home::handle(git),
+        Commands::Exec {
+            branch,
+            all,
+            keep_going,
+            jobs,
+            command,
+        } => command::exec::handle(
+            git,
+            config,
+            branch.as_deref(),
+            all,
+            keep_going,
+            jobs as usize,
+            &command,
+        ),
+        Commands::Run {
+            branch,
+            create,
+            command,
+        } => command::exec::run(git, config, &branch, create, &command),
+        Commands::Clone { url, name } => command::clone::handle(git, config, &url, name.as_deref()),
+        Commands::Lock { branch, reason } => {
+            command::worktree::lock(git, config, &branch, reason.as_deref())
+        }
+        Commands::Unlock { branch } => command::worktree::unlock(git, config, &branch),
+        Commands::Pr { number, remote } => command::worktree::pr(git, config, number, &remote),
+        Commands::Mv { old, new } => command::worktree::rename(git, config, &old, &new),
+        Commands::Clean {
+            older_than,
+            merged_only,
+            dry_run,
+            skip_confirmation,
+            force,
+        } => command::worktree::clean(
+            git,
+            config,
+            older_than,
+            merged_only,
+            dry_run,
             skip_confirmation,
+            force,
         ),
-        Commands::Init { shell } => command::shell::handle(&shell),
-        Commands::Current => command::current::handle(),
-        Commands::Completion { shell } => command::completion::handle(shell),
-        Commands::Home => command::home::handle(),
+        Commands::Sync {
+            pattern,
+            remote,
+            prune,
+            skip_confirmation,
+        } => command::worktree::sync(git, config, &pattern, &remote, prune, skip_confirmation),
+        Commands::Export { format, output } => {
+            command::worktree::export(git, config, format, output.as_deref())
+        }
+        Commands::Import { file } => command::worktree::import(git, config, &file),
+        Commands::Migrate { from, dry_run } => {
+            command::worktree::migrate(git, config, from.as_deref(), dry_run)
+        }
+        Commands::Repair => command::worktree::repair(git, config),
+        Commands::Open { branch, create } => {
+            command::worktree::open(git, config, branch.as_deref(), create)
+        }
+        Commands::Recent { count, pick } => command::recent::handle(config, count, pick),
+        Commands::Doctor { json } => command::doctor::handle(git, json),
+        Commands::Version { json } => command::version::handle(git, json),
+        Commands::Fetch { all } => command::fetch::handle(git, config, all),
+        Commands::Du { json } => command::du::handle(git, json),
+        Commands::Repos { du, json } => command::repos::handle(config, du, json),
+        Commands::Forget {
+            repo,
+            dry_run,
+            skip_confirmation,
+            force,
+        } => command::repos::forget(config, &repo, dry_run, skip_confirmation, force),
+        Commands::Status { json } => command::status::handle(git, json),
+        Commands::Prompt { format, starship } => {
+            command::prompt::handle(git, config, format.as_deref(), starship)
+        }
+        Commands::Which { path } => command::which::handle(git, path.as_deref()),
+        Commands::Env { json, export } => command::env::handle(git, config, json, export),
+        Commands::GenDocs { format, out } => command::docs::handle(format, &out),
+    };
+    if let Some(file) = stale_cache {
+        cache::invalidate(&file);
+    }
+    // Only a command that succeeded may change the directory of `eval`
+    if result.is_ok() {
+        ui::flush_cd();
     }
+    result
 }
@@ -0,0 +1,226 @@
+use crate::command::worktree::{self, RemoveOptions, SwitchOptions, Switched, compute_target_path};
+use crate::config::Config;
+use crate::utility::{Git, GitBackend, Worktrees};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// The worktree operations behind `gwt sw`, `gwt rm` and `gwt ls`, for one
+/// repository and one configuration.
+///
+/// Operations run git through `git` and keep their worktrees under the
+/// configured `worktree_root`, exactly as the commands do. They report
+/// progress on stderr and, like the commands, may ask for confirmation when
+/// stdin is a terminal: `switch` when the worktree root doesn't exist yet,
+/// `remove` unless `skip_confirmation` is set.
+///
+/// ```no_run
+/// use gwt::{Config, Git, SwitchOptions, WorktreeManager};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let config = Config::from_file(gwt::config::file_path()?)?;
+/// let manager = WorktreeManager::new(config, Git::new());
+///
+/// let path = manager.switch("feature/login", &SwitchOptions {
+///     create: true,
+///     ..Default::default()
+/// })?;
+/// println!("worktree at {}", path.display());
+///
+/// for wt in manager.list()?.iter() {
+///     println!("{:?} {}", wt.branch(), wt.path().display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct WorktreeManager<G: GitBackend = Git> {
+    config: Config,
+    git: G,
+}
+
+impl<G: GitBackend> WorktreeManager<G> {
+    /// Operate on the repository `git` runs in, with `config`
+    pub fn new(config: Config, git: G) -> Self {
+        Self { config, git }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn git(&self) -> &G {
+        &self.git
+    }
+
+    /// The worktree of `branch`, created when there's none yet, as `gwt sw`
+    /// would switch to it. `options.tmux` is ignored; with `options.strict`,
+    /// a pull that couldn't fast-forward is an error.
+    ///
+    /// Asking for the branch already checked out where `git` runs returns
+    /// that worktree.
+    ///
+    /// ```no_run
+    /// # use gwt::{Config, Git, SwitchOptions, WorktreeManager};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let manager = WorktreeManager::new(Config::from_file(gwt::config::file_path()?)?, Git::new());
+    /// // The worktree of the main branch, fast-forwarded to its upstream
+    /// let main = manager.switch("", &SwitchOptions {
+    ///     main: true,
+    ///     pull: true,
+    ///     ..Default::default()
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn switch(&self, branch: &str, options: &SwitchOptions) -> Result<PathBuf> {
+        let branch = Some(branch).filter(|_| !options.main);
+        let Switched { path, pulled, .. } = self.switch_worktree(branch, options)?;
+        if !pulled && options.strict {
+            return Err(crate::error::GwtError::NotFastForwarded(path).into());
+        }
+        Ok(path)
+    }
+
+    /// [`switch`](Self::switch) with everything `gwt sw` reports about it:
+    /// whether the worktree was created, already current or pulled. `branch`
+    /// is ignored with `options.main`, and `options.strict` is left to the
+    /// caller.
+    pub fn switch_worktree(
+        &self,
+        branch: Option<&str>,
+        options: &SwitchOptions,
+    ) -> Result<Switched> {
+        worktree::switch_worktree(&self.git, &self.config, branch, options)
+    }
+
+    /// Remove the worktree of `target` (a branch, a worktree directory or a
+    /// directory id), as `gwt rm` would.
+    ///
+    /// When the current directory is inside that worktree, the main
+    /// worktree's path is returned for the caller to change to.
+    ///
+    /// ```no_run
+    /// # use gwt::{Config, Git, RemoveOptions, WorktreeManager};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let manager = WorktreeManager::new(Config::from_file(gwt::config::file_path()?)?, Git::new());
+    /// let options = RemoveOptions {
+    ///     delete_branch: true,
+    ///     skip_confirmation: true,
+    ///     ..Default::default()
+    /// };
+    /// if let Some(main) = manager.remove("feature/login", &options)? {
+    ///     std::env::set_current_dir(main)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove(&self, target: &str, options: &RemoveOptions) -> Result<Option<PathBuf>> {
+        worktree::remove(&self.git, &self.config, target, options)
+    }
+
+    /// Remove every linked worktree whose branch's upstream is gone, as
+    /// `gwt rm` without a target would: one confirmation for the whole list
+    /// unless `skip_confirmation` is set, then each removal carries on past
+    /// failures. Returns the main worktree's path like [`remove`](Self::remove).
+    pub fn remove_gone(&self, options: &RemoveOptions) -> Result<Option<PathBuf>> {
+        worktree::remove_gone(&self.git, &self.config, options)
+    }
+
+    /// Remove the worktrees of the branches read from stdin, one per line, as
+    /// `gwt rm -` would. Returns the main worktree's path like
+    /// [`remove`](Self::remove).
+    pub fn remove_from_stdin(&self, options: &RemoveOptions) -> Result<Option<PathBuf>> {
+        worktree::remove_from_stdin(&self.git, &self.config, options)
+    }
+
+    /// Every worktree of the repository, sorted by branch like `gwt ls`,
+    /// detached ones last
+    pub fn list(&self) -> Result<Worktrees> {
+        let mut worktrees = self.git.list_worktrees()?;
+        worktrees.sort_by_branch();
+        Ok(worktrees)
+    }
+
+    /// Where gwt puts the worktree of `branch`, whether or not it exists
+    ///
+    /// ```no_run
+    /// # use gwt::{Config, Git, WorktreeManager};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let manager = WorktreeManager::new(Config::from_file(gwt::config::file_path()?)?, Git::new());
+    /// let path = manager.target_path("feature/login")?;
+    /// assert!(path.starts_with(&manager.config().data().unwrap().worktree_root));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn target_path(&self, branch: &str) -> Result<PathBuf> {
+        compute_target_path(&self.git, &self.config, branch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigData;
+    use crate::utility::FakeGit;
+
+    fn manager(git: FakeGit) -> (tempfile::TempDir, WorktreeManager<FakeGit>) {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::Loaded(
+            ConfigData {
+                worktree_root: dir.path().join("store"),
+                ..Default::default()
+            },
+            dir.path().join("config.toml"),
+        );
+        std::fs::create_dir_all(dir.path().join("store")).unwrap();
+        (dir, WorktreeManager::new(config, git))
+    }
+
+    #[test]
+    fn test_switch_creates_and_reuses_worktrees() {
+        let (_dir, manager) = manager(FakeGit::new("/path/to/repo", "main"));
+        let options = SwitchOptions {
+            create: true,
+            ..Default::default()
+        };
+
+        let path = manager.switch("feature", &options).unwrap();
+        assert_eq!(path, manager.target_path("feature").unwrap());
+        // FakeGit registers the worktree without creating it
+        std::fs::create_dir_all(&path).unwrap();
+        assert_eq!(manager.switch("feature", &options).unwrap(), path);
+        assert_eq!(
+            manager.switch("main", &options).unwrap(),
+            PathBuf::from("/path/to/repo")
+        );
+
+        let branches: Vec<_> = manager
+            .list()
+            .unwrap()
+            .iter()
+            .map(|wt| wt.branch().map(str::to_string))
+            .collect();
+        assert_eq!(
+            branches,
+            [Some("feature".to_string()), Some("main".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let (_dir, manager) = manager(
+            FakeGit::new("/path/to/repo", "main").with_worktree("/store/feature", "feature"),
+        );
+        let cd = manager
+            .remove(
+                "feature",
+                &RemoveOptions {
+                    skip_confirmation: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // The test doesn't run inside the removed worktree
+        assert_eq!(cd, None);
+        assert_eq!(manager.list().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::utility::{slash_path, unix_now, write_atomic};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// File in the worktree root that records when each worktree was created and
+/// last switched to, and for which branch and repository. It's what tells
+/// the store's directories apart once their repository is gone.
+const META_FILE_NAME: &str = ".gwt-meta.toml";
+
+/// What gwt knows about one worktree directory in the store
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub branch: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Seconds since the Unix epoch
+    pub last_switched_at: u64,
+    /// Main worktree of the repository the worktree was created from; unset
+    /// for entries written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<PathBuf>,
+}
+
+/// Store metadata keyed by the worktree's path relative to the worktree root,
+/// with `/` separators: the hash `gwt sw` derives for the default layout
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Meta {
+    #[serde(default)]
+    worktrees: BTreeMap<String, Entry>,
+}
+
+/// Record that the worktree at `path` was just created for `branch` of the
+/// repository whose main worktree is `repo`
+pub fn record_created(root: &Path, path: &Path, branch: &str, repo: &Path) -> Result<()> {
+    update(root, path, |entry, now| {
+        *entry = Some(Entry {
+            branch: branch.to_string(),
+            created_at: now,
+            last_switched_at: now,
+            repo: Some(repo.to_path_buf()),
+        });
+    })
+}
+
+/// Record a switch to the worktree at `path`. A worktree gwt has no entry for,
+/// e.g. one created before metadata was kept, gets one dated by its directory.
+pub fn record_switch(root: &Path, path: &Path, branch: &str, repo: Option<&Path>) -> Result<()> {
+    update(root, path, |entry, now| {
+        let created_at = entry
+            .as_ref()
+            .map(|e| e.created_at)
+            .or_else(|| modified_at(path))
+            .unwrap_or(now);
+        let repo = repo
+            .map(Path::to_path_buf)
+            .or_else(|| entry.as_ref().and_then(|e| e.repo.clone()));
+        *entry = Some(Entry {
+            branch: branch.to_string(),
+            created_at,
+            last_switched_at: now,
+            repo,
+        });
+    })
+}
+
+/// Record that the worktree at `from` was moved to `to` and its branch
+/// renamed to `branch`
+pub fn record_moved(root: &Path, from: &Path, to: &Path, branch: &str) -> Result<()> {
+    let mut moved = None;
+    update(root, from, |entry, _| moved = entry.take())?;
+    update(root, to, |entry, now| {
+        *entry = Some(match moved {
+            Some(e) => Entry {
+                branch: branch.to_string(),
+                ..e
+            },
+            None => Entry {
+                branch: branch.to_string(),
+                created_at: now,
+                last_switched_at: now,
+                repo: None,
+            },
+        });
+    })
+}
+
+/// Forget the worktree at `path`, which was just removed
+pub fn record_removed(root: &Path, path: &Path) -> Result<()> {
+    update(root, path, |entry, _| *entry = None)
+}
+
+/// The entries gwt has for the worktrees at `paths`. A missing or unreadable
+/// metadata file gives none.
+pub fn entries<'a>(
+    root: &Path,
+    paths: impl Iterator<Item = &'a PathBuf>,
+) -> BTreeMap<PathBuf, Entry> {
+    let mut meta = load(root);
+    paths
+        .filter_map(|path| {
+            let entry = meta.worktrees.remove(&key(root, path)?)?;
+            Some((path.clone(), entry))
+        })
+        .collect()
+}
+
+/// When each worktree at `paths` was last used: its last switch, or the
+/// directory's modification time when gwt has no record of it. Paths without
+/// either are left out.
+pub fn last_active<'a>(
+    root: &Path,
+    paths: impl Iterator<Item = &'a PathBuf>,
+) -> BTreeMap<PathBuf, u64> {
+    let meta = load(root);
+    paths
+        .filter_map(|path| {
+            let recorded = key(root, path)
+                .and_then(|key| meta.worktrees.get(&key))
+                .map(|e| e.last_switched_at);
+            Some((path.clone(), recorded.or_else(|| modified_at(path))?))
+        })
+        .collect()
+}
+
+/// Compact age of `timestamp` for table columns, e.g. "45m", "3d", "2mo"
+pub fn short_age(timestamp: u64) -> String {
+    short_age_from(timestamp, unix_now())
+}
+
+fn short_age_from(timestamp: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(timestamp);
+    match elapsed {
+        0..3_600 => format!("{}m", elapsed / 60),
+        3_600..86_400 => format!("{}h", elapsed / 3_600),
+        86_400..2_592_000 => format!("{}d", elapsed / 86_400),
+        2_592_000..31_536_000 => format!("{}mo", elapsed / 2_592_000),
+        _ => format!("{}y", elapsed / 31_536_000),
+    }
+}
+
+/// Apply `change` to the entry of the worktree at `path`. Worktrees outside
+/// the store aren't tracked.
+fn update(root: &Path, path: &Path, change: impl FnOnce(&mut Option<Entry>, u64)) -> Result<()> {
+    let Some(key) = key(root, path) else {
+        return Ok(());
+    };
+    let mut meta = load(root);
+    let mut entry = meta.worktrees.remove(&key);
+    change(&mut entry, unix_now());
+    if let Some(entry) = entry {
+        meta.worktrees.insert(key, entry);
+    }
+    // Forget worktrees that were removed since the last write
+    meta.worktrees.retain(|name, _| root.join(name).is_dir());
+    save(root, &meta)
+}
+
+/// The key of the worktree at `path`: its path under `root`, the same on
+/// every platform
+fn key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    slash_path(relative)
+}
+
+/// The metadata in `root`. A missing or unreadable file is treated as empty, so
+/// entries are recreated as worktrees are switched to.
+fn load(root: &Path) -> Meta {
+    fs::read_to_string(root.join(META_FILE_NAME))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(root: &Path, meta: &Meta) -> Result<()> {
+    let file = root.join(META_FILE_NAME);
+    let content = toml::to_string_pretty(meta).context("Failed to serialize worktree metadata")?;
+    write_atomic(&file, content)
+        .with_context(|| format!("Failed to write metadata file '{}'", file.display()))
+}
+
+fn modified_at(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let wt = root.join("abcd");
+        fs::create_dir_all(&wt).unwrap();
+
+        record_created(root, &wt, "feature", Path::new("/src/api")).unwrap();
+        let entry = load(root).worktrees["abcd"].clone();
+        assert_eq!(entry.branch, "feature");
+        assert_eq!(entry.created_at, entry.last_switched_at);
+        assert_eq!(entry.repo.as_deref(), Some(Path::new("/src/api")));
+        assert_eq!(
+            last_active(root, [&wt].into_iter())[&wt],
+            entry.last_switched_at
+        );
+
+        // Outside the store: not tracked, but still aged by the directory
+        let outside = tempfile::tempdir().unwrap();
+        record_switch(root, outside.path(), "x", None).unwrap();
+        assert_eq!(load(root).worktrees.len(), 1);
+        let outside = outside.path().to_path_buf();
+        assert!(last_active(root, [&outside].into_iter()).contains_key(&outside));
+    }
+
+    #[test]
+    fn test_corrupt_file_is_regenerated() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let wt = root.join("abcd");
+        fs::create_dir_all(&wt).unwrap();
+        fs::write(root.join(META_FILE_NAME), "not [valid toml").unwrap();
+
+        // Falls back to the directory's modification time
+        assert_eq!(
+            last_active(root, [&wt].into_iter()).get(&wt).copied(),
+            modified_at(&wt)
+        );
+
+        record_switch(root, &wt, "feature", None).unwrap();
+        let entry = &load(root).worktrees["abcd"];
+        assert_eq!(entry.created_at, modified_at(&wt).unwrap());
+        assert_eq!(entry.branch, "feature");
+    }
+
+    #[test]
+    fn test_removed_worktrees_are_forgotten() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["a", "b"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            record_created(root, &root.join(name), name, Path::new("/src/api")).unwrap();
+        }
+        fs::remove_dir(root.join("a")).unwrap();
+
+        record_switch(root, &root.join("b"), "b", None).unwrap();
+        let names: Vec<_> = load(root).worktrees.into_keys().collect();
+        assert_eq!(names, ["b"]);
+    }
+
+    #[test]
+    fn test_nested_move_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let old = root.join("api/feature");
+        let new = root.join("api/feature-v2");
+        fs::create_dir_all(&old).unwrap();
+        record_created(root, &old, "feature", Path::new("/src/api")).unwrap();
+        assert!(load(root).worktrees.contains_key("api/feature"));
+
+        fs::rename(&old, &new).unwrap();
+        record_moved(root, &old, &new, "feature-v2").unwrap();
+        let entries = entries(root, [&old, &new].into_iter());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[&new].branch, "feature-v2");
+        assert_eq!(entries[&new].repo.as_deref(), Some(Path::new("/src/api")));
+
+        // Switching without knowing the repository keeps the recorded one
+        record_switch(root, &new, "feature-v2", None).unwrap();
+        assert!(load(root).worktrees["api/feature-v2"].repo.is_some());
+
+        record_removed(root, &new).unwrap();
+        assert!(load(root).worktrees.is_empty());
+    }
+
+    #[test]
+    fn test_short_age() {
+        assert_eq!(short_age_from(100, 130), "0m");
+        assert_eq!(short_age_from(0, 45 * 60), "45m");
+        assert_eq!(short_age_from(0, 7_200), "2h");
+        assert_eq!(short_age_from(0, 86_400 * 3), "3d");
+        assert_eq!(short_age_from(0, 86_400 * 65), "2mo");
+        assert_eq!(short_age_from(0, 86_400 * 800), "2y");
+        // Clock skew doesn't produce nonsense
+        assert_eq!(short_age_from(200, 100), "0m");
+    }
+}
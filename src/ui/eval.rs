@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Syntax of the `cd` command printed by `--eval`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EvalShell {
+    /// bash, zsh and other POSIX shells
+    #[default]
+    Sh,
+    Fish,
+}
+
+static EVAL: Mutex<Option<EvalShell>> = Mutex::new(None);
+/// Directory to change to, held back until the command succeeded
+static PENDING: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Print `cd` commands instead of bare paths (`--eval`)
+pub fn set_eval(shell: Option<EvalShell>) {
+    *EVAL.lock().unwrap() = shell;
+}
+
+/// Print the directory the shell should change to: for the shell wrapper,
+/// right away as a bare path. With `--eval` it becomes a `cd` command, printed
+/// by [`flush_cd`] only once the whole command succeeded, so a failure leaves
+/// `eval` nothing to run.
+pub fn print_cd(path: &Path) {
+    if EVAL.lock().unwrap().is_some() {
+        *PENDING.lock().unwrap() = Some(path.to_path_buf());
+    } else {
+        println!("{}", path.display());
+    }
+}
+
+/// Print the `cd` command held back by [`print_cd`], if any
+pub fn flush_cd() {
+    let shell = *EVAL.lock().unwrap();
+    if let (Some(shell), Some(path)) = (shell, PENDING.lock().unwrap().take()) {
+        println!("{}", cd_command(shell, &path));
+    }
+}
+
+/// A command changing to `path`, quoted so any path survives `eval`
+pub fn cd_command(shell: EvalShell, path: &Path) -> String {
+    let path = shell_quote(shell, &path.to_string_lossy());
+    match shell {
+        EvalShell::Sh => format!("cd -- {path}"),
+        EvalShell::Fish => format!("cd {path}"),
+    }
+}
+
+/// `word` in single quotes, so `shell` reads it back unchanged
+pub fn shell_quote(shell: EvalShell, word: &str) -> String {
+    match shell {
+        // Nothing is special inside single quotes but the quote itself
+        EvalShell::Sh => format!("'{}'", word.replace('\'', r"'\''")),
+        // fish unescapes \\ and \' inside single quotes
+        EvalShell::Fish => format!("'{}'", word.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_cd_command() {
+        let path = Path::new("/home/me/it's a dir");
+        assert_eq!(
+            cd_command(EvalShell::Sh, path),
+            r"cd -- '/home/me/it'\''s a dir'"
+        );
+        assert_eq!(
+            cd_command(EvalShell::Fish, path),
+            r"cd '/home/me/it\'s a dir'"
+        );
+        assert_eq!(
+            cd_command(EvalShell::Fish, Path::new(r"/tmp/back\slash")),
+            r"cd '/tmp/back\\slash'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sh_evaluates_the_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("it's a $dir `x` \\ \"y\"");
+        std::fs::create_dir_all(&target).unwrap();
+
+        let script = r#"eval "$1" && pwd"#;
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .arg("sh")
+            .arg(cd_command(EvalShell::Sh, &target))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let pwd = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(
+            Path::new(pwd.trim_end_matches('\n'))
+                .canonicalize()
+                .unwrap(),
+            target.canonicalize().unwrap()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Messages gwt prints on stderr around its actual output.
+//!
+//! Informational and progress messages go through [`info!`] and can be turned
+//! off with `--quiet` (or `GWT_QUIET=1`). Warnings go through [`warn!`] and
+//! are always shown, like errors.
+
+use crate::error::GwtError;
+use console::Term;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod eval;
+mod progress;
+mod theme;
+pub use eval::{EvalShell, cd_command, flush_cd, print_cd, set_eval, shell_quote};
+pub use progress::{Outcome, Progress};
+pub use theme::{ColorChoice, Theme, set_theme, theme};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Suppress informational messages (`--quiet`); `GWT_QUIET=1` does the same
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether informational messages are suppressed
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed) || env_enabled(std::env::var("GWT_QUIET").ok().as_deref())
+}
+
+fn env_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// `eprintln!` for status and progress messages, silent with `--quiet`
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !$crate::ui::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// `eprintln!` in yellow, for problems gwt works around
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        eprintln!("{}", ::console::style(format!($($arg)*)).yellow())
+    };
+}
+
+/// Whether a prompt failed because Ctrl-C was pressed. Prompts read keys in
+/// raw mode, so the interrupt arrives as an error rather than a signal.
+pub fn interrupted(error: &dialoguer::Error) -> bool {
+    let dialoguer::Error::IO(e) = error;
+    e.kind() == std::io::ErrorKind::Interrupted
+}
+
+/// Error of an interactive prompt. Ctrl-C becomes [`GwtError::Cancelled`],
+/// after showing the cursor the prompt hid.
+pub fn prompt_error(error: dialoguer::Error) -> anyhow::Error {
+    if interrupted(&error) {
+        let term = Term::stderr();
+        if term.is_term() {
+            let _ = term.show_cursor();
+        }
+        GwtError::Cancelled.into()
+    } else {
+        error.into()
+    }
+}
+
+pub(crate) use info;
+// `warn` alone would clash with the built-in lint attribute
+pub(crate) use warning as warn;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_error() {
+        let interrupt = dialoguer::Error::IO(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "read interrupted",
+        ));
+        let err = prompt_error(interrupt);
+        let cancelled = GwtError::find(&err).unwrap();
+        assert_eq!(cancelled.exit_code(), 130);
+        assert_eq!(cancelled.to_string(), "Cancelled.");
+
+        let other = dialoguer::Error::IO(std::io::Error::other("not a terminal"));
+        assert!(!interrupted(&other));
+        assert!(GwtError::find(&prompt_error(other)).is_none());
+    }
+
+    #[test]
+    fn test_env_enabled() {
+        assert!(env_enabled(Some("1")));
+        assert!(env_enabled(Some("true")));
+        assert!(!env_enabled(Some("0")));
+        assert!(!env_enabled(Some("")));
+        assert!(!env_enabled(None));
+    }
+}
@@ -0,0 +1,251 @@
+use super::{QUIET, is_quiet};
+use crate::utility::{Glyphs, glyphs};
+use anyhow::Result;
+use console::{Term, style};
+use std::sync::atomic::Ordering;
+
+/// How one item of an operation over several worktrees ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Succeeded,
+    /// Left alone, for the given reason
+    Skipped(String),
+    Failed(String),
+}
+
+/// Progress of an operation over several worktrees, on stderr.
+///
+/// On a terminal, each item gets a line that shows it's in progress and is
+/// then replaced by its outcome, with the item's own messages held back.
+/// Elsewhere, or for a single item, each outcome is logged as a plain line
+/// once known. Failures are shown even with `--quiet`.
+pub struct Progress {
+    total: usize,
+    /// Redraw the line of the current item; only on a terminal
+    live: bool,
+    results: Vec<(String, Outcome)>,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            live: total > 1 && !is_quiet() && Term::stderr().is_term(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Run `f` for `item` and record how it went: an error is a failure
+    pub fn run(&mut self, item: &str, f: impl FnOnce() -> Result<()>) {
+        self.run_with(item, || f().map(|()| Outcome::Succeeded));
+    }
+
+    /// Run `f` for `item` and record the outcome it returns, or a failure
+    pub fn run_with(&mut self, item: &str, f: impl FnOnce() -> Result<Outcome>) {
+        let term = Term::stderr();
+        let outcome = if self.live {
+            let _ = term.write_str(&pending_line(
+                item,
+                self.results.len() + 1,
+                self.total,
+                glyphs(),
+            ));
+            // The outcome line stands in for the item's status messages
+            let quiet = QUIET.swap(true, Ordering::Relaxed);
+            let result = f();
+            QUIET.store(quiet, Ordering::Relaxed);
+            let _ = term.clear_line();
+            result
+        } else {
+            f()
+        }
+        .unwrap_or_else(|e| Outcome::Failed(format!("{:#}", e)));
+
+        let line = if self.live {
+            live_line(item, &outcome, glyphs())
+        } else {
+            plain_line(item, &outcome)
+        };
+        if matches!(outcome, Outcome::Failed(_)) || !is_quiet() {
+            eprintln!("{}", line);
+        }
+        self.results.push((item.to_string(), outcome));
+    }
+
+    /// Number of items that failed so far
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+            .count()
+    }
+
+    /// Print the counts, and the items that didn't succeed with the reason,
+    /// after more than one item
+    pub fn print_summary(&self) {
+        if self.results.len() < 2 || is_quiet() {
+            return;
+        }
+        for line in summary(&self.results) {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+fn pending_line(item: &str, position: usize, total: usize, glyphs: &Glyphs) -> String {
+    format!(
+        "{} {} {}",
+        style(glyphs.pending).for_stderr().cyan(),
+        item,
+        style(format!("({}/{})", position, total))
+            .for_stderr()
+            .dim()
+    )
+}
+
+fn live_line(item: &str, outcome: &Outcome, glyphs: &Glyphs) -> String {
+    match outcome {
+        Outcome::Succeeded => format!("{} {}", style(glyphs.success).for_stderr().green(), item),
+        Outcome::Skipped(reason) => format!(
+            "{} {} {}",
+            style(glyphs.skipped).for_stderr().dim(),
+            item,
+            style(format!("({})", reason)).for_stderr().dim()
+        ),
+        Outcome::Failed(reason) => {
+            format!(
+                "{} {}",
+                style(glyphs.error).for_stderr().red(),
+                style(format!("{}: {}", item, reason)).for_stderr().red()
+            )
+        }
+    }
+}
+
+fn plain_line(item: &str, outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Succeeded => format!("ok {}", item),
+        Outcome::Skipped(reason) => format!("skipped {} ({})", item, reason),
+        Outcome::Failed(reason) => format!("failed {}: {}", item, reason),
+    }
+}
+
+/// A count of each outcome, then a table of the items that didn't succeed
+fn summary(results: &[(String, Outcome)]) -> Vec<String> {
+    let count = |f: fn(&Outcome) -> bool| results.iter().filter(|(_, o)| f(o)).count();
+    let mut lines = vec![format!(
+        "{} succeeded, {} skipped, {} failed",
+        count(|o| *o == Outcome::Succeeded),
+        count(|o| matches!(o, Outcome::Skipped(_))),
+        count(|o| matches!(o, Outcome::Failed(_))),
+    )];
+
+    let rest: Vec<(&str, &str, &str)> = results
+        .iter()
+        .filter_map(|(item, outcome)| match outcome {
+            Outcome::Succeeded => None,
+            Outcome::Skipped(reason) => Some(("skipped", item.as_str(), reason.as_str())),
+            Outcome::Failed(reason) => Some(("failed", item.as_str(), reason.as_str())),
+        })
+        .collect();
+    let width = rest
+        .iter()
+        .map(|(_, item, _)| item.len())
+        .max()
+        .unwrap_or(0);
+    lines.extend(
+        rest.iter()
+            .map(|(label, item, reason)| format!("  {:<7}  {:<width$}  {}", label, item, reason)),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::ASCII_GLYPHS;
+
+    fn results() -> Vec<(String, Outcome)> {
+        vec![
+            ("feature".to_string(), Outcome::Succeeded),
+            (
+                "release".to_string(),
+                Outcome::Skipped("locked".to_string()),
+            ),
+            (
+                "wip".to_string(),
+                Outcome::Failed("worktree has local changes".to_string()),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_plain_line() {
+        let lines: Vec<String> = results()
+            .iter()
+            .map(|(item, outcome)| plain_line(item, outcome))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "ok feature",
+                "skipped release (locked)",
+                "failed wip: worktree has local changes",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            summary(&results()),
+            [
+                "1 succeeded, 1 skipped, 1 failed",
+                "  skipped  release  locked",
+                "  failed   wip      worktree has local changes",
+            ]
+        );
+        assert_eq!(
+            summary(&results()[..1]),
+            ["1 succeeded, 0 skipped, 0 failed"]
+        );
+    }
+
+    #[test]
+    fn test_live_lines_use_the_glyphs() {
+        console::set_colors_enabled_stderr(false);
+        let lines: Vec<String> = results()
+            .iter()
+            .map(|(item, outcome)| live_line(item, outcome, &ASCII_GLYPHS))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "[ok] feature",
+                "- release (locked)",
+                "x wip: worktree has local changes",
+            ]
+        );
+        assert_eq!(
+            pending_line("feature", 2, 3, &ASCII_GLYPHS),
+            "... feature (2/3)"
+        );
+    }
+
+    #[test]
+    fn test_run_records_outcomes() {
+        // Not a terminal under `cargo test`, so nothing is redrawn
+        let mut progress = Progress::new(3);
+        progress.run("feature", || Ok(()));
+        progress.run("wip", || anyhow::bail!("worktree has local changes"));
+        progress.run_with("release", || Ok(Outcome::Skipped("locked".to_string())));
+        assert_eq!(progress.failed(), 1);
+        assert_eq!(
+            progress.results[1],
+            (
+                "wip".to_string(),
+                Outcome::Failed("worktree has local changes".to_string())
+            )
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::utility::{Glyphs, force_ascii, glyphs};
+use console::{Style, style};
+use dialoguer::theme::ColorfulTheme;
+use serde::{Deserialize, Serialize};
+
+/// Look of prompts and listings (`theme` in the config)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Colors, and Unicode glyphs when the locale supports them
+    #[default]
+    Default,
+    /// No colors at all
+    Plain,
+    /// ASCII glyphs such as `[ok]` and `?`, whatever the locale
+    Ascii,
+}
+
+/// When to color the output (`--color`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal, unless the theme is `plain` or
+    /// `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Apply `theme` and `--color` to everything gwt prints from here on
+pub fn set_theme(theme: Theme, color: ColorChoice) {
+    force_ascii(theme == Theme::Ascii);
+    let no_color = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+    if let Some(enabled) = colors_enabled(theme, color, no_color) {
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+}
+
+/// Whether to color the output; `None` leaves it to `console`, which colors
+/// terminals only
+fn colors_enabled(theme: Theme, color: ColorChoice, no_color: bool) -> Option<bool> {
+    match color {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto if theme == Theme::Plain || no_color => Some(false),
+        ColorChoice::Auto => None,
+    }
+}
+
+/// Theme of gwt's interactive prompts, following [`set_theme`]
+pub fn theme() -> ColorfulTheme {
+    theme_with(glyphs())
+}
+
+fn theme_with(glyphs: &Glyphs) -> ColorfulTheme {
+    let glyph = |s: &str| style(s.to_string()).for_stderr();
+    ColorfulTheme {
+        prompt_style: Style::new().for_stderr().cyan().bright(),
+        prompt_prefix: glyph("?").cyan().bright(),
+        prompt_suffix: glyph(glyphs.prompt_suffix).black().bright(),
+        success_prefix: glyph(glyphs.success).green().bright(),
+        success_suffix: glyph(glyphs.success_suffix).black().bright(),
+        error_prefix: glyph(glyphs.error).red(),
+        values_style: Style::new().for_stderr().cyan().bright(),
+        active_item_prefix: glyph(glyphs.pointer).green(),
+        checked_item_prefix: glyph(glyphs.checked).green(),
+        unchecked_item_prefix: glyph(glyphs.unchecked).magenta(),
+        picked_item_prefix: glyph(glyphs.pointer).green(),
+        ..ColorfulTheme::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::ASCII_GLYPHS;
+    use dialoguer::theme::Theme as _;
+
+    #[test]
+    fn test_ascii_theme_renders_ascii() {
+        let theme = theme_with(&ASCII_GLYPHS);
+        let mut out = String::new();
+        theme
+            .format_confirm_prompt(&mut out, "Create it?", Some(true))
+            .unwrap();
+        theme
+            .format_confirm_prompt_selection(&mut out, "Create it?", Some(true))
+            .unwrap();
+        theme
+            .format_input_prompt(&mut out, "Worktree root directory", Some("/tmp"))
+            .unwrap();
+        theme
+            .format_input_prompt_selection(&mut out, "Worktree root directory", "/tmp")
+            .unwrap();
+        theme.format_error(&mut out, "invalid input").unwrap();
+
+        assert!(out.contains("[ok]"));
+        assert!(out.contains('?'));
+        assert!(out.is_ascii(), "non-ASCII output: {out:?}");
+    }
+
+    #[test]
+    fn test_colors_enabled() {
+        use ColorChoice::*;
+        assert_eq!(colors_enabled(Theme::Default, Auto, false), None);
+        assert_eq!(colors_enabled(Theme::Plain, Auto, false), Some(false));
+        assert_eq!(colors_enabled(Theme::Default, Auto, true), Some(false));
+        assert_eq!(colors_enabled(Theme::Ascii, Never, false), Some(false));
+        // An explicit --color wins over the theme and NO_COLOR
+        assert_eq!(colors_enabled(Theme::Plain, Always, true), Some(true));
+    }
+
+    #[test]
+    fn test_theme_round_trip() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            theme: Theme,
+        }
+        for (text, theme) in [
+            ("default", Theme::Default),
+            ("plain", Theme::Plain),
+            ("ascii", Theme::Ascii),
+        ] {
+            let parsed: Wrapper = toml::from_str(&format!("theme = '{text}'")).unwrap();
+            assert_eq!(parsed.theme, theme);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use super::{AddWorktreeOptions, Git, MergeState, MergedDetection, Tracking, Worktree, Worktrees};
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The git operations the worktree commands are built on.
+///
+/// Handlers take `&dyn GitBackend` rather than creating a `Git` themselves, so
+/// tests can hand them an in-memory fake instead of a mock script on `GWT_GIT`.
+pub trait GitBackend {
+    fn get_current_branch(&self) -> Result<String>;
+    fn list_worktrees(&self) -> Result<Worktrees>;
+    fn branch_exists(&self, branch: &str) -> Result<bool>;
+    fn local_branches(&self) -> Result<Vec<String>>;
+    fn resolve_commit(&self, rev: &str) -> Result<Option<String>>;
+    fn remote_branch_exists(&self, remote_branch: &str) -> Result<bool>;
+    fn find_remote_branches_by_name(&self, name: &str) -> Result<Vec<String>>;
+    fn remote_branches_matching(&self, remote: &str, pattern: &str) -> Result<Vec<String>>;
+    fn fetch_remote(&self, remote: &str) -> Result<()>;
+    fn fetch_prune(&self, remote: &str) -> Result<()>;
+    fn remotes(&self) -> Result<Vec<String>>;
+    fn remote_url(&self, remote: &str) -> Result<Option<String>>;
+    fn remote_head(&self, remote: &str) -> Result<Option<String>>;
+    fn config_value(&self, key: &str) -> Result<Option<String>>;
+    fn pull_ff_only(&self, path: &Path) -> Result<()>;
+    fn stash_push(&self, path: &Path, message: &str) -> Result<Option<String>>;
+    fn stash_apply(&self, path: &Path, stash: &str) -> Result<()>;
+    fn stash_drop(&self, stash: &str) -> Result<()>;
+    fn create_branch(&self, branch: &str) -> Result<()>;
+    fn create_branch_from_remote(&self, local: &str, remote: &str) -> Result<()>;
+    fn add_worktree(&self, path: &str, branch: &str, options: &AddWorktreeOptions) -> Result<()>;
+    fn git_toplevel(&self) -> Result<PathBuf>;
+    fn git_common_dir(&self) -> Result<PathBuf>;
+    fn remove_worktree(&self, path: &str) -> Result<()>;
+    fn force_remove_worktree(&self, path: &str) -> Result<()>;
+    fn prune_worktrees(&self) -> Result<()>;
+    fn lock_worktree(&self, path: &str, reason: Option<&str>) -> Result<()>;
+    fn unlock_worktree(&self, path: &str) -> Result<()>;
+    fn repair_worktree(&self, path: &str) -> Result<bool>;
+    fn move_worktree(&self, from: &str, to: &str) -> Result<()>;
+    fn rename_branch(&self, old: &str, new: &str) -> Result<()>;
+    fn delete_branch(&self, branch: &str, force: bool) -> Result<()>;
+    fn delete_remote_branch(&self, remote: &str, branch: &str) -> Result<()>;
+    fn merge_state(
+        &self,
+        branch: &str,
+        base: &str,
+        detection: MergedDetection,
+    ) -> Result<MergeState>;
+    fn branch_tracking(&self) -> Result<HashMap<String, Tracking>>;
+    fn is_dirty(&self, path: &Path) -> Result<bool>;
+    fn dirty_files(&self, path: &Path) -> Result<usize>;
+
+    fn get_main_worktree(&self) -> Result<Worktree> {
+        self.list_worktrees()?
+            .into_iter()
+            .find(Worktree::is_main)
+            .ok_or_else(|| anyhow!("No worktrees found"))
+    }
+
+    fn find_worktree_by_branch(&self, branch: &str) -> Result<Option<Worktree>> {
+        Ok(self
+            .list_worktrees()?
+            .into_iter()
+            .find(|wt| wt.branch().is_some_and(|b| b == branch)))
+    }
+
+    /// The branch others are based on and merged into: `configured` (the
+    /// `default_base_branch` setting), the branch `origin/HEAD` points at,
+    /// `init.defaultBranch`, and finally the branch of the main worktree. The
+    /// two git settings only count when that branch exists locally.
+    fn default_branch(&self, configured: Option<&str>) -> Result<String> {
+        if let Some(branch) = configured.filter(|b| !b.trim().is_empty()) {
+            return Ok(branch.to_string());
+        }
+        if let Some(branch) = self.remote_head("origin")?
+            && self.branch_exists(&branch)?
+        {
+            return Ok(branch);
+        }
+        if let Some(branch) = self.config_value("init.defaultBranch")?
+            && self.branch_exists(&branch)?
+        {
+            return Ok(branch);
+        }
+        self.get_main_worktree()?
+            .branch()
+            .map(str::to_string)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Can't tell the default branch: the main worktree is detached. Set `default_base_branch` in the config."
+                )
+            })
+    }
+
+    /// A detached worktree with the commit `rev` names checked out, for
+    /// targets that aren't branches (a SHA prefix, a tag, ...)
+    fn find_detached_worktree(&self, rev: &str) -> Result<Option<Worktree>> {
+        let Some(commit) = self.resolve_commit(rev)? else {
+            return Ok(None);
+        };
+        Ok(self
+            .list_worktrees()?
+            .into_iter()
+            .find(|wt| wt.branch().is_none() && wt.head().starts_with(&commit)))
+    }
+}
+
+impl GitBackend for Git {
+    fn get_current_branch(&self) -> Result<String> {
+        Git::get_current_branch(self)
+    }
+
+    fn list_worktrees(&self) -> Result<Worktrees> {
+        Git::list_worktrees(self)
+    }
+
+    fn branch_exists(&self, branch: &str) -> Result<bool> {
+        Git::branch_exists(self, branch)
+    }
+
+    fn local_branches(&self) -> Result<Vec<String>> {
+        Git::local_branches(self)
+    }
+
+    fn resolve_commit(&self, rev: &str) -> Result<Option<String>> {
+        Git::resolve_commit(self, rev)
+    }
+
+    fn remote_branch_exists(&self, remote_branch: &str) -> Result<bool> {
+        Git::remote_branch_exists(self, remote_branch)
+    }
+
+    fn find_remote_branches_by_name(&self, name: &str) -> Result<Vec<String>> {
+        Git::find_remote_branches_by_name(self, name)
+    }
+
+    fn remote_branches_matching(&self, remote: &str, pattern: &str) -> Result<Vec<String>> {
+        Git::remote_branches_matching(self, remote, pattern)
+    }
+
+    fn fetch_remote(&self, remote: &str) -> Result<()> {
+        Git::fetch_remote(self, remote)
+    }
+
+    fn fetch_prune(&self, remote: &str) -> Result<()> {
+        Git::fetch_prune(self, remote)
+    }
+
+    fn remotes(&self) -> Result<Vec<String>> {
+        Git::remotes(self)
+    }
+
+    fn remote_url(&self, remote: &str) -> Result<Option<String>> {
+        Git::remote_url(self, remote)
+    }
+
+    fn remote_head(&self, remote: &str) -> Result<Option<String>> {
+        Git::remote_head(self, remote)
+    }
+
+    fn config_value(&self, key: &str) -> Result<Option<String>> {
+        Git::config_value(self, key)
+    }
+
+    fn pull_ff_only(&self, path: &Path) -> Result<()> {
+        Git::pull_ff_only(self, path)
+    }
+
+    fn stash_push(&self, path: &Path, message: &str) -> Result<Option<String>> {
+        Git::stash_push(self, path, message)
+    }
+
+    fn stash_apply(&self, path: &Path, stash: &str) -> Result<()> {
+        Git::stash_apply(self, path, stash)
+    }
+
+    fn stash_drop(&self, stash: &str) -> Result<()> {
+        Git::stash_drop(self, stash)
+    }
+
+    fn create_branch(&self, branch: &str) -> Result<()> {
+        Git::create_branch(self, branch)
+    }
+
+    fn create_branch_from_remote(&self, local: &str, remote: &str) -> Result<()> {
+        Git::create_branch_from_remote(self, local, remote)
+    }
+
+    fn add_worktree(&self, path: &str, branch: &str, options: &AddWorktreeOptions) -> Result<()> {
+        Git::add_worktree(self, path, branch, options)
+    }
+
+    fn git_toplevel(&self) -> Result<PathBuf> {
+        Git::git_toplevel(self)
+    }
+
+    fn git_common_dir(&self) -> Result<PathBuf> {
+        Git::git_common_dir(self)
+    }
+
+    fn remove_worktree(&self, path: &str) -> Result<()> {
+        Git::remove_worktree(self, path)
+    }
+
+    fn force_remove_worktree(&self, path: &str) -> Result<()> {
+        Git::force_remove_worktree(self, path)
+    }
+
+    fn prune_worktrees(&self) -> Result<()> {
+        Git::prune_worktrees(self)
+    }
+
+    fn lock_worktree(&self, path: &str, reason: Option<&str>) -> Result<()> {
+        Git::lock_worktree(self, path, reason)
+    }
+
+    fn unlock_worktree(&self, path: &str) -> Result<()> {
+        Git::unlock_worktree(self, path)
+    }
+
+    fn repair_worktree(&self, path: &str) -> Result<bool> {
+        Git::repair_worktree(self, path)
+    }
+
+    fn move_worktree(&self, from: &str, to: &str) -> Result<()> {
+        Git::move_worktree(self, from, to)
+    }
+
+    fn rename_branch(&self, old: &str, new: &str) -> Result<()> {
+        Git::rename_branch(self, old, new)
+    }
+
+    fn delete_branch(&self, branch: &str, force: bool) -> Result<()> {
+        Git::delete_branch(self, branch, force)
+    }
+
+    fn delete_remote_branch(&self, remote: &str, branch: &str) -> Result<()> {
+        Git::delete_remote_branch(self, remote, branch)
+    }
+
+    fn merge_state(
+        &self,
+        branch: &str,
+        base: &str,
+        detection: MergedDetection,
+    ) -> Result<MergeState> {
+        Git::merge_state(self, branch, base, detection)
+    }
+
+    fn branch_tracking(&self) -> Result<HashMap<String, Tracking>> {
+        Git::branch_tracking(self)
+    }
+
+    fn is_dirty(&self, path: &Path) -> Result<bool> {
+        Git::is_dirty(self, path)
+    }
+
+    fn dirty_files(&self, path: &Path) -> Result<usize> {
+        Git::dirty_files(self, path)
+    }
+}
@@ -0,0 +1,449 @@
+use anyhow::{Result, bail};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How the output of an external command is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Collect stdout and stderr and return them to the caller
+    Capture,
+    /// Forward the child's stdout to our stderr so gwt's stdout stays clean
+    Stream,
+    /// Let the child use the terminal directly (editors, pickers)
+    Inherit,
+    /// Forward stdout and stderr line by line, each line starting with the
+    /// configured prefix. Lines from concurrent commands never interleave.
+    Prefixed,
+    /// Collect stdout and leave stderr on the terminal, for pickers that draw
+    /// there while printing the choice on stdout
+    CaptureStdout,
+}
+
+impl OutputMode {
+    /// Whether the command may read from the terminal
+    fn interactive(self) -> bool {
+        matches!(self, OutputMode::Inherit | OutputMode::CaptureStdout)
+    }
+}
+
+/// Options for running a user or repo controlled command (hooks, editors, pickers, ...)
+///
+/// These are deliberately separate from the git invocation settings: external
+/// commands get their own timeout, and a timeout kills the whole process group
+/// so that children spawned by a shell script die as well.
+#[derive(Debug, Clone)]
+pub struct ExternalOptions {
+    timeout: Option<Duration>,
+    output: OutputMode,
+    prefix: String,
+    env: Vec<(OsString, OsString)>,
+    input: Option<String>,
+}
+
+/// Result of a finished external command
+#[derive(Debug)]
+pub struct ExternalOutput {
+    pub status: ExitStatus,
+    /// Captured stdout, empty unless the output mode is `Capture` or
+    /// `CaptureStdout`
+    pub stdout: String,
+    /// Captured stderr, empty unless the output mode is `Capture`
+    pub stderr: String,
+}
+
+impl Default for ExternalOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            output: OutputMode::Stream,
+            prefix: String::new(),
+            env: Vec::new(),
+            input: None,
+        }
+    }
+}
+
+impl ExternalOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kill the command (and everything it spawned) after `timeout`
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn output(mut self, output: OutputMode) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Line prefix for `OutputMode::Prefixed`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Inject an environment variable into the child
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Write `input` to the child's stdin instead of connecting it elsewhere
+    pub fn input(mut self, input: impl Into<String>) -> Self {
+        self.input = Some(input.into());
+        self
+    }
+}
+
+/// Run an external command with the given options.
+///
+/// The command is started in its own process group on Unix. When the timeout
+/// expires the whole group is killed and an error naming the command is returned.
+pub fn run_external(mut cmd: Command, opts: &ExternalOptions) -> Result<ExternalOutput> {
+    for (key, value) in &opts.env {
+        cmd.env(key, value);
+    }
+
+    match opts.output {
+        OutputMode::Capture | OutputMode::Prefixed => {
+            cmd.stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+        }
+        OutputMode::Stream => {
+            cmd.stdin(Stdio::null())
+                .stdout(std::io::stderr())
+                .stderr(Stdio::inherit());
+        }
+        OutputMode::Inherit => {
+            cmd.stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit());
+        }
+        OutputMode::CaptureStdout => {
+            cmd.stdin(Stdio::inherit())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit());
+        }
+    }
+    if opts.input.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+
+    // Interactive commands need to stay in the foreground process group to read
+    // from the terminal, so only detach them when we may have to kill them.
+    #[cfg(unix)]
+    if !opts.output.interactive() || opts.timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let display = describe(&cmd);
+    let mut child = cmd.spawn().map_err(|e| {
+        let message = if e.kind() == std::io::ErrorKind::NotFound {
+            format!("command '{display}' not found")
+        } else {
+            format!("failed to run '{display}'")
+        };
+        anyhow::Error::new(e).context(message)
+    })?;
+    let writer = opts.input.clone().and_then(|input| {
+        let mut stdin = child.stdin.take()?;
+        // A command may exit before reading everything; that isn't an error
+        Some(thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        }))
+    });
+
+    let (stdout_reader, stderr_reader) = if opts.output == OutputMode::Prefixed {
+        let prefix = opts.prefix.clone();
+        (
+            child
+                .stdout
+                .take()
+                .map(|r| spawn_line_forwarder(r, prefix.clone(), Target::Stdout)),
+            child
+                .stderr
+                .take()
+                .map(|r| spawn_line_forwarder(r, prefix, Target::Stderr)),
+        )
+    } else {
+        (
+            child.stdout.take().map(spawn_reader),
+            child.stderr.take().map(spawn_reader),
+        )
+    };
+
+    let status = wait_with_timeout(&mut child, opts.timeout);
+
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    let stdout = stdout_reader
+        .map(|h| h.join().unwrap_or_default())
+        .unwrap_or_default();
+    let stderr = stderr_reader
+        .map(|h| h.join().unwrap_or_default())
+        .unwrap_or_default();
+
+    match status? {
+        Some(status) => Ok(ExternalOutput {
+            status,
+            stdout,
+            stderr,
+        }),
+        None => bail!(
+            "'{display}' timed out after {}s",
+            opts.timeout.unwrap_or_default().as_secs_f32()
+        ),
+    }
+}
+
+/// `Command::output` with a deadline, for git invocations. On timeout the
+/// process group is killed (so helpers like `git-remote-https` die too) and an
+/// error of kind `TimedOut` is returned.
+pub(super) fn output_with_timeout(cmd: &mut Command, timeout: Duration) -> std::io::Result<Output> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let mut child = cmd.spawn()?;
+    let read_all = |mut reader: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = reader.read_to_end(&mut buf);
+            buf
+        })
+    };
+    let stdout = child.stdout.take().map(|r| read_all(Box::new(r)));
+    let stderr = child.stderr.take().map(|r| read_all(Box::new(r)));
+
+    let status = wait_with_timeout(&mut child, Some(timeout)).map_err(std::io::Error::other)?;
+    let collect = |h: Option<thread::JoinHandle<Vec<u8>>>| {
+        h.map(|h| h.join().unwrap_or_default()).unwrap_or_default()
+    };
+    let (stdout, stderr) = (collect(stdout), collect(stderr));
+
+    match status {
+        Some(status) => Ok(Output {
+            status,
+            stdout,
+            stderr,
+        }),
+        None => Err(std::io::ErrorKind::TimedOut.into()),
+    }
+}
+
+/// Wait for the child to exit. Returns `None` when the timeout expired and the
+/// process group was killed.
+fn wait_with_timeout(child: &mut Child, timeout: Option<Duration>) -> Result<Option<ExitStatus>> {
+    let Some(timeout) = timeout else {
+        return Ok(Some(child.wait()?));
+    };
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            kill_process_group(child);
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    // The child is the leader of its own process group (see `process_group(0)`),
+    // so its pid is also the group id.
+    let pgid = child.id() as libc::pid_t;
+    unsafe {
+        libc::killpg(pgid, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+fn spawn_reader<R: Read + Send + 'static>(mut reader: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = reader.read_to_end(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    Stdout,
+    Stderr,
+}
+
+/// Whether `err` is `run_external` failing to find the program
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Copy lines from `reader` to our stdout or stderr, each starting with
+/// `prefix`. Every line is written while holding the stream lock, so output
+/// of commands running in parallel stays line-atomic.
+fn spawn_line_forwarder<R: Read + Send + 'static>(
+    reader: R,
+    prefix: String,
+    target: Target,
+) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\n', '\r']);
+            let _ = match target {
+                Target::Stdout => writeln!(std::io::stdout().lock(), "{prefix}{text}"),
+                Target::Stderr => writeln!(std::io::stderr().lock(), "{prefix}{text}"),
+            };
+            line.clear();
+        }
+        String::new()
+    })
+}
+
+fn describe(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|s| s.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        cmd
+    }
+
+    #[test]
+    fn test_run_external_captures_output() {
+        let opts = ExternalOptions::new().output(OutputMode::Capture);
+        let output = run_external(sh("echo out; echo err >&2"), &opts).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[test]
+    fn test_run_external_injects_env() {
+        let opts = ExternalOptions::new()
+            .output(OutputMode::Capture)
+            .env("GWT_BRANCH", "feature");
+        let output = run_external(sh("printf %s \"$GWT_BRANCH\""), &opts).unwrap();
+        assert_eq!(output.stdout, "feature");
+    }
+
+    #[test]
+    fn test_run_external_reports_exit_status() {
+        let opts = ExternalOptions::new().output(OutputMode::Capture);
+        let output = run_external(sh("exit 7"), &opts).unwrap();
+        assert_eq!(output.status.code(), Some(7));
+    }
+
+    #[test]
+    fn test_run_external_not_found() {
+        let opts = ExternalOptions::new().output(OutputMode::Capture);
+        let err = run_external(Command::new("gwt-no-such-command"), &opts).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(is_not_found(&err));
+        assert!(!is_not_found(&anyhow::anyhow!("command 'x' not found")));
+    }
+
+    #[test]
+    fn test_run_external_writes_input() {
+        let opts = ExternalOptions::new()
+            .output(OutputMode::CaptureStdout)
+            .input("b\na\n");
+        let output = run_external(Command::new("sort"), &opts).unwrap();
+        assert_eq!(output.stdout, "a\nb\n");
+    }
+
+    #[cfg(target_os = "linux")]
+    fn process_alive(pid: &str) -> bool {
+        // Killed processes may linger as zombies until reaped, which counts as gone
+        std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .map(|stat| {
+                stat.rsplit(')')
+                    .next()
+                    .is_some_and(|rest| !rest.trim_start().starts_with('Z'))
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_external_timeout_kills_grandchildren() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("grandchild.pid");
+        let script = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+
+        let opts = ExternalOptions::new()
+            .output(OutputMode::Capture)
+            .timeout(Some(Duration::from_millis(300)));
+        let start = Instant::now();
+        let err = run_external(sh(&script), &opts).unwrap_err();
+
+        assert!(err.to_string().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let gone = (0..100).any(|_| {
+            if process_alive(pid) {
+                thread::sleep(POLL_INTERVAL);
+                false
+            } else {
+                true
+            }
+        });
+        assert!(gone, "grandchild {pid} survived the timeout");
+    }
+
+    #[test]
+    fn test_output_with_timeout() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2"]);
+        let output = output_with_timeout(&mut cmd, Duration::from_secs(10)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        // A grandchild holding the pipes open dies with the group
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "sleep 5 & sleep 5"]);
+        let start = Instant::now();
+        let err = output_with_timeout(&mut cmd, Duration::from_millis(200)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+}
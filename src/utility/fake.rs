@@ -0,0 +1,477 @@
+use super::{
+    AddWorktreeOptions, GitBackend, MergeState, MergedDetection, Tracking, Worktree, Worktrees,
+    ref_pattern_matches,
+};
+use crate::error::GwtError;
+use anyhow::{Result, bail};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// In-memory repository for handler tests.
+///
+/// Mutating operations update the state the way git would and are recorded in
+/// `calls()`, e.g. `"add_worktree /store/abc feature"`, so tests can assert on
+/// what a command did without a mock script or touching `GWT_GIT`.
+pub struct FakeGit {
+    toplevel: PathBuf,
+    current_branch: String,
+    worktrees: RefCell<Vec<Worktree>>,
+    branches: RefCell<Vec<String>>,
+    remote_branches: Vec<String>,
+    remote_urls: Vec<(String, String)>,
+    /// Branch `refs/remotes/<remote>/HEAD` points at, per remote
+    remote_heads: Vec<(String, String)>,
+    git_config: Vec<(String, String)>,
+    merge_states: Vec<(String, MergeState)>,
+    tracking: HashMap<String, Tracking>,
+    /// Worktrees with uncommitted changes
+    dirty: Vec<PathBuf>,
+    /// Operations that fail, with the stderr git would print
+    failing: Vec<(&'static str, String)>,
+    calls: RefCell<Vec<String>>,
+}
+
+impl FakeGit {
+    /// A repository whose main worktree at `toplevel` has `branch` checked out;
+    /// an empty `branch` means a detached HEAD
+    pub fn new(toplevel: impl Into<PathBuf>, branch: &str) -> Self {
+        let toplevel = toplevel.into();
+        let main = Worktree::new(
+            toplevel.clone(),
+            "0".repeat(40),
+            Some(branch.to_string()).filter(|b| !b.is_empty()),
+        )
+        .into_main();
+        Self {
+            toplevel,
+            current_branch: branch.to_string(),
+            branches: RefCell::new(main.branch().map(str::to_string).into_iter().collect()),
+            worktrees: RefCell::new(vec![main]),
+            remote_branches: Vec::new(),
+            remote_urls: Vec::new(),
+            remote_heads: Vec::new(),
+            git_config: Vec::new(),
+            merge_states: Vec::new(),
+            tracking: HashMap::new(),
+            dirty: Vec::new(),
+            failing: Vec::new(),
+            calls: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn with_branch(self, branch: &str) -> Self {
+        self.branches.borrow_mut().push(branch.to_string());
+        self
+    }
+
+    /// A linked worktree at `path` with `branch` checked out
+    pub fn with_worktree(self, path: impl Into<PathBuf>, branch: &str) -> Self {
+        self.push_worktree(Worktree::new(
+            path.into(),
+            "1".repeat(40),
+            Some(branch.into()),
+        ))
+    }
+
+    pub fn with_detached_worktree(self, path: impl Into<PathBuf>) -> Self {
+        self.push_worktree(Worktree::new(path.into(), "1".repeat(40), None))
+    }
+
+    /// A detached worktree at `path` with the commit `head` checked out
+    pub fn with_detached_worktree_at(self, path: impl Into<PathBuf>, head: &str) -> Self {
+        self.push_worktree(Worktree::new(path.into(), head.to_string(), None))
+    }
+
+    pub fn with_locked_worktree(
+        self,
+        path: impl Into<PathBuf>,
+        branch: &str,
+        reason: &str,
+    ) -> Self {
+        self.push_worktree(
+            Worktree::new(path.into(), "1".repeat(40), Some(branch.into()))
+                .with_lock(reason.to_string()),
+        )
+    }
+
+    /// A remote-tracking branch such as `origin/feature`
+    pub fn with_remote_branch(mut self, remote_branch: &str) -> Self {
+        self.remote_branches.push(remote_branch.to_string());
+        self
+    }
+
+    /// `remote` is configured with `url`
+    pub fn with_remote_url(mut self, remote: &str, url: &str) -> Self {
+        self.remote_urls.push((remote.to_string(), url.to_string()));
+        self
+    }
+
+    /// `refs/remotes/<remote>/HEAD` points at `<remote>/<branch>`
+    pub fn with_remote_head(mut self, remote: &str, branch: &str) -> Self {
+        self.remote_heads
+            .push((remote.to_string(), branch.to_string()));
+        self
+    }
+
+    /// The git config `key` is set to `value`
+    pub fn with_git_config(mut self, key: &str, value: &str) -> Self {
+        self.git_config.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// What `merge_state` reports for `branch`; anything else is not merged
+    pub fn with_merge_state(mut self, branch: &str, state: MergeState) -> Self {
+        self.merge_states.push((branch.to_string(), state));
+        self
+    }
+
+    /// `branch` tracks `upstream`, whose remote branch was deleted when `gone`
+    pub fn with_upstream(mut self, branch: &str, upstream: &str, gone: bool) -> Self {
+        self.tracking.insert(
+            branch.to_string(),
+            Tracking {
+                upstream: Some(upstream.to_string()),
+                gone,
+                ..Default::default()
+            },
+        );
+        self
+    }
+
+    /// Give the worktree at `path` uncommitted changes
+    pub fn with_dirty(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dirty.push(path.into());
+        self
+    }
+
+    /// Make the named operation (e.g. `"add_worktree"`) fail
+    pub fn failing(self, operation: &'static str) -> Self {
+        self.failing_with(operation, "fatal: failed")
+    }
+
+    /// Make the named operation fail the way git does, with `stderr`
+    pub fn failing_with(mut self, operation: &'static str, stderr: &str) -> Self {
+        self.failing.push((operation, stderr.to_string()));
+        self
+    }
+
+    /// The mutating operations performed so far, in order
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+
+    fn push_worktree(self, worktree: Worktree) -> Self {
+        if let Some(branch) = worktree.branch() {
+            self.branches.borrow_mut().push(branch.to_string());
+        }
+        self.worktrees.borrow_mut().push(worktree);
+        self
+    }
+
+    fn record(&self, operation: &'static str, args: &[&str]) -> Result<()> {
+        let mut call = vec![operation];
+        call.extend(args);
+        let call = call.join(" ");
+        self.calls.borrow_mut().push(call.clone());
+        match self.failing.iter().find(|(op, _)| *op == operation) {
+            Some((_, stderr)) => Err(GwtError::git_failure(
+                &self.toplevel,
+                call,
+                "exit status: 128".to_string(),
+                stderr.clone(),
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    fn set_lock(&self, path: &str, reason: Option<String>) -> Result<()> {
+        let mut worktrees = self.worktrees.borrow_mut();
+        let Some(worktree) = worktrees
+            .iter_mut()
+            .find(|wt| wt.path().as_os_str() == path)
+        else {
+            bail!("'{}' is not a working tree", path);
+        };
+        let unlocked = Worktree::new(
+            worktree.path().clone(),
+            worktree.head().to_string(),
+            worktree.branch().map(str::to_string),
+        );
+        *worktree = match reason {
+            Some(reason) => unlocked.with_lock(reason),
+            None => unlocked,
+        };
+        Ok(())
+    }
+}
+
+impl GitBackend for FakeGit {
+    fn get_current_branch(&self) -> Result<String> {
+        Ok(self.current_branch.clone())
+    }
+
+    fn list_worktrees(&self) -> Result<Worktrees> {
+        Ok(Worktrees::new(self.worktrees.borrow().clone()))
+    }
+
+    fn branch_exists(&self, branch: &str) -> Result<bool> {
+        Ok(self.branches.borrow().iter().any(|b| b == branch))
+    }
+
+    fn local_branches(&self) -> Result<Vec<String>> {
+        Ok(self.branches.borrow().clone())
+    }
+
+    /// Only knows the commits checked out in worktrees, by SHA prefix
+    fn resolve_commit(&self, rev: &str) -> Result<Option<String>> {
+        let mut heads: Vec<String> = self
+            .worktrees
+            .borrow()
+            .iter()
+            .map(|wt| wt.head().to_string())
+            .filter(|head| head.starts_with(rev))
+            .collect();
+        heads.sort();
+        heads.dedup();
+        match heads.as_slice() {
+            [] => Ok(None),
+            [head] => Ok(Some(head.clone())),
+            _ => Err(GwtError::Usage(format!("Commit prefix '{}' is ambiguous", rev)).into()),
+        }
+    }
+
+    fn remote_branch_exists(&self, remote_branch: &str) -> Result<bool> {
+        Ok(self.remote_branches.iter().any(|b| b == remote_branch))
+    }
+
+    fn find_remote_branches_by_name(&self, name: &str) -> Result<Vec<String>> {
+        Ok(self
+            .remote_branches
+            .iter()
+            .filter(|b| b.split_once('/').is_some_and(|(_, branch)| branch == name))
+            .cloned()
+            .collect())
+    }
+
+    fn remote_branches_matching(&self, remote: &str, pattern: &str) -> Result<Vec<String>> {
+        Ok(self
+            .remote_branches
+            .iter()
+            .filter(|b| {
+                b.strip_prefix(remote)
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .is_some_and(|name| ref_pattern_matches(pattern, name))
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn fetch_remote(&self, remote: &str) -> Result<()> {
+        self.record("fetch_remote", &[remote])
+    }
+
+    fn fetch_prune(&self, remote: &str) -> Result<()> {
+        self.record("fetch_prune", &[remote])
+    }
+
+    fn pull_ff_only(&self, path: &Path) -> Result<()> {
+        self.record("pull_ff_only", &[&path.to_string_lossy()])
+    }
+
+    /// Stashes the changes of worktrees marked dirty as `stash1`
+    fn stash_push(&self, path: &Path, message: &str) -> Result<Option<String>> {
+        if !self.dirty.iter().any(|p| p == path) {
+            return Ok(None);
+        }
+        self.record("stash_push", &[&path.to_string_lossy(), message])?;
+        Ok(Some("stash1".to_string()))
+    }
+
+    fn stash_apply(&self, path: &Path, stash: &str) -> Result<()> {
+        self.record("stash_apply", &[&path.to_string_lossy(), stash])
+    }
+
+    fn stash_drop(&self, stash: &str) -> Result<()> {
+        self.record("stash_drop", &[stash])
+    }
+
+    /// The remotes the remote branches belong to
+    fn remotes(&self) -> Result<Vec<String>> {
+        let mut remotes: Vec<String> = self
+            .remote_branches
+            .iter()
+            .filter_map(|b| b.split_once('/').map(|(remote, _)| remote.to_string()))
+            .collect();
+        remotes.dedup();
+        Ok(remotes)
+    }
+
+    fn remote_url(&self, remote: &str) -> Result<Option<String>> {
+        Ok(self
+            .remote_urls
+            .iter()
+            .find(|(name, _)| name == remote)
+            .map(|(_, url)| url.clone()))
+    }
+
+    fn remote_head(&self, remote: &str) -> Result<Option<String>> {
+        Ok(self
+            .remote_heads
+            .iter()
+            .find(|(name, _)| name == remote)
+            .map(|(_, branch)| branch.clone()))
+    }
+
+    fn config_value(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .git_config
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone()))
+    }
+
+    fn create_branch(&self, branch: &str) -> Result<()> {
+        self.record("create_branch", &[branch])?;
+        self.branches.borrow_mut().push(branch.to_string());
+        Ok(())
+    }
+
+    fn create_branch_from_remote(&self, local: &str, remote: &str) -> Result<()> {
+        self.record("create_branch_from_remote", &[local, remote])?;
+        self.branches.borrow_mut().push(local.to_string());
+        Ok(())
+    }
+
+    fn add_worktree(&self, path: &str, branch: &str, options: &AddWorktreeOptions) -> Result<()> {
+        self.record("add_worktree", &options.args(path, branch))?;
+        if options.track.is_some() {
+            self.branches.borrow_mut().push(branch.to_string());
+        }
+        self.worktrees.borrow_mut().push(Worktree::new(
+            PathBuf::from(path),
+            "1".repeat(40),
+            Some(branch.to_string()),
+        ));
+        Ok(())
+    }
+
+    fn git_toplevel(&self) -> Result<PathBuf> {
+        Ok(self.toplevel.clone())
+    }
+
+    fn git_common_dir(&self) -> Result<PathBuf> {
+        Ok(self.toplevel.join(".git"))
+    }
+
+    fn remove_worktree(&self, path: &str) -> Result<()> {
+        self.record("remove_worktree", &[path])?;
+        let mut worktrees = self.worktrees.borrow_mut();
+        match worktrees
+            .iter()
+            .position(|wt| wt.path().as_os_str() == path)
+        {
+            Some(index) if worktrees[index].locked().is_some() => {
+                bail!("'{}' is locked", path)
+            }
+            Some(index) => {
+                worktrees.remove(index);
+                Ok(())
+            }
+            None => bail!("'{}' is not a working tree", path),
+        }
+    }
+
+    fn force_remove_worktree(&self, path: &str) -> Result<()> {
+        self.record("force_remove_worktree", &[path])?;
+        self.worktrees
+            .borrow_mut()
+            .retain(|wt| wt.path().as_os_str() != path);
+        Ok(())
+    }
+
+    fn prune_worktrees(&self) -> Result<()> {
+        self.record("prune_worktrees", &[])
+    }
+
+    fn lock_worktree(&self, path: &str, reason: Option<&str>) -> Result<()> {
+        self.record("lock_worktree", &[path, reason.unwrap_or("")])?;
+        self.set_lock(path, Some(reason.unwrap_or("").to_string()))
+    }
+
+    fn unlock_worktree(&self, path: &str) -> Result<()> {
+        self.record("unlock_worktree", &[path])?;
+        self.set_lock(path, None)
+    }
+
+    fn repair_worktree(&self, path: &str) -> Result<bool> {
+        self.record("repair_worktree", &[path])?;
+        Ok(false)
+    }
+
+    fn move_worktree(&self, from: &str, to: &str) -> Result<()> {
+        self.record("move_worktree", &[from, to])?;
+        let mut worktrees = self.worktrees.borrow_mut();
+        let Some(worktree) = worktrees
+            .iter_mut()
+            .find(|wt| wt.path().as_os_str() == from)
+        else {
+            bail!("'{}' is not a working tree", from);
+        };
+        *worktree = Worktree::new(
+            PathBuf::from(to),
+            worktree.head().to_string(),
+            worktree.branch().map(str::to_string),
+        );
+        Ok(())
+    }
+
+    fn rename_branch(&self, old: &str, new: &str) -> Result<()> {
+        self.record("rename_branch", &[old, new])?;
+        let mut branches = self.branches.borrow_mut();
+        let Some(branch) = branches.iter_mut().find(|b| *b == old) else {
+            bail!("refname refs/heads/{} not found", old);
+        };
+        *branch = new.to_string();
+        let mut worktrees = self.worktrees.borrow_mut();
+        if let Some(worktree) = worktrees.iter_mut().find(|wt| wt.branch() == Some(old)) {
+            *worktree = Worktree::new(
+                worktree.path().clone(),
+                worktree.head().to_string(),
+                Some(new.to_string()),
+            );
+        }
+        Ok(())
+    }
+
+    fn delete_branch(&self, branch: &str, force: bool) -> Result<()> {
+        self.record("delete_branch", &[branch, if force { "-D" } else { "-d" }])?;
+        self.branches.borrow_mut().retain(|b| b != branch);
+        Ok(())
+    }
+
+    fn delete_remote_branch(&self, remote: &str, branch: &str) -> Result<()> {
+        self.record("delete_remote_branch", &[remote, branch])
+    }
+
+    fn merge_state(&self, branch: &str, _: &str, _: MergedDetection) -> Result<MergeState> {
+        Ok(self
+            .merge_states
+            .iter()
+            .find(|(b, _)| b == branch)
+            .map_or(MergeState::NotMerged, |(_, state)| *state))
+    }
+
+    fn branch_tracking(&self) -> Result<HashMap<String, Tracking>> {
+        Ok(self.tracking.clone())
+    }
+
+    fn is_dirty(&self, path: &Path) -> Result<bool> {
+        Ok(self.dirty.iter().any(|p| p == path))
+    }
+
+    fn dirty_files(&self, path: &Path) -> Result<usize> {
+        Ok(self.dirty.iter().filter(|p| *p == path).count())
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Replace `file` with `content`, creating missing parent directories. The
+/// content goes to a sibling first and is renamed into place, so a concurrent
+/// reader never sees half a file.
+pub fn write_atomic(file: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(parent) = file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty() && !p.exists())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory '{}'", parent.display()))?;
+    }
+
+    let tmp = file.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&tmp, content).with_context(|| format!("Failed to write '{}'", tmp.display()))?;
+    fs::rename(&tmp, file).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        anyhow::Error::new(e).context(format!("Failed to replace '{}'", file.display()))
+    })
+}
+
+/// Seconds since the Unix epoch, the timestamps of gwt's own files; 0 when
+/// the clock is set before it
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("nested/state.json");
+
+        write_atomic(&file, "first").unwrap();
+        write_atomic(&file, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second");
+        // Only the file itself is left behind
+        let names: Vec<_> = fs::read_dir(dir.path().join("nested"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["state.json"]);
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Decorative characters used by prompts and listings
+///
+/// Every glyph has an ASCII fallback so the output stays readable on terminals
+/// that can't render UTF-8 (minimal consoles, `LANG=C`, serial lines, ...).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Prefix of an answered prompt
+    pub success: &'static str,
+    /// Prefix of a prompt error
+    pub error: &'static str,
+    /// Separator between an unanswered prompt and its input
+    pub prompt_suffix: &'static str,
+    /// Separator between an answered prompt and its value
+    pub success_suffix: &'static str,
+    /// Marker of the highlighted item in a selection list
+    pub pointer: &'static str,
+    /// Checkbox states in multi-selection lists
+    pub checked: &'static str,
+    pub unchecked: &'static str,
+    /// Commits ahead of / behind the upstream branch in `gwt status`
+    pub ahead: &'static str,
+    pub behind: &'static str,
+    /// Branch marker in the default `gwt prompt` format
+    pub branch: &'static str,
+    /// Item skipped by an operation over several worktrees
+    pub skipped: &'static str,
+    /// Item an operation over several worktrees is working on
+    pub pending: &'static str,
+}
+
+pub const UNICODE_GLYPHS: Glyphs = Glyphs {
+    success: "✔",
+    error: "✘",
+    prompt_suffix: "›",
+    success_suffix: "·",
+    pointer: "❯",
+    checked: "✔",
+    unchecked: "⬚",
+    ahead: "↑",
+    behind: "↓",
+    branch: "⎇",
+    skipped: "–",
+    pending: "…",
+};
+
+pub const ASCII_GLYPHS: Glyphs = Glyphs {
+    success: "[ok]",
+    error: "x",
+    prompt_suffix: ">",
+    success_suffix: "-",
+    pointer: ">",
+    checked: "[x]",
+    unchecked: "[ ]",
+    ahead: "+",
+    behind: "-",
+    branch: "git:",
+    skipped: "-",
+    pending: "...",
+};
+
+static FORCE_ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Force ASCII glyphs regardless of the locale (`theme = "ascii"` in the config)
+pub fn force_ascii(enabled: bool) {
+    FORCE_ASCII.store(enabled, Ordering::Relaxed);
+}
+
+/// Glyph set for the current terminal
+pub fn glyphs() -> &'static Glyphs {
+    if FORCE_ASCII.load(Ordering::Relaxed) || !terminal_supports_utf8() {
+        &ASCII_GLYPHS
+    } else {
+        &UNICODE_GLYPHS
+    }
+}
+
+#[cfg(windows)]
+fn terminal_supports_utf8() -> bool {
+    // Windows consoles render Unicode regardless of the (usually unset) locale variables
+    true
+}
+
+#[cfg(not(windows))]
+fn terminal_supports_utf8() -> bool {
+    locale_is_utf8(|name| std::env::var(name).ok())
+}
+
+/// Whether the effective character set of the locale is UTF-8.
+///
+/// Follows the POSIX precedence: `LC_ALL` overrides `LC_CTYPE`, which overrides
+/// `LANG`. An unset locale means the "C" locale, which is ASCII.
+#[cfg_attr(windows, allow(dead_code))]
+fn locale_is_utf8(lookup: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| lookup(name))
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_locale_is_utf8() {
+        assert!(locale_is_utf8(lookup(&[("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(lookup(&[("LANG", "de_DE.utf8")])));
+        assert!(!locale_is_utf8(lookup(&[("LANG", "C")])));
+        assert!(!locale_is_utf8(lookup(&[])));
+    }
+
+    #[test]
+    fn test_locale_precedence() {
+        // LC_ALL wins over everything else
+        assert!(!locale_is_utf8(lookup(&[
+            ("LC_ALL", "POSIX"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        // LC_CTYPE wins over LANG
+        assert!(locale_is_utf8(lookup(&[
+            ("LC_CTYPE", "C.UTF-8"),
+            ("LANG", "C")
+        ])));
+        // Empty values are treated as unset
+        assert!(locale_is_utf8(lookup(&[
+            ("LC_ALL", ""),
+            ("LANG", "en_US.UTF-8")
+        ])));
+    }
+
+    #[test]
+    fn test_ascii_glyphs_are_ascii() {
+        let Glyphs {
+            success,
+            error,
+            prompt_suffix,
+            success_suffix,
+            pointer,
+            checked,
+            unchecked,
+            ahead,
+            behind,
+            branch,
+            skipped,
+            pending,
+        } = ASCII_GLYPHS;
+        for glyph in [
+            success,
+            error,
+            prompt_suffix,
+            success_suffix,
+            pointer,
+            checked,
+            unchecked,
+            ahead,
+            behind,
+            branch,
+            skipped,
+            pending,
+        ] {
+            assert!(glyph.is_ascii(), "'{glyph}' is not ASCII");
+        }
+    }
+
+    #[test]
+    fn test_force_ascii() {
+        force_ascii(true);
+        assert_eq!(glyphs(), &ASCII_GLYPHS);
+        force_ascii(false);
+    }
+}
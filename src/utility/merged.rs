@@ -0,0 +1,242 @@
+use super::Git;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How to decide whether a branch has been merged (`merged_detection` in the config)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergedDetection {
+    /// The branch tip is an ancestor of the base branch (regular merges, fast-forwards)
+    Ancestor,
+    /// The branch's changes are present on the base branch in equivalent form
+    /// (rebase merges, squash merges)
+    PatchId,
+    /// Try `ancestor` first and fall back to `patch-id`
+    #[default]
+    Auto,
+}
+
+/// Result of checking a branch against its base branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeState {
+    /// The branch tip is reachable from the base branch
+    Merged,
+    /// Equivalent changes exist on the base branch. This is a heuristic: the
+    /// content matches, but git has no record of the merge.
+    SquashMerged,
+    NotMerged,
+}
+
+impl fmt::Display for MergeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Merged => write!(f, "merged"),
+            Self::SquashMerged => write!(f, "squash-merged (probable)"),
+            Self::NotMerged => write!(f, "not merged"),
+        }
+    }
+}
+
+impl Git {
+    /// Decide whether `branch` has been merged into `base` using the given detection mode
+    pub fn merge_state(
+        &self,
+        branch: &str,
+        base: &str,
+        detection: MergedDetection,
+    ) -> Result<MergeState> {
+        if detection != MergedDetection::PatchId && self.is_ancestor(branch, base)? {
+            return Ok(MergeState::Merged);
+        }
+
+        if detection != MergedDetection::Ancestor
+            && (self.commits_applied(branch, base)? || self.squash_applied(branch, base)?)
+        {
+            return Ok(MergeState::SquashMerged);
+        }
+
+        Ok(MergeState::NotMerged)
+    }
+
+    /// Whether `branch` is an ancestor of `base`
+    pub fn is_ancestor(&self, branch: &str, base: &str) -> Result<bool> {
+        let mut command = self.command();
+        command.args(["merge-base", "--is-ancestor", branch, base]);
+        let output = super::trace::output(&mut command)
+            .map_err(|e| self.spawn_error(e, &super::trace::command_line(&command)))?;
+
+        // Exit code 1 means "not an ancestor", anything else is a real failure
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(self
+                .failure(
+                    super::trace::command_line(&command),
+                    output.status.to_string(),
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                )
+                .into()),
+        }
+    }
+
+    /// Whether every commit of `branch` has a patch-equivalent commit on `base`
+    /// (e.g. after a rebase merge). Uses `git cherry`.
+    fn commits_applied(&self, branch: &str, base: &str) -> Result<bool> {
+        let output = self.run(&["cherry", base, branch])?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines().peekable();
+        Ok(lines.peek().is_some() && lines.all(|line| line.starts_with('-')))
+    }
+
+    /// Whether the cumulative change of `branch` since its merge base was
+    /// applied to `base` as a single commit, i.e. a squash merge.
+    ///
+    /// Builds a throwaway commit holding the branch's tree on top of the merge
+    /// base and asks `git cherry` whether `base` has a patch-equivalent commit.
+    /// The commit is never referenced and gets garbage collected by git.
+    fn squash_applied(&self, branch: &str, base: &str) -> Result<bool> {
+        let merge_base = self.rev_parse(&["merge-base", base, branch])?;
+        let tree = self.rev_parse(&["rev-parse", &format!("{branch}^{{tree}}")])?;
+        let base_tree = self.rev_parse(&["rev-parse", &format!("{merge_base}^{{tree}}")])?;
+        if tree == base_tree {
+            // No changes relative to the merge base, nothing to compare
+            return Ok(false);
+        }
+
+        let squashed = self.rev_parse(&[
+            "-c",
+            "user.name=gwt",
+            "-c",
+            "user.email=gwt@localhost",
+            "commit-tree",
+            &tree,
+            "-p",
+            &merge_base,
+            "-m",
+            "gwt squash check",
+        ])?;
+
+        let output = self.run(&["cherry", base, &squashed])?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.trim_start().starts_with('-'))
+    }
+
+    fn rev_parse(&self, args: &[&str]) -> Result<String> {
+        let output = self.run(args)?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+    use std::path::Path;
+
+    /// Runs real git inside `repo` without touching GWT_GIT or the process cwd
+    fn git_in(repo: &Path) -> Git {
+        Git::with_program("git").with_cwd(repo)
+    }
+
+    /// A repo with `main` and a two-commit branch `feature` forked from it
+    fn setup_repo() -> tempfile::TempDir {
+        let repo = tempfile::tempdir().unwrap();
+        sh(
+            repo.path(),
+            "git init -q -b main && \
+             echo base > base.txt && git add . && git commit -q -m base && \
+             git checkout -q -b feature && \
+             echo one > one.txt && git add . && git commit -q -m one && \
+             echo two > two.txt && git add . && git commit -q -m two && \
+             git checkout -q main && \
+             echo other > other.txt && git add . && git commit -q -m other",
+        );
+        repo
+    }
+
+    #[test]
+    fn test_merge_state_regular_merge() {
+        let repo = setup_repo();
+        sh(repo.path(), "git merge -q --no-edit feature");
+        let git = git_in(repo.path());
+
+        for mode in [MergedDetection::Ancestor, MergedDetection::Auto] {
+            assert_eq!(
+                git.merge_state("feature", "main", mode).unwrap(),
+                MergeState::Merged
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_state_squash_merge() {
+        let repo = setup_repo();
+        sh(
+            repo.path(),
+            "git merge -q --squash feature && git commit -q -m 'feature (#1)'",
+        );
+        let git = git_in(repo.path());
+
+        assert_eq!(
+            git.merge_state("feature", "main", MergedDetection::Ancestor)
+                .unwrap(),
+            MergeState::NotMerged
+        );
+        for mode in [MergedDetection::PatchId, MergedDetection::Auto] {
+            assert_eq!(
+                git.merge_state("feature", "main", mode).unwrap(),
+                MergeState::SquashMerged
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_state_rebase_merge() {
+        let repo = setup_repo();
+        sh(repo.path(), "git cherry-pick main..feature >/dev/null");
+        let git = git_in(repo.path());
+
+        assert_eq!(
+            git.merge_state("feature", "main", MergedDetection::Auto)
+                .unwrap(),
+            MergeState::SquashMerged
+        );
+    }
+
+    #[test]
+    fn test_merge_state_not_merged() {
+        let repo = setup_repo();
+        // Only part of the branch made it to main
+        sh(repo.path(), "git cherry-pick feature~1 >/dev/null");
+        let git = git_in(repo.path());
+
+        for mode in [
+            MergedDetection::Ancestor,
+            MergedDetection::PatchId,
+            MergedDetection::Auto,
+        ] {
+            assert_eq!(
+                git.merge_state("feature", "main", mode).unwrap(),
+                MergeState::NotMerged
+            );
+        }
+    }
+
+    #[test]
+    fn test_merged_detection_from_config_value() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            mode: MergedDetection,
+        }
+
+        let parse = |s: &str| {
+            toml::from_str::<Wrapper>(&format!("mode = \"{s}\""))
+                .unwrap()
+                .mode
+        };
+        assert_eq!(parse("ancestor"), MergedDetection::Ancestor);
+        assert_eq!(parse("patch-id"), MergedDetection::PatchId);
+        assert_eq!(parse("auto"), MergedDetection::Auto);
+    }
+}
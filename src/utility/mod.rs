@@ -1,36 +1,266 @@
-use anyhow::{Result, anyhow, bail};
+use crate::error::GwtError;
+use anyhow::{Result, anyhow};
 use std::{
-    path::PathBuf,
-    process::{Command, Output},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
 
+mod backend;
+mod external;
+#[cfg(test)]
+mod fake;
+mod file;
+mod glyphs;
+mod merged;
+mod path;
+mod refname;
+mod stash;
+mod status;
+mod store;
+mod tmux;
+mod trace;
 mod worktree;
-pub use worktree::{BranchRenderMode, Worktree, Worktrees};
+pub use backend::GitBackend;
+pub use external::{ExternalOptions, OutputMode, is_not_found, run_external};
+#[cfg(test)]
+pub use fake::FakeGit;
+pub use file::{unix_now, write_atomic};
+#[cfg(test)]
+pub use glyphs::ASCII_GLYPHS;
+pub use glyphs::{Glyphs, force_ascii, glyphs};
+pub use merged::{MergeState, MergedDetection};
+pub use path::{canonical, slash_path, strip_verbatim};
+pub use refname::{ref_pattern_matches, validate_branch_name};
+pub use status::Tracking;
+pub use store::{
+    StoreOwner, in_store, pointed_repository, repository_path, scan_store, worktree_common_dir,
+};
+pub use tmux::{Tmux, TmuxMode};
+pub use trace::set_verbose;
+use worktree::parse_porcelain;
+pub use worktree::{
+    BranchRenderMode, IdColumn, MAX_BRANCH_WIDTH, RenderOptions, Worktree, Worktrees,
+};
+
+static GIT_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// Kill git commands whose output gwt captures once they run longer than
+/// `timeout` (`git_timeout_secs` in the config). `None` or zero waits forever.
+///
+/// Commands streaming progress to the terminal (clone, fetch) are exempt:
+/// the user can see them working and interrupt them.
+pub fn set_git_timeout(timeout: Option<Duration>) {
+    let secs = timeout.map_or(0, |t| t.as_secs());
+    GIT_TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+}
+
+fn git_timeout() -> Option<Duration> {
+    match GIT_TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
 
+/// Flags of `git worktree add`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AddWorktreeOptions<'a> {
+    /// Check out a branch even if it's checked out in another worktree
+    pub force: bool,
+    /// Don't populate the worktree, e.g. to set up sparse-checkout first
+    pub no_checkout: bool,
+    /// Create the branch tracking this remote branch instead of checking out
+    /// an existing branch
+    pub track: Option<&'a str>,
+}
+
+impl AddWorktreeOptions<'_> {
+    /// The arguments after `git worktree add`
+    fn args<'a>(&'a self, path: &'a str, branch: &'a str) -> Vec<&'a str> {
+        let mut args = Vec::new();
+        if self.force {
+            args.push("--force");
+        }
+        if self.no_checkout {
+            args.push("--no-checkout");
+        }
+        match self.track {
+            Some(upstream) => args.extend(["--track", "-b", branch, path, upstream]),
+            None => args.extend([path, branch]),
+        }
+        args
+    }
+}
+
+/// Output of `git --version`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitVersion {
+    /// e.g. `git version 2.39.3 (Apple Git-145)`
+    pub raw: String,
+    /// Major and minor version; `None` when `raw` isn't in the usual format
+    pub number: Option<(u32, u32)>,
+}
+
+impl GitVersion {
+    fn parse(raw: &str) -> Self {
+        Self {
+            raw: raw.trim().to_string(),
+            number: parse_version_number(raw.trim()),
+        }
+    }
+}
+
+/// Major and minor version from `git --version` output, e.g.
+/// `git version 2.39.3 (Apple Git-145)` or `git version 2.42.0.windows.1`
+fn parse_version_number(output: &str) -> Option<(u32, u32)> {
+    let version = output
+        .strip_prefix("git version ")?
+        .split_whitespace()
+        .next()?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+#[derive(Clone)]
 pub struct Git {
-    exec: String,
+    /// Program and leading arguments, e.g. `git -c core.fsmonitor=false`
+    exec: Vec<String>,
+    /// Directory git runs in (`git -C`); the process cwd when `None`
+    cwd: Option<PathBuf>,
+}
+
+impl Default for Git {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Git {
+    /// Run `$GWT_GIT` (`git` when unset) in the current directory
     pub fn new() -> Self {
         Self {
-            exec: std::env::var("GWT_GIT").unwrap_or_else(|_| "git".to_string()),
+            exec: std::env::var("GWT_GIT")
+                .map(|value| parse_exec(&value))
+                .unwrap_or_else(|_| vec!["git".to_string()]),
+            cwd: None,
+        }
+    }
+
+    /// Run `program` as git whatever `GWT_GIT` says, so tests can use a mock
+    /// script without changing the process environment
+    #[cfg(test)]
+    pub(crate) fn with_program(program: impl AsRef<Path>) -> Self {
+        Self {
+            exec: vec![program.as_ref().to_string_lossy().into_owned()],
+            cwd: None,
+        }
+    }
+
+    /// Run every command of this `Git` in `dir`, e.g. another worktree or a
+    /// repository we're not inside of
+    pub fn with_cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Program and leading arguments this `Git` runs, from `$GWT_GIT`
+    pub fn executable(&self) -> &[String] {
+        &self.exec
+    }
+
+    /// The git command with `-C <cwd>` applied, ready for arguments
+    fn command(&self) -> Command {
+        let (program, leading_args) = self.exec.split_first().expect("exec is never empty");
+        let mut command = Command::new(program);
+        command.args(leading_args);
+        if let Some(cwd) = &self.cwd {
+            command.arg("-C").arg(cwd);
+        }
+        command
+    }
+
+    /// Error for a git command that ran and failed
+    fn failure(&self, line: String, status: String, stderr: String) -> GwtError {
+        let cwd = match &self.cwd {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        GwtError::git_failure(&cwd, line, status, stderr)
+    }
+
+    /// Error for a git command that couldn't be started
+    fn spawn_error(&self, e: std::io::Error, line: &str) -> anyhow::Error {
+        if e.kind() == ErrorKind::NotFound {
+            GwtError::GitNotFound(self.exec[0].clone()).into()
+        } else {
+            anyhow!("git error: failed to run {line}: {e}")
         }
     }
 
     pub fn run(&self, args: &[&str]) -> Result<Output> {
-        let output = Command::new(&self.exec)
-            .args(args)
-            .output()
-            .map_err(|e| anyhow!("git error: {e}"))?;
+        let mut command = self.command();
+        // Nobody would see a credential prompt behind captured output, so
+        // make git fail right away instead of waiting for an answer
+        command.args(args).env("GIT_TERMINAL_PROMPT", "0");
+        let line = trace::command_line(&command);
+
+        let timeout = git_timeout();
+        let output = match timeout {
+            None => trace::output(&mut command),
+            Some(timeout) => trace::traced(&mut command, |c| {
+                external::output_with_timeout(c, timeout).map(|o| (o.status, o))
+            }),
+        }
+        .map_err(|e| match e.kind() {
+            ErrorKind::TimedOut => GwtError::GitTimedOut {
+                args: line.clone(),
+                secs: timeout.unwrap_or_default().as_secs(),
+            }
+            .into(),
+            _ => self.spawn_error(e, &line),
+        })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            bail!("git error: {stderr}");
+            return Err(self
+                .failure(line, output.status.to_string(), stderr.trim().to_string())
+                .into());
         }
         Ok(output)
     }
 
+    /// Run git with its output going to our stderr, so progress (clone,
+    /// fetch) is visible while stdout stays clean for the shell wrapper
+    pub fn run_streaming(&self, args: &[&str]) -> Result<()> {
+        let mut command = self.command();
+        command
+            .args(args)
+            .stdout(std::io::stderr())
+            .stderr(Stdio::inherit());
+        let line = trace::command_line(&command);
+
+        let status = trace::status(&mut command).map_err(|e| self.spawn_error(e, &line))?;
+
+        if !status.success() {
+            return Err(self.failure(line, status.to_string(), String::new()).into());
+        }
+        Ok(())
+    }
+
+    /// Run a single command in `dir` instead of this `Git`'s directory
+    pub fn run_in(&self, dir: &Path, args: &[&str]) -> Result<Output> {
+        self.clone().with_cwd(dir).run(args)
+    }
+
+    pub fn version(&self) -> Result<GitVersion> {
+        let output = self.run(&["--version"])?;
+        Ok(GitVersion::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     pub fn get_current_branch(&self) -> Result<String> {
         let output = self.run(&["branch", "--show-current"])?;
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -50,6 +280,38 @@ impl Git {
         Ok(stdout.lines().any(|line| line.trim() == ref_name))
     }
 
+    /// Names of all local branches
+    pub fn local_branches(&self) -> Result<Vec<String>> {
+        let output = self.run(&["for-each-ref", "--format=%(refname)", "refs/heads"])?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("refs/heads/"))
+            .map(String::from)
+            .collect())
+    }
+
+    /// Full SHA of the commit `rev` (a SHA prefix, tag, ...) names, `None`
+    /// when it names none. A SHA prefix matching several objects is an error.
+    pub fn resolve_commit(&self, rev: &str) -> Result<Option<String>> {
+        let spec = format!("{rev}^{{commit}}");
+        match self.run(&["rev-parse", "--verify", "--end-of-options", &spec]) {
+            Ok(output) => Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            )),
+            Err(e) => match GwtError::find(&e) {
+                Some(GwtError::GitFailure { stderr, .. }) if stderr.contains("is ambiguous") => {
+                    Err(GwtError::Usage(format!(
+                        "Commit prefix '{}' is ambiguous; give more characters.",
+                        rev
+                    ))
+                    .into())
+                }
+                Some(GwtError::GitFailure { .. }) => Ok(None),
+                _ => Err(e),
+            },
+        }
+    }
+
     pub fn remote_branch_exists(&self, remote_branch: &str) -> Result<bool> {
         let ref_name = format!("refs/remotes/{remote_branch}");
         let output = self.run(&["for-each-ref", "--format=%(refname)", &ref_name])?;
@@ -72,6 +334,94 @@ impl Git {
             .collect())
     }
 
+    /// Remote-tracking branches of `remote` matching `pattern` (see
+    /// `ref_pattern_matches`), e.g. `origin/release/1.0`
+    pub fn remote_branches_matching(&self, remote: &str, pattern: &str) -> Result<Vec<String>> {
+        let refs = format!("refs/remotes/{}/{}", remote, pattern);
+        let output = self.run(&["for-each-ref", "--format=%(refname)", &refs])?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("refs/remotes/"))
+            .filter(|name| *name != format!("{}/HEAD", remote))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Fetch `remote`, dropping remote-tracking branches deleted there
+    pub fn fetch_remote(&self, remote: &str) -> Result<()> {
+        self.run(&["fetch", remote, "--prune", "--quiet"])?;
+        Ok(())
+    }
+
+    /// `git fetch --prune <remote>` with its progress shown on stderr
+    pub fn fetch_prune(&self, remote: &str) -> Result<()> {
+        self.run_streaming(&["fetch", "--prune", remote])
+    }
+
+    /// `git pull --ff-only` in the worktree at `path`, with its output on stderr
+    pub fn pull_ff_only(&self, path: &Path) -> Result<()> {
+        self.clone()
+            .with_cwd(path)
+            .run_streaming(&["pull", "--ff-only"])
+    }
+
+    /// `git push <remote> --delete <branch>` with its output on stderr
+    pub fn delete_remote_branch(&self, remote: &str, branch: &str) -> Result<()> {
+        self.run_streaming(&["push", remote, "--delete", branch])
+    }
+
+    pub fn remotes(&self) -> Result<Vec<String>> {
+        let output = self.run(&["remote"])?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// URL of `remote`, or `None` when there's no such remote
+    pub fn remote_url(&self, remote: &str) -> Result<Option<String>> {
+        if !self.remotes()?.iter().any(|r| r == remote) {
+            return Ok(None);
+        }
+        let output = self.run(&["remote", "get-url", remote])?;
+        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Some(url).filter(|u| !u.is_empty()))
+    }
+
+    /// The branch `refs/remotes/<remote>/HEAD` points at, without the remote
+    /// name; `None` when it isn't set, e.g. in a repository that was never
+    /// cloned
+    pub fn remote_head(&self, remote: &str) -> Result<Option<String>> {
+        let head = format!("refs/remotes/{remote}/HEAD");
+        let output = match self.run(&["symbolic-ref", "--quiet", "--short", &head]) {
+            Ok(output) => output,
+            Err(e) if matches!(GwtError::find(&e), Some(GwtError::GitFailure { .. })) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let target = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(target
+            .strip_prefix(&format!("{remote}/"))
+            .map(str::to_string))
+    }
+
+    /// Value of the git config `key`, `None` when it isn't set
+    pub fn config_value(&self, key: &str) -> Result<Option<String>> {
+        let output = match self.run(&["config", "--get", key]) {
+            Ok(output) => output,
+            Err(e) if matches!(GwtError::find(&e), Some(GwtError::GitFailure { .. })) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Some(value).filter(|v| !v.is_empty()))
+    }
+
     pub fn create_branch(&self, branch: &str) -> Result<()> {
         self.run(&["branch", branch])?;
         Ok(())
@@ -82,15 +432,22 @@ impl Git {
         Ok(())
     }
 
-    pub fn add_worktree(&self, path: &str, branch: &str) -> Result<()> {
-        self.run(&["worktree", "add", path, branch])?;
+    pub fn add_worktree(
+        &self,
+        path: &str,
+        branch: &str,
+        options: &AddWorktreeOptions,
+    ) -> Result<()> {
+        let mut args = vec!["worktree", "add"];
+        args.extend(options.args(path, branch));
+        self.run(&args)?;
         Ok(())
     }
 
     pub fn git_toplevel(&self) -> Result<PathBuf> {
         let output = self.run(&["rev-parse", "--show-toplevel"])?;
         let stdout = String::from_utf8_lossy(&output.stdout);
-        Ok(PathBuf::from(stdout.trim()))
+        Ok(strip_verbatim(PathBuf::from(stdout.trim())))
     }
 
     pub fn remove_worktree(&self, path: &str) -> Result<()> {
@@ -98,77 +455,83 @@ impl Git {
         Ok(())
     }
 
-    pub fn delete_branch(&self, branch: &str, force: bool) -> Result<()> {
-        let flag = if force { "-D" } else { "-d" };
-        self.run(&["branch", flag, branch])?;
+    /// Remove a worktree even with local changes; for cleaning up after a failed `add`
+    pub fn force_remove_worktree(&self, path: &str) -> Result<()> {
+        self.run(&["worktree", "remove", "--force", path])?;
         Ok(())
     }
 
-    pub fn get_main_worktree(&self) -> Result<Worktree> {
-        let worktrees = self.list_worktrees()?;
-        worktrees
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("No worktrees found"))
+    pub fn prune_worktrees(&self) -> Result<()> {
+        self.run(&["worktree", "prune"])?;
+        Ok(())
     }
 
-    pub fn find_worktree_by_branch(&self, branch: &str) -> Result<Option<Worktree>> {
-        let worktrees = self.list_worktrees()?;
-        Ok(worktrees
-            .into_iter()
-            .find(|wt| wt.branch().is_some_and(|b| b == branch)))
+    pub fn lock_worktree(&self, path: &str, reason: Option<&str>) -> Result<()> {
+        match reason {
+            Some(reason) => self.run(&["worktree", "lock", "--reason", reason, path])?,
+            None => self.run(&["worktree", "lock", path])?,
+        };
+        Ok(())
     }
-}
 
-fn parse_porcelain(input: &str) -> Worktrees {
-    let mut trees = Vec::new();
+    pub fn unlock_worktree(&self, path: &str) -> Result<()> {
+        self.run(&["worktree", "unlock", path])?;
+        Ok(())
+    }
 
-    let mut current_path: Option<PathBuf> = None;
-    let mut current_head: Option<String> = None;
-    let mut current_branch: Option<String> = None;
+    /// Run `git worktree repair` for the worktree at `path`; true when git
+    /// reported links it fixed
+    pub fn repair_worktree(&self, path: &str) -> Result<bool> {
+        let output = self.run(&["worktree", "repair", path])?;
+        Ok(!output.stderr.is_empty())
+    }
 
-    for line in input.lines() {
-        let line = line.trim_end();
-        if line.is_empty() {
-            // finalize current block
-            if let (Some(path), Some(head)) = (current_path.take(), current_head.take()) {
-                trees.push(Worktree::new(path, head, current_branch.take()));
-            }
-            current_path = None;
-            current_head = None;
-            current_branch = None;
-            continue;
-        }
+    pub fn move_worktree(&self, from: &str, to: &str) -> Result<()> {
+        self.run(&["worktree", "move", from, to])?;
+        Ok(())
+    }
 
-        if let Some(rest) = line.strip_prefix("worktree ") {
-            current_path = Some(PathBuf::from(rest));
-        } else if let Some(rest) = line.strip_prefix("HEAD ") {
-            current_head = Some(rest.to_string());
-        } else if let Some(rest) = line.strip_prefix("branch ") {
-            // branch may be in the form refs/heads/<name>
-            let branch_name = rest.strip_prefix("refs/heads/").unwrap_or(rest).to_string();
-            current_branch = Some(branch_name);
-        } else if line == "detached" {
-            current_branch = None;
-        }
+    pub fn rename_branch(&self, old: &str, new: &str) -> Result<()> {
+        self.run(&["branch", "-m", old, new])?;
+        Ok(())
     }
 
-    // finalize last block if any
-    if let (Some(path), Some(head)) = (current_path.take(), current_head.take()) {
-        trees.push(Worktree::new(path, head, current_branch.take()));
+    pub fn delete_branch(&self, branch: &str, force: bool) -> Result<()> {
+        let flag = if force { "-D" } else { "-d" };
+        self.run(&["branch", flag, branch])?;
+        Ok(())
     }
+}
 
-    Worktrees::new(trees)
+/// Split `GWT_GIT` into program and leading arguments with shell quoting rules.
+///
+/// A value naming an existing file is taken verbatim, so a path containing
+/// spaces keeps working without quotes. On Windows, backslashes are kept as
+/// path separators instead of escaping the next character.
+fn parse_exec(value: &str) -> Vec<String> {
+    if Path::new(value).is_file() {
+        return vec![value.to_string()];
+    }
+    let words = if cfg!(windows) {
+        shell_words::split(&value.replace('\\', r"\\"))
+    } else {
+        shell_words::split(value)
+    };
+    match words {
+        Ok(words) if !words.is_empty() => words,
+        // Blank, as if unset
+        Ok(_) => vec!["git".to_string()],
+        // Unbalanced quotes: let spawning fail with the value as written
+        Err(_) => vec![value.to_string()],
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Mutex;
     use tempfile::tempdir;
 
-    static ENV_LOCK: Mutex<()> = Mutex::new(());
-
+    #[cfg(unix)]
     fn create_mock_git_script(script_content: &str) -> (PathBuf, tempfile::TempDir) {
         let dir = tempdir().unwrap();
         let mock_git = dir.path().join("mock-git");
@@ -183,69 +546,7 @@ mod tests {
         (mock_git, dir)
     }
 
-    #[test]
-    fn parse_porcelain_two_worktrees() {
-        let input = "worktree /path/to/main
-HEAD abc123
-branch refs/heads/main
-
-worktree /path/to/feature
-HEAD def456
-branch refs/heads/feature-branch
-";
-
-        let parsed = parse_porcelain(input);
-        assert_eq!(parsed.len(), 2);
-
-        assert_eq!(parsed[0].path(), &PathBuf::from("/path/to/main"));
-        assert_eq!(parsed[0].head(), "abc123");
-        assert_eq!(parsed[0].branch(), Some("main"));
-
-        assert_eq!(parsed[1].path(), &PathBuf::from("/path/to/feature"));
-        assert_eq!(parsed[1].head(), "def456");
-        assert_eq!(parsed[1].branch(), Some("feature-branch"));
-    }
-
-    #[test]
-    fn parse_porcelain_detached_worktree() {
-        let input = "worktree /path/to/detached
-HEAD ghi789
-detached
-";
-
-        let parsed = parse_porcelain(input);
-        assert_eq!(parsed.len(), 1);
-        assert_eq!(parsed[0].path(), &PathBuf::from("/path/to/detached"));
-        assert_eq!(parsed[0].head(), "ghi789");
-        assert_eq!(parsed[0].branch(), None);
-    }
-
-    #[test]
-    fn parse_branch_with_slash() {
-        let input = "worktree /path/to/feature
-HEAD abcabc
-branch refs/heads/feature/my-feature
-";
-
-        let parsed = parse_porcelain(input);
-        assert_eq!(parsed.len(), 1);
-        assert_eq!(parsed[0].branch(), Some("feature/my-feature"));
-    }
-
-    #[test]
-    fn parse_multiple_blocks_last_block_without_trailing_blank_correct() {
-        let input = "worktree /a
-HEAD a1
-branch refs/heads/a
-
-worktree /b
-HEAD b1
-branch refs/heads/b";
-        let parsed = parse_porcelain(input);
-        assert_eq!(parsed.len(), 2);
-        assert_eq!(parsed[1].branch(), Some("b"));
-    }
-
+    #[cfg(unix)]
     #[test]
     fn test_list_worktrees_with_mock_git() {
         let script = r#"#!/bin/sh
@@ -259,25 +560,16 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        let _guard = ENV_LOCK.lock().unwrap();
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         let wts = git.list_worktrees().unwrap();
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
-
         assert_eq!(wts.len(), 1);
         assert_eq!(wts[0].branch(), Some("main"));
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_branch_exists_true() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "for-each-ref" ] && [ "$2" = "--format=%(refname)" ] && [ "$3" = "refs/heads/existing-branch" ]; then
     echo "refs/heads/existing-branch"
@@ -288,21 +580,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.branch_exists("existing-branch").unwrap());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_branch_exists_false() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "for-each-ref" ] && [ "$2" = "--format=%(refname)" ] && [ "$3" = "refs/heads/non-existent" ]; then
     exit 0
@@ -312,21 +596,156 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
+        let git = Git::with_program(&mock_git);
+        assert!(!git.branch_exists("non-existent").unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_commit() {
+        let script = r#"#!/bin/sh
+case "$4" in
+    'v1.0^{commit}') echo 0123456789abcdef0123456789abcdef01234567 ;;
+    'abc^{commit}') echo "error: short object ID abc is ambiguous" >&2; exit 128 ;;
+    *) echo "fatal: Needed a single revision" >&2; exit 128 ;;
+esac
+"#;
+        let (mock_git, _dir) = create_mock_git_script(script);
+        let git = Git::with_program(&mock_git);
+        let tag = git.resolve_commit("v1.0");
+        let missing = git.resolve_commit("nope");
+        let ambiguous = git.resolve_commit("abc");
+
+        assert_eq!(
+            tag.unwrap().as_deref(),
+            Some("0123456789abcdef0123456789abcdef01234567")
+        );
+        assert_eq!(missing.unwrap(), None);
+        let err = ambiguous.unwrap_err();
+        assert!(matches!(GwtError::find(&err), Some(GwtError::Usage(_))));
+    }
+
+    /// A repository whose main worktree is on `master`, with local branches
+    /// `master` and `trunk`, and the given `origin/HEAD` and
+    /// `init.defaultBranch`
+    #[cfg(unix)]
+    fn default_branch_script(origin_head: Option<&str>, init_default: Option<&str>) -> String {
+        let origin_head = match origin_head {
+            Some(branch) => format!("echo origin/{branch}"),
+            None => "exit 1".to_string(),
+        };
+        let init_default = match init_default {
+            Some(branch) => format!("echo {branch}"),
+            None => "exit 1".to_string(),
+        };
+        format!(
+            r#"#!/bin/sh
+case "$1" in
+    symbolic-ref) {origin_head} ;;
+    config) {init_default} ;;
+    for-each-ref)
+        case "$3" in
+            refs/heads/master|refs/heads/trunk) echo "$3" ;;
+        esac ;;
+    worktree) printf 'worktree /src/api\nHEAD 0123456789abcdef0123456789abcdef01234567\nbranch refs/heads/master\n\n' ;;
+    *) echo "unexpected args: $@" >&2; exit 1 ;;
+esac
+"#
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_default_branch() {
+        let cases = [
+            // The configured branch wins, even over origin/HEAD
+            (Some("develop"), Some("trunk"), None, "develop"),
+            (None, Some("trunk"), Some("master"), "trunk"),
+            (None, None, Some("trunk"), "trunk"),
+            // origin/HEAD unset and init.defaultBranch naming a missing branch
+            (None, None, Some("main"), "master"),
+            (None, Some("gone"), None, "master"),
+            (None, None, None, "master"),
+        ];
+        let mut results = Vec::new();
+        for (configured, origin_head, init_default, _) in cases {
+            let (mock_git, _dir) =
+                create_mock_git_script(&default_branch_script(origin_head, init_default));
+            results.push(Git::with_program(&mock_git).default_branch(configured));
         }
 
-        let git = Git::new();
-        assert!(!git.branch_exists("non-existent").unwrap());
+        for ((configured, origin_head, init_default, expected), result) in cases.iter().zip(results)
+        {
+            assert_eq!(
+                result.unwrap(),
+                *expected,
+                "configured {configured:?}, origin/HEAD {origin_head:?}, init.defaultBranch {init_default:?}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_head_unset() {
+        let (mock_git, _dir) = create_mock_git_script(&default_branch_script(None, None));
+        let git = Git::with_program(&mock_git);
+        let head = git.remote_head("origin");
+        let init_default = git.config_value("init.defaultBranch");
+
+        assert_eq!(head.unwrap(), None);
+        assert_eq!(init_default.unwrap(), None);
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
+    #[cfg(unix)]
+    #[test]
+    fn test_add_worktree_args() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("args");
+        let (mock_git, _script_dir) =
+            create_mock_git_script(&format!("#!/bin/sh\necho \"$*\" >> '{}'\n", log.display()));
+        let git = Git::with_program(&mock_git);
+        let cases = [
+            AddWorktreeOptions::default(),
+            AddWorktreeOptions {
+                force: true,
+                ..Default::default()
+            },
+            AddWorktreeOptions {
+                no_checkout: true,
+                ..Default::default()
+            },
+            AddWorktreeOptions {
+                track: Some("origin/dev"),
+                ..Default::default()
+            },
+            AddWorktreeOptions {
+                force: true,
+                no_checkout: true,
+                track: Some("origin/dev"),
+            },
+        ];
+        for options in &cases {
+            git.add_worktree("/wt", "dev", options).unwrap();
         }
+
+        assert_eq!(
+            std::fs::read_to_string(&log)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            [
+                "worktree add /wt dev",
+                "worktree add --force /wt dev",
+                "worktree add --no-checkout /wt dev",
+                "worktree add --track -b dev /wt origin/dev",
+                "worktree add --force --no-checkout --track -b dev /wt origin/dev",
+            ]
+        );
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_create_branch() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "branch" ] && [ "$2" = "new-branch" ]; then
     exit 0
@@ -336,21 +755,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.create_branch("new-branch").is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_remove_worktree() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "worktree" ] && [ "$2" = "remove" ] && [ "$3" = "/path/to/worktree" ]; then
     exit 0
@@ -360,21 +771,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.remove_worktree("/path/to/worktree").is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_delete_branch() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "branch" ] && [ "$2" = "-d" ] && [ "$3" = "my-branch" ]; then
     exit 0
@@ -384,21 +787,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.delete_branch("my-branch", false).is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_delete_branch_force() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "branch" ] && [ "$2" = "-D" ] && [ "$3" = "my-branch" ]; then
     exit 0
@@ -408,21 +803,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.delete_branch("my-branch", true).is_ok());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_get_main_worktree() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "worktree" ] && [ "$2" = "list" ] && [ "$3" = "--porcelain" ]; then
     echo "worktree /path/to/main
@@ -439,23 +826,15 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         let main_wt = git.get_main_worktree().unwrap();
         assert_eq!(main_wt.path(), &PathBuf::from("/path/to/main"));
         assert_eq!(main_wt.branch(), Some("main"));
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_find_worktree_by_branch() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "worktree" ] && [ "$2" = "list" ] && [ "$3" = "--porcelain" ]; then
     echo "worktree /path/to/main
@@ -472,26 +851,18 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         let wt = git.find_worktree_by_branch("feature-branch").unwrap();
         assert!(wt.is_some());
         assert_eq!(wt.unwrap().path(), &PathBuf::from("/path/to/feature"));
 
         let wt2 = git.find_worktree_by_branch("non-existent").unwrap();
         assert!(wt2.is_none());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_find_remote_branches_by_name() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "for-each-ref" ] && [ "$2" = "--format=%(refname)" ] && [ "$3" = "refs/remotes/*/my-branch" ]; then
     echo "refs/remotes/origin/my-branch"
@@ -503,24 +874,16 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         let matches = git.find_remote_branches_by_name("my-branch").unwrap();
         assert_eq!(matches.len(), 2);
         assert!(matches.contains(&"origin/my-branch".to_string()));
         assert!(matches.contains(&"upstream/my-branch".to_string()));
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_remote_branch_exists_true() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "for-each-ref" ] && [ "$2" = "--format=%(refname)" ] && [ "$3" = "refs/remotes/origin/existing-remote" ]; then
     echo "refs/remotes/origin/existing-remote"
@@ -531,21 +894,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(git.remote_branch_exists("origin/existing-remote").unwrap());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_remote_branch_exists_false() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "for-each-ref" ] && [ "$2" = "--format=%(refname)" ] && [ "$3" = "refs/remotes/origin/non-existent" ]; then
     exit 0
@@ -555,21 +910,13 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(!git.remote_branch_exists("origin/non-existent").unwrap());
-
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_create_branch_from_remote() {
-        let _guard = ENV_LOCK.lock().unwrap();
         let script = r#"#!/bin/sh
 if [ "$1" = "branch" ] && [ "$2" = "--track" ] && [ "$3" = "local-branch" ] && [ "$4" = "origin/remote-branch" ]; then
     exit 0
@@ -579,18 +926,162 @@ else
 fi
 "#;
         let (mock_git, _dir) = create_mock_git_script(script);
-        unsafe {
-            std::env::set_var("GWT_GIT", &mock_git);
-        }
-
-        let git = Git::new();
+        let git = Git::with_program(&mock_git);
         assert!(
             git.create_branch_from_remote("local-branch", "origin/remote-branch")
                 .is_ok()
         );
+    }
 
-        unsafe {
-            std::env::remove_var("GWT_GIT");
-        }
+    #[cfg(unix)]
+    #[test]
+    fn test_with_cwd_passes_directory_to_git() {
+        let script = r#"#!/bin/sh
+if [ "$1" = "-C" ] && [ "$2" = "/path/to/worktree" ] && [ "$3" = "branch" ] && [ "$4" = "--show-current" ]; then
+    echo "feature"
+    exit 0
+else
+    echo "unexpected args: $@" >&2
+    exit 1
+fi
+"#;
+        let (mock_git, _dir) = create_mock_git_script(script);
+        let git = Git::with_program(&mock_git).with_cwd("/path/to/worktree");
+        let branch = git.get_current_branch();
+        // Without a directory, git runs in the process cwd
+        let plain = Git::with_program(&mock_git).get_current_branch();
+
+        assert_eq!(branch.unwrap(), "feature");
+        assert!(plain.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_in_overrides_directory_for_one_command() {
+        let script = r#"#!/bin/sh
+echo "$@"
+"#;
+        let (mock_git, _dir) = create_mock_git_script(script);
+        let git = Git::with_program(&mock_git).with_cwd("/repo");
+        let once = git.run_in(Path::new("/other"), &["status", "--porcelain"]);
+        let after = git.run(&["status"]);
+
+        let stdout = |o: Result<Output>| String::from_utf8(o.unwrap().stdout).unwrap();
+        assert_eq!(stdout(once), "-C /other status --porcelain\n");
+        assert_eq!(stdout(after), "-C /repo status\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_command_reports_argv_and_disables_prompts() {
+        let script = r#"#!/bin/sh
+echo "prompt=$GIT_TERMINAL_PROMPT" >&2
+exit 3
+"#;
+        let (mock_git, _dir) = create_mock_git_script(script);
+
+        let result = Git::with_program(&mock_git).run(&["fetch", "my remote"]);
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("fetch 'my remote' failed with"), "{err}");
+        assert!(err.contains("3"), "{err}");
+        assert!(err.ends_with("prompt=0"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_exec() {
+        let dir = tempdir().unwrap();
+        let spaced = dir.path().join("my git");
+        std::fs::write(&spaced, "").unwrap();
+        let spaced = spaced.to_str().unwrap();
+
+        assert_eq!(parse_exec("git"), ["git"]);
+        assert_eq!(
+            parse_exec("git -c core.fsmonitor=false"),
+            ["git", "-c", "core.fsmonitor=false"]
+        );
+        assert_eq!(
+            parse_exec("'/opt/my tools/git' --no-pager"),
+            ["/opt/my tools/git", "--no-pager"]
+        );
+        assert_eq!(parse_exec(spaced), [spaced]);
+        assert_eq!(parse_exec("  "), ["git"]);
+        assert_eq!(parse_exec("git 'unterminated"), ["git 'unterminated"]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_parse_exec_keeps_windows_separators() {
+        assert_eq!(
+            parse_exec(r"C:\tools\git.exe -c core.longpaths=true"),
+            [r"C:\tools\git.exe", "-c", "core.longpaths=true"]
+        );
+        assert_eq!(
+            parse_exec(r#""C:\Program Files\Git\cmd\git.exe" --no-pager"#),
+            [r"C:\Program Files\Git\cmd\git.exe", "--no-pager"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_gwt_git_leading_arguments_come_first() {
+        let script = r#"#!/bin/sh
+if [ "$1" = "-c" ] && [ "$2" = "core.fsmonitor=false" ] && [ "$3" = "-C" ] && [ "$4" = "/repo" ] && [ "$5" = "branch" ]; then
+    echo "main"
+    exit 0
+else
+    echo "unexpected args: $@" >&2
+    exit 1
+fi
+"#;
+        let (mock_git, _dir) = create_mock_git_script(script);
+        let git = Git {
+            exec: parse_exec(&format!("'{}' -c core.fsmonitor=false", mock_git.display())),
+            cwd: None,
+        };
+
+        let branch = git.with_cwd("/repo").get_current_branch();
+
+        assert_eq!(branch.unwrap(), "main");
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            GitVersion::parse("git version 2.39.5\n"),
+            GitVersion {
+                raw: "git version 2.39.5".to_string(),
+                number: Some((2, 39)),
+            }
+        );
+        assert_eq!(
+            parse_version_number("git version 2.39.3 (Apple Git-145)"),
+            Some((2, 39))
+        );
+        assert_eq!(
+            parse_version_number("git version 2.42.0.windows.1"),
+            Some((2, 42))
+        );
+        assert_eq!(parse_version_number("hub version 2.14.2"), None);
+    }
+
+    #[test]
+    fn test_missing_git_executable() {
+        let git = Git {
+            exec: parse_exec("/nonexistent/bin/git"),
+            cwd: None,
+        };
+
+        let result = git.get_current_branch();
+
+        let err = result.unwrap_err();
+        assert!(matches!(
+            GwtError::find(&err),
+            Some(GwtError::GitNotFound(_))
+        ));
+        assert_eq!(
+            err.to_string(),
+            "git executable '/nonexistent/bin/git' not found; install git or set GWT_GIT"
+        );
     }
 }
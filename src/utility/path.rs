@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+/// `path` without the `\\?\` prefix Windows puts on long and canonicalized
+/// paths, so it compares equal to the same path as git prints it.
+/// `\\?\UNC\server\share` becomes `\\server\share`; other paths are returned
+/// as they are.
+pub fn strip_verbatim(path: PathBuf) -> PathBuf {
+    let Some(s) = path.to_str() else {
+        return path;
+    };
+    if let Some(unc) = s.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{unc}"));
+    }
+    match s.strip_prefix(r"\\?\") {
+        Some(rest) => PathBuf::from(rest),
+        None => path,
+    }
+}
+
+/// `path` with symlinks resolved and without a `\\?\` prefix, or `path`
+/// itself when it can't be resolved
+pub fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .map(strip_verbatim)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `path` as a string with `/` separators, the way git prints paths on every
+/// platform. Unix paths are returned unchanged, since a backslash is a valid
+/// character in a file name there.
+pub fn slash_path(path: &Path) -> Option<String> {
+    let s = strip_verbatim(path.to_path_buf()).to_str()?.to_string();
+    Some(if cfg!(windows) {
+        s.replace('\\', "/")
+    } else {
+        s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_verbatim() {
+        let cases = [
+            (r"\\?\C:\src\api", r"C:\src\api"),
+            (r"\\?\UNC\server\share\api", r"\\server\share\api"),
+            (r"C:\src\api", r"C:\src\api"),
+            ("/src/api", "/src/api"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(strip_verbatim(PathBuf::from(path)), PathBuf::from(expected));
+        }
+    }
+
+    #[test]
+    fn test_slash_path() {
+        assert_eq!(slash_path(Path::new("/src/api")).unwrap(), "/src/api");
+        assert_eq!(slash_path(Path::new(r"\\?\C:/src")).unwrap(), "C:/src");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_slash_path_on_windows() {
+        assert_eq!(slash_path(Path::new(r"C:\src\api")).unwrap(), "C:/src/api");
+        assert_eq!(
+            slash_path(Path::new(r"\\?\C:\src\api")).unwrap(),
+            "C:/src/api"
+        );
+    }
+
+    #[test]
+    fn test_canonical_of_a_missing_path() {
+        let path = Path::new("/no/such/dir");
+        assert_eq!(canonical(path), path);
+    }
+}
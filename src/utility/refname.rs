@@ -0,0 +1,165 @@
+use crate::error::GwtError;
+
+/// Check `name` against git's rules for branch names (`git check-ref-format
+/// --branch`), so a bad name fails with a clear message before gwt creates a
+/// branch or a directory, instead of with a confusing git error halfway through.
+///
+/// Unusual but valid names, with slashes or non-ASCII characters, pass.
+pub fn validate_branch_name(name: &str) -> Result<(), GwtError> {
+    match invalid_reason(name) {
+        None => Ok(()),
+        Some(reason) => Err(GwtError::InvalidBranchName {
+            name: name.to_string(),
+            reason,
+        }),
+    }
+}
+
+fn invalid_reason(name: &str) -> Option<&'static str> {
+    if name.trim().is_empty() {
+        return Some("it is empty");
+    }
+    // Would be parsed as an option by the git commands gwt runs
+    if name.starts_with('-') {
+        return Some("it can't start with '-'");
+    }
+    if name == "@" || name == "HEAD" {
+        return Some("it is reserved by git");
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_ascii_control() || " ~^:?*[\\".contains(*c))
+    {
+        return Some(match c {
+            ' ' => "it can't contain spaces",
+            c if c.is_ascii_control() => "it can't contain control characters",
+            _ => "it can't contain any of ~ ^ : ? * [ \\",
+        });
+    }
+    if name.contains("..") {
+        return Some("it can't contain '..'");
+    }
+    if name.contains("@{") {
+        return Some("it can't contain '@{'");
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        return Some("it can't start or end with '/' or contain '//'");
+    }
+    if name.ends_with('.') {
+        return Some("it can't end with '.'");
+    }
+    if name
+        .split('/')
+        .any(|part| part.starts_with('.') || part.ends_with(".lock"))
+    {
+        return Some("no part of it can start with '.' or end with '.lock'");
+    }
+    None
+}
+
+/// Whether branch `name` matches `pattern` the way `git for-each-ref` matches
+/// refs: `*` and `?` don't cross a `/`, `**` does, and a pattern without
+/// wildcards matches the name itself and everything below it
+/// (`release` matches `release/1.0`).
+pub fn ref_pattern_matches(pattern: &str, name: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return name == pattern
+            || name
+                .strip_prefix(pattern)
+                .is_some_and(|rest| rest.starts_with('/'));
+    }
+    wildmatch(pattern.as_bytes(), name.as_bytes())
+}
+
+fn wildmatch(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern {
+        [] => name.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=name.len()).any(|i| wildmatch(rest, &name[i..])),
+        [b'*', rest @ ..] => (0..=name.len())
+            .take_while(|&i| i == 0 || name[i - 1] != b'/')
+            .any(|i| wildmatch(rest, &name[i..])),
+        [b'?', rest @ ..] => {
+            matches!(name, [c, tail @ ..] if *c != b'/' && wildmatch(rest, tail))
+        }
+        [p, rest @ ..] => matches!(name, [c, tail @ ..] if c == p && wildmatch(rest, tail)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_branch_names() {
+        for name in [
+            "main",
+            "feature/login",
+            "troy/fix-123",
+            "release-1.2",
+            "naïve-café",
+            "功能/分支",
+            "a.b",
+            "with@sign",
+        ] {
+            assert!(validate_branch_name(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_branch_names() {
+        for (name, reason) in [
+            ("", "it is empty"),
+            ("   ", "it is empty"),
+            ("-b", "it can't start with '-'"),
+            ("foo bar", "it can't contain spaces"),
+            ("a..b", "it can't contain '..'"),
+            ("a~1", "it can't contain any of ~ ^ : ? * [ \\"),
+            ("x:y", "it can't contain any of ~ ^ : ? * [ \\"),
+            ("tab\there", "it can't contain control characters"),
+            ("a@{1}", "it can't contain '@{'"),
+            ("/lead", "it can't start or end with '/' or contain '//'"),
+            ("trail/", "it can't start or end with '/' or contain '//'"),
+            ("a//b", "it can't start or end with '/' or contain '//'"),
+            ("dot.", "it can't end with '.'"),
+            (
+                "feature/.hidden",
+                "no part of it can start with '.' or end with '.lock'",
+            ),
+            (
+                "topic.lock",
+                "no part of it can start with '.' or end with '.lock'",
+            ),
+            ("@", "it is reserved by git"),
+            ("HEAD", "it is reserved by git"),
+        ] {
+            match validate_branch_name(name) {
+                Err(GwtError::InvalidBranchName { reason: r, .. }) => {
+                    assert_eq!(r, reason, "{name:?}")
+                }
+                other => panic!("{name:?} should be invalid, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_ref_pattern_matches() {
+        for (pattern, name, expected) in [
+            ("release/*", "release/1.0", true),
+            ("release/*", "release/2.0/hotfix", false),
+            ("release/**", "release/2.0/hotfix", true),
+            ("release/*", "releases", false),
+            ("rel*", "releases", true),
+            ("rel*", "release/1.0", false),
+            ("release/?.0", "release/1.0", true),
+            ("release", "release/1.0", true),
+            ("release", "release", true),
+            ("release", "releases", false),
+        ] {
+            assert_eq!(
+                ref_pattern_matches(pattern, name),
+                expected,
+                "{pattern} {name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::Git;
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+impl Git {
+    /// Stash the uncommitted changes of the worktree at `path`, untracked files
+    /// included, and return the stash commit. `None` when there was nothing to
+    /// stash.
+    pub fn stash_push(&self, path: &Path, message: &str) -> Result<Option<String>> {
+        if !self.is_dirty(path)? {
+            return Ok(None);
+        }
+        self.run_in(
+            path,
+            &["stash", "push", "--include-untracked", "-m", message],
+        )?;
+        let output = self.run_in(path, &["rev-parse", "--verify", "refs/stash"])?;
+        Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    }
+
+    /// Apply the stash commit `stash` to the worktree at `path`
+    pub fn stash_apply(&self, path: &Path, stash: &str) -> Result<()> {
+        self.run_in(path, &["stash", "apply", stash])?;
+        Ok(())
+    }
+
+    /// Drop the stash entry of the stash commit `stash`. The stash list is
+    /// shared by all worktrees, so its position may have changed since it
+    /// was pushed.
+    pub fn stash_drop(&self, stash: &str) -> Result<()> {
+        let output = self.run(&["stash", "list", "--format=%H"])?;
+        let index = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .position(|sha| sha == stash)
+            .ok_or_else(|| anyhow!("Stash {} not found", stash))?;
+        self.run(&["stash", "drop", &format!("stash@{{{}}}", index)])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn test_stash_moves_changes_between_worktrees() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "git init -q -b main repo && \
+                 echo one > repo/tracked && git -C repo add tracked && \
+                 git -C repo -c user.name=t -c user.email=t@e commit -q -m init && \
+                 git -C repo worktree add -q ../wt -b feature",
+            )
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let repo = dir.path().join("repo");
+        let wt = dir.path().join("wt");
+        let git = Git::with_program("git").with_cwd(&repo);
+
+        assert_eq!(git.stash_push(&repo, "gwt-carry").unwrap(), None);
+
+        fs::write(repo.join("tracked"), "two\n").unwrap();
+        fs::write(repo.join("new"), "untracked\n").unwrap();
+        let stash = git.stash_push(&repo, "gwt-carry").unwrap().unwrap();
+        assert!(!repo.join("new").exists());
+
+        git.stash_apply(&wt, &stash).unwrap();
+        git.stash_drop(&stash).unwrap();
+
+        assert_eq!(fs::read_to_string(wt.join("tracked")).unwrap(), "two\n");
+        assert_eq!(fs::read_to_string(wt.join("new")).unwrap(), "untracked\n");
+        assert!(git.stash_drop(&stash).is_err());
+    }
+}
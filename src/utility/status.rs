@@ -0,0 +1,106 @@
+use super::Git;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How a local branch relates to its upstream
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tracking {
+    /// Short name of the configured upstream, e.g. `origin/main`
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// An upstream is configured but its ref no longer exists, usually because
+    /// the remote branch was deleted after a merge
+    pub gone: bool,
+}
+
+impl Git {
+    /// Upstream tracking information of every local branch, keyed by branch name
+    pub fn branch_tracking(&self) -> Result<HashMap<String, Tracking>> {
+        let output = self.run(&[
+            "for-each-ref",
+            "--format=%(refname:short)%09%(upstream:short)%09%(upstream:track,nobracket)",
+            "refs/heads",
+        ])?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().filter_map(parse_tracking).collect())
+    }
+
+    /// Whether the worktree at `path` has uncommitted changes, including
+    /// untracked files
+    pub fn is_dirty(&self, path: &Path) -> Result<bool> {
+        let output = self.run_in(path, &["status", "--porcelain"])?;
+        Ok(!output.stdout.is_empty())
+    }
+
+    /// Number of files with uncommitted changes in the worktree at `path`,
+    /// untracked ones included
+    pub fn dirty_files(&self, path: &Path) -> Result<usize> {
+        let output = self.run_in(path, &["status", "--porcelain"])?;
+        Ok(String::from_utf8_lossy(&output.stdout).lines().count())
+    }
+}
+
+/// Parse one `branch<TAB>upstream<TAB>track` line of `git for-each-ref`
+fn parse_tracking(line: &str) -> Option<(String, Tracking)> {
+    let mut fields = line.splitn(3, '\t');
+    let branch = fields.next().filter(|b| !b.is_empty())?;
+    let upstream = fields.next().unwrap_or_default();
+    let track = fields.next().unwrap_or_default().trim();
+
+    let mut tracking = Tracking {
+        upstream: (!upstream.is_empty()).then(|| upstream.to_string()),
+        ..Default::default()
+    };
+
+    // Branches without an upstream have nothing to be ahead of, or gone from
+    if tracking.upstream.is_some() {
+        for part in track.split(", ") {
+            match part.split_once(' ') {
+                Some(("ahead", n)) => tracking.ahead = n.parse().unwrap_or(0),
+                Some(("behind", n)) => tracking.behind = n.parse().unwrap_or(0),
+                _ if part == "gone" => tracking.gone = true,
+                _ => {}
+            }
+        }
+    }
+
+    Some((branch.to_string(), tracking))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tracking() {
+        assert_eq!(
+            parse_tracking("main\torigin/main\tahead 2, behind 1"),
+            Some((
+                "main".to_string(),
+                Tracking {
+                    upstream: Some("origin/main".to_string()),
+                    ahead: 2,
+                    behind: 1,
+                    gone: false,
+                }
+            ))
+        );
+
+        let (_, up_to_date) = parse_tracking("feature\torigin/feature\t").unwrap();
+        assert_eq!(up_to_date.upstream.as_deref(), Some("origin/feature"));
+        assert_eq!((up_to_date.ahead, up_to_date.behind), (0, 0));
+
+        let (_, gone) = parse_tracking("old\torigin/old\tgone").unwrap();
+        assert!(gone.gone);
+    }
+
+    #[test]
+    fn test_parse_tracking_without_upstream_is_never_gone() {
+        let (branch, tracking) = parse_tracking("local-only\t\t").unwrap();
+        assert_eq!(branch, "local-only");
+        assert_eq!(tracking, Tracking::default());
+        assert_eq!(parse_tracking(""), None);
+    }
+}
@@ -0,0 +1,245 @@
+use super::{Git, canonical, strip_verbatim};
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Which repository a directory in the worktree store belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreOwner {
+    /// A worktree of the repository we're running in
+    Current,
+    /// A worktree of another repository or clone, identified by its git common dir.
+    /// Two clones of the same project are different owners.
+    OtherClone(PathBuf),
+    /// Not a worktree, or its repository no longer exists
+    Orphaned,
+}
+
+/// A directory directly under the worktree root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreEntry {
+    pub path: PathBuf,
+    pub owner: StoreOwner,
+}
+
+impl StoreEntry {
+    /// Only entries of the current repository may be modified by per-repo
+    /// operations; everything else belongs to someone else or needs a human.
+    pub fn is_mine(&self) -> bool {
+        self.owner == StoreOwner::Current
+    }
+}
+
+impl fmt::Display for StoreOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Current => write!(f, "current repository"),
+            Self::OtherClone(common_dir) => {
+                write!(f, "other clone: {}", repository_path(common_dir).display())
+            }
+            Self::Orphaned => write!(f, "orphaned"),
+        }
+    }
+}
+
+impl Git {
+    /// Absolute path of the repository's common git directory, shared by all of
+    /// its worktrees (`<repo>/.git` for a regular clone)
+    pub fn git_common_dir(&self) -> Result<PathBuf> {
+        let output = self.run(&["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(strip_verbatim(PathBuf::from(stdout.trim())))
+    }
+}
+
+/// Classify every directory under `root` by the repository it belongs to.
+///
+/// Ownership is decided by following each worktree's `.git` pointer to its
+/// common dir, never by the directory name, so clones of the same project that
+/// share a store are told apart.
+pub fn scan_store(root: &Path, current_common_dir: &Path) -> Result<Vec<StoreEntry>> {
+    let current = canonical(current_common_dir);
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(root)
+        .with_context(|| format!("Failed to read worktree root '{}'", root.display()))?
+    {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let owner = match worktree_common_dir(&path) {
+            Some(common_dir) if common_dir == current => StoreOwner::Current,
+            Some(common_dir) => StoreOwner::OtherClone(common_dir),
+            None => StoreOwner::Orphaned,
+        };
+        entries.push(StoreEntry { path, owner });
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Resolve the common git dir of the linked worktree at `worktree`.
+///
+/// A linked worktree has a `.git` file containing `gitdir: <repo>/.git/worktrees/<name>`,
+/// and that directory has a `commondir` file pointing back to `<repo>/.git`.
+/// Returns `None` when any link is missing or points to a repository that is gone.
+pub fn worktree_common_dir(worktree: &Path) -> Option<PathBuf> {
+    let gitdir = read_gitdir_pointer(worktree)?;
+    let commondir = fs::read_to_string(gitdir.join("commondir")).ok()?;
+    let common_dir = gitdir.join(commondir.trim());
+    common_dir.is_dir().then(|| canonical(&common_dir))
+}
+
+/// Path of the repository the linked worktree at `worktree` was created
+/// from, read from its `.git` pointer alone, so it's known even after that
+/// repository was moved or deleted
+pub fn pointed_repository(worktree: &Path) -> Option<PathBuf> {
+    let gitdir = read_gitdir_pointer(worktree)?;
+    // <common dir>/worktrees/<name>
+    let common_dir = gitdir.parent()?.parent()?;
+    Some(repository_path(common_dir).to_path_buf())
+}
+
+/// Read the `gitdir:` line of a worktree's `.git` file
+fn read_gitdir_pointer(worktree: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(worktree.join(".git")).ok()?;
+    let gitdir = content.lines().find_map(|l| l.strip_prefix("gitdir:"))?;
+    let gitdir = PathBuf::from(gitdir.trim());
+    // Relative pointers (worktree.useRelativePaths) are relative to the worktree
+    Some(if gitdir.is_relative() {
+        worktree.join(gitdir)
+    } else {
+        gitdir
+    })
+}
+
+/// Path users know a repository by: the clone directory, or the common dir
+/// itself for bare repositories
+pub fn repository_path(common_dir: &Path) -> &Path {
+    match common_dir.file_name() {
+        Some(name) if name == ".git" => common_dir.parent().unwrap_or(common_dir),
+        _ => common_dir,
+    }
+}
+
+/// Whether `dir`, with symlinks resolved, is inside the worktree root `root`
+pub fn in_store(dir: &Path, root: &Path) -> bool {
+    if dir.starts_with(root) {
+        return true;
+    }
+    // The current directory comes with symlinks resolved; the root may not
+    root.canonicalize()
+        .is_ok_and(|root| dir.starts_with(strip_verbatim(root)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::worktree::test_utils::sh;
+
+    #[test]
+    fn test_in_store_compares_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("store");
+        fs::create_dir_all(root.join("a1b2c3d4/src")).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&root, &link).unwrap();
+        let inside = fs::canonicalize(root.join("a1b2c3d4/src")).unwrap();
+
+        assert!(in_store(&inside, &root));
+        assert!(in_store(&inside, &link));
+        assert!(!in_store(dir.path(), &root));
+        assert!(!in_store(&dir.path().join("store-old/x"), &root));
+    }
+
+    /// Two clones of the same project ("work" and "fork") with one worktree
+    /// each in a shared store, plus a stray directory
+    fn setup_shared_store() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        sh(
+            dir.path(),
+            "git init -q -b main origin && \
+             (cd origin && echo a > a && git add . && git commit -q -m a) && \
+             git clone -q origin work/api && git clone -q origin fork/api && \
+             mkdir -p store/leftover && \
+             git -C work/api worktree add -q ../../store/1111 -b work-feature && \
+             git -C fork/api worktree add -q ../../store/2222 -b fork-feature",
+        );
+        dir
+    }
+
+    #[test]
+    fn test_worktree_common_dir_follows_gitdir_pointer() {
+        let dir = setup_shared_store();
+        let work = dir.path().join("work/api/.git").canonicalize().unwrap();
+        let fork = dir.path().join("fork/api/.git").canonicalize().unwrap();
+
+        assert_eq!(
+            worktree_common_dir(&dir.path().join("store/1111")),
+            Some(work)
+        );
+        assert_eq!(
+            worktree_common_dir(&dir.path().join("store/2222")),
+            Some(fork)
+        );
+        assert_eq!(
+            worktree_common_dir(&dir.path().join("store/leftover")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scan_store_separates_clones() {
+        let dir = setup_shared_store();
+        let root = dir.path().join("store");
+        let work = dir.path().join("work/api/.git");
+
+        let entries = scan_store(&root, &work).unwrap();
+        let owner = |name: &str| {
+            entries
+                .iter()
+                .find(|e| e.path == root.join(name))
+                .map(|e| e.owner.clone())
+                .unwrap()
+        };
+
+        assert_eq!(owner("1111"), StoreOwner::Current);
+        assert_eq!(owner("leftover"), StoreOwner::Orphaned);
+        let fork = owner("2222");
+        assert!(matches!(fork, StoreOwner::OtherClone(_)));
+        assert_eq!(
+            fork.to_string(),
+            format!(
+                "other clone: {}",
+                dir.path()
+                    .join("fork/api")
+                    .canonicalize()
+                    .unwrap()
+                    .display()
+            )
+        );
+
+        // Only the current clone's worktree is ever a candidate for changes
+        let mine: Vec<_> = entries.iter().filter(|e| e.is_mine()).collect();
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].path, root.join("1111"));
+    }
+
+    #[test]
+    fn test_scan_store_treats_deleted_repository_as_orphaned() {
+        let dir = setup_shared_store();
+        let root = dir.path().join("store");
+        fs::remove_dir_all(dir.path().join("fork")).unwrap();
+
+        let entries = scan_store(&root, &dir.path().join("work/api/.git")).unwrap();
+        let fork = entries
+            .iter()
+            .find(|e| e.path == root.join("2222"))
+            .unwrap();
+        assert_eq!(fork.owner, StoreOwner::Orphaned);
+    }
+}
@@ -0,0 +1,133 @@
+use super::{ExternalOptions, OutputMode, run_external};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Where `gwt sw --tmux` opens a worktree (`tmux_mode` in the config)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TmuxMode {
+    /// A window in the current session
+    #[default]
+    Window,
+    /// A session of its own, which the client switches to
+    Session,
+}
+
+/// A window of the current tmux session
+#[derive(Debug, PartialEq, Eq)]
+pub struct TmuxWindow {
+    pub id: String,
+    pub name: String,
+    /// Working directory of the window's active pane
+    pub path: PathBuf,
+}
+
+/// Runs tmux commands against the server of the session gwt runs in
+pub struct Tmux {
+    program: String,
+    /// How long each tmux command may run
+    timeout: Option<Duration>,
+}
+
+impl Default for Tmux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tmux {
+    pub fn new() -> Self {
+        Self {
+            program: "tmux".to_string(),
+            timeout: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_program(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            timeout: None,
+        }
+    }
+
+    /// Kill tmux commands that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether gwt runs inside a tmux session
+    pub fn is_active() -> bool {
+        std::env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+    }
+
+    pub fn list_windows(&self) -> Result<Vec<TmuxWindow>> {
+        let output = self.run(&[
+            "list-windows",
+            "-F",
+            "#{window_id}\t#{window_name}\t#{pane_current_path}",
+        ])?;
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(TmuxWindow {
+                    id: fields.next()?.to_string(),
+                    name: fields.next()?.to_string(),
+                    path: PathBuf::from(fields.next()?),
+                })
+            })
+            .collect())
+    }
+
+    pub fn new_window(&self, name: &str, path: &Path) -> Result<()> {
+        let path = path_arg(path)?;
+        self.run(&["new-window", "-c", path, "-n", name]).map(drop)
+    }
+
+    pub fn select_window(&self, id: &str) -> Result<()> {
+        self.run(&["select-window", "-t", id]).map(drop)
+    }
+
+    pub fn has_session(&self, name: &str) -> bool {
+        // `=` asks for an exact match instead of a prefix
+        self.run(&["has-session", "-t", &format!("={}", name)])
+            .is_ok()
+    }
+
+    pub fn new_session(&self, name: &str, path: &Path) -> Result<()> {
+        let path = path_arg(path)?;
+        self.run(&["new-session", "-d", "-s", name, "-c", path])
+            .map(drop)
+    }
+
+    pub fn switch_client(&self, session: &str) -> Result<()> {
+        self.run(&["switch-client", "-t", &format!("={}", session)])
+            .map(drop)
+    }
+
+    /// Run `tmux args...` and return its stdout
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(args);
+        let opts = ExternalOptions::new()
+            .output(OutputMode::Capture)
+            .timeout(self.timeout);
+        let output = run_external(cmd, &opts)?;
+        if !output.status.success() {
+            bail!("tmux {} failed: {}", args[0], output.stderr.trim_end());
+        }
+        Ok(output.stdout)
+    }
+}
+
+fn path_arg(path: &Path) -> Result<&str> {
+    match path.to_str() {
+        Some(path) => Ok(path),
+        None => bail!("Invalid worktree path '{}'", path.display()),
+    }
+}
@@ -0,0 +1,108 @@
+use std::io;
+use std::process::{Command, ExitStatus, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Log every git invocation to stderr (`--verbose`); `GWT_DEBUG=1` does the same
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+fn enabled() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+        || std::env::var("GWT_DEBUG").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// `Command::output`, logged when tracing is enabled
+pub(super) fn output(command: &mut Command) -> io::Result<Output> {
+    traced(command, |c| c.output().map(|o| (o.status, o)))
+}
+
+/// `Command::status`, logged when tracing is enabled
+pub(super) fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    traced(command, |c| c.status().map(|s| (s, s)))
+}
+
+/// Run `command` through `run`, logging it before and its exit status after
+pub(super) fn traced<T>(
+    command: &mut Command,
+    run: impl FnOnce(&mut Command) -> io::Result<(ExitStatus, T)>,
+) -> io::Result<T> {
+    if !enabled() {
+        return run(command).map(|(_, result)| result);
+    }
+
+    let line = command_line(command);
+    let cwd = command
+        .get_current_dir()
+        .map(|d| d.to_path_buf())
+        .or_else(|| std::env::current_dir().ok())
+        .map(|d| d.display().to_string())
+        .unwrap_or_default();
+    eprintln!("gwt: exec {} (cwd {})", line, cwd);
+
+    let start = Instant::now();
+    let result = run(command);
+    let elapsed = start.elapsed();
+    match &result {
+        Ok((status, _)) => eprintln!("gwt: {} {}: {}", exit(status), duration(elapsed), line),
+        Err(e) => eprintln!("gwt: error {}: {}: {}", duration(elapsed), line, e),
+    }
+    result.map(|(_, result)| result)
+}
+
+/// Program and arguments, quoted so the line can be pasted into a shell
+pub(super) fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,^{}".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn exit(status: &ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit {}", code),
+        None => format!("exit {}", status),
+    }
+}
+
+fn duration(elapsed: Duration) -> String {
+    format!("after {}ms", elapsed.as_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_quotes_when_needed() {
+        let mut command = Command::new("git");
+        command.args([
+            "-C",
+            "/store/my repo",
+            "log",
+            "--format=%(refname)",
+            "it's",
+            "",
+        ]);
+        assert_eq!(
+            command_line(&command),
+            r#"git -C '/store/my repo' log '--format=%(refname)' 'it'\''s' ''"#
+        );
+    }
+}
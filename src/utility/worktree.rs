@@ -1,14 +1,26 @@
-use console::style;
-use std::path::PathBuf;
-
-const MAX_BRANCH_WIDTH: usize = 32;
+use super::Tracking;
+use crate::error::GwtError;
+use anyhow::Result;
+use console::{Style, measure_text_width, style};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Widest branch column `gwt ls` shows on a terminal
+pub const MAX_BRANCH_WIDTH: usize = 32;
+const GONE_TAG: &str = "[gone]";
+const MISSING_TAG: &str = "[missing]";
 
 /// Representation of a Git worktree
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Worktree {
     path: PathBuf,
     head: String,
     branch: Option<String>,
+    /// Lock reason (empty when locked without one), `None` when not locked
+    locked: Option<String>,
+    /// The repository's main worktree, which gwt never removes unasked
+    is_main: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -17,9 +29,73 @@ pub enum BranchRenderMode {
     Truncated(usize),
 }
 
+/// Options controlling how a worktree is rendered by `gwt ls`
+#[derive(Clone, Copy)]
+pub struct RenderOptions<'a> {
+    pub branch_mode: BranchRenderMode,
+    /// Show the directory id column for worktrees managed under this root
+    pub ids: Option<IdColumn<'a>>,
+    /// Tag branches by upstream state (`gwt ls --status`), keyed by branch name
+    pub tracking: Option<&'a HashMap<String, Tracking>>,
+    /// Show how long ago each worktree was last used (`gwt ls --age`), from
+    /// timestamps keyed by worktree path
+    pub ages: Option<&'a BTreeMap<PathBuf, u64>>,
+    /// Elide the middle of longer paths to this width (truncated mode only)
+    pub path_width: Option<usize>,
+    /// Worktrees whose directory is gone, shown in red and tagged [missing]
+    pub missing: Option<&'a HashSet<PathBuf>>,
+}
+
+#[derive(Clone, Copy)]
+pub struct IdColumn<'a> {
+    pub root: &'a Path,
+    pub width: usize,
+}
+
+impl RenderOptions<'_> {
+    pub fn new(branch_mode: BranchRenderMode) -> Self {
+        Self {
+            branch_mode,
+            ids: None,
+            tracking: None,
+            ages: None,
+            path_width: None,
+            missing: None,
+        }
+    }
+}
+
 impl Worktree {
     pub fn new(path: PathBuf, head: String, branch: Option<String>) -> Self {
-        Self { path, head, branch }
+        Self {
+            path,
+            head,
+            branch,
+            locked: None,
+            is_main: false,
+        }
+    }
+
+    /// Mark the worktree as the repository's main worktree
+    pub fn into_main(mut self) -> Self {
+        self.is_main = true;
+        self
+    }
+
+    /// Whether this is the main worktree: the first non-bare one git lists
+    pub fn is_main(&self) -> bool {
+        self.is_main
+    }
+
+    /// Mark the worktree as locked, as reported by `git worktree list`
+    pub fn with_lock(mut self, reason: String) -> Self {
+        self.locked = Some(reason);
+        self
+    }
+
+    /// Return the lock reason if the worktree is locked (empty if no reason was given)
+    pub fn locked(&self) -> Option<&str> {
+        self.locked.as_deref()
     }
 
     /// Return the worktree path
@@ -28,7 +104,6 @@ impl Worktree {
     }
 
     /// Return the head SHA
-    #[allow(dead_code)]
     pub fn head(&self) -> &str {
         &self.head
     }
@@ -38,43 +113,171 @@ impl Worktree {
         self.branch.as_deref()
     }
 
-    pub fn render(&self, current: &Option<PathBuf>, branch_mode: BranchRenderMode) -> String {
+    /// Return the directory id of a worktree managed under `root`: the final
+    /// component of its path (e.g. the hash directory name)
+    pub fn id(&self, root: &Path) -> Option<&str> {
+        if self.path == root || !self.path.starts_with(root) {
+            return None;
+        }
+        self.path.file_name().and_then(|name| name.to_str())
+    }
+
+    pub fn render(&self, current: &Option<PathBuf>, options: &RenderOptions) -> String {
         let is_active = current.as_ref().is_some_and(|cw| cw == self.path());
         let commit = style(&self.head()[..7.min(self.head().len())]).green();
         let branch = self.branch().unwrap_or("(detached)");
-        let path = style(self.path().display()).cyan();
+        let is_missing = options.missing.is_some_and(|m| m.contains(self.path()));
+        let path_style = if is_missing {
+            Style::new().red()
+        } else {
+            Style::new().cyan()
+        };
+        let path = path_style.apply_to(self.path().display().to_string());
+        let id = options
+            .ids
+            .map(|ids| (self.id(ids.root).unwrap_or(""), ids.width));
+        let lock = self
+            .lock_tag()
+            .map(|tag| format!(" {}", style(tag).yellow()))
+            .unwrap_or_default();
+        let gone = if self.is_gone(options.tracking) {
+            format!(" {}", style(GONE_TAG).dim())
+        } else {
+            String::new()
+        };
+        let missing = if is_missing {
+            format!(" {}", style(MISSING_TAG).red())
+        } else {
+            String::new()
+        };
+        let tags = missing + &lock + &gone;
+        let age = options.ages.map(|ages| {
+            ages.get(self.path())
+                .map_or_else(|| "-".to_string(), |t| crate::meta::short_age(*t))
+        });
 
-        match branch_mode {
+        match options.branch_mode {
             BranchRenderMode::Full => {
                 // * b1f0fed fix/issue-76
                 //   /Users/troydai/.gwt_store/69fa950d86b47897
-                // - 5a37e92 main
+                // M 5a37e92 main
                 //   /Users/troydai/code/github.com/troydai/gwt
-                let marker = if is_active { "*" } else { "-" };
-                format!("{} {} {}\n  {}", marker, commit, branch, path)
+                let marker = self.marker(is_active).unwrap_or("-");
+                let id = id
+                    .filter(|(id, _)| !id.is_empty())
+                    .map(|(id, _)| format!(" {}", style(id).dim()))
+                    .unwrap_or_default();
+                let age = age
+                    .map(|age| format!(" {}", style(age).dim()))
+                    .unwrap_or_default();
+                format!(
+                    "{} {} {}{}{}{}\n  {}",
+                    marker, commit, branch, id, age, tags, path
+                )
             }
             BranchRenderMode::Truncated(width) => {
-                // truncates the branch name
-                let branch_name = if branch.len() <= width {
-                    branch.to_string()
-                } else {
-                    format!("{}..", &branch[..width - 2]) // TODO: I think this is wrong
+                let branch_name = truncate_end(branch, width);
+                let path = match options.path_width {
+                    Some(path_width) => path_style
+                        .apply_to(elide_middle(&self.path().display().to_string(), path_width)),
+                    None => path,
                 };
 
                 // * b1f0fed fix/issue-76 /Users/troydai/.gwt_store/69fa950d86b47897
-                //   5a37e92 main         /Users/troydai/code/github.com/troydai/gwt
-                let marker = if is_active { "*" } else { " " };
+                // M 5a37e92 main         /Users/troydai/code/github.com/troydai/gwt
+                let marker = self.marker(is_active).unwrap_or(" ");
+                let id = id
+                    .map(|(id, width)| format!("{} ", style(format!("{id:<width$}")).dim()))
+                    .unwrap_or_default();
+                let age = age
+                    .map(|age| format!("{} ", style(format!("{age:<4}")).dim()))
+                    .unwrap_or_default();
                 format!(
-                    "{} {} {:<width$} {}",
+                    "{} {} {:<width$} {}{}{}{}",
                     marker,
                     commit,
                     branch_name,
+                    id,
+                    age,
                     path,
+                    tags,
                     width = width,
                 )
             }
         }
     }
+
+    /// Width of the tags `render` puts after the path, e.g. ` [locked]`
+    pub fn tags_width(
+        &self,
+        tracking: Option<&HashMap<String, Tracking>>,
+        missing: Option<&HashSet<PathBuf>>,
+    ) -> usize {
+        let missing = if missing.is_some_and(|m| m.contains(self.path())) {
+            MISSING_TAG.len() + 1
+        } else {
+            0
+        };
+        let lock = self
+            .lock_tag()
+            .map_or(0, |tag| measure_text_width(&tag) + 1);
+        let gone = if self.is_gone(tracking) {
+            GONE_TAG.len() + 1
+        } else {
+            0
+        };
+        missing + lock + gone
+    }
+
+    fn lock_tag(&self) -> Option<String> {
+        match self.locked()? {
+            "" => Some("[locked]".to_string()),
+            reason => Some(format!("[locked: {reason}]")),
+        }
+    }
+
+    fn is_gone(&self, tracking: Option<&HashMap<String, Tracking>>) -> bool {
+        tracking
+            .zip(self.branch())
+            .and_then(|(tracking, branch)| tracking.get(branch))
+            .is_some_and(|t| t.gone)
+    }
+
+    /// `*` for the worktree gwt runs in, which wins over `M` for the main one
+    fn marker(&self, is_active: bool) -> Option<&'static str> {
+        if is_active {
+            Some("*")
+        } else if self.is_main {
+            Some("M")
+        } else {
+            None
+        }
+    }
+}
+
+/// `text` cut to `width` columns, ending in `..` when it was longer
+fn truncate_end(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(width.saturating_sub(2)).collect();
+    format!("{kept}..")
+}
+
+/// `path` with its middle replaced by `..` to fit `width` columns, keeping
+/// the end, which tells worktrees apart, longer than the start
+fn elide_middle(path: &str, width: usize) -> String {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.len() <= width {
+        return path.to_string();
+    }
+    let kept = width.saturating_sub(2);
+    let head = kept / 3;
+    let tail = kept - head;
+    let mut elided: String = chars[..head].iter().collect();
+    elided.push_str("..");
+    elided.extend(&chars[chars.len() - tail..]);
+    elided
 }
 
 /// Representation of a collection of Git worktrees
@@ -117,11 +320,39 @@ impl Worktrees {
         }
     }
 
-    pub fn max_branch_width(&self) -> usize {
+    /// Find a managed worktree whose directory id starts with `prefix`.
+    /// Errors when more than one worktree matches.
+    pub fn find_by_id_prefix(&self, prefix: &str, root: &Path) -> Result<Option<&Worktree>> {
+        if prefix.is_empty() {
+            return Ok(None);
+        }
+
+        let matches: Vec<&Worktree> = self
+            .iter()
+            .filter(|wt| wt.id(root).is_some_and(|id| id.starts_with(prefix)))
+            .collect();
+
+        match matches.as_slice() {
+            [] => Ok(None),
+            [wt] => Ok(Some(wt)),
+            _ => Err(GwtError::Usage(format!(
+                "Ambiguous worktree id '{}'. Matches: {}",
+                prefix,
+                matches
+                    .iter()
+                    .filter_map(|wt| wt.id(root))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .into()),
+        }
+    }
+
+    pub fn max_id_width(&self, root: &Path) -> usize {
         self.0
             .iter()
-            .map(|br| br.branch().map_or_else(|| "(detached)".len(), |s| s.len()))
-            .map(|l| l.min(MAX_BRANCH_WIDTH))
+            .filter_map(|wt| wt.id(root))
+            .map(str::len)
             .max()
             .unwrap_or(0)
     }
@@ -159,6 +390,83 @@ impl<'a> IntoIterator for &'a Worktrees {
     }
 }
 
+/// Parse the output of `git worktree list --porcelain`
+pub(super) fn parse_porcelain(input: &str) -> Worktrees {
+    let mut trees = Vec::new();
+
+    let mut current_path: Option<PathBuf> = None;
+    let mut current_head: Option<String> = None;
+    let mut current_branch: Option<String> = None;
+    let mut current_lock: Option<String> = None;
+
+    for line in input.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            // finalize current block
+            if let (Some(path), Some(head)) = (current_path.take(), current_head.take()) {
+                trees.push(new_worktree(
+                    path,
+                    head,
+                    current_branch.take(),
+                    current_lock.take(),
+                ));
+            }
+            current_path = None;
+            current_head = None;
+            current_branch = None;
+            current_lock = None;
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("worktree ") {
+            current_path = Some(PathBuf::from(rest));
+        } else if let Some(rest) = line.strip_prefix("HEAD ") {
+            current_head = Some(rest.to_string());
+        } else if let Some(rest) = line.strip_prefix("branch ") {
+            // branch may be in the form refs/heads/<name>
+            let branch_name = rest.strip_prefix("refs/heads/").unwrap_or(rest).to_string();
+            current_branch = Some(branch_name);
+        } else if line == "detached" {
+            current_branch = None;
+        } else if line == "locked" {
+            current_lock = Some(String::new());
+        } else if let Some(reason) = line.strip_prefix("locked ") {
+            current_lock = Some(reason.to_string());
+        }
+    }
+
+    // finalize last block if any
+    if let (Some(path), Some(head)) = (current_path.take(), current_head.take()) {
+        trees.push(new_worktree(
+            path,
+            head,
+            current_branch.take(),
+            current_lock.take(),
+        ));
+    }
+
+    // Bare entries have no HEAD and were skipped, so the first one left is
+    // the main worktree
+    if let Some(main) = trees.first_mut() {
+        main.is_main = true;
+    }
+
+    Worktrees::new(trees)
+}
+
+fn new_worktree(
+    path: PathBuf,
+    head: String,
+    branch: Option<String>,
+    lock: Option<String>,
+) -> Worktree {
+    let worktree = Worktree::new(path, head, branch);
+    match lock {
+        Some(reason) => worktree.with_lock(reason),
+        None => worktree,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,16 +478,22 @@ mod tests {
                 path: PathBuf::from("/z"),
                 head: "h1".into(),
                 branch: Some("zebra".into()),
+                locked: None,
+                is_main: false,
             },
             Worktree {
                 path: PathBuf::from("/d"),
                 head: "h2".into(),
                 branch: None,
+                locked: None,
+                is_main: false,
             },
             Worktree {
                 path: PathBuf::from("/a"),
                 head: "h3".into(),
                 branch: Some("apple".into()),
+                locked: None,
+                is_main: false,
             },
         ]);
 
@@ -200,6 +514,8 @@ mod tests {
             path: PathBuf::from("/a"),
             head: "h1".into(),
             branch: Some("b1".into()),
+            locked: None,
+            is_main: false,
         }]);
 
         // Test IntoIterator for &Worktrees
@@ -224,25 +540,210 @@ mod tests {
             path: PathBuf::from("/path/to/repo"),
             head: "abc123456789".into(),
             branch: Some("feature-branch".into()),
+            locked: None,
+            is_main: false,
         };
 
         // Test Full mode
-        let full_output = wt.render(&None, BranchRenderMode::Full);
+        let full_output = wt.render(&None, &RenderOptions::new(BranchRenderMode::Full));
         assert_eq!(full_output, "- abc1234 feature-branch\n  /path/to/repo");
 
         // Test Truncated mode
-        let trunc_output = wt.render(&None, BranchRenderMode::Truncated(10));
+        let trunc_output = wt.render(&None, &RenderOptions::new(BranchRenderMode::Truncated(10)));
         // feature-branch is 14 chars, width is 10. Truncated to "feature-.." (8 chars + "..")
         assert_eq!(trunc_output, "  abc1234 feature-.. /path/to/repo");
 
         // Test active worktree
         let active_output = wt.render(
             &Some(PathBuf::from("/path/to/repo")),
-            BranchRenderMode::Full,
+            &RenderOptions::new(BranchRenderMode::Full),
         );
         assert!(active_output.starts_with("*"));
     }
 
+    #[test]
+    fn test_worktree_render_with_ids() {
+        console::set_colors_enabled(false);
+
+        let root = PathBuf::from("/store");
+        let managed = Worktree::new(
+            PathBuf::from("/store/3f2a9c1d"),
+            "abc123456789".into(),
+            Some("feature".into()),
+        );
+        let main = Worktree::new(
+            PathBuf::from("/path/to/repo"),
+            "def123456789".into(),
+            Some("main".into()),
+        )
+        .into_main();
+        let options = RenderOptions {
+            branch_mode: BranchRenderMode::Truncated(7),
+            ids: Some(IdColumn {
+                root: &root,
+                width: 8,
+            }),
+            tracking: None,
+            ages: None,
+            path_width: None,
+            missing: None,
+        };
+
+        assert_eq!(
+            managed.render(&None, &options),
+            "  abc1234 feature 3f2a9c1d /store/3f2a9c1d"
+        );
+        assert_eq!(
+            main.render(&None, &options),
+            "M def1234 main             /path/to/repo"
+        );
+
+        let full = RenderOptions {
+            branch_mode: BranchRenderMode::Full,
+            ..options
+        };
+        assert_eq!(
+            managed.render(&None, &full),
+            "- abc1234 feature 3f2a9c1d\n  /store/3f2a9c1d"
+        );
+        assert_eq!(main.render(&None, &full), "M def1234 main\n  /path/to/repo");
+        // The current marker wins
+        let current = Some(PathBuf::from("/path/to/repo"));
+        assert!(main.render(&current, &full).starts_with("* def1234"));
+    }
+
+    #[test]
+    fn test_worktree_render_locked() {
+        console::set_colors_enabled(false);
+
+        let wt = Worktree::new(
+            PathBuf::from("/mnt/usb/wt"),
+            "abc123456789".into(),
+            Some("usb".into()),
+        );
+        let locked = wt.clone().with_lock("on the usb drive".into());
+        let no_reason = wt.with_lock(String::new());
+
+        assert_eq!(
+            locked.render(&None, &RenderOptions::new(BranchRenderMode::Truncated(3))),
+            "  abc1234 usb /mnt/usb/wt [locked: on the usb drive]"
+        );
+        assert_eq!(
+            no_reason.render(&None, &RenderOptions::new(BranchRenderMode::Full)),
+            "- abc1234 usb [locked]\n  /mnt/usb/wt"
+        );
+    }
+
+    #[test]
+    fn test_worktree_render_gone() {
+        console::set_colors_enabled(false);
+
+        let wt = Worktree::new(
+            PathBuf::from("/store/wt"),
+            "abc123456789".into(),
+            Some("merged".into()),
+        );
+        let mut tracking = HashMap::new();
+        tracking.insert(
+            "merged".to_string(),
+            Tracking {
+                upstream: Some("origin/merged".into()),
+                gone: true,
+                ..Default::default()
+            },
+        );
+        let options = RenderOptions {
+            tracking: Some(&tracking),
+            ..RenderOptions::new(BranchRenderMode::Truncated(6))
+        };
+
+        assert_eq!(
+            wt.render(&None, &options),
+            "  abc1234 merged /store/wt [gone]"
+        );
+        // Only shown with --status
+        assert_eq!(
+            wt.render(&None, &RenderOptions::new(BranchRenderMode::Truncated(6))),
+            "  abc1234 merged /store/wt"
+        );
+    }
+
+    #[test]
+    fn test_worktree_render_age() {
+        console::set_colors_enabled(false);
+
+        let wt = Worktree::new(
+            PathBuf::from("/store/wt"),
+            "abc123456789".into(),
+            Some("old".into()),
+        );
+        let unknown = Worktree::new(
+            PathBuf::from("/code/repo"),
+            "abc123456789".into(),
+            Some("new".into()),
+        );
+        let three_days_ago = crate::utility::unix_now() - 3 * 86_400;
+        let ages = BTreeMap::from([(PathBuf::from("/store/wt"), three_days_ago)]);
+        let options = RenderOptions {
+            ages: Some(&ages),
+            ..RenderOptions::new(BranchRenderMode::Truncated(3))
+        };
+
+        assert_eq!(wt.render(&None, &options), "  abc1234 old 3d   /store/wt");
+        assert_eq!(
+            unknown.render(&None, &options),
+            "  abc1234 new -    /code/repo"
+        );
+        let full = RenderOptions {
+            branch_mode: BranchRenderMode::Full,
+            ..options
+        };
+        assert_eq!(wt.render(&None, &full), "- abc1234 old 3d\n  /store/wt");
+    }
+
+    #[test]
+    fn test_worktree_id() {
+        let root = PathBuf::from("/store");
+        let managed = Worktree::new(PathBuf::from("/store/repo/3f2a9c1d"), "h".into(), None);
+        let unmanaged = Worktree::new(PathBuf::from("/code/repo"), "h".into(), None);
+
+        assert_eq!(managed.id(&root), Some("3f2a9c1d"));
+        assert_eq!(unmanaged.id(&root), None);
+    }
+
+    #[test]
+    fn test_find_by_id_prefix() {
+        let root = PathBuf::from("/store");
+        let wts = Worktrees(vec![
+            Worktree::new(
+                PathBuf::from("/code/repo"),
+                "h0".into(),
+                Some("main".into()),
+            ),
+            Worktree::new(
+                PathBuf::from("/store/3f2a9c1d"),
+                "h1".into(),
+                Some("a".into()),
+            ),
+            Worktree::new(
+                PathBuf::from("/store/3f2b0000"),
+                "h2".into(),
+                Some("b".into()),
+            ),
+        ]);
+
+        let found = wts.find_by_id_prefix("3f2a", &root).unwrap().unwrap();
+        assert_eq!(found.branch(), Some("a"));
+
+        assert!(wts.find_by_id_prefix("ffff", &root).unwrap().is_none());
+        assert!(wts.find_by_id_prefix("", &root).unwrap().is_none());
+
+        let err = wts.find_by_id_prefix("3f2", &root).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous worktree id '3f2'"));
+
+        assert_eq!(wts.max_id_width(&root), 8);
+    }
+
     #[test]
     fn test_worktrees_branches() {
         let wts = Worktrees(vec![
@@ -256,4 +757,195 @@ mod tests {
         let full_branches = wts.branches(ListBranchMode::Full("none"));
         assert_eq!(full_branches, vec!["b1", "none"]);
     }
+
+    #[test]
+    fn parse_porcelain_two_worktrees() {
+        let input = "worktree /path/to/main
+HEAD abc123
+branch refs/heads/main
+
+worktree /path/to/feature
+HEAD def456
+branch refs/heads/feature-branch
+";
+
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 2);
+
+        assert_eq!(parsed[0].path(), &PathBuf::from("/path/to/main"));
+        assert_eq!(parsed[0].head(), "abc123");
+        assert_eq!(parsed[0].branch(), Some("main"));
+        assert!(parsed[0].is_main());
+
+        assert_eq!(parsed[1].path(), &PathBuf::from("/path/to/feature"));
+        assert_eq!(parsed[1].head(), "def456");
+        assert_eq!(parsed[1].branch(), Some("feature-branch"));
+        assert!(!parsed[1].is_main());
+    }
+
+    #[test]
+    fn parse_porcelain_bare_repository() {
+        let input = "worktree /path/to/repo.git
+bare
+
+worktree /path/to/main
+HEAD abc123
+branch refs/heads/main
+
+worktree /path/to/feature
+HEAD def456
+branch refs/heads/feature
+";
+
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed[0].is_main());
+        assert_eq!(parsed[0].path(), &PathBuf::from("/path/to/main"));
+        assert!(!parsed[1].is_main());
+    }
+
+    #[test]
+    fn parse_porcelain_detached_worktree() {
+        let input = "worktree /path/to/detached
+HEAD ghi789
+detached
+";
+
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].path(), &PathBuf::from("/path/to/detached"));
+        assert_eq!(parsed[0].head(), "ghi789");
+        assert_eq!(parsed[0].branch(), None);
+    }
+
+    #[test]
+    fn parse_porcelain_locked_worktrees() {
+        let input = "worktree /path/to/main
+HEAD abc123
+branch refs/heads/main
+
+worktree /path/to/usb
+HEAD def456
+branch refs/heads/usb
+locked on the usb drive
+
+worktree /path/to/plain
+HEAD fed654
+branch refs/heads/plain
+locked
+";
+
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].locked(), None);
+        assert_eq!(parsed[1].locked(), Some("on the usb drive"));
+        assert_eq!(parsed[2].locked(), Some(""));
+    }
+
+    #[test]
+    fn parse_branch_with_slash() {
+        let input = "worktree /path/to/feature
+HEAD abcabc
+branch refs/heads/feature/my-feature
+";
+
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].branch(), Some("feature/my-feature"));
+    }
+
+    #[test]
+    fn parse_multiple_blocks_last_block_without_trailing_blank_correct() {
+        let input = "worktree /a
+HEAD a1
+branch refs/heads/a
+
+worktree /b
+HEAD b1
+branch refs/heads/b";
+        let parsed = parse_porcelain(input);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].branch(), Some("b"));
+    }
+
+    #[test]
+    fn test_elide_middle() {
+        assert_eq!(elide_middle("/store/69fa950d", 20), "/store/69fa950d");
+        let elided = elide_middle("/Users/someone/.gwt_store/69fa950d86b47897", 20);
+        assert_eq!(elided, "/Users..950d86b47897");
+        assert_eq!(elided.chars().count(), 20);
+    }
+
+    #[test]
+    fn test_render_elides_path() {
+        let wt = Worktree::new(
+            PathBuf::from("/Users/someone/.gwt_store/69fa950d86b47897"),
+            "b1f0fed".to_string(),
+            Some("fix/issue-76".to_string()),
+        );
+        let options = RenderOptions {
+            path_width: Some(20),
+            ..RenderOptions::new(BranchRenderMode::Truncated(6))
+        };
+        assert_eq!(
+            console::strip_ansi_codes(&wt.render(&None, &options)),
+            "  b1f0fed fix/.. /Users..950d86b47897"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_directory() {
+        let wt = Worktree::new(
+            PathBuf::from("/store/wt"),
+            "abc1234".to_string(),
+            Some("gone-dir".to_string()),
+        );
+        let missing = HashSet::from([PathBuf::from("/store/wt")]);
+        let options = RenderOptions {
+            missing: Some(&missing),
+            ..RenderOptions::new(BranchRenderMode::Truncated(8))
+        };
+        assert_eq!(
+            wt.render(&None, &options),
+            "  abc1234 gone-dir /store/wt [missing]"
+        );
+    }
+
+    #[test]
+    fn test_tags_width() {
+        let wt = Worktree::new(PathBuf::from("/store/1"), "1".repeat(40), Some("a".into()));
+        assert_eq!(wt.tags_width(None, None), 0);
+        let tracking = HashMap::from([(
+            "a".to_string(),
+            Tracking {
+                gone: true,
+                ..Default::default()
+            },
+        )]);
+        let locked = wt.with_lock("usb".to_string());
+        let missing = HashSet::from([PathBuf::from("/store/1")]);
+        assert_eq!(
+            locked.tags_width(Some(&tracking), Some(&missing)),
+            " [missing] [locked: usb] [gone]".len()
+        );
+    }
+
+    #[test]
+    fn test_worktree_accessors() {
+        let worktree = Worktree::new(
+            PathBuf::from("/store/1111"),
+            "abc123".to_string(),
+            Some("feature".to_string()),
+        );
+        assert_eq!(worktree.path(), &PathBuf::from("/store/1111"));
+        assert_eq!(worktree.head(), "abc123");
+        assert_eq!(worktree.branch(), Some("feature"));
+        assert_eq!(worktree.locked(), None);
+
+        let locked = worktree.with_lock("usb drive".to_string());
+        assert_eq!(locked.locked(), Some("usb drive"));
+
+        let detached = Worktree::new(PathBuf::from("/store/2222"), "def456".to_string(), None);
+        assert_eq!(detached.branch(), None);
+    }
 }
@@ -0,0 +1,97 @@
+//! Aliases from the `[aliases]` table of the config, expanded by the real
+//! binary in a real repository
+
+mod common;
+
+use common::{gwtree, init_repo, stdout, write_config};
+use std::path::PathBuf;
+use std::process::Output;
+
+/// A repository at `<dir>/repo` with one commit on `main`, and a config in
+/// `<dir>/home` with the given `[aliases]` table
+fn setup(aliases: &str) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = init_repo(dir.path(), "repo").canonicalize().unwrap();
+    write_config(
+        &dir.path().join("home"),
+        &dir.path().join("store"),
+        &format!("\n[aliases]\n{aliases}"),
+    );
+    (dir, repo)
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+const ALIASES: &str = r#"co = ["sw", "-b"]
+here = ["home"]
+ls = ["ls", "--full"]
+outer = ["-q", "inner"]
+inner = ["home"]
+"#;
+
+#[test]
+fn test_alias_runs_the_command_it_expands_to() {
+    let (dir, repo) = setup(ALIASES);
+    let output = gwtree(dir.path(), &repo, &["co", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    let path = PathBuf::from(stdout(&output));
+    assert!(path.starts_with(dir.path().join("store").canonicalize().unwrap()));
+
+    let output = gwtree(dir.path(), &path, &["-q", "here"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), repo.display().to_string());
+}
+
+#[test]
+fn test_mistakes_are_reported_for_the_expanded_command() {
+    let (dir, repo) = setup(ALIASES);
+    let output = gwtree(dir.path(), &repo, &["here", "--bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("'--bogus'"), "{stderr}");
+    assert!(stderr.contains("Usage: gwtree home"), "{stderr}");
+}
+
+#[test]
+fn test_aliases_of_aliases_are_refused() {
+    let (dir, repo) = setup(ALIASES);
+    let output = gwtree(dir.path(), &repo, &["outer"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("alias 'outer' expands to the alias 'inner'"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_alias_list() {
+    let (dir, repo) = setup(ALIASES);
+    let output = gwtree(dir.path(), &repo, &["alias", "list"]);
+    assert!(output.status.success(), "{output:?}");
+    let list = stdout(&output);
+    assert!(list.contains("co     sw -b\n"), "{list}");
+    assert!(list.contains("built-in command"), "{list}");
+
+    // Only usable aliases are completed
+    let output = gwtree(dir.path(), &repo, &["alias", "list", "--raw"]);
+    assert_eq!(stdout(&output), "co\nhere\ninner");
+
+    let output = gwtree(
+        dir.path(),
+        &repo,
+        &["alias", "expand", "sh", "--", "co", "it's"],
+    );
+    assert_eq!(stdout(&output), r"'sw' '-b' 'it'\''s'");
+
+    // PowerShell and nushell read one word per line
+    let output = gwtree(
+        dir.path(),
+        &repo,
+        &["alias", "expand", "lines", "--", "co", "it's"],
+    );
+    assert_eq!(stdout(&output), "sw\n-b\nit's");
+}
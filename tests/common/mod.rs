@@ -0,0 +1,81 @@
+//! Fixtures shared by the integration tests, which run the built binary
+//! against real repositories
+
+// Each test crate uses only some of the helpers
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Run git with `args` in `cwd`, with an identity for the commits it makes
+pub fn git(cwd: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=t", "-c", "user.email=t@e"])
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// A repository at `<parent>/<name>` with one commit on `main`
+pub fn init_repo(parent: &Path, name: &str) -> PathBuf {
+    git(parent, &["init", "-q", "-b", "main", name]);
+    let repo = parent.join(name);
+    git(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+    repo
+}
+
+/// A config in `<home>/.gwt` with `worktree_root` set to `store`, followed by
+/// the TOML in `extra`
+pub fn write_config(home: &Path, store: &Path, extra: &str) {
+    std::fs::create_dir_all(home.join(".gwt")).unwrap();
+    std::fs::create_dir_all(store).unwrap();
+    // A basic string, so backslashes in Windows paths are escaped
+    let root = toml::Value::String(store.to_str().unwrap().to_string());
+    std::fs::write(
+        home.join(".gwt/config.toml"),
+        format!("worktree_root = {root}\n{extra}"),
+    )
+    .unwrap();
+}
+
+/// `GWT_HOME` at `<dir>/home` with a config pointing at `<dir>/store`, and a
+/// repository at `<dir>/repo` with one commit on `main`
+pub fn setup() -> tempfile::TempDir {
+    setup_with("")
+}
+
+/// [`setup`] with more settings in the config
+pub fn setup_with(extra: &str) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_config(&dir.path().join("home"), &dir.path().join("store"), extra);
+    init_repo(dir.path(), "repo");
+    dir
+}
+
+/// Run gwtree in `cwd` with the `GWT_HOME` of [`setup`]
+pub fn gwtree(dir: &Path, cwd: &Path, args: &[&str]) -> Output {
+    gwtree_with(&dir.join("home"), cwd, &[], args)
+}
+
+/// Run gwtree in `cwd` with `GWT_HOME` at `home` and the variables in `envs`
+pub fn gwtree_with(home: &Path, cwd: &Path, envs: &[(&str, &str)], args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gwtree"))
+        .args(args)
+        .current_dir(cwd)
+        .env("GWT_HOME", home)
+        .env_remove("GWT_GIT")
+        .env_remove("GWT_QUIET")
+        .envs(envs.iter().copied())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// stdout without the trailing newline
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string()
+}
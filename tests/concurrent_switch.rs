@@ -0,0 +1,55 @@
+//! Concurrent `gwt sw` runs for the same branch, against a real repository
+
+mod common;
+
+use common::{git, setup};
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_concurrent_switches_create_one_worktree() {
+    let dir = setup();
+    let home = dir.path().join("home");
+    let repo = dir.path().join("repo");
+    git(&repo, &["branch", "feature"]);
+
+    let children: Vec<_> = (0..4)
+        .map(|_| {
+            Command::new(env!("CARGO_BIN_EXE_gwtree"))
+                .args(["sw", "feature"])
+                .current_dir(&repo)
+                .env("GWT_HOME", &home)
+                .env_remove("GWT_GIT")
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    let outputs: Vec<_> = children
+        .into_iter()
+        .map(|c| c.wait_with_output().unwrap())
+        .collect();
+
+    for output in &outputs {
+        assert!(output.status.success(), "{output:?}");
+    }
+    // Every run prints the same worktree
+    let paths: Vec<_> = outputs
+        .iter()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .collect();
+    assert!(paths.iter().all(|p| *p == paths[0]), "{paths:?}");
+
+    let output = Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .current_dir(&repo)
+        .output()
+        .unwrap();
+    let list = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        list.matches("branch refs/heads/feature\n").count(),
+        1,
+        "{list}"
+    );
+}
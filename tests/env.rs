@@ -0,0 +1,61 @@
+//! `gwt env` inside and outside a repository, checked against the built binary
+
+mod common;
+
+use common::{gwtree, setup_with, stdout};
+
+/// [`setup_with`] a path template that puts worktrees under the repository
+/// name, so their paths are predictable
+fn setup() -> tempfile::TempDir {
+    setup_with("path_template = '{repo}/{branch_slug}'\n")
+}
+
+#[test]
+fn test_env_in_a_repository() {
+    let dir = setup();
+    let root = dir.path().canonicalize().unwrap();
+    let output = gwtree(dir.path(), &dir.path().join("repo"), &["env", "--export"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = stdout(&output);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert!(
+        lines.contains(
+            &format!(
+                "export GWT_REPO_STORE={}",
+                dir.path().join("store/repo").display()
+            )
+            .as_str()
+        ),
+        "{stdout}"
+    );
+    assert!(
+        lines.contains(
+            &format!("export GWT_MAIN_WORKTREE={}", root.join("repo").display()).as_str()
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_env_outside_a_repository_leaves_out_the_repository() {
+    let dir = setup();
+    let output = gwtree(dir.path(), dir.path(), &["env"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "GWT_CONFIG_PATH={}\nGWT_WORKTREE_ROOT={}",
+            dir.path().join("home/.gwt/config.toml").display(),
+            dir.path().join("store").display()
+        )
+    );
+
+    let output = gwtree(dir.path(), dir.path(), &["env", "--json"]);
+    assert!(output.status.success(), "{output:?}");
+    let env: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(env.get("main_worktree").is_none(), "{env}");
+    assert_eq!(
+        env["worktree_root"],
+        dir.path().join("store").display().to_string()
+    );
+}
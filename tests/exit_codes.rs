@@ -0,0 +1,207 @@
+//! Exit codes scripts rely on, checked against the built binary
+
+mod common;
+
+use common::{git, gwtree, gwtree_with, setup, setup_with};
+use std::path::Path;
+use std::process::Output;
+
+fn code(output: &Output) -> Option<i32> {
+    output.status.code()
+}
+
+#[test]
+fn test_usage_error_exits_2() {
+    let dir = setup();
+    let output = gwtree(dir.path(), dir.path(), &["no-such-command"]);
+    assert_eq!(code(&output), Some(2));
+}
+
+#[test]
+fn test_outside_a_repository_exits_3() {
+    let dir = setup();
+    let cwd = dir.path().canonicalize().unwrap();
+    let expected = format!(
+        "gwt: not inside a git repository (cwd: {})\n",
+        cwd.display()
+    );
+    for args in [&["current"][..], &["ls"], &["home"], &["sw", "feature"]] {
+        let output = gwtree(dir.path(), dir.path(), args);
+        assert_eq!(code(&output), Some(3), "{args:?}: {output:?}");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected,
+            "{args:?}"
+        );
+    }
+
+    let output = gwtree(dir.path(), dir.path(), &["sw", "--json", "feature"]);
+    assert_eq!(code(&output), Some(3), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(r#""error_kind":"not_a_repository""#),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_missing_branch_or_worktree_exits_4() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+
+    let output = gwtree(dir.path(), &repo, &["sw", "nope"]);
+    assert_eq!(code(&output), Some(4), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Branch 'nope' not found locally or in any remote."),
+        "{stderr}"
+    );
+
+    let output = gwtree(dir.path(), &repo, &["rm", "-y", "nope"]);
+    assert_eq!(code(&output), Some(4), "{output:?}");
+}
+
+#[test]
+fn test_dirty_worktree_exits_6() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+
+    let output = gwtree(dir.path(), &repo, &["sw", "-b", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    let worktree = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    std::fs::write(Path::new(&worktree).join("scratch"), "wip").unwrap();
+
+    let output = gwtree(dir.path(), &repo, &["rm", "-y", "feature"]);
+    assert_eq!(code(&output), Some(6), "{output:?}");
+    assert!(Path::new(&worktree).exists());
+}
+
+#[test]
+fn test_missing_config_without_a_terminal_exits_7() {
+    let dir = setup();
+    std::fs::remove_file(dir.path().join("home/.gwt/config.toml")).unwrap();
+    let output = gwtree(dir.path(), &dir.path().join("repo"), &["ls"]);
+    assert_eq!(code(&output), Some(7), "{output:?}");
+}
+
+#[test]
+fn test_unmerged_branch_kept_by_rm_exits_9() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+
+    let output = gwtree(dir.path(), &repo, &["sw", "-b", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    let worktree = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    git(
+        Path::new(&worktree),
+        &["commit", "-q", "--allow-empty", "-m", "wip"],
+    );
+
+    let output = gwtree(dir.path(), &repo, &["rm", "-y", "-b", "feature"]);
+    assert_eq!(code(&output), Some(9), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Branch 'feature' is not merged into 'main' and was kept."),
+        "{stderr}"
+    );
+    assert!(!Path::new(&worktree).exists());
+}
+
+#[test]
+fn test_prompt_never_fails_outside_a_repository() {
+    let dir = setup();
+    let output = gwtree(dir.path(), dir.path(), &["prompt", "--starship"]);
+    assert_eq!(code(&output), Some(0), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "managed=false\nrepo=\nbranch=\ndirty=\n"
+    );
+
+    // Not even without a configuration
+    let output = gwtree(&dir.path().join("nowhere"), dir.path(), &["prompt"]);
+    assert_eq!(code(&output), Some(0), "{output:?}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_sw_json_reports_the_result_and_errors() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+
+    let output = gwtree(dir.path(), &repo, &["sw", "-b", "feature", "--json"]);
+    assert!(output.status.success(), "{output:?}");
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["branch"], "feature");
+    assert_eq!(result["created"], true);
+    assert_eq!(result["repo"], "repo");
+    let worktree = result["path"].as_str().unwrap().to_string();
+    assert!(Path::new(&worktree).is_dir(), "{worktree}");
+
+    let output = gwtree(dir.path(), &repo, &["sw", "feature", "--json"]);
+    assert!(output.status.success(), "{output:?}");
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["created"], false);
+    assert_eq!(result["path"], worktree.as_str());
+
+    let output = gwtree(dir.path(), &repo, &["sw", "nope", "--json"]);
+    assert_eq!(code(&output), Some(4), "{output:?}");
+    assert!(output.stdout.is_empty());
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error_kind"], "branch_not_found_anywhere");
+    assert_eq!(error["exit_code"], 4);
+}
+
+#[test]
+fn test_run_exits_with_the_commands_status() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+
+    let output = gwtree(dir.path(), &repo, &["run", "feature", "--", "true"]);
+    assert_eq!(code(&output), Some(4), "{output:?}");
+
+    git(&repo, &["branch", "feature"]);
+
+    let output = gwtree(
+        dir.path(),
+        &repo,
+        &[
+            "run",
+            "-c",
+            "feature",
+            "--",
+            "sh",
+            "-c",
+            r#"echo "$GWT_BRANCH"; exit 5"#,
+        ],
+    );
+    assert_eq!(code(&output), Some(5), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "feature\n");
+
+    let output = gwtree(
+        dir.path(),
+        &repo,
+        &["run", "feature", "--", "sh", "-c", "test -e .git"],
+    );
+    assert_eq!(code(&output), Some(0), "{output:?}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_git_timeout_exits_5() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = setup_with("git_timeout_secs = 1\n");
+    let slow_git = dir.path().join("slow-git");
+    std::fs::write(&slow_git, "#!/bin/sh\nsleep 10\n").unwrap();
+    std::fs::set_permissions(&slow_git, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = gwtree_with(
+        &dir.path().join("home"),
+        &dir.path().join("repo"),
+        &[("GWT_GIT", slow_git.to_str().unwrap())],
+        &["sw", "feature", "--json"],
+    );
+    assert_eq!(code(&output), Some(5), "{output:?}");
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error_kind"], "git_timed_out");
+}
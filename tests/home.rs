@@ -0,0 +1,85 @@
+//! `gwt home` and the other commands that run without a config, against a
+//! real repository and a `GWT_HOME` that has never been set up
+
+mod common;
+
+use common::{gwtree, init_repo, stdout};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A repository at `<dir>/<parent>/repo` with one commit on `main` and a
+/// `repo/a/b/c` subdirectory, and an empty `<dir>/home` for `GWT_HOME`
+fn setup(parent: &str) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let parent = dir.path().join(parent);
+    std::fs::create_dir_all(&parent).unwrap();
+    std::fs::create_dir_all(dir.path().join("home")).unwrap();
+
+    let repo = init_repo(&parent, "repo").canonicalize().unwrap();
+    std::fs::create_dir_all(repo.join("a/b/c")).unwrap();
+    (dir, repo)
+}
+
+#[test]
+fn test_home_from_a_subdirectory() {
+    let (dir, repo) = setup("code");
+    let output = gwtree(dir.path(), &repo.join("a/b/c"), &["home"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), repo.display().to_string());
+}
+
+#[test]
+fn test_home_with_spaces_in_the_path() {
+    let (dir, repo) = setup("my code/with spaces");
+    let output = gwtree(dir.path(), &repo.join("a/b"), &["home"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), repo.display().to_string());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_home_eval_changes_directory() {
+    let (dir, repo) = setup("it's my code");
+    let output = gwtree(dir.path(), &repo.join("a/b"), &["home", "--eval"]);
+    assert!(output.status.success(), "{output:?}");
+    let command = stdout(&output);
+    assert!(command.starts_with("cd -- '"), "{command}");
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(r#"eval "$1" && pwd"#)
+        .arg("sh")
+        .arg(&command)
+        .current_dir("/")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), repo.display().to_string());
+
+    let output = gwtree(dir.path(), &repo, &["home", "--eval=fish"]);
+    assert!(stdout(&output).starts_with(r"cd '"), "{output:?}");
+}
+
+#[test]
+fn test_home_eval_prints_nothing_on_failure() {
+    let (dir, _repo) = setup("code");
+    let output = gwtree(dir.path(), dir.path(), &["home", "--eval"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "{output:?}");
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_read_only_commands_skip_the_config() {
+    let (dir, repo) = setup("code");
+    for args in [
+        &["home"][..],
+        &["current"],
+        &["init", "bash"],
+        &["completion", "zsh"],
+    ] {
+        let output = gwtree(dir.path(), &repo, args);
+        assert!(output.status.success(), "{args:?}: {output:?}");
+    }
+    assert!(!dir.path().join("home/.gwt/config.toml").exists());
+}
@@ -0,0 +1,134 @@
+//! `gwt export` and `gwt import` round-tripping a working set between two
+//! clones of the same repository, each with a store of its own.
+
+mod common;
+
+use common::{git, gwtree_with, write_config};
+use std::path::Path;
+use std::process::Output;
+
+/// An `origin` repository with the branches `main`, `feature` and `release`,
+/// cloned to `<dir>/first` and `<dir>/second`. `<dir>/home-<clone>` holds each
+/// clone's config, pointing at `<dir>/store-<clone>`.
+fn setup() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main", "origin"]);
+    let origin = dir.path().join("origin");
+    git(&origin, &["commit", "-q", "--allow-empty", "-m", "init"]);
+    git(&origin, &["branch", "feature"]);
+    git(&origin, &["branch", "release"]);
+
+    for clone in ["first", "second"] {
+        git(dir.path(), &["clone", "-q", "origin", clone]);
+        write_config(
+            &dir.path().join(format!("home-{clone}")),
+            &dir.path().join(format!("store-{clone}")),
+            "",
+        );
+    }
+    dir
+}
+
+/// Run gwtree in the clone `clone` with its own config
+fn gwtree(dir: &Path, clone: &str, args: &[&str]) -> Output {
+    gwtree_with(
+        &dir.join(format!("home-{clone}")),
+        &dir.join(clone),
+        &[],
+        args,
+    )
+}
+
+fn succeed(output: Output) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// `gwt import` report line of `branch`, without the padding
+fn report_line<'a>(report: &'a str, branch: &str) -> Vec<&'a str> {
+    report
+        .lines()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>())
+        .find(|words| words.get(1) == Some(&branch))
+        .map(|words| words[..2].to_vec())
+        .unwrap_or_default()
+}
+
+fn round_trip(format: &str) {
+    let dir = setup();
+    let d = dir.path();
+    succeed(gwtree(d, "first", &["sw", "feature"]));
+    succeed(gwtree(d, "first", &["sw", "release"]));
+    succeed(gwtree(d, "first", &["sw", "-b", "local-only"]));
+    succeed(gwtree(
+        d,
+        "first",
+        &["lock", "release", "--reason", "on a USB drive"],
+    ));
+
+    let manifest = d.join(format!("worktrees.{format}"));
+    let manifest_arg = manifest.to_str().unwrap();
+    succeed(gwtree(
+        d,
+        "first",
+        &["export", "--format", format, "-o", manifest_arg],
+    ));
+
+    // A branch that exists nowhere is reported without failing the import
+    let report = succeed(gwtree(d, "second", &["import", manifest_arg]));
+    assert_eq!(report_line(&report, "feature"), ["created", "feature"]);
+    assert_eq!(report_line(&report, "release"), ["created", "release"]);
+    assert_eq!(
+        report_line(&report, "local-only"),
+        ["missing", "local-only"]
+    );
+
+    let list = succeed(gwtree(d, "second", &["ls", "--json"]));
+    let list: serde_json::Value = serde_json::from_str(&list).unwrap();
+    let release = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|wt| wt["branch"] == "release")
+        .unwrap();
+    assert_eq!(release["locked"], true);
+    let store = d.join("store-second").canonicalize().unwrap();
+    let path = Path::new(release["path"].as_str().unwrap());
+    assert!(path.canonicalize().unwrap().starts_with(&store));
+
+    // The new clone exports the same working set, minus what was missing
+    let second = succeed(gwtree(d, "second", &["export", "--format", format]));
+    assert!(second.contains("feature") && second.contains("on a USB drive"));
+    assert!(!second.contains("local-only"), "{second}");
+
+    // Importing again finds everything in place
+    let report = succeed(gwtree(d, "second", &["import", manifest_arg]));
+    assert_eq!(report_line(&report, "feature"), ["skipped", "feature"]);
+    assert_eq!(report_line(&report, "release"), ["skipped", "release"]);
+}
+
+#[test]
+fn test_round_trip_toml() {
+    round_trip("toml");
+}
+
+#[test]
+fn test_round_trip_json() {
+    round_trip("json");
+}
+
+#[test]
+fn test_import_of_an_invalid_manifest_fails() {
+    let dir = setup();
+    let manifest = dir.path().join("broken.toml");
+    std::fs::write(&manifest, "not [valid toml").unwrap();
+
+    let output = gwtree(
+        dir.path(),
+        "second",
+        &["import", manifest.to_str().unwrap()],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Invalid manifest"), "{stderr}");
+}
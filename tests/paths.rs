@@ -0,0 +1,55 @@
+//! Worktree paths checked against the built binary. Unlike the other
+//! integration tests, these only need `git` on the PATH and no POSIX shell,
+//! so they run on Windows CI too.
+
+mod common;
+
+use common::{gwtree, setup};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+fn stdout_path(output: &Output) -> PathBuf {
+    assert!(output.status.success(), "{output:?}");
+    PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a.canonicalize().unwrap() == b.canonicalize().unwrap()
+}
+
+#[test]
+fn test_worktree_is_created_in_the_store_and_found_again() {
+    let dir = setup();
+    let repo = dir.path().join("repo");
+    let store = dir.path().join("store");
+
+    let created = stdout_path(&gwtree(dir.path(), &repo, &["sw", "-b", "feature"]));
+    assert!(created.is_dir(), "{}", created.display());
+    assert!(same_file(created.parent().unwrap(), &store));
+    let name = created.file_name().unwrap().to_str().unwrap();
+    assert_eq!(name.len(), 16, "{name}");
+
+    // Switching back finds the same directory instead of creating another
+    let found = stdout_path(&gwtree(dir.path(), &repo, &["sw", "feature"]));
+    assert!(same_file(&found, &created));
+    let worktrees = std::fs::read_dir(&store)
+        .unwrap()
+        .filter(|e| {
+            !e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with('.')
+        })
+        .count();
+    assert_eq!(worktrees, 1);
+
+    let output = gwtree(dir.path(), &found, &["current"]);
+    assert!(output.status.success(), "{output:?}");
+    let current = String::from_utf8_lossy(&output.stdout);
+    assert!(current.starts_with("Branch feature @ "), "{current}");
+
+    let output = gwtree(dir.path(), &repo, &["rm", "-y", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!created.exists());
+}
@@ -0,0 +1,53 @@
+//! `--quiet` and `GWT_QUIET`, checked against the built binary
+
+mod common;
+
+use common::{gwtree_with, setup};
+use std::path::Path;
+use std::process::Output;
+
+/// Run gwtree in `<dir>/repo` with the variables in `envs`
+fn gwtree(dir: &Path, envs: &[(&str, &str)], args: &[&str]) -> Output {
+    gwtree_with(&dir.join("home"), &dir.join("repo"), envs, args)
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn test_status_messages_by_default() {
+    let dir = setup();
+    let output = gwtree(dir.path(), &[], &["sw", "-b", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stderr(&output).contains("Created worktree for branch 'feature'"));
+}
+
+#[test]
+fn test_quiet_keeps_only_the_output() {
+    let dir = setup();
+    let output = gwtree(dir.path(), &[], &["-q", "sw", "-b", "feature"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert!(
+        Path::new(&path).starts_with(dir.path().join("store")),
+        "{path}"
+    );
+
+    let output = gwtree(
+        dir.path(),
+        &[("GWT_QUIET", "1")],
+        &["rm", "-y", "-b", "feature"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn test_quiet_keeps_errors() {
+    let dir = setup();
+    let output = gwtree(dir.path(), &[], &["sw", "nope", "--quiet"]);
+    assert_eq!(output.status.code(), Some(4), "{output:?}");
+    assert!(stderr(&output).contains("Branch 'nope' not found"));
+}
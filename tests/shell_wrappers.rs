@@ -0,0 +1,228 @@
+//! The wrappers printed by `gwtree init`, sourced in the real shells. Shells
+//! that aren't installed are skipped.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+fn shell_available(shell: &str) -> bool {
+    Command::new(shell)
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// A temp directory with a stub binary, the wrapper for `shell` generated by
+/// the real `gwtree` to call the stub, and a worktree directory `target`.
+///
+/// The stub prints usage for `--help`, fails with exit code 4 for the branch
+/// `missing`, knows no aliases, and prints `target` for anything else. The
+/// branch `noisy` and `recent` warn on stderr first, and `recent` prints two
+/// lines that aren't a directory.
+struct Env {
+    dir: tempfile::TempDir,
+    target: PathBuf,
+}
+
+impl Env {
+    fn new(shell: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().canonicalize().unwrap().join("worktree");
+        std::fs::create_dir(&target).unwrap();
+
+        let stub = dir.path().join("gwt-stub");
+        std::fs::write(
+            &stub,
+            format!(
+                r#"#!/bin/sh
+if [ "$1" = "alias" ]; then
+    exit 0
+fi
+for arg in "$@"; do
+    if [ "$arg" = "--help" ]; then
+        echo "Usage: gwtree $1"
+        exit 0
+    fi
+done
+if [ "$2" = "missing" ]; then
+    echo "Branch 'missing' not found locally or in any remote." >&2
+    exit 4
+fi
+if [ "$2" = "noisy" ] || [ "$1" = "recent" ]; then
+    echo "warning: the worktree you left has local changes" >&2
+fi
+if [ "$1" = "recent" ]; then
+    printf 'two  words\n  indented *\n'
+    exit 0
+fi
+echo "{}"
+"#,
+                target.display()
+            ),
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let output = Command::new(env!("CARGO_BIN_EXE_gwtree"))
+            .args(["init", shell, "--binary-name"])
+            .arg(&stub)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        std::fs::write(dir.path().join("init"), &output.stdout).unwrap();
+
+        Self { dir, target }
+    }
+
+    /// Source the wrapper and run `script` from the temp directory
+    fn run(&self, shell: &str, script: &str) -> Output {
+        Command::new(shell)
+            .arg("-c")
+            .arg(format!(
+                "source {}; {}",
+                self.dir.path().join("init").display(),
+                script
+            ))
+            .current_dir(self.dir.path())
+            .output()
+            .unwrap()
+    }
+
+    fn start(&self) -> PathBuf {
+        self.dir.path().canonicalize().unwrap()
+    }
+}
+
+/// `a && b` in the syntax of `shell`
+fn and(shell: &str, a: &str, b: &str) -> String {
+    match shell {
+        "fish" => format!("{a}; and {b}"),
+        _ => format!("{a} && {b}"),
+    }
+}
+
+fn status_var(shell: &str) -> &'static str {
+    match shell {
+        "fish" => "$status",
+        _ => "$?",
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn for_each_shell(test: impl Fn(&str)) {
+    for shell in SHELLS {
+        if shell_available(shell) {
+            test(shell);
+        } else {
+            eprintln!("{shell} not installed, skipping");
+        }
+    }
+}
+
+#[test]
+fn test_sw_changes_directory() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        let output = env.run(shell, &and(shell, "gwt sw foo", "pwd"));
+        assert!(output.status.success(), "{shell}: {output:?}");
+        assert_eq!(stdout(&output), env.target.display().to_string(), "{shell}");
+    });
+}
+
+#[test]
+fn test_failure_propagates_exit_code() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        let output = env.run(
+            shell,
+            &format!("gwt sw missing; echo status={}; pwd", status_var(shell)),
+        );
+        let out = stdout(&output);
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("status=4"), "{shell}: {output:?}");
+        // Still where it started
+        assert_eq!(lines.next(), Some(env.start().to_str().unwrap()), "{shell}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Branch 'missing' not found"),
+            "{shell}: {stderr}"
+        );
+    });
+}
+
+#[test]
+fn test_help_passes_through() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        for cmd in ["sw", "rm", "home"] {
+            let output = env.run(shell, &and(shell, &format!("gwt {cmd} --help"), "pwd"));
+            assert!(output.status.success(), "{shell} {cmd}: {output:?}");
+            let out = stdout(&output);
+            let start = env.start();
+            let expected = format!("Usage: gwtree {cmd}\n{}", start.display());
+            assert_eq!(out, expected, "{shell} {cmd}");
+        }
+    });
+}
+
+#[test]
+fn test_other_commands_run_unchanged() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        let output = env.run(shell, &and(shell, "gwt ls", "pwd"));
+        assert!(output.status.success(), "{shell}: {output:?}");
+        // `ls` prints its output and never changes directory
+        let expected = format!("{}\n{}", env.target.display(), env.start().display());
+        assert_eq!(stdout(&output), expected, "{shell}");
+    });
+}
+
+#[test]
+fn test_warnings_pass_through_on_success() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        let output = env.run(shell, &and(shell, "gwt sw noisy 2>&1", "pwd"));
+        assert!(output.status.success(), "{shell}: {output:?}");
+        let expected = format!(
+            "warning: the worktree you left has local changes\n{}",
+            env.target.display()
+        );
+        assert_eq!(stdout(&output), expected, "{shell}");
+    });
+}
+
+#[test]
+fn test_other_output_is_printed_verbatim() {
+    for_each_shell(|shell| {
+        let env = Env::new(shell);
+        let output = env.run(shell, "gwt recent");
+        assert!(output.status.success(), "{shell}: {output:?}");
+        // Neither split into words nor glob-expanded
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "two  words\n  indented *\n",
+            "{shell}"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "warning: the worktree you left has local changes\n",
+            "{shell}"
+        );
+
+        // The warning comes first, as gwtree printed it before its result
+        let output = env.run(shell, "gwt recent 2>&1");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "warning: the worktree you left has local changes\ntwo  words\n  indented *\n",
+            "{shell}"
+        );
+    });
+}